@@ -0,0 +1,283 @@
+//! Procedural macros of the [`cancellable`](https://docs.rs/cancellable/) crate.
+//!
+//! This crate isn't meant to be used directly. Enable the `macros` feature of
+//! `cancellable` and use `cancellable::service` instead.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{quote, ToTokens};
+use syn::{
+    parse_macro_input, parse_quote, Error, Expr, Field, Fields, GenericArgument, ImplItem, Item,
+    ItemImpl, ItemStruct, PathArguments, Type,
+};
+
+/// Generates the handle plumbing of a service.
+///
+/// See the documentation of `cancellable::service`.
+#[proc_macro_attribute]
+pub fn service(args: TokenStream, input: TokenStream) -> TokenStream {
+    if !args.is_empty() {
+        let error = Error::new(Span::call_site(), "`service` doesn't take any arguments");
+        return error.into_compile_error().into();
+    }
+
+    let output = match parse_macro_input!(input as Item) {
+        Item::Struct(item) => expand_struct(item),
+        Item::Impl(item) => Ok(expand_impl(item)),
+        item => Err(Error::new_spanned(
+            item,
+            "`service` can only be applied to a struct or to its `Cancellable` impl",
+        )),
+    };
+
+    output.unwrap_or_else(Error::into_compile_error).into()
+}
+
+/// Field of the service providing its handle.
+enum HandleField {
+    /// The field is the handle itself, or an `Option` of it.
+    Sender { field: Field },
+
+    /// The field is the receiving half of the channel, whose sending half is
+    /// the handle.
+    Receiver {
+        field: Field,
+        item: Box<Type>,
+        capacity: Option<Box<Expr>>,
+    },
+}
+
+const HANDLE_SENDER: &str = "handle_sender";
+const HANDLE_RECEIVER: &str = "handle_receiver";
+
+fn expand_struct(mut item: ItemStruct) -> syn::Result<TokenStream2> {
+    let vis = item.vis.clone();
+    let Fields::Named(fields) = &mut item.fields else {
+        return Err(Error::new_spanned(
+            &item,
+            "`service` can only be applied to a struct with named fields",
+        ));
+    };
+
+    let mut handle_field = None;
+    for field in fields.named.iter_mut() {
+        let Some(parsed) = parse_handle_field(field)? else {
+            continue;
+        };
+        if handle_field.is_some() {
+            return Err(Error::new_spanned(
+                field,
+                "a service can have only a single handle field",
+            ));
+        }
+        handle_field = Some(parsed);
+    }
+
+    let (handle, take_handle, constructor) = match handle_field {
+        None => (quote! { () }, quote! {}, None),
+        Some(HandleField::Sender { field }) => {
+            let ident = &field.ident;
+            match option_inner(&field.ty) {
+                Some(inner) => (
+                    inner.to_token_stream(),
+                    quote! { self.#ident.take().expect("handle to be taken only once") },
+                    None,
+                ),
+                None => (
+                    field.ty.to_token_stream(),
+                    quote! { ::std::clone::Clone::clone(&self.#ident) },
+                    None,
+                ),
+            }
+        }
+        Some(HandleField::Receiver {
+            field,
+            item: item_type,
+            capacity,
+        }) => {
+            let (handle, channel) = match capacity {
+                Some(capacity) => (
+                    quote! { ::cancellable::MpscSenderHandle<#item_type> },
+                    quote! { ::cancellable::MpscSenderHandle::channel(#capacity) },
+                ),
+                None => (
+                    quote! { ::cancellable::UnboundedSenderHandle<#item_type> },
+                    quote! { ::cancellable::UnboundedSenderHandle::channel() },
+                ),
+            };
+
+            let others: Vec<_> = fields
+                .named
+                .iter()
+                .filter(|f| f.ident != field.ident)
+                .map(|f| (f.ident.clone(), f.ty.clone()))
+                .collect();
+            fields.named.push(parse_quote! {
+                __cancellable_handle: ::std::option::Option<#handle>
+            });
+
+            let receiver = &field.ident;
+            let params = others.iter().map(|(ident, ty)| quote! { #ident: #ty });
+            let idents = others.iter().map(|(ident, _)| ident);
+            let constructor = quote! {
+                /// Constructs the service along with the channel of its handle.
+                #vis fn new(#(#params),*) -> Self {
+                    let (handle, receiver) = #channel;
+                    Self {
+                        #(#idents,)*
+                        #receiver: receiver,
+                        __cancellable_handle: ::std::option::Option::Some(handle),
+                    }
+                }
+            };
+
+            (
+                handle,
+                quote! { self.__cancellable_handle.take().expect("handle to be taken only once") },
+                Some(constructor),
+            )
+        }
+    };
+
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = item.generics.split_for_impl();
+    let constructor = constructor.map(|constructor| {
+        quote! {
+            impl #impl_generics #name #ty_generics #where_clause {
+                #constructor
+            }
+        }
+    });
+
+    Ok(quote! {
+        #item
+
+        impl #impl_generics ::cancellable::__private::ServiceHandle for #name #ty_generics #where_clause {
+            type Handle = #handle;
+
+            fn take_handle(&mut self) -> Self::Handle {
+                #take_handle
+            }
+        }
+
+        #constructor
+    })
+}
+
+/// Parses the handle attribute of `field`, if there's any, and removes it
+/// from the field.
+fn parse_handle_field(field: &mut Field) -> syn::Result<Option<HandleField>> {
+    let Some(index) = field.attrs.iter().position(|attr| {
+        attr.path().is_ident(HANDLE_SENDER) || attr.path().is_ident(HANDLE_RECEIVER)
+    }) else {
+        return Ok(None);
+    };
+    let attr = field.attrs.remove(index);
+
+    if attr.path().is_ident(HANDLE_SENDER) {
+        attr.meta.require_path_only()?;
+        return Ok(Some(HandleField::Sender {
+            field: field.clone(),
+        }));
+    }
+
+    let mut capacity = None;
+    if !matches!(attr.meta, syn::Meta::Path(_)) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("capacity") {
+                capacity = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `capacity`"))
+            }
+        })?;
+    }
+
+    let (segment, item) = last_segment(&field.ty, 1).ok_or_else(|| {
+        Error::new_spanned(
+            &field.ty,
+            "expected `Receiver<T>` or `UnboundedReceiver<T>` of `tokio::sync::mpsc`",
+        )
+    })?;
+    match (segment.as_str(), &capacity) {
+        ("Receiver", Some(_)) | ("UnboundedReceiver", None) => {}
+        ("Receiver", None) => {
+            return Err(Error::new_spanned(
+                &attr,
+                "the capacity of a bounded channel is required, e.g. `#[handle_receiver(capacity = 16)]`",
+            ))
+        }
+        ("UnboundedReceiver", Some(_)) => {
+            return Err(Error::new_spanned(
+                &attr,
+                "an unbounded channel doesn't take a capacity",
+            ))
+        }
+        _ => {
+            return Err(Error::new_spanned(
+                &field.ty,
+                "expected `Receiver<T>` or `UnboundedReceiver<T>` of `tokio::sync::mpsc`",
+            ))
+        }
+    }
+
+    Ok(Some(HandleField::Receiver {
+        field: field.clone(),
+        item: Box::new(item),
+        capacity: capacity.map(Box::new),
+    }))
+}
+
+/// Returns the name of the last segment of the path of `ty` along with its
+/// only type argument.
+fn last_segment(ty: &Type, arguments: usize) -> Option<(String, Type)> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    if args.args.len() != arguments {
+        return None;
+    }
+    let Some(GenericArgument::Type(inner)) = args.args.first() else {
+        return None;
+    };
+
+    Some((segment.ident.to_string(), inner.clone()))
+}
+
+/// Returns `T` if `ty` is `Option<T>`.
+fn option_inner(ty: &Type) -> Option<Type> {
+    match last_segment(ty, 1)? {
+        (segment, inner) if segment == "Option" => Some(inner),
+        _ => None,
+    }
+}
+
+fn expand_impl(mut item: ItemImpl) -> TokenStream2 {
+    let has_handle = item
+        .items
+        .iter()
+        .any(|item| matches!(item, ImplItem::Type(ty) if ty.ident == "Handle"));
+    let has_new_handle = item
+        .items
+        .iter()
+        .any(|item| matches!(item, ImplItem::Fn(f) if f.sig.ident == "new_handle"));
+
+    if !has_handle {
+        item.items.push(parse_quote! {
+            type Handle = <Self as ::cancellable::__private::ServiceHandle>::Handle;
+        });
+    }
+    if !has_new_handle {
+        item.items.push(parse_quote! {
+            async fn new_handle(&mut self) -> Self::Handle {
+                ::cancellable::__private::ServiceHandle::take_handle(self)
+            }
+        });
+    }
+
+    quote! { #item }
+}
@@ -1,28 +1,19 @@
 use std::{collections::VecDeque, error::Error};
 
-use cancellable::{async_trait, Cancellable, CancellationResult, CancellationToken};
-use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
-
-#[derive(Debug)]
-struct NumberSender {
-    inner: UnboundedSender<i32>,
-}
-
-impl NumberSender {
-    async fn send(&mut self, item: i32) -> Result<(), i32> {
-        self.inner.send(item).map_err(|_| item)?;
-        Ok(())
-    }
-}
+use cancellable::{
+    CallbackResult, Cancellable, CancellationResult, CancellationToken, MpscSenderHandle,
+    SenderHandle,
+};
+use tokio::sync::mpsc::Receiver;
 
 struct Multiplier {
-    number_receiver: UnboundedReceiver<i32>,
-    number_sender: Option<UnboundedSender<i32>>,
+    number_receiver: Receiver<i32>,
+    number_sender: Option<MpscSenderHandle<i32>>,
 }
 
 impl Multiplier {
     fn new() -> Self {
-        let (sender, receiver) = unbounded_channel();
+        let (sender, receiver) = MpscSenderHandle::channel(16);
 
         Self {
             number_receiver: receiver,
@@ -31,22 +22,19 @@ impl Multiplier {
     }
 }
 
-#[async_trait]
 impl Cancellable for Multiplier {
     type Result = i32;
-    type Handle = NumberSender;
+    type Handle = MpscSenderHandle<i32>;
     type Error = anyhow::Error;
+    type Output = ();
 
     async fn new_handle(&mut self) -> Self::Handle {
-        let sender = self
-            .number_sender
+        self.number_sender
             .take()
-            .expect("number_sender to be present");
-
-        NumberSender { inner: sender }
+            .expect("number_sender to be present")
     }
 
-    async fn run(&mut self) -> Result<CancellationResult<Self::Result>, Self::Error> {
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
         let number = self
             .number_receiver
             .recv()
@@ -65,7 +53,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let input: VecDeque<_> = [42, 13, 37].into();
     let mut expected: VecDeque<_> = input.iter().map(|i| i * 2).collect();
 
-    let mut handle = listener
+    let handle = listener
         .spawn_with_callback(cancellation_token.child_token(), move |number| {
             let Some(front) = expected.pop_front() else {
                 panic!("Too many inputs");
@@ -73,7 +61,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
 
             println!("Received {}.", number);
             assert_eq!(front, number);
-            Ok(())
+            CallbackResult::Continue
         })
         .await;
 
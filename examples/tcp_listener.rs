@@ -1,6 +1,6 @@
 use std::{error::Error, net::SocketAddr, time::Duration};
 
-use cancellable::{async_trait, Cancellable, CancellationResult, CancellationToken};
+use cancellable::{CallbackResult, Cancellable, CancellationResult, CancellationToken};
 use tokio::{
     net::{TcpListener, TcpStream},
     time::sleep,
@@ -18,15 +18,15 @@ impl Listener {
     }
 }
 
-#[async_trait]
 impl Cancellable for Listener {
     type Result = (TcpStream, SocketAddr);
     type Handle = ();
     type Error = std::io::Error;
+    type Output = ();
 
     async fn new_handle(&mut self) -> Self::Handle {}
 
-    async fn run(&mut self) -> Result<CancellationResult<Self::Result>, Self::Error> {
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
         let (addr, stream) = self.tcp_listener.accept().await?;
 
         Ok(CancellationResult::item((addr, stream)))
@@ -46,7 +46,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let handle = listener
         .spawn_with_callback(cancellation_token.child_token(), |(stream, addr)| {
             handle_connection(stream, addr);
-            Ok(())
+            CallbackResult::Continue
         })
         .await;
 
@@ -0,0 +1,10 @@
+//! Items used by the code generated by the `cancellable-macros` crate.
+
+/// Handle plumbing of a service, generated by the `service` attribute macro.
+pub trait ServiceHandle {
+    /// See [`Cancellable::Handle`](crate::Cancellable::Handle).
+    type Handle;
+
+    /// Returns a new handle to the service.
+    fn take_handle(&mut self) -> Self::Handle;
+}
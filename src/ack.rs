@@ -0,0 +1,122 @@
+use std::{
+    ops::Deref,
+    sync::{Arc, Mutex},
+};
+
+use crate::in_flight::InFlight;
+
+/// Value yielded by a service spawned with [`Cancellable::spawn_with_acks`],
+/// which has to be acknowledged once it has been processed.
+///
+/// It dereferences to the value. If it's dropped without being acknowledged,
+/// then the value is handed back to the work loop, which passes it to
+/// [`Cancellable::on_item_dropped`], or diverts it to the dead letters, see
+/// [`SpawnOptions::dead_letters`].
+///
+/// [`Cancellable::spawn_with_acks`]: crate::Cancellable::spawn_with_acks
+/// [`Cancellable::on_item_dropped`]: crate::Cancellable::on_item_dropped
+/// [`SpawnOptions::dead_letters`]: crate::SpawnOptions::dead_letters
+#[derive(Debug)]
+pub struct Ack<T> {
+    item: Option<T>,
+    tracker: AckTracker<T>,
+}
+
+impl<T> Ack<T> {
+    /// Acknowledges the value, and returns it.
+    pub fn ack(mut self) -> T {
+        let item = self.item.take().expect("item to be present");
+        self.tracker.resolve(None);
+        item
+    }
+
+    /// Hands the value back to the work loop without acknowledging it.
+    ///
+    /// It's equivalent to dropping the value.
+    pub fn nack(self) {}
+}
+
+impl<T> Deref for Ack<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.item.as_ref().expect("item to be present")
+    }
+}
+
+impl<T> Drop for Ack<T> {
+    fn drop(&mut self) {
+        if let Some(item) = self.item.take() {
+            self.tracker.resolve(Some(item));
+        }
+    }
+}
+
+/// Values passed to the callback which haven't been acknowledged yet.
+#[derive(Debug)]
+pub(crate) struct AckTracker<T> {
+    in_flight: InFlight,
+    unacked: Arc<Mutex<Vec<T>>>,
+}
+
+impl<T> AckTracker<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            in_flight: InFlight::new(),
+            unacked: Arc::default(),
+        }
+    }
+
+    /// Returns the number of values which haven't been acknowledged or
+    /// dropped yet.
+    pub(crate) fn in_flight(&self) -> &InFlight {
+        &self.in_flight
+    }
+
+    /// Wraps `item` passed to the callback.
+    pub(crate) fn track(&self, item: T) -> Ack<T> {
+        self.in_flight.acquire();
+
+        Ack {
+            item: Some(item),
+            tracker: self.clone(),
+        }
+    }
+
+    /// Takes the values which have been dropped without being acknowledged.
+    pub(crate) fn take_unacked(&self) -> Vec<T> {
+        std::mem::take(&mut self.unacked.lock().expect("lock not to be poisoned"))
+    }
+
+    /// Waits until each of the tracked values has either been acknowledged or
+    /// dropped, and passes the ones which have been dropped to `on_unacked`.
+    pub(crate) async fn settle(&self, mut on_unacked: impl FnMut(T)) {
+        let mut in_flight = self.in_flight.subscribe();
+        loop {
+            // A value is handed back before it's released, so none of them is
+            // missed once there are none in flight.
+            let count = *in_flight.borrow_and_update();
+            self.take_unacked().into_iter().for_each(&mut on_unacked);
+            if count == 0 || in_flight.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+
+    fn resolve(&self, unacked: Option<T>) {
+        self.unacked
+            .lock()
+            .expect("lock not to be poisoned")
+            .extend(unacked);
+        self.in_flight.release();
+    }
+}
+
+impl<T> Clone for AckTracker<T> {
+    fn clone(&self) -> Self {
+        Self {
+            in_flight: self.in_flight.clone(),
+            unacked: Arc::clone(&self.unacked),
+        }
+    }
+}
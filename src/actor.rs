@@ -0,0 +1,374 @@
+use std::{future::Future, time::Duration};
+
+use tokio::sync::{
+    mpsc::{
+        self,
+        error::{SendError, SendTimeoutError, TrySendError},
+    },
+    oneshot,
+};
+
+use crate::{CallError, Cancellable, CancellationResult, MpscSenderHandle, SenderHandle};
+
+/// Defines an interface for an actor, i.e. a service handling the messages
+/// sent to its mailbox one at a time.
+///
+/// The actor is driven by [`ActorService`], whose handle is the actor's
+/// [`Mailbox`]. The messages are usually an enum, whose variants asking for a
+/// response carry a [`Reply`], see [`Mailbox::ask`].
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{Actor, ActorService, Cancellable, CancellationToken, Reply, SenderHandle};
+///
+/// enum Message {
+///     Add(u64),
+///     Get(Reply<u64>),
+/// }
+///
+/// struct Counter {
+///     value: u64,
+/// }
+///
+/// impl Actor for Counter {
+///     type Message = Message;
+///     type Error = std::io::Error;
+///
+///     async fn handle_message(&mut self, message: Self::Message) -> Result<(), Self::Error> {
+///         match message {
+///             Message::Add(value) => self.value += value,
+///             Message::Get(reply) => reply.send(self.value),
+///         }
+///
+///         Ok(())
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mailbox = ActorService::new(Counter { value: 0 }, 16)
+///     .spawn(CancellationToken::new())
+///     .await;
+///
+/// mailbox.send(Message::Add(2)).await.unwrap();
+/// mailbox.send(Message::Add(3)).await.unwrap();
+///
+/// assert_eq!(Ok(5), mailbox.ask(Message::Get).await);
+/// # }
+/// ```
+pub trait Actor: Send {
+    /// Type of messages handled by the actor.
+    type Message: Send;
+
+    /// Type of errors failing the actor.
+    ///
+    /// See [`Cancellable::Error`].
+    type Error: std::fmt::Debug + std::fmt::Display + Send;
+
+    /// Handles a single message.
+    ///
+    /// If it returns an error, then the actor fails with it, and the rest of
+    /// its messages are dropped.
+    fn handle_message(
+        &mut self,
+        message: Self::Message,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send;
+
+    /// Called once, before the first message is handled.
+    ///
+    /// See [`Cancellable::on_start`].
+    fn on_start(&mut self) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        async { Ok(()) }
+    }
+
+    /// Called once, after the actor has stopped without an error.
+    ///
+    /// See [`Cancellable::on_stop`].
+    fn on_stop(&mut self) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+}
+
+/// Sending side of a response to a message, see [`Mailbox::ask`].
+#[derive(Debug)]
+pub struct Reply<R>(oneshot::Sender<R>);
+
+impl<R> Reply<R> {
+    /// Sends `response` to the asking side.
+    ///
+    /// The response is dropped if the asking side has stopped waiting for it.
+    pub fn send(self, response: R) {
+        let _ = self.0.send(response);
+    }
+}
+
+/// Handle sending messages to an [`Actor`].
+///
+/// Besides sending messages as a [`SenderHandle`], it asks the actor for
+/// responses with [`Self::ask`].
+#[derive(Debug)]
+pub struct Mailbox<M> {
+    inner: MpscSenderHandle<M>,
+}
+
+impl<M> Mailbox<M>
+where
+    M: Send,
+{
+    /// Sends a message built by `message` around a [`Reply`], and waits for
+    /// the actor's response.
+    ///
+    /// Sending waits until there's capacity in the actor's mailbox.
+    pub async fn ask<R, F>(&self, message: F) -> Result<R, CallError>
+    where
+        F: FnOnce(Reply<R>) -> M,
+    {
+        let (reply, response) = oneshot::channel();
+        self.inner
+            .send(message(Reply(reply)))
+            .await
+            .map_err(|_| CallError::Closed)?;
+
+        response.await.map_err(|_| CallError::Dropped)
+    }
+}
+
+impl<M> Clone for Mailbox<M> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<M> SenderHandle for Mailbox<M>
+where
+    M: Send,
+{
+    type Item = M;
+
+    fn send(
+        &self,
+        item: Self::Item,
+    ) -> impl Future<Output = Result<(), SendError<Self::Item>>> + Send {
+        self.inner.send(item)
+    }
+
+    fn try_send(&self, item: Self::Item) -> Result<(), TrySendError<Self::Item>> {
+        self.inner.try_send(item)
+    }
+
+    fn send_timeout(
+        &self,
+        item: Self::Item,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<(), SendTimeoutError<Self::Item>>> + Send {
+        self.inner.send_timeout(item, timeout)
+    }
+
+    fn close(&self) {
+        self.inner.close();
+    }
+
+    fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+}
+
+/// Service driving an [`Actor`].
+///
+/// Its handle is the actor's [`Mailbox`]. The service completes once all of
+/// its mailboxes have been dropped or closed. When graceful shutdown is
+/// enabled, then the messages already in the mailbox by the time the service
+/// is cancelled are still handled.
+pub struct ActorService<A>
+where
+    A: Actor,
+{
+    actor: A,
+    receiver: mpsc::Receiver<A::Message>,
+    mailbox: Option<Mailbox<A::Message>>,
+}
+
+impl<A> ActorService<A>
+where
+    A: Actor,
+{
+    /// Constructs a new service driving `actor`.
+    ///
+    /// At most `capacity` messages can be queued in the actor's mailbox.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `capacity` is zero.
+    pub fn new(actor: A, capacity: usize) -> Self {
+        let (inner, receiver) = MpscSenderHandle::channel(capacity);
+
+        Self {
+            actor,
+            receiver,
+            mailbox: Some(Mailbox { inner }),
+        }
+    }
+}
+
+impl<A> Cancellable for ActorService<A>
+where
+    A: Actor,
+{
+    type Result = ();
+    type Handle = Mailbox<A::Message>;
+    type Error = A::Error;
+    type Output = ();
+
+    fn name(&self) -> &str {
+        std::any::type_name::<A>()
+    }
+
+    async fn new_handle(&mut self) -> Self::Handle {
+        self.mailbox.take().expect("mailbox to be present")
+    }
+
+    async fn on_start(&mut self) -> Result<(), Self::Error> {
+        self.actor.on_start().await
+    }
+
+    async fn on_stop(&mut self) {
+        self.actor.on_stop().await
+    }
+
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        match self.receiver.recv().await {
+            Some(message) => {
+                self.actor.handle_message(message).await?;
+                Ok(CancellationResult::Continue)
+            }
+            None => Ok(CancellationResult::Break),
+        }
+    }
+
+    async fn drain(
+        &mut self,
+    ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        match self.receiver.try_recv() {
+            Ok(message) => {
+                self.actor.handle_message(message).await?;
+                Ok(CancellationResult::Continue)
+            }
+            Err(_) => Ok(CancellationResult::Break),
+        }
+    }
+}
+
+impl<A> std::fmt::Debug for ActorService<A>
+where
+    A: Actor,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ActorService").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{
+        Actor, ActorService, CallError, Cancellable, CancellableError, Reply, SenderHandle,
+    };
+
+    enum Message {
+        Push(String),
+        Len(Reply<usize>),
+        Forget(Reply<usize>),
+        Fail,
+    }
+
+    struct Journal {
+        entries: Vec<String>,
+        stopped: Arc<AtomicBool>,
+    }
+
+    impl Actor for Journal {
+        type Message = Message;
+        type Error = anyhow::Error;
+
+        async fn handle_message(&mut self, message: Self::Message) -> Result<(), Self::Error> {
+            match message {
+                Message::Push(entry) => self.entries.push(entry),
+                Message::Len(reply) => reply.send(self.entries.len()),
+                Message::Forget(reply) => drop(reply),
+                Message::Fail => anyhow::bail!("Journal error"),
+            }
+
+            Ok(())
+        }
+
+        async fn on_start(&mut self) -> Result<(), Self::Error> {
+            self.entries.push("started".to_owned());
+            Ok(())
+        }
+
+        async fn on_stop(&mut self) {
+            self.stopped.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn journal(stopped: &Arc<AtomicBool>) -> ActorService<Journal> {
+        let journal = Journal {
+            entries: Vec::new(),
+            stopped: Arc::clone(stopped),
+        };
+
+        ActorService::new(journal, 4)
+    }
+
+    #[tokio::test]
+    async fn should_handle_messages_in_order() {
+        // Arrange
+        let stopped = Arc::new(AtomicBool::new(false));
+        let mailbox = journal(&stopped).spawn(CancellationToken::new()).await;
+
+        // Act
+        mailbox
+            .send(Message::Push("first".to_owned()))
+            .await
+            .unwrap();
+        mailbox
+            .send(Message::Push("second".to_owned()))
+            .await
+            .unwrap();
+        let len = mailbox.ask(Message::Len).await;
+        let forgotten = mailbox.ask(Message::Forget).await;
+        mailbox.close();
+
+        // Assert
+        assert_eq!(Ok(3), len);
+        assert_eq!(Err(CallError::Dropped), forgotten);
+        assert!(mailbox.join().await.is_ok());
+        assert!(stopped.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn should_fail_on_message_error() {
+        // Arrange
+        let stopped = Arc::new(AtomicBool::new(false));
+        let mailbox = journal(&stopped).spawn(CancellationToken::new()).await;
+        let asking = (*mailbox).clone();
+
+        // Act
+        mailbox.send(Message::Fail).await.unwrap();
+        let result = mailbox.join().await;
+
+        // Assert
+        assert!(matches!(result, Err(CancellableError::Service(_))));
+        assert_eq!(Err(CallError::Closed), asking.ask(Message::Len).await);
+        assert!(!stopped.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,1325 @@
+use std::{any::Any, future::Future, pin::Pin, time::Duration};
+
+use tokio::{sync::mpsc, time::Instant};
+
+use crate::{
+    clock::SharedClock,
+    run_context::{ItemSender, YieldedItem},
+    Cancellable, CancellationResult, ErrorDirective, RunContext, Yielder,
+};
+
+/// Result of a single call to [`Cancellable::run`] of the adapted service.
+type RunResult<T> = Result<
+    CancellationResult<<T as Cancellable>::Result, <T as Cancellable>::Output>,
+    <T as Cancellable>::Error,
+>;
+
+/// Adaptation of the results of the adapted service.
+///
+/// It's applied both to the results returned by the service and to the
+/// values it yields with [`RunContext::yield_item`], see [`Yields`].
+trait Adapt<T, O, E> {
+    /// Type of the adapted values.
+    type Item;
+
+    fn adapt(
+        &mut self,
+        result: Result<CancellationResult<T, O>, E>,
+    ) -> Result<CancellationResult<Self::Item, O>, E>;
+}
+
+/// Values yielded by the adapted service with [`RunContext::yield_item`].
+///
+/// The service is given a context of its own, so its values are passed
+/// through the adapter's [`Adapt`] before they're yielded to the adapter's
+/// consumer, just like the ones it returns.
+#[derive(Debug, Default)]
+struct Yields {
+    channel: Option<(ItemSender, mpsc::Receiver<YieldedItem>)>,
+}
+
+impl Yields {
+    /// Awaits `future`, meanwhile adapting the values of type `T` yielded by
+    /// it with `adapter`.
+    fn scope<'a, Fut, T, O, E, A>(
+        &'a mut self,
+        future: Fut,
+        adapter: &'a mut A,
+    ) -> impl Future<Output = Fut::Output> + 'a
+    where
+        Fut: Future<Output = Result<CancellationResult<T, O>, E>> + Send + 'a,
+        T: Send + 'static,
+        O: 'a,
+        E: 'a,
+        A: Adapt<T, O, E>,
+        A::Item: Send + 'static,
+    {
+        let context = RunContext::current();
+        let (sender, receiver) = self.channel.get_or_insert_with(ItemSender::channel::<T>);
+        let yielder = context.as_ref().and_then(RunContext::yielder::<A::Item>);
+        // Boxed, so the futures of nested adapters don't grow exponentially.
+        let mut future: Pin<Box<dyn Future<Output = Fut::Output> + Send + 'a>> = match context {
+            Some(context) => Box::pin(context.with_items(sender.clone()).enter(future)),
+            None => Box::pin(future),
+        };
+
+        async move {
+            let output = loop {
+                tokio::select! {
+                    biased;
+                    output = &mut future => break output,
+                    Some(item) = receiver.recv() => {
+                        pass::<T, O, E, A>(item, adapter, yielder.as_ref(), receiver).await;
+                    }
+                }
+            };
+
+            // Pass the values yielded right before the future has completed.
+            while let Ok(item) = receiver.try_recv() {
+                pass::<T, O, E, A>(item, adapter, yielder.as_ref(), receiver).await;
+            }
+
+            output
+        }
+    }
+}
+
+/// Adapts a single value yielded by the adapted service with `adapter`, and
+/// yields the adapted values with `yielder`.
+///
+/// Once either the adapter or the consumer stops accepting values,
+/// `receiver` is closed, so the next values are returned back to the adapted
+/// service.
+async fn pass<T, O, E, A>(
+    item: YieldedItem,
+    adapter: &mut A,
+    yielder: Option<&Yielder<A::Item>>,
+    receiver: &mut mpsc::Receiver<YieldedItem>,
+) where
+    T: 'static,
+    A: Adapt<T, O, E>,
+    A::Item: Send + 'static,
+{
+    let item = *item.downcast::<T>().expect("item type to be checked");
+    let (items, last) = match adapter.adapt(Ok(CancellationResult::Item(item))) {
+        Ok(CancellationResult::Item(item)) => (vec![item], false),
+        Ok(CancellationResult::Items(items)) => (items, false),
+        Ok(CancellationResult::LastItem(item)) => (vec![item], true),
+        Ok(CancellationResult::Continue | CancellationResult::Delay(_)) => (Vec::new(), false),
+        _ => (Vec::new(), true),
+    };
+
+    let Some(yielder) = yielder else {
+        receiver.close();
+        return;
+    };
+    for item in items {
+        if yielder.send(item).await.is_err() {
+            receiver.close();
+            return;
+        }
+    }
+    if last {
+        receiver.close();
+    }
+}
+
+/// Implements the methods of [`Cancellable`] by delegating them to the adapted
+/// service and passing every result through `self.adapter`.
+///
+/// With `run = method`, [`Cancellable::run`] is implemented with `Self::method`
+/// instead, and so is [`Cancellable::drain`] with `drain = method`.
+macro_rules! delegate {
+    () => {
+        async fn run(
+            &mut self,
+        ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+            let result = self.yields.scope(self.service.run(), &mut self.adapter).await;
+            self.adapter.adapt(result)
+        }
+
+        delegate!(@drain);
+        delegate!(@hooks);
+    };
+    (run = $run:ident) => {
+        async fn run(
+            &mut self,
+        ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+            self.$run().await
+        }
+
+        delegate!(@drain);
+        delegate!(@hooks);
+    };
+    (run = $run:ident, drain = $drain:ident) => {
+        async fn run(
+            &mut self,
+        ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+            self.$run().await
+        }
+
+        async fn drain(
+            &mut self,
+        ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+            self.$drain().await
+        }
+
+        delegate!(@hooks);
+    };
+    (@drain) => {
+        async fn drain(
+            &mut self,
+        ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+            let result = self.service.drain().await;
+            self.adapter.adapt(result)
+        }
+    };
+    (@hooks) => {
+        fn name(&self) -> &str {
+            self.service.name()
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {
+            self.service.new_handle().await
+        }
+
+        async fn try_new_handle(&mut self) -> Result<Self::Handle, Self::Error> {
+            self.service.try_new_handle().await
+        }
+
+        async fn on_start(&mut self) -> Result<(), Self::Error> {
+            self.service.on_start().await
+        }
+
+        async fn on_stop(&mut self) {
+            self.service.on_stop().await
+        }
+
+        async fn on_cancel(&mut self) {
+            self.service.on_cancel().await
+        }
+
+        fn cancelled_output(&mut self) -> Option<Self::Output> {
+            self.service.cancelled_output()
+        }
+
+        async fn on_timeout(
+            &mut self,
+        ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+            let result = self.service.on_timeout().await;
+            self.adapter.adapt(result)
+        }
+
+        async fn on_panic(
+            &mut self,
+            panic: Box<dyn Any + Send>,
+        ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+            let result = self.service.on_panic(panic).await;
+            self.adapter.adapt(result)
+        }
+
+        async fn on_error(&mut self, error: Self::Error) -> ErrorDirective<Self::Error> {
+            self.service.on_error(error).await
+        }
+
+        async fn restart(&mut self, error: Self::Error) -> Result<(), Self::Error> {
+            self.service.restart(error).await
+        }
+    };
+    // Only for the adapters which pass the service's values through as is.
+    (@on_item_dropped) => {
+        fn on_item_dropped(&mut self, item: Self::Result) {
+            self.service.on_item_dropped(item)
+        }
+    };
+}
+
+/// Applies `f` to every value of `result`, dropping the values for which it
+/// returns `None`.
+fn filter_map<T, U, O, E>(
+    result: Result<CancellationResult<T, O>, E>,
+    mut f: impl FnMut(T) -> Option<U>,
+) -> Result<CancellationResult<U, O>, E> {
+    let result = match result? {
+        CancellationResult::Item(item) => match f(item) {
+            Some(item) => CancellationResult::Item(item),
+            None => CancellationResult::Continue,
+        },
+        CancellationResult::Items(items) => {
+            CancellationResult::Items(items.into_iter().filter_map(f).collect())
+        }
+        CancellationResult::LastItem(item) => match f(item) {
+            Some(item) => CancellationResult::LastItem(item),
+            None => CancellationResult::Break,
+        },
+        CancellationResult::Continue => CancellationResult::Continue,
+        CancellationResult::Delay(delay) => CancellationResult::Delay(delay),
+        CancellationResult::Break => CancellationResult::Break,
+        CancellationResult::BreakWith(output) => CancellationResult::BreakWith(output),
+        CancellationResult::Cancelled => CancellationResult::Cancelled,
+    };
+
+    Ok(result)
+}
+
+/// Function of [`Map`].
+#[derive(Debug)]
+struct MapFn<F>(F);
+
+impl<T, U, O, E, F> Adapt<T, O, E> for MapFn<F>
+where
+    F: FnMut(T) -> U,
+{
+    type Item = U;
+
+    fn adapt(
+        &mut self,
+        result: Result<CancellationResult<T, O>, E>,
+    ) -> Result<CancellationResult<U, O>, E> {
+        filter_map(result, |item| Some((self.0)(item)))
+    }
+}
+
+/// Service transforming the values yielded by another service.
+///
+/// See [`Cancellable::map`].
+#[derive(Debug)]
+pub struct Map<S, F> {
+    service: S,
+    adapter: MapFn<F>,
+    yields: Yields,
+}
+
+impl<S, F> Map<S, F> {
+    pub(crate) fn new(service: S, f: F) -> Self {
+        Self {
+            service,
+            adapter: MapFn(f),
+            yields: Yields::default(),
+        }
+    }
+}
+
+impl<S, F, U> Cancellable for Map<S, F>
+where
+    S: Cancellable + Send,
+    S::Result: 'static,
+    F: FnMut(S::Result) -> U + Send,
+    U: Send + 'static,
+{
+    type Result = U;
+    type Handle = S::Handle;
+    type Error = S::Error;
+    type Output = S::Output;
+
+    delegate!();
+}
+
+/// Predicate of [`Filter`].
+#[derive(Debug)]
+struct Predicate<P>(P);
+
+impl<T, O, E, P> Adapt<T, O, E> for Predicate<P>
+where
+    P: FnMut(&T) -> bool,
+{
+    type Item = T;
+
+    fn adapt(
+        &mut self,
+        result: Result<CancellationResult<T, O>, E>,
+    ) -> Result<CancellationResult<T, O>, E> {
+        filter_map(result, |item| (self.0)(&item).then_some(item))
+    }
+}
+
+/// Service dropping the values yielded by another service which don't match
+/// a predicate.
+///
+/// See [`Cancellable::filter`].
+#[derive(Debug)]
+pub struct Filter<S, P> {
+    service: S,
+    adapter: Predicate<P>,
+    yields: Yields,
+}
+
+impl<S, P> Filter<S, P> {
+    pub(crate) fn new(service: S, predicate: P) -> Self {
+        Self {
+            service,
+            adapter: Predicate(predicate),
+            yields: Yields::default(),
+        }
+    }
+}
+
+impl<S, P> Cancellable for Filter<S, P>
+where
+    S: Cancellable + Send,
+    S::Result: 'static,
+    P: FnMut(&S::Result) -> bool + Send,
+{
+    type Result = S::Result;
+    type Handle = S::Handle;
+    type Error = S::Error;
+    type Output = S::Output;
+
+    delegate!();
+    delegate!(@on_item_dropped);
+}
+
+/// Function of [`FilterMap`].
+#[derive(Debug)]
+struct FilterMapFn<F>(F);
+
+impl<T, U, O, E, F> Adapt<T, O, E> for FilterMapFn<F>
+where
+    F: FnMut(T) -> Option<U>,
+{
+    type Item = U;
+
+    fn adapt(
+        &mut self,
+        result: Result<CancellationResult<T, O>, E>,
+    ) -> Result<CancellationResult<U, O>, E> {
+        filter_map(result, &mut self.0)
+    }
+}
+
+/// Service transforming the values yielded by another service and dropping
+/// the ones which have been transformed into `None`.
+///
+/// See [`Cancellable::filter_map`].
+#[derive(Debug)]
+pub struct FilterMap<S, F> {
+    service: S,
+    adapter: FilterMapFn<F>,
+    yields: Yields,
+}
+
+impl<S, F> FilterMap<S, F> {
+    pub(crate) fn new(service: S, f: F) -> Self {
+        Self {
+            service,
+            adapter: FilterMapFn(f),
+            yields: Yields::default(),
+        }
+    }
+}
+
+impl<S, F, U> Cancellable for FilterMap<S, F>
+where
+    S: Cancellable + Send,
+    S::Result: 'static,
+    F: FnMut(S::Result) -> Option<U> + Send,
+    U: Send + 'static,
+{
+    type Result = U;
+    type Handle = S::Handle;
+    type Error = S::Error;
+    type Output = S::Output;
+
+    delegate!();
+}
+
+/// Function of [`Inspect`].
+#[derive(Debug)]
+struct InspectFn<F>(F);
+
+impl<T, O, E, F> Adapt<T, O, E> for InspectFn<F>
+where
+    F: FnMut(&T),
+{
+    type Item = T;
+
+    fn adapt(
+        &mut self,
+        result: Result<CancellationResult<T, O>, E>,
+    ) -> Result<CancellationResult<T, O>, E> {
+        filter_map(result, |item| {
+            (self.0)(&item);
+            Some(item)
+        })
+    }
+}
+
+/// Service calling a function with a reference to every value yielded by
+/// another service.
+///
+/// See [`Cancellable::inspect`].
+#[derive(Debug)]
+pub struct Inspect<S, F> {
+    service: S,
+    adapter: InspectFn<F>,
+    yields: Yields,
+}
+
+impl<S, F> Inspect<S, F> {
+    pub(crate) fn new(service: S, f: F) -> Self {
+        Self {
+            service,
+            adapter: InspectFn(f),
+            yields: Yields::default(),
+        }
+    }
+}
+
+impl<S, F> Cancellable for Inspect<S, F>
+where
+    S: Cancellable + Send,
+    S::Result: 'static,
+    F: FnMut(&S::Result) + Send,
+{
+    type Result = S::Result;
+    type Handle = S::Handle;
+    type Error = S::Error;
+    type Output = S::Output;
+
+    delegate!();
+    delegate!(@on_item_dropped);
+}
+
+/// Function of [`InspectErr`].
+#[derive(Debug)]
+struct InspectErrFn<F>(F);
+
+impl<T, O, E, F> Adapt<T, O, E> for InspectErrFn<F>
+where
+    F: FnMut(&E),
+{
+    type Item = T;
+
+    fn adapt(
+        &mut self,
+        result: Result<CancellationResult<T, O>, E>,
+    ) -> Result<CancellationResult<T, O>, E> {
+        result.inspect_err(|e| (self.0)(e))
+    }
+}
+
+/// Service calling a function with a reference to every error returned by
+/// another service.
+///
+/// See [`Cancellable::inspect_err`].
+#[derive(Debug)]
+pub struct InspectErr<S, F> {
+    service: S,
+    adapter: InspectErrFn<F>,
+    yields: Yields,
+}
+
+impl<S, F> InspectErr<S, F> {
+    pub(crate) fn new(service: S, f: F) -> Self {
+        Self {
+            service,
+            adapter: InspectErrFn(f),
+            yields: Yields::default(),
+        }
+    }
+}
+
+impl<S, F> Cancellable for InspectErr<S, F>
+where
+    S: Cancellable + Send,
+    S::Result: 'static,
+    F: FnMut(&S::Error) + Send,
+{
+    type Result = S::Result;
+    type Handle = S::Handle;
+    type Error = S::Error;
+    type Output = S::Output;
+
+    delegate!();
+    delegate!(@on_item_dropped);
+}
+
+/// Number of the values [`Take`] has yet to yield.
+#[derive(Debug)]
+struct Remaining(usize);
+
+impl<T, O, E> Adapt<T, O, E> for Remaining {
+    type Item = T;
+
+    fn adapt(
+        &mut self,
+        result: Result<CancellationResult<T, O>, E>,
+    ) -> Result<CancellationResult<T, O>, E> {
+        let result = match result? {
+            CancellationResult::Items(mut items) => {
+                items.truncate(self.0);
+                self.0 -= items.len();
+                CancellationResult::Items(items)
+            }
+            CancellationResult::Item(_) | CancellationResult::LastItem(_) if self.0 == 0 => {
+                CancellationResult::Break
+            }
+            CancellationResult::Item(item) => {
+                self.0 -= 1;
+                match self.0 {
+                    0 => CancellationResult::LastItem(item),
+                    _ => CancellationResult::Item(item),
+                }
+            }
+            CancellationResult::LastItem(item) => {
+                self.0 -= 1;
+                CancellationResult::LastItem(item)
+            }
+            result => result,
+        };
+
+        Ok(result)
+    }
+}
+
+/// Service completing after another service has yielded a number of values.
+///
+/// See [`Cancellable::take`].
+#[derive(Debug)]
+pub struct Take<S> {
+    service: S,
+    adapter: Remaining,
+    yields: Yields,
+}
+
+impl<S> Take<S> {
+    pub(crate) fn new(service: S, n: usize) -> Self {
+        Self {
+            service,
+            adapter: Remaining(n),
+            yields: Yields::default(),
+        }
+    }
+}
+
+impl<S> Take<S>
+where
+    S: Cancellable,
+    S::Result: 'static,
+{
+    async fn run_take(&mut self) -> RunResult<S> {
+        if self.adapter.0 == 0 {
+            return Ok(CancellationResult::Break);
+        }
+
+        let result = self
+            .yields
+            .scope(self.service.run(), &mut self.adapter)
+            .await;
+        self.adapter.adapt(result)
+    }
+}
+
+impl<S> Cancellable for Take<S>
+where
+    S: Cancellable + Send,
+    S::Result: 'static,
+{
+    type Result = S::Result;
+    type Handle = S::Handle;
+    type Error = S::Error;
+    type Output = S::Output;
+
+    delegate!(run = run_take);
+    delegate!(@on_item_dropped);
+}
+
+/// Adapter of [`TakeUntil`], passing the results through as is.
+#[derive(Debug)]
+struct Unchanged;
+
+impl<T, O, E> Adapt<T, O, E> for Unchanged {
+    type Item = T;
+
+    fn adapt(
+        &mut self,
+        result: Result<CancellationResult<T, O>, E>,
+    ) -> Result<CancellationResult<T, O>, E> {
+        result
+    }
+}
+
+/// Service completing once a future completes.
+///
+/// See [`Cancellable::take_until`].
+pub struct TakeUntil<S, F> {
+    service: S,
+    until: Pin<Box<F>>,
+    completed: bool,
+    // The values the service yields are passed through as is, since they
+    // share the service's context.
+    adapter: Unchanged,
+}
+
+impl<S, F> TakeUntil<S, F> {
+    pub(crate) fn new(service: S, until: F) -> Self {
+        Self {
+            service,
+            until: Box::pin(until),
+            completed: false,
+            adapter: Unchanged,
+        }
+    }
+}
+
+impl<S, F> std::fmt::Debug for TakeUntil<S, F>
+where
+    S: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TakeUntil")
+            .field("service", &self.service)
+            .field("completed", &self.completed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S, F> TakeUntil<S, F>
+where
+    S: Cancellable,
+    F: Future,
+{
+    async fn run_until(&mut self) -> RunResult<S> {
+        if self.completed {
+            return Ok(CancellationResult::Break);
+        }
+
+        tokio::select! {
+            biased;
+            _ = self.until.as_mut() => {
+                self.completed = true;
+                Ok(CancellationResult::Break)
+            }
+            result = self.service.run() => result,
+        }
+    }
+}
+
+impl<S, F> Cancellable for TakeUntil<S, F>
+where
+    S: Cancellable + Send,
+    F: Future + Send,
+{
+    type Result = S::Result;
+    type Handle = S::Handle;
+    type Error = S::Error;
+    type Output = S::Output;
+
+    delegate!(run = run_until);
+    delegate!(@on_item_dropped);
+}
+
+/// Values held back by [`Debounce`] or [`Throttle`].
+struct Held<T, O> {
+    window: Duration,
+    throttle: bool,
+    /// End of the current throttling window.
+    window_end: Option<Instant>,
+    /// Latest value held back, along with the instant it's released at.
+    pending: Option<(T, Instant)>,
+    /// Result completing the service, returned once the pending value has
+    /// been released.
+    completion: Option<CancellationResult<T, O>>,
+}
+
+impl<T, O> Held<T, O> {
+    fn new(window: Duration, throttle: bool) -> Self {
+        Self {
+            window,
+            throttle,
+            window_end: None,
+            pending: None,
+            completion: None,
+        }
+    }
+
+    /// Holds `item` yielded at `now` back, or returns it if it's to be yielded
+    /// right away.
+    fn hold(&mut self, item: T, now: Instant) -> Option<T> {
+        if !self.throttle {
+            self.pending = Some((item, now + self.window));
+            return None;
+        }
+
+        match self.window_end {
+            Some(window_end) if now < window_end => {
+                self.pending = Some((item, window_end));
+                None
+            }
+            _ => {
+                self.window_end = Some(now + self.window);
+                Some(item)
+            }
+        }
+    }
+
+    /// Releases the pending value at `now`.
+    fn release(&mut self, now: Instant) -> Option<T> {
+        let (item, _) = self.pending.take()?;
+        if self.throttle {
+            self.window_end = Some(now + self.window);
+        }
+        Some(item)
+    }
+
+    /// Returns the result to complete with, or the pending value, if any.
+    fn flush(&mut self) -> Option<CancellationResult<T, O>> {
+        self.completion.take().or_else(|| {
+            self.pending
+                .take()
+                .map(|(item, _)| CancellationResult::Item(item))
+        })
+    }
+
+    fn adapt_at<E>(
+        &mut self,
+        result: Result<CancellationResult<T, O>, E>,
+        now: Instant,
+    ) -> Result<CancellationResult<T, O>, E> {
+        let result = match result? {
+            CancellationResult::Item(item) => match self.hold(item, now) {
+                Some(item) => CancellationResult::Item(item),
+                None => CancellationResult::Continue,
+            },
+            CancellationResult::Items(items) => {
+                let items: Vec<_> = items
+                    .into_iter()
+                    .filter_map(|item| self.hold(item, now))
+                    .collect();
+                if items.is_empty() {
+                    CancellationResult::Continue
+                } else {
+                    CancellationResult::Items(items)
+                }
+            }
+            CancellationResult::LastItem(item) => {
+                // The last value supersedes the pending one.
+                self.pending = None;
+                CancellationResult::LastItem(item)
+            }
+            result @ (CancellationResult::Break
+            | CancellationResult::BreakWith(_)
+            | CancellationResult::Cancelled) => match self.pending.take() {
+                Some((item, _)) => {
+                    self.completion = Some(result);
+                    CancellationResult::Item(item)
+                }
+                None => result,
+            },
+            result => result,
+        };
+
+        Ok(result)
+    }
+
+    /// Awaits `run`, unless the pending value is due to be released first.
+    ///
+    /// `run` is dropped if the pending value is released while it's in flight.
+    async fn run<E>(
+        &mut self,
+        run: impl Future<Output = Result<CancellationResult<T, O>, E>> + Send,
+        yields: &mut Yields,
+    ) -> Result<CancellationResult<T, O>, E>
+    where
+        T: Send + 'static,
+    {
+        if let Some(completion) = self.completion.take() {
+            return Ok(completion);
+        }
+
+        let clock = SharedClock::current();
+        let Some(release_at) = self.pending.as_ref().map(|(_, at)| *at) else {
+            let result = yields.scope(run, self).await;
+            return self.adapt_at(result, clock.now());
+        };
+
+        tokio::select! {
+            biased;
+            _ = clock.sleep(release_at.saturating_duration_since(clock.now())) => {
+                match self.release(clock.now()) {
+                    Some(item) => Ok(CancellationResult::Item(item)),
+                    None => Ok(CancellationResult::Continue),
+                }
+            }
+            result = yields.scope(run, self) => self.adapt_at(result, clock.now()),
+        }
+    }
+}
+
+impl<T, O, E> Adapt<T, O, E> for Held<T, O> {
+    type Item = T;
+
+    fn adapt(
+        &mut self,
+        result: Result<CancellationResult<T, O>, E>,
+    ) -> Result<CancellationResult<T, O>, E> {
+        self.adapt_at(result, SharedClock::current().now())
+    }
+}
+
+/// Service yielding the values of another service once it has stopped
+/// yielding them for a while.
+///
+/// See [`Cancellable::debounce`].
+pub struct Debounce<S>
+where
+    S: Cancellable,
+{
+    service: S,
+    adapter: Held<S::Result, S::Output>,
+    yields: Yields,
+}
+
+impl<S> Debounce<S>
+where
+    S: Cancellable,
+{
+    pub(crate) fn new(service: S, window: Duration) -> Self {
+        Self {
+            service,
+            adapter: Held::new(window, false),
+            yields: Yields::default(),
+        }
+    }
+}
+
+impl<S> Debounce<S>
+where
+    S: Cancellable,
+    S::Result: 'static,
+{
+    async fn run_held(&mut self) -> RunResult<S> {
+        self.adapter.run(self.service.run(), &mut self.yields).await
+    }
+
+    async fn drain_held(&mut self) -> RunResult<S> {
+        match self.adapter.flush() {
+            Some(result) => Ok(result),
+            None => self.service.drain().await,
+        }
+    }
+}
+
+impl<S> std::fmt::Debug for Debounce<S>
+where
+    S: Cancellable + std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Debounce")
+            .field("service", &self.service)
+            .field("window", &self.adapter.window)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> Cancellable for Debounce<S>
+where
+    S: Cancellable + Send,
+    S::Result: 'static,
+{
+    type Result = S::Result;
+    type Handle = S::Handle;
+    type Error = S::Error;
+    type Output = S::Output;
+
+    delegate!(run = run_held, drain = drain_held);
+    delegate!(@on_item_dropped);
+}
+
+/// Service yielding at most one value of another service per window.
+///
+/// See [`Cancellable::throttle`].
+pub struct Throttle<S>
+where
+    S: Cancellable,
+{
+    service: S,
+    adapter: Held<S::Result, S::Output>,
+    yields: Yields,
+}
+
+impl<S> Throttle<S>
+where
+    S: Cancellable,
+{
+    pub(crate) fn new(service: S, window: Duration) -> Self {
+        Self {
+            service,
+            adapter: Held::new(window, true),
+            yields: Yields::default(),
+        }
+    }
+}
+
+impl<S> Throttle<S>
+where
+    S: Cancellable,
+    S::Result: 'static,
+{
+    async fn run_held(&mut self) -> RunResult<S> {
+        self.adapter.run(self.service.run(), &mut self.yields).await
+    }
+
+    async fn drain_held(&mut self) -> RunResult<S> {
+        match self.adapter.flush() {
+            Some(result) => Ok(result),
+            None => self.service.drain().await,
+        }
+    }
+}
+
+impl<S> std::fmt::Debug for Throttle<S>
+where
+    S: Cancellable + std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Throttle")
+            .field("service", &self.service)
+            .field("window", &self.adapter.window)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> Cancellable for Throttle<S>
+where
+    S: Cancellable + Send,
+    S::Result: 'static,
+{
+    type Result = S::Result;
+    type Handle = S::Handle;
+    type Error = S::Error;
+    type Output = S::Output;
+
+    delegate!(run = run_held, drain = drain_held);
+    delegate!(@on_item_dropped);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use tokio::sync::oneshot;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{
+        CallbackResult, Cancellable, CancellationResult, MpscSenderHandle, RunContext, SenderHandle,
+    };
+
+    struct NumbersCancellable {
+        done: bool,
+    }
+
+    impl Cancellable for NumbersCancellable {
+        type Result = i32;
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<i32>, Self::Error> {
+            if std::mem::replace(&mut self.done, true) {
+                Ok(CancellationResult::Break)
+            } else {
+                Ok(CancellationResult::items([1, 2, 3, 4]))
+            }
+        }
+    }
+
+    struct YieldingCancellable;
+
+    impl Cancellable for YieldingCancellable {
+        type Result = i32;
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<i32>, Self::Error> {
+            let context = RunContext::current().expect("to be called by the work loop");
+            for item in 0..5 {
+                if context.yield_item(item).await.is_err() {
+                    break;
+                }
+            }
+
+            Ok(CancellationResult::Break)
+        }
+    }
+
+    struct FailingHandleCancellable;
+
+    impl Cancellable for FailingHandleCancellable {
+        type Result = i32;
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn try_new_handle(&mut self) -> Result<Self::Handle, Self::Error> {
+            Err(anyhow::anyhow!("FailingHandleCancellable error"))
+        }
+
+        async fn run(&mut self) -> Result<CancellationResult<i32>, Self::Error> {
+            Ok(CancellationResult::Break)
+        }
+    }
+
+    struct FailingCancellable;
+
+    impl Cancellable for FailingCancellable {
+        type Result = i32;
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<i32>, Self::Error> {
+            Err(anyhow::anyhow!("FailingCancellable error"))
+        }
+    }
+
+    async fn collect<S>(service: S) -> Vec<S::Result>
+    where
+        S: Cancellable + Send + 'static,
+    {
+        let items = Arc::new(Mutex::new(Vec::new()));
+        let items_clone = Arc::clone(&items);
+
+        service
+            .spawn_with_callback(CancellationToken::new(), move |item| {
+                items_clone.lock().unwrap().push(item);
+                CallbackResult::Continue
+            })
+            .await
+            .join()
+            .await
+            .unwrap();
+
+        let mut items = items.lock().unwrap();
+        std::mem::take(&mut *items)
+    }
+
+    #[tokio::test]
+    async fn should_map_yielded_values() {
+        // Arrange
+        let service = NumbersCancellable { done: false };
+
+        // Act
+        let items = collect(service.map(|item| item.to_string())).await;
+
+        // Assert
+        assert_eq!(vec!["1", "2", "3", "4"], items);
+    }
+
+    #[tokio::test]
+    async fn should_filter_yielded_values() {
+        // Arrange
+        let service = NumbersCancellable { done: false };
+
+        // Act
+        let items = collect(service.filter(|item| item % 2 == 0)).await;
+
+        // Assert
+        assert_eq!(vec![2, 4], items);
+    }
+
+    #[tokio::test]
+    async fn should_filter_map_yielded_values() {
+        // Arrange
+        let service = NumbersCancellable { done: false };
+
+        // Act
+        let items = collect(service.filter_map(|item| (item > 2).then_some(item * 10))).await;
+
+        // Assert
+        assert_eq!(vec![30, 40], items);
+    }
+
+    #[tokio::test]
+    async fn should_inspect_yielded_values() {
+        // Arrange
+        let service = NumbersCancellable { done: false };
+        let inspected = Arc::new(Mutex::new(Vec::new()));
+        let inspected_clone = Arc::clone(&inspected);
+
+        // Act
+        let items = collect(service.inspect(move |item| {
+            inspected_clone.lock().unwrap().push(*item);
+        }))
+        .await;
+
+        // Assert
+        assert_eq!(vec![1, 2, 3, 4], items);
+        assert_eq!(items, *inspected.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn should_inspect_returned_errors() {
+        // Arrange
+        let service = FailingCancellable;
+        let inspected = Arc::new(Mutex::new(Vec::new()));
+        let inspected_clone = Arc::clone(&inspected);
+
+        // Act
+        let handle = service
+            .inspect_err(move |e| inspected_clone.lock().unwrap().push(e.to_string()))
+            .spawn(CancellationToken::new())
+            .await;
+
+        // Assert
+        assert!(handle.join().await.is_err());
+        assert_eq!(vec!["FailingCancellable error"], *inspected.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn should_return_error_of_adapted_handle() {
+        // Arrange
+        let service = FailingHandleCancellable;
+
+        // Act
+        let result = service
+            .map(|item| item * 10)
+            .try_spawn(CancellationToken::new())
+            .await;
+
+        // Assert
+        let Err(error) = result else {
+            panic!("handle construction to fail");
+        };
+        assert_eq!("FailingHandleCancellable error", error.to_string());
+    }
+
+    #[tokio::test]
+    async fn should_take_yielded_values() {
+        // Arrange
+        let service = NumbersCancellable { done: false };
+
+        // Act
+        let items = collect(service.take(3)).await;
+
+        // Assert
+        assert_eq!(vec![1, 2, 3], items);
+    }
+
+    #[tokio::test]
+    async fn should_map_values_yielded_mid_iteration() {
+        // Arrange
+        let service = YieldingCancellable;
+
+        // Act
+        let items = collect(service.map(|item| item * 10)).await;
+
+        // Assert
+        assert_eq!(vec![0, 10, 20, 30, 40], items);
+    }
+
+    #[tokio::test]
+    async fn should_filter_and_take_values_yielded_mid_iteration() {
+        // Arrange
+        let service = YieldingCancellable;
+
+        // Act
+        let items = collect(service.filter(|item| item % 2 == 0).take(2)).await;
+
+        // Assert
+        assert_eq!(vec![0, 2], items);
+    }
+
+    #[tokio::test]
+    async fn should_stop_the_service_yielding_once_taken() {
+        // Arrange
+        let service = YieldingCancellable;
+
+        // Act
+        let items = collect(
+            service
+                .filter(|item| item % 2 == 0)
+                .take(1)
+                .map(|item| item + 1),
+        )
+        .await;
+
+        // Assert
+        assert_eq!(vec![1], items);
+    }
+
+    #[tokio::test]
+    async fn should_complete_when_until_completes() {
+        // Arrange
+        let (sender, receiver) = oneshot::channel::<()>();
+        let service = crate::from_stream(futures::stream::pending::<i32>()).take_until(receiver);
+        let handle = service.spawn(CancellationToken::new()).await;
+
+        // Act
+        sender.send(()).unwrap();
+
+        // Assert
+        assert!(handle.join().await.is_ok());
+    }
+
+    /// Sends `bursts` of numbers to `service`, sleeping for the given time
+    /// after each of them, and collects the values passed to the callback.
+    async fn collect_bursts<S>(service: S, bursts: &[(&[i32], u64)]) -> Vec<i32>
+    where
+        S: Cancellable<Result = i32, Handle = MpscSenderHandle<i32>> + Send + 'static,
+    {
+        let items = Arc::new(Mutex::new(Vec::new()));
+        let items_clone = Arc::clone(&items);
+        let handle = service
+            .spawn_with_callback(CancellationToken::new(), move |item| {
+                items_clone.lock().unwrap().push(item);
+                CallbackResult::Continue
+            })
+            .await;
+
+        for (burst, millis) in bursts {
+            for number in *burst {
+                handle.send(*number).await.unwrap();
+            }
+            tokio::time::sleep(Duration::from_millis(*millis)).await;
+        }
+        handle.close();
+        handle.join().await.unwrap();
+
+        let mut items = items.lock().unwrap();
+        std::mem::take(&mut *items)
+    }
+
+    fn numbers() -> impl Cancellable<Result = i32, Handle = MpscSenderHandle<i32>> + 'static {
+        crate::from_channel(
+            16,
+            |number: i32| async move { Ok::<_, anyhow::Error>(number) },
+        )
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn should_debounce_yielded_values() {
+        // Arrange
+        let service = numbers().debounce(Duration::from_millis(100));
+
+        // Act
+        let items = collect_bursts(service, &[(&[1, 2], 150), (&[3], 50), (&[4], 0)]).await;
+
+        // Assert
+        assert_eq!(vec![2, 4], items);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn should_throttle_yielded_values() {
+        // Arrange
+        let service = numbers().throttle(Duration::from_millis(100));
+
+        // Act
+        let items = collect_bursts(service, &[(&[1, 2, 3], 150), (&[4], 100), (&[5], 0)]).await;
+
+        // Assert
+        assert_eq!(vec![1, 3, 4, 5], items);
+    }
+}
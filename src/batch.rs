@@ -0,0 +1,95 @@
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+
+use crate::{clock::SharedClock, CallbackResult};
+
+/// Verdict of the batched callback, handed over to the work loop.
+type Verdict<E> = Arc<Mutex<Option<CallbackResult<E>>>>;
+
+/// Splits the batched `callback` into a per-item callback passed to the work
+/// loop and a future delivering the batches.
+///
+/// The future completes once the per-item callback has been dropped and the
+/// last batch has been delivered.
+pub(crate) fn batched<T, E, F>(
+    batch_size: usize,
+    max_delay: Duration,
+    clock: SharedClock,
+    callback: F,
+) -> (
+    impl FnMut(T) -> CallbackResult<E, T> + Send + 'static,
+    impl Future<Output = ()> + Send + 'static,
+)
+where
+    T: Send + 'static,
+    E: Send + 'static,
+    F: FnMut(Vec<T>) -> CallbackResult<E> + Send + 'static,
+{
+    assert!(batch_size > 0, "batch size must be positive");
+
+    let (sender, receiver) = unbounded_channel();
+    let verdict: Verdict<E> = Arc::new(Mutex::new(None));
+
+    let batches = deliver(
+        receiver,
+        batch_size,
+        max_delay,
+        clock,
+        callback,
+        Arc::clone(&verdict),
+    );
+    let item_callback = move |item| {
+        if let Some(verdict) = verdict.lock().unwrap().take() {
+            return verdict.widen();
+        }
+
+        match sender.send(item) {
+            Ok(()) => CallbackResult::Continue,
+            Err(_) => CallbackResult::Break,
+        }
+    };
+
+    (item_callback, batches)
+}
+
+/// Delivers the items of `receiver` to `callback` in batches.
+async fn deliver<T, E, F>(
+    mut receiver: UnboundedReceiver<T>,
+    batch_size: usize,
+    max_delay: Duration,
+    clock: SharedClock,
+    mut callback: F,
+    verdict: Verdict<E>,
+) where
+    F: FnMut(Vec<T>) -> CallbackResult<E>,
+{
+    while let Some(item) = receiver.recv().await {
+        let mut batch = Vec::with_capacity(batch_size);
+        batch.push(item);
+
+        let mut deadline = clock.sleep(max_delay);
+        while batch.len() < batch_size {
+            tokio::select! {
+                item = receiver.recv() => match item {
+                    Some(item) => batch.push(item),
+                    None => break,
+                },
+                _ = &mut deadline => break,
+            }
+        }
+
+        match callback(batch) {
+            CallbackResult::Continue => {}
+            result => {
+                *verdict.lock().unwrap() = Some(result);
+                receiver.close();
+                return;
+            }
+        }
+    }
+}
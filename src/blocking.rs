@@ -0,0 +1,215 @@
+use tokio::task::JoinHandle;
+
+use crate::{Cancellable, CancellationResult, RunContext};
+
+/// Result of a single call to [`BlockingCancellable::run`].
+type RunResult<S> = Result<
+    CancellationResult<<S as BlockingCancellable>::Result, <S as BlockingCancellable>::Output>,
+    <S as BlockingCancellable>::Error,
+>;
+
+/// Defines an interface for a service whose units of work are CPU-bound or
+/// block the current thread, e.g. image processing.
+///
+/// Unlike [`Cancellable::run`], [`Self::run`] is a regular function. The
+/// service is wrapped with [`Blocking`] to be spawned.
+pub trait BlockingCancellable: Send + 'static {
+    /// Type of values that _can_ be yielded by the service.
+    ///
+    /// See [`Cancellable::Result`].
+    type Result: Send + 'static;
+
+    /// Error returned by [`Self::run`] method.
+    ///
+    /// See [`Cancellable::Error`].
+    type Error: std::fmt::Debug + std::fmt::Display + Send + 'static;
+
+    /// Type of the final value the service _can_ complete with.
+    ///
+    /// See [`Cancellable::Output`].
+    type Output: std::fmt::Debug + Send + 'static;
+
+    /// Performs a single unit of work.
+    ///
+    /// The work loop checks the service's cancellation between the calls. A
+    /// long unit of work can check it on its own with
+    /// [`RunContext::is_cancelled`].
+    ///
+    /// See [`Cancellable::run`].
+    fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error>;
+}
+
+/// Service performing the units of work of a [`BlockingCancellable`] on the
+/// runtime's blocking threads.
+///
+/// Each call to [`BlockingCancellable::run`] is moved to a blocking thread
+/// with [`tokio::task::spawn_blocking`], so it doesn't block the
+/// asynchronous tasks of the runtime. A call cannot be interrupted, so if the
+/// service is cancelled, or its iteration times out, in the middle of a call,
+/// then the call completes in the background. In the latter case, the next
+/// iteration waits for it, instead of starting a new one.
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{
+///     Blocking, BlockingCancellable, Cancellable, CancellationResult, CancellationToken,
+///     RunContext,
+/// };
+///
+/// struct Thumbnailer {
+///     images: Vec<Vec<u8>>,
+/// }
+///
+/// impl BlockingCancellable for Thumbnailer {
+///     type Result = Vec<u8>;
+///     type Error = std::io::Error;
+///     type Output = ();
+///
+///     fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+///         let Some(image) = self.images.pop() else {
+///             return Ok(CancellationResult::Break);
+///         };
+///
+///         let context = RunContext::current().expect("to be called by the work loop");
+///         let mut thumbnail = Vec::new();
+///         for row in image.chunks(64) {
+///             if context.is_cancelled() {
+///                 return Ok(CancellationResult::Cancelled);
+///             }
+///             thumbnail.push(row[0]);
+///         }
+///
+///         Ok(CancellationResult::item(thumbnail))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let service = Blocking::new(Thumbnailer { images: vec![vec![0; 4096]] });
+/// let handle = service.spawn(CancellationToken::new()).await;
+/// # }
+/// ```
+pub struct Blocking<S>
+where
+    S: BlockingCancellable,
+{
+    service: Option<S>,
+    in_flight: Option<JoinHandle<(S, RunResult<S>)>>,
+}
+
+impl<S> Blocking<S>
+where
+    S: BlockingCancellable,
+{
+    /// Constructs a service performing the units of work of `service` on the
+    /// runtime's blocking threads.
+    pub fn new(service: S) -> Self {
+        Self {
+            service: Some(service),
+            in_flight: None,
+        }
+    }
+}
+
+impl<S> std::fmt::Debug for Blocking<S>
+where
+    S: BlockingCancellable,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Blocking")
+            .field("in_flight", &self.in_flight.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> Cancellable for Blocking<S>
+where
+    S: BlockingCancellable,
+{
+    type Result = S::Result;
+    type Handle = ();
+    type Error = S::Error;
+    type Output = S::Output;
+
+    fn name(&self) -> &str {
+        std::any::type_name::<S>()
+    }
+
+    async fn new_handle(&mut self) -> Self::Handle {}
+
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        let in_flight = match self.in_flight.as_mut() {
+            Some(in_flight) => in_flight,
+            None => {
+                let mut service = self
+                    .service
+                    .take()
+                    .expect("service to be returned by the previous call");
+                let context = RunContext::current();
+                let unit = move || {
+                    let result = match context {
+                        Some(context) => context.enter_sync(|| service.run()),
+                        None => service.run(),
+                    };
+                    (service, result)
+                };
+                self.in_flight.insert(tokio::task::spawn_blocking(unit))
+            }
+        };
+
+        let joined = in_flight.await;
+        self.in_flight = None;
+        match joined {
+            Ok((service, result)) => {
+                self.service = Some(service);
+                result
+            }
+            Err(e) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            Err(e) => panic!("blocking call has been aborted: {e}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{Blocking, BlockingCancellable, Cancellable, CancellationResult};
+
+    struct SummingCancellable {
+        numbers: std::vec::IntoIter<u64>,
+    }
+
+    impl BlockingCancellable for SummingCancellable {
+        type Result = u64;
+        type Error = anyhow::Error;
+        type Output = ();
+
+        fn run(&mut self) -> Result<CancellationResult<u64>, Self::Error> {
+            let context = crate::RunContext::current().unwrap();
+            assert!(!context.is_cancelled());
+
+            match self.numbers.next() {
+                Some(n) => Ok(CancellationResult::item((1..=n).sum::<u64>())),
+                None => Ok(CancellationResult::Break),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn should_run_units_on_blocking_threads() {
+        // Arrange
+        let service = Blocking::new(SummingCancellable {
+            numbers: vec![1, 10, 100].into_iter(),
+        });
+
+        // Act
+        let (handle, items) = service.spawn_stream(CancellationToken::new()).await;
+
+        // Assert
+        assert_eq!(vec![1, 55, 5050], items.collect::<Vec<_>>().await);
+        assert!(handle.join().await.is_ok());
+    }
+}
@@ -0,0 +1,130 @@
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Policy deciding what a [`Subscription`] does when it lags behind the
+/// service.
+///
+/// A subscription lags when the service yields more values than the capacity
+/// of the broadcast channel before the subscriber receives them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LagPolicy {
+    /// Skips the values that have been missed and continues with the oldest
+    /// value still available.
+    #[default]
+    Skip,
+
+    /// Ends the subscription.
+    Close,
+}
+
+/// Handle for subscribing to the values yielded by a service spawned with
+/// [`Cancellable::spawn_broadcast`].
+///
+/// [`Cancellable::spawn_broadcast`]: crate::Cancellable::spawn_broadcast
+#[derive(Debug)]
+pub struct SubscriberHandle<T> {
+    receiver: broadcast::Receiver<T>,
+}
+
+impl<T> SubscriberHandle<T>
+where
+    T: Clone,
+{
+    pub(crate) fn new(receiver: broadcast::Receiver<T>) -> Self {
+        Self { receiver }
+    }
+
+    /// Subscribes to the values yielded by the service from now on.
+    pub fn subscribe(&self, policy: LagPolicy) -> Subscription<T> {
+        Subscription {
+            receiver: self.receiver.resubscribe(),
+            policy,
+            closed: false,
+        }
+    }
+}
+
+impl<T> Clone for SubscriberHandle<T>
+where
+    T: Clone,
+{
+    fn clone(&self) -> Self {
+        Self::new(self.receiver.resubscribe())
+    }
+}
+
+/// Subscription to the values yielded by a service.
+///
+/// See [`SubscriberHandle::subscribe`].
+#[derive(Debug)]
+pub struct Subscription<T> {
+    receiver: broadcast::Receiver<T>,
+    policy: LagPolicy,
+    closed: bool,
+}
+
+impl<T> Subscription<T>
+where
+    T: Clone,
+{
+    /// Receives the next value yielded by the service.
+    ///
+    /// Returns `None` once the service has completed, or once the
+    /// subscription has lagged with [`LagPolicy::Close`].
+    pub async fn recv(&mut self) -> Option<T> {
+        if self.closed {
+            return None;
+        }
+
+        loop {
+            match self.receiver.recv().await {
+                Ok(value) => return Some(value),
+                Err(RecvError::Lagged(_)) if self.policy == LagPolicy::Skip => continue,
+                Err(_) => {
+                    self.closed = true;
+                    return None;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::broadcast;
+
+    use crate::{LagPolicy, SubscriberHandle};
+
+    #[tokio::test]
+    async fn should_skip_missed_values() {
+        // Arrange
+        let (sender, receiver) = broadcast::channel(2);
+        let handle = SubscriberHandle::new(receiver);
+        let mut subscription = handle.subscribe(LagPolicy::Skip);
+
+        // Act
+        for value in 0..4 {
+            sender.send(value).unwrap();
+        }
+
+        // Assert
+        assert_eq!(Some(2), subscription.recv().await);
+        assert_eq!(Some(3), subscription.recv().await);
+    }
+
+    #[tokio::test]
+    async fn should_close_lagged_subscription() {
+        // Arrange
+        let (sender, receiver) = broadcast::channel(2);
+        let handle = SubscriberHandle::new(receiver);
+        let mut subscription = handle.subscribe(LagPolicy::Close);
+
+        // Act
+        for value in 0..4 {
+            sender.send(value).unwrap();
+        }
+
+        // Assert
+        assert_eq!(None, subscription.recv().await);
+        assert_eq!(None, subscription.recv().await);
+    }
+}
@@ -0,0 +1,84 @@
+use std::any::Any;
+
+/// What's done when the callback of a service panics.
+///
+/// See [`SpawnOptions::callback_panic`].
+///
+/// [`SpawnOptions::callback_panic`]: crate::SpawnOptions::callback_panic
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CallbackPanicPolicy {
+    /// The panic unwinds the service's task, so joining the service fails
+    /// with [`CancellableError::Panicked`].
+    ///
+    /// [`CancellableError::Panicked`]: crate::CancellableError::Panicked
+    #[default]
+    Propagate,
+
+    /// The value the callback has panicked on is skipped, and the service
+    /// keeps running.
+    Skip,
+
+    /// The service is cancelled with [`CallbackPanicked`] as the reason, so it
+    /// shuts down the way it does when it's cancelled, e.g. it drains if the
+    /// graceful shutdown is enabled.
+    ///
+    /// The callback isn't called anymore, so the rest of the values, e.g. of
+    /// the same [`CancellationResult::Items`], or the ones yielded while
+    /// draining, are dropped, see [`SpawnOptions::dead_letters`].
+    ///
+    /// [`CancellationResult::Items`]: crate::CancellationResult#variant.Items
+    /// [`SpawnOptions::dead_letters`]: crate::SpawnOptions::dead_letters
+    Cancel,
+}
+
+/// Reason of the cancellation of a service whose callback has panicked.
+///
+/// See [`CallbackPanicPolicy::Cancel`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallbackPanicked {
+    message: Option<String>,
+}
+
+impl CallbackPanicked {
+    pub(crate) fn new(panic: &(dyn Any + Send)) -> Self {
+        Self {
+            message: panic_message(panic).map(str::to_owned),
+        }
+    }
+
+    /// Returns the message the callback has panicked with, if it was a string.
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+}
+
+/// Returns the message of a panic's payload, if it's a string.
+pub(crate) fn panic_message(panic: &(dyn Any + Send)) -> Option<&str> {
+    panic
+        .downcast_ref::<&'static str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CallbackPanicked;
+
+    #[test]
+    fn should_keep_message_of_string_payloads_only() {
+        // Arrange
+        let literal: Box<dyn std::any::Any + Send> = Box::new("literal");
+        let formatted: Box<dyn std::any::Any + Send> = Box::new(format!("formatted {}", 1));
+        let other: Box<dyn std::any::Any + Send> = Box::new(1);
+
+        // Act
+        let literal = CallbackPanicked::new(literal.as_ref());
+        let formatted = CallbackPanicked::new(formatted.as_ref());
+        let other = CallbackPanicked::new(other.as_ref());
+
+        // Assert
+        assert_eq!(Some("literal"), literal.message());
+        assert_eq!(Some("formatted 1"), formatted.message());
+        assert_eq!(None, other.message());
+    }
+}
@@ -0,0 +1,53 @@
+use std::convert::Infallible;
+
+/// Result of passing a single yielded value to the callback.
+///
+/// Callbacks which get the yielded values by value, e.g. the one of
+/// [`Cancellable::spawn_with_options`], can hand a value back with
+/// [`Self::Reject`]. Elsewhere `T` is [`Infallible`], so no value can be
+/// rejected.
+///
+/// [`Cancellable::spawn_with_options`]: crate::Cancellable::spawn_with_options
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CallbackResult<E, T = Infallible> {
+    /// Indicates that the value has been consumed and the loop should
+    /// continue.
+    Continue,
+
+    /// Indicates that the consumer has shut down and the loop should end.
+    ///
+    /// The service completes as if it has returned
+    /// [`CancellationResult::Break`].
+    ///
+    /// [`CancellationResult::Break`]: crate::CancellationResult#variant.Break
+    Break,
+
+    /// Indicates that the consumer has failed and the loop should end.
+    ///
+    /// The service completes with the wrapped error.
+    Fail(E),
+
+    /// Indicates that the consumer cannot process the value, and the loop
+    /// should continue.
+    ///
+    /// The value is diverted to the dead letters, see
+    /// [`SpawnOptions::dead_letters`], or passed to
+    /// [`Cancellable::on_item_dropped`] if there are none.
+    ///
+    /// [`SpawnOptions::dead_letters`]: crate::SpawnOptions::dead_letters
+    /// [`Cancellable::on_item_dropped`]: crate::Cancellable::on_item_dropped
+    Reject(T),
+}
+
+impl<E> CallbackResult<E> {
+    /// Converts a result which cannot reject a value, into one of callbacks
+    /// which can.
+    pub(crate) fn widen<T>(self) -> CallbackResult<E, T> {
+        match self {
+            Self::Continue => CallbackResult::Continue,
+            Self::Break => CallbackResult::Break,
+            Self::Fail(e) => CallbackResult::Fail(e),
+            Self::Reject(never) => match never {},
+        }
+    }
+}
@@ -0,0 +1,74 @@
+use std::{
+    any::Any,
+    sync::{Arc, OnceLock},
+};
+
+/// Slot for the reason of a service's cancellation, shared by its handle and
+/// its work loop.
+pub(crate) type ReasonSlot = Arc<OnceLock<CancelReason>>;
+
+/// Reason of a service's cancellation.
+///
+/// The reason can be of any type, e.g. an application-specific enum. It's
+/// provided with [`CancellableHandle::cancel_with_reason`] and observed from
+/// within the service with [`RunContext::cancel_reason`], e.g. in
+/// [`Cancellable::on_cancel`] or [`Cancellable::drain`].
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::CancelReason;
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Shutdown {
+///     Graceful,
+///     Fatal(String),
+/// }
+///
+/// let reason = CancelReason::new(Shutdown::Fatal("disk is full".into()));
+///
+/// assert!(reason.is::<Shutdown>());
+/// assert_eq!(
+///     Some(&Shutdown::Fatal("disk is full".into())),
+///     reason.downcast_ref::<Shutdown>(),
+/// );
+/// ```
+///
+/// [`CancellableHandle::cancel_with_reason`]: crate::CancellableHandle::cancel_with_reason
+/// [`RunContext::cancel_reason`]: crate::RunContext::cancel_reason
+/// [`Cancellable::on_cancel`]: crate::Cancellable::on_cancel
+/// [`Cancellable::drain`]: crate::Cancellable::drain
+#[derive(Clone)]
+pub struct CancelReason(Arc<dyn Any + Send + Sync>);
+
+impl CancelReason {
+    /// Constructs a new reason.
+    pub fn new<R>(reason: R) -> Self
+    where
+        R: Any + Send + Sync,
+    {
+        Self(Arc::new(reason))
+    }
+
+    /// Checks if the reason is of type `R`.
+    pub fn is<R>(&self) -> bool
+    where
+        R: Any,
+    {
+        self.0.is::<R>()
+    }
+
+    /// Returns a reference to the reason, if it's of type `R`.
+    pub fn downcast_ref<R>(&self) -> Option<&R>
+    where
+        R: Any,
+    {
+        self.0.downcast_ref()
+    }
+}
+
+impl std::fmt::Debug for CancelReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CancelReason")
+    }
+}
@@ -1,270 +1,2773 @@
-use async_trait::async_trait;
+use std::{any::Any, future::Future, hash::Hash, sync::Arc, time::Duration};
+
+use tokio::{
+    runtime::Handle,
+    sync::{
+        broadcast,
+        mpsc::{self, error::SendError, unbounded_channel},
+        oneshot, watch,
+    },
+    time::Instant,
+};
 use tokio_util::sync::CancellationToken;
 
-use crate::{cancellation_result::CancellationResult, CancellableHandle};
+use crate::{
+    ack::AckTracker,
+    adapters::{Debounce, Filter, FilterMap, Inspect, InspectErr, Map, Take, TakeUntil, Throttle},
+    batch::batched,
+    cancellable_handle::StateSlot,
+    cancellation_result::CancellationResult,
+    deadline::Deadline,
+    error_budget::ErrorBudget,
+    in_flight::InFlight,
+    work_loop::{Tracking, WorkLoop},
+    Ack, CallbackResult, CancellableHandle, ErrorDirective, ItemStream, LatestHandle, PipeHandle,
+    RestartPolicy, Router, SenderHandle, SpawnOptions, SubscriberHandle,
+};
+
+/// Defines an interface for a cancellable service with an optional callback.
+///
+/// The asynchronous methods of this trait are declared as returning
+/// `impl Future + Send`, so they can be implemented with plain `async fn`s.
+/// The returned futures must be [`Send`], because the service is driven by a
+/// task spawned onto the [`tokio`] runtime, hence the service itself must be
+/// [`Send`] as well. See [`LocalCancellable`] for services which aren't.
+///
+/// [`LocalCancellable`]: crate::LocalCancellable
+pub trait Cancellable: Send {
+    /// Type of values that _can_ be yielded by the service.
+    type Result: Send;
+
+    /// Type of a handle for communicating with the service.
+    type Handle;
+
+    /// Error returned by [`Self::run`] method.
+    ///
+    /// A service which cannot fail should use [`std::convert::Infallible`],
+    /// so it can be joined with [`CancellableHandle::join_infallible`].
+    type Error: std::fmt::Debug + std::fmt::Display + Send;
+
+    /// Type of the final value the service _can_ complete with.
+    ///
+    /// The value is returned by [`Self::run`] via
+    /// [`CancellationResult::BreakWith`] and it's the output of the service's
+    /// [`CancellableHandle`].
+    ///
+    /// [`CancellationResult::BreakWith`]: crate::CancellationResult#variant.BreakWith
+    type Output: std::fmt::Debug + Send;
+
+    /// Performs a single unit of work.
+    ///
+    /// The return value of this method controls whether the service will
+    /// continue to loop. If the returned value is either
+    /// `Ok(CancellableResult::Break)`, `Ok(CancellableResult::BreakWith(_))` or
+    /// `Err(Self::Error)`, then the service will complete.
+    ///
+    /// See [CancellationResult::Break].
+    ///
+    /// # Returns
+    ///
+    /// Returned value controls whether the work loop will continue. If the
+    /// returned value is `Err(Self::Error)` or `Ok(CancellationResult::Break)`,
+    /// then the work loop finishes. The method can optionally yield a value by
+    /// returning [`CancellationResult::Item`].
+    ///
+    /// [CancellableResult::Break]: crate::CancellationResult#variant.Break
+    /// [`CancellationResult::Item`]: crate::CancellationResult#variant.Item
+    fn run(
+        &mut self,
+    ) -> impl Future<Output = Result<CancellationResult<Self::Result, Self::Output>, Self::Error>> + Send;
+
+    /// Returns the name of the service.
+    ///
+    /// The name is used to identify the service in diagnostics. The default
+    /// implementation returns the name of the type implementing this trait.
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Constructs a new handle for communicating with the service.
+    ///
+    /// This method is intended to be called only once. If it's called more than
+    /// once, then the behavior is undefined. Services whose handle implements
+    /// [`Clone`] can hand out additional handles with
+    /// [`CancellableHandle::clone_handle`].
+    ///
+    /// Services whose handle construction can fail should also implement
+    /// [`Self::try_new_handle`], which is used by [`Self::try_spawn`].
+    fn new_handle(&mut self) -> impl Future<Output = Self::Handle> + Send;
+
+    /// Constructs a new handle for communicating with the service, if it's
+    /// possible.
+    ///
+    /// It's the fallible counterpart of [`Self::new_handle`], used by
+    /// [`Self::try_spawn`] and [`Self::try_spawn_with_options`]. The default
+    /// implementation calls [`Self::new_handle`], so it never fails.
+    fn try_new_handle(&mut self) -> impl Future<Output = Result<Self::Handle, Self::Error>> + Send {
+        async { Ok(self.new_handle().await) }
+    }
+
+    /// Called once, before the first call to [`Self::run`].
+    ///
+    /// If this method returns `Err(Self::Error)`, then the service completes
+    /// with the same error without ever calling [`Self::run`].
+    fn on_start(&mut self) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        async { Ok(()) }
+    }
+
+    /// Called once, after the work loop has completed without an error.
+    ///
+    /// This method is called both when the service completes on its own and
+    /// when it has been cancelled. In the latter case it's called after
+    /// [`Self::on_cancel`].
+    fn on_stop(&mut self) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Called once, when the service has been cancelled.
+    fn on_cancel(&mut self) -> impl Future<Output = ()> + Send {
+        async {}
+    }
+
+    /// Returns the final value of the service once it has been cancelled.
+    ///
+    /// This method is called once, after [`Self::on_cancel`], unless the
+    /// service has completed with a value while draining, see
+    /// [`Self::drain`]. It allows the service to report what it has collected
+    /// so far, e.g. the exit status of a child process it has terminated. The
+    /// default implementation returns `None`.
+    fn cancelled_output(&mut self) -> Option<Self::Output> {
+        None
+    }
+
+    /// Performs a single unit of work after the service has been cancelled.
+    ///
+    /// This method is called only if graceful shutdown has been enabled with
+    /// [`SpawnOptions::graceful_shutdown`]. It's called repetitively until it
+    /// returns `Ok(CancellationResult::Break)` or `Err(Self::Error)`, which
+    /// allows the service to process the work it has already accepted. The
+    /// default implementation breaks immediately.
+    fn drain(
+        &mut self,
+    ) -> impl Future<Output = Result<CancellationResult<Self::Result, Self::Output>, Self::Error>> + Send
+    {
+        async { Ok(CancellationResult::Break) }
+    }
+
+    /// Called when a single call to [`Self::run`] has exceeded the iteration
+    /// timeout.
+    ///
+    /// This method is called only if the iteration timeout has been set with
+    /// [`SpawnOptions::iteration_timeout`]. Its return value is handled as if
+    /// it has been returned by [`Self::run`], which allows the service to
+    /// decide whether a stuck iteration should be skipped, end the service, or
+    /// fail it. The default implementation skips the iteration.
+    fn on_timeout(
+        &mut self,
+    ) -> impl Future<Output = Result<CancellationResult<Self::Result, Self::Output>, Self::Error>> + Send
+    {
+        async { Ok(CancellationResult::Continue) }
+    }
+
+    /// Called when a single call to [`Self::run`] has panicked.
+    ///
+    /// The method receives the panic's payload. Its return value is handled as
+    /// if it has been returned by [`Self::run`], which allows the service to
+    /// release its resources and convert the panic into `Self::Error`. The
+    /// default implementation resumes the panic, so the service's handle
+    /// resolves to [`JoinError`].
+    ///
+    /// [`JoinError`]: tokio::task::JoinError
+    fn on_panic(
+        &mut self,
+        panic: Box<dyn Any + Send>,
+    ) -> impl Future<Output = Result<CancellationResult<Self::Result, Self::Output>, Self::Error>> + Send
+    {
+        async move { std::panic::resume_unwind(panic) }
+    }
+
+    /// Called when [`Self::run`] has returned an error.
+    ///
+    /// The returned directive decides whether the error is recoverable, e.g.
+    /// a single malformed message, and can be skipped, or whether it fails
+    /// the service. The default implementation fails the service with the
+    /// error.
+    ///
+    /// See [`ErrorDirective`].
+    fn on_error(
+        &mut self,
+        error: Self::Error,
+    ) -> impl Future<Output = ErrorDirective<Self::Error>> + Send {
+        async { ErrorDirective::Fail(error) }
+    }
+
+    /// Prepares the service to be run again after [`Self::run`] has failed.
+    ///
+    /// This method is called only if a restart policy has been set with
+    /// [`SpawnOptions::restart_policy`]. It receives the error returned by
+    /// [`Self::run`]. If it returns `Err(Self::Error)`, then the service
+    /// completes with the returned error. The default implementation discards
+    /// the error and leaves the service as is.
+    fn restart(
+        &mut self,
+        error: Self::Error,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send {
+        let _ = error;
+        async { Ok(()) }
+    }
+
+    /// Called with a value yielded by the service, which won't be passed to
+    /// the callback.
+    ///
+    /// A value is dropped only if the callback has already broken the loop,
+    /// e.g. it's the rest of [`CancellationResult::Items`], or it has been
+    /// yielded with [`RunContext::yield_item`] in the meantime, or if the
+    /// callback hasn't acknowledged it, see [`Self::spawn_with_acks`]. The
+    /// values of an iteration which has completed by the time the service is
+    /// cancelled are still passed to the callback. The default implementation
+    /// drops the value.
+    ///
+    /// [`CancellationResult::Items`]: crate::CancellationResult#variant.Items
+    /// [`RunContext::yield_item`]: crate::RunContext::yield_item
+    fn on_item_dropped(&mut self, item: Self::Result) {
+        let _ = item;
+    }
+
+    /// Transforms the values yielded by the service with `f`, before they're
+    /// passed to the callback.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use cancellable::{Cancellable, CancellationResult};
+    /// # struct Listener;
+    /// # impl Cancellable for Listener {
+    /// #     type Result = (String, u16);
+    /// #     type Handle = ();
+    /// #     type Error = std::io::Error;
+    /// #     type Output = ();
+    /// #     async fn new_handle(&mut self) -> Self::Handle {}
+    /// #     async fn run(&mut self) -> Result<CancellationResult<Self::Result>, Self::Error> {
+    /// #         Ok(CancellationResult::Break)
+    /// #     }
+    /// # }
+    /// let ports = Listener.map(|(_host, port)| port);
+    /// ```
+    fn map<U, F>(self, f: F) -> Map<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(Self::Result) -> U,
+    {
+        Map::new(self, f)
+    }
+
+    /// Drops the values yielded by the service for which `predicate` returns
+    /// `false`, so they're never passed to the callback.
+    fn filter<P>(self, predicate: P) -> Filter<Self, P>
+    where
+        Self: Sized,
+        P: FnMut(&Self::Result) -> bool,
+    {
+        Filter::new(self, predicate)
+    }
+
+    /// Transforms the values yielded by the service with `f` and drops the
+    /// ones for which it returns `None`, before they're passed to the callback.
+    fn filter_map<U, F>(self, f: F) -> FilterMap<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(Self::Result) -> Option<U>,
+    {
+        FilterMap::new(self, f)
+    }
+
+    /// Calls `f` with a reference to every value yielded by the service,
+    /// before it's passed to the callback, e.g. to log or count the values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use cancellable::{Cancellable, CancellationResult};
+    /// # struct Listener;
+    /// # impl Cancellable for Listener {
+    /// #     type Result = (String, u16);
+    /// #     type Handle = ();
+    /// #     type Error = std::io::Error;
+    /// #     type Output = ();
+    /// #     async fn new_handle(&mut self) -> Self::Handle {}
+    /// #     async fn run(&mut self) -> Result<CancellationResult<Self::Result>, Self::Error> {
+    /// #         Ok(CancellationResult::Break)
+    /// #     }
+    /// # }
+    /// let listener = Listener
+    ///     .inspect(|(host, port)| println!("Accepted {host}:{port}."))
+    ///     .inspect_err(|e| eprintln!("Failed to accept: {e}."));
+    /// ```
+    fn inspect<F>(self, f: F) -> Inspect<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Result),
+    {
+        Inspect::new(self, f)
+    }
+
+    /// Calls `f` with a reference to every error returned by the service,
+    /// before it's handled by the work loop.
+    ///
+    /// See [`Self::inspect`].
+    fn inspect_err<F>(self, f: F) -> InspectErr<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Error),
+    {
+        InspectErr::new(self, f)
+    }
+
+    /// Completes the service once it has yielded `n` values.
+    ///
+    /// The service isn't called anymore after its `n`-th value, so at most
+    /// `n` values are passed to the callback.
+    fn take(self, n: usize) -> Take<Self>
+    where
+        Self: Sized,
+    {
+        Take::new(self, n)
+    }
+
+    /// Completes the service once `until` completes, e.g. once a
+    /// [`CancellationToken`] is cancelled.
+    ///
+    /// Unlike the service's cancellation, `until` completes the service as if
+    /// it has returned [`CancellationResult::Break`], so it isn't drained. A
+    /// call to [`Self::run`] in flight when `until` completes is dropped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use cancellable::{Cancellable, CancellationResult, CancellationToken};
+    /// # struct Listener;
+    /// # impl Cancellable for Listener {
+    /// #     type Result = (String, u16);
+    /// #     type Handle = ();
+    /// #     type Error = std::io::Error;
+    /// #     type Output = ();
+    /// #     async fn new_handle(&mut self) -> Self::Handle {}
+    /// #     async fn run(&mut self) -> Result<CancellationResult<Self::Result>, Self::Error> {
+    /// #         Ok(CancellationResult::Break)
+    /// #     }
+    /// # }
+    /// let maintenance = CancellationToken::new();
+    /// let listener = Listener.take_until(maintenance.clone().cancelled_owned());
+    /// ```
+    ///
+    /// [`CancellationResult::Break`]: crate::CancellationResult#variant.Break
+    fn take_until<F>(self, until: F) -> TakeUntil<Self, F>
+    where
+        Self: Sized,
+        F: Future,
+    {
+        TakeUntil::new(self, until)
+    }
+
+    /// Holds every value yielded by the service back until the service hasn't
+    /// yielded another one for `window`, so only the latest value of a burst
+    /// is passed to the callback.
+    ///
+    /// The held value is passed to the callback before the service completes,
+    /// or while it's draining. A call to [`Self::run`] in flight when the
+    /// held value is due is dropped, so the service's `run` should be
+    /// cancellation safe, e.g. waiting for a channel or a stream.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # use cancellable::{Cancellable, CancellationResult};
+    /// # struct ConfigWatcher;
+    /// # impl Cancellable for ConfigWatcher {
+    /// #     type Result = String;
+    /// #     type Handle = ();
+    /// #     type Error = std::io::Error;
+    /// #     type Output = ();
+    /// #     async fn new_handle(&mut self) -> Self::Handle {}
+    /// #     async fn run(&mut self) -> Result<CancellationResult<Self::Result>, Self::Error> {
+    /// #         Ok(CancellationResult::Break)
+    /// #     }
+    /// # }
+    /// use std::time::Duration;
+    ///
+    /// // Reloads the configuration once the editor is done saving it.
+    /// let watcher = ConfigWatcher.debounce(Duration::from_millis(200));
+    /// ```
+    fn debounce(self, window: Duration) -> Debounce<Self>
+    where
+        Self: Sized,
+    {
+        Debounce::new(self, window)
+    }
+
+    /// Passes at most one value yielded by the service to the callback per
+    /// `window`.
+    ///
+    /// The first value is passed right away and opens the window. The values
+    /// yielded within the window are held back, and the latest of them is
+    /// passed once the window ends, opening the next one. Just like with
+    /// [`Self::debounce`], a call to [`Self::run`] in flight at that point is
+    /// dropped.
+    fn throttle(self, window: Duration) -> Throttle<Self>
+    where
+        Self: Sized,
+    {
+        Throttle::new(self, window)
+    }
+
+    /// Tolerates up to `max_errors` errors of the service within each
+    /// `window`, instead of failing on the first one.
+    ///
+    /// Errors for which [`Self::on_error`] returns [`ErrorDirective::Fail`]
+    /// count against the budget and are skipped while it lasts. When the
+    /// budget is exceeded, the service fails with all of the errors collected
+    /// within the last `window`.
+    fn with_error_budget(self, max_errors: usize, window: Duration) -> ErrorBudget<Self>
+    where
+        Self: Sized,
+    {
+        ErrorBudget::new(self, max_errors, window)
+    }
+
+    /// Fails the service with [`DeadlineError::Elapsed`] once `deadline`
+    /// passes, unless it completes earlier.
+    ///
+    /// The deadline is raced with each call to [`Self::run`], so a call in
+    /// flight when the deadline passes is dropped. The service isn't drained,
+    /// but its handle stays usable until then.
+    ///
+    /// The deadline is measured with the service's clock. See
+    /// [`SpawnOptions::clock`].
+    ///
+    /// [`DeadlineError::Elapsed`]: crate::DeadlineError#variant.Elapsed
+    fn with_deadline(self, deadline: Instant) -> Deadline<Self>
+    where
+        Self: Sized,
+    {
+        Deadline::at(self, deadline)
+    }
+
+    /// Fails the service with [`DeadlineError::Elapsed`] once `timeout` has
+    /// elapsed since its work loop has started, unless it completes earlier.
+    ///
+    /// See [`Self::with_deadline`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use cancellable::{Cancellable, CancellableError, CancellationToken, DeadlineError};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let service = cancellable::from_stream(futures::stream::pending::<u32>())
+    ///     .with_timeout(Duration::from_millis(10));
+    ///
+    /// let handle = service.spawn(CancellationToken::new()).await;
+    /// match handle.join().await {
+    ///     Err(CancellableError::Service(DeadlineError::Elapsed)) => println!("Timed out."),
+    ///     _ => unreachable!(),
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// [`DeadlineError::Elapsed`]: crate::DeadlineError#variant.Elapsed
+    fn with_timeout(self, timeout: Duration) -> Deadline<Self>
+    where
+        Self: Sized,
+    {
+        Deadline::after_spawn(self, timeout)
+    }
+
+    /// Consumes the service and spawns its work loop.
+    ///
+    /// It's equivalent to [`Self::spawn_with_callback`] in every way, besides
+    /// the callback.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if [`Self::new_handle`] panics, e.g. when the
+    /// service's handle couldn't be constructed. Use [`Self::try_spawn`] to
+    /// handle the failure of [`Self::try_new_handle`] instead.
+    fn spawn(
+        self,
+        cancellation_token: CancellationToken,
+    ) -> impl Future<Output = CancellableHandle<Self>> + Send
+    where
+        Self: Sized + Send + 'static,
+    {
+        self.spawn_with_callback(cancellation_token, |_| CallbackResult::Continue)
+    }
+
+    /// Consumes the service and spawns its work loop, without a parent
+    /// cancellation token.
+    ///
+    /// It's equivalent to [`Self::spawn`] with a fresh token, so the service
+    /// can be cancelled only through its handle, e.g. with
+    /// [`CancellableHandle::cancel`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cancellable::Cancellable;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let handle = cancellable::from_stream(futures::stream::pending::<i32>())
+    ///     .spawn_detached()
+    ///     .await;
+    ///
+    /// handle.cancel();
+    /// assert!(handle.join().await.is_ok());
+    /// # }
+    /// ```
+    fn spawn_detached(self) -> impl Future<Output = CancellableHandle<Self>> + Send
+    where
+        Self: Sized + Send + 'static,
+    {
+        self.spawn(CancellationToken::new())
+    }
+
+    /// Consumes the service and spawns its work loop.
+    ///
+    /// It's equivalent to [`Self::spawn`], but the service is restarted
+    /// according to `policy` whenever it fails.
+    ///
+    /// See [`Self::restart`].
+    fn spawn_with_restart(
+        self,
+        cancellation_token: CancellationToken,
+        policy: RestartPolicy,
+    ) -> impl Future<Output = CancellableHandle<Self>> + Send
+    where
+        Self: Sized + Send + 'static,
+    {
+        let options = SpawnOptions::new().restart_policy(policy);
+        self.spawn_with_options(cancellation_token, options, |_| CallbackResult::Continue)
+    }
+
+    /// Consumes the service and spawns its work loop onto the runtime of
+    /// `runtime`.
+    ///
+    /// It's equivalent to [`Self::spawn`], but the service is scheduled on the
+    /// given runtime rather than on the one it's spawned from, e.g. to isolate
+    /// it from latency-sensitive services.
+    ///
+    /// See [`SpawnOptions::runtime`].
+    fn spawn_on(
+        self,
+        runtime: &Handle,
+        cancellation_token: CancellationToken,
+    ) -> impl Future<Output = CancellableHandle<Self>> + Send
+    where
+        Self: Sized + Send + 'static,
+    {
+        let options = SpawnOptions::new().runtime(runtime.clone());
+        self.spawn_with_options(cancellation_token, options, |_| CallbackResult::Continue)
+    }
+
+    /// Consumes the service and spawns its work loop onto a new thread,
+    /// driven by a current-thread runtime of its own.
+    ///
+    /// It's equivalent to [`Self::spawn_on`], but the runtime is dedicated to
+    /// the service, so the service is isolated from all other tasks, e.g.
+    /// when it occasionally blocks. The thread is named after the service and
+    /// it exits once the service completes, or its task is aborted.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the runtime or the thread cannot be created.
+    fn spawn_on_dedicated_thread(
+        self,
+        cancellation_token: CancellationToken,
+    ) -> impl Future<Output = CancellableHandle<Self>> + Send
+    where
+        Self: Sized + Send + 'static,
+    {
+        // The handle is a future itself, but it's meant to be returned, not awaited.
+        #[allow(clippy::async_yields_async)]
+        async move {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .expect("to build the service's runtime");
+            let options = SpawnOptions::new().runtime(runtime.handle().clone());
+
+            // The sender is owned by the callback, so it's dropped along with
+            // the work loop, whether the service completes or is aborted.
+            let (completed, until_completed) = oneshot::channel::<()>();
+            let callback = move |_| {
+                let _completed = &completed;
+                CallbackResult::Continue
+            };
+
+            let name = self.name().to_owned();
+            let handle = self
+                .spawn_with_options(cancellation_token, options, callback)
+                .await;
+            std::thread::Builder::new()
+                .name(name)
+                .spawn(move || {
+                    runtime.block_on(async {
+                        let _ = until_completed.await;
+                    })
+                })
+                .expect("to spawn the service's thread");
+
+            handle
+        }
+    }
+
+    /// Consumes the service and spawns its work loop.
+    ///
+    /// Instead of passing the yielded values to a callback, they are forwarded
+    /// to the returned stream. The stream ends when the service completes. If
+    /// the stream is dropped, then the service completes as soon as it yields
+    /// its next value.
+    ///
+    /// # Returns
+    ///
+    /// Handle that can be used to await for the service to complete and a
+    /// stream of values yielded by the service.
+    fn spawn_stream(
+        self,
+        cancellation_token: CancellationToken,
+    ) -> impl Future<Output = (CancellableHandle<Self>, ItemStream<Self::Result>)> + Send
+    where
+        Self: Sized + Send + 'static,
+        Self::Result: 'static,
+    {
+        self.spawn_stream_with_options(cancellation_token, SpawnOptions::default())
+    }
+
+    /// Consumes the service and spawns its work loop.
+    ///
+    /// It's equivalent to [`Self::spawn_stream`], but additionally allows to
+    /// control the behavior of the work loop with `options`. The values which
+    /// haven't been taken from the stream yet are in flight, see
+    /// [`SpawnOptions::max_in_flight`].
+    fn spawn_stream_with_options(
+        mut self,
+        cancellation_token: CancellationToken,
+        options: SpawnOptions,
+    ) -> impl Future<Output = (CancellableHandle<Self>, ItemStream<Self::Result>)> + Send
+    where
+        Self: Sized + Send + 'static,
+        Self::Result: 'static,
+    {
+        async move {
+            let (sender, receiver) = unbounded_channel();
+            let in_flight = InFlight::new();
+            let callback = {
+                let in_flight = in_flight.clone();
+                move |item| {
+                    // The value is acquired before it's sent, so the stream
+                    // cannot release it first.
+                    in_flight.acquire();
+                    match sender.send(item) {
+                        Ok(()) => CallbackResult::Continue,
+                        Err(SendError(_)) => {
+                            in_flight.release();
+                            CallbackResult::Break
+                        }
+                    }
+                }
+            };
+
+            let inner = self.new_handle().await;
+            let handle = spawn_work_loop(
+                self,
+                cancellation_token,
+                options,
+                callback,
+                inner,
+                async {},
+                Tracking::InFlight(in_flight.clone()),
+            );
+
+            (handle, ItemStream::new(receiver).with_in_flight(in_flight))
+        }
+    }
+
+    /// Consumes the service and spawns its work loop.
+    ///
+    /// Values yielded by the service are broadcast to all subscriptions of
+    /// the returned [`SubscriberHandle`] through a channel with the given
+    /// `capacity`. Subscriptions receive only the values yielded after they
+    /// have been made, and they end when the service completes.
+    ///
+    /// # Returns
+    ///
+    /// Handle that can be used to await for the service to complete and a
+    /// handle for subscribing to the values yielded by the service.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `capacity` is zero.
+    fn spawn_broadcast(
+        self,
+        cancellation_token: CancellationToken,
+        capacity: usize,
+    ) -> impl Future<Output = (CancellableHandle<Self>, SubscriberHandle<Self::Result>)> + Send
+    where
+        Self: Sized + Send + 'static,
+        Self::Result: Clone + 'static,
+    {
+        async move {
+            let (sender, receiver) = broadcast::channel(capacity);
+            let handle = self
+                .spawn_with_callback(cancellation_token, move |item| {
+                    // Values yielded while nobody is subscribed are dropped.
+                    let _ = sender.send(item);
+                    CallbackResult::Continue
+                })
+                .await;
+
+            (handle, SubscriberHandle::new(receiver))
+        }
+    }
+
+    /// Consumes the service and spawns its work loop.
+    ///
+    /// Values yielded by the service are published to the returned
+    /// [`LatestHandle`], which keeps only the latest of them. It suits
+    /// services whose consumers don't care about every value, e.g. ones
+    /// reloading a configuration.
+    ///
+    /// # Returns
+    ///
+    /// Handle that can be used to await for the service to complete and a
+    /// handle for observing the latest value yielded by the service.
+    fn spawn_watch(
+        self,
+        cancellation_token: CancellationToken,
+    ) -> impl Future<Output = (CancellableHandle<Self>, LatestHandle<Self::Result>)> + Send
+    where
+        Self: Sized + Send + 'static,
+        Self::Result: Sync + 'static,
+    {
+        async move {
+            let (sender, receiver) = watch::channel(None);
+            let handle = self
+                .spawn_with_callback(cancellation_token, move |item| {
+                    sender.send_replace(Some(item));
+                    CallbackResult::Continue
+                })
+                .await;
+
+            (handle, LatestHandle::new(receiver))
+        }
+    }
+
+    /// Consumes both services and spawns them as a pipeline.
+    ///
+    /// Values yielded by this service are sent to `downstream` through its
+    /// [`SenderHandle`]. Cancelling `cancellation_token` cancels both services
+    /// at once, whereas [`PipeHandle::cancel`] shuts the pipeline down in
+    /// order, so `downstream` is expected to complete once its handle has been
+    /// dropped.
+    ///
+    /// If `downstream` stops accepting values, then this service completes as
+    /// soon as it yields its next value.
+    fn pipe<B>(
+        self,
+        downstream: B,
+        cancellation_token: CancellationToken,
+    ) -> impl Future<Output = PipeHandle<Self, B>> + Send
+    where
+        Self: Sized + Send + 'static,
+        Self::Result: 'static,
+        B: Cancellable + Send + 'static,
+        B::Handle: SenderHandle<Item = Self::Result> + Send + 'static,
+    {
+        PipeHandle::spawn(self, downstream, cancellation_token)
+    }
+
+    /// Consumes the service and spawns its work loop.
+    ///
+    /// Schedules a new background task, that repetitively calls [`Self::run`]
+    /// and performs its work. If `cancellation_token` is cancelled, then the
+    /// service completes instantly.
+    ///
+    /// # Arguments
+    ///
+    /// * `cancellation_token` - provides a way of cancelling the service mid
+    ///   work.
+    /// * `callback` - if the service yields a new value, then it's passed to
+    ///   the callback. If the callback returns [`CallbackResult::Break`], then
+    ///   the service completes. If it returns [`CallbackResult::Fail`], then the
+    ///   service completes with the wrapped error. If it returns
+    ///   [`CallbackResult::Reject`], then the value is dropped, see
+    ///   [`SpawnOptions::dead_letters`].
+    ///
+    /// [`CallbackResult::Break`]: crate::CallbackResult#variant.Break
+    /// [`CallbackResult::Fail`]: crate::CallbackResult#variant.Fail
+    /// [`CallbackResult::Reject`]: crate::CallbackResult#variant.Reject
+    ///
+    /// # Returns
+    ///
+    /// Handle that can be used to await for the service to complete.
+    fn spawn_with_callback<F>(
+        self,
+        cancellation_token: CancellationToken,
+        callback: F,
+    ) -> impl Future<Output = CancellableHandle<Self>> + Send
+    where
+        Self: Sized + Send + 'static,
+        F: FnMut(Self::Result) -> CallbackResult<Self::Error, Self::Result> + Send + 'static,
+    {
+        self.spawn_with_options(cancellation_token, SpawnOptions::default(), callback)
+    }
+
+    /// Consumes the service and spawns its work loop.
+    ///
+    /// It's equivalent to [`Self::spawn_with_callback`], but additionally
+    /// allows to control the behavior of the work loop with `options`.
+    ///
+    /// See [`SpawnOptions`].
+    ///
+    /// # Panics
+    ///
+    /// This method panics if [`Self::new_handle`] panics, e.g. when the
+    /// service's handle couldn't be constructed. Use
+    /// [`Self::try_spawn_with_options`] to handle the failure of
+    /// [`Self::try_new_handle`] instead.
+    fn spawn_with_options<F>(
+        mut self,
+        cancellation_token: CancellationToken,
+        options: SpawnOptions,
+        callback: F,
+    ) -> impl Future<Output = CancellableHandle<Self>> + Send
+    where
+        Self: Sized + Send + 'static,
+        F: FnMut(Self::Result) -> CallbackResult<Self::Error, Self::Result> + Send + 'static,
+    {
+        // The handle is a future itself, but it's meant to be returned, not awaited.
+        #[allow(clippy::async_yields_async)]
+        async move {
+            let inner = self.new_handle().await;
+            spawn_work_loop(
+                self,
+                cancellation_token,
+                options,
+                callback,
+                inner,
+                async {},
+                Tracking::None,
+            )
+        }
+    }
+
+    /// Consumes the service and spawns its work loop.
+    ///
+    /// Values yielded by the service are accumulated and passed to the
+    /// callback in batches. A batch is delivered once it has `batch_size`
+    /// values, or once `max_delay` has elapsed since its first value was
+    /// yielded, measured with the clock of `options`, see
+    /// [`SpawnOptions::clock`]. The last, possibly incomplete, batch is
+    /// delivered before the service completes.
+    ///
+    /// If the callback doesn't return [`CallbackResult::Continue`], then the
+    /// service completes accordingly as soon as it yields its next value.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `batch_size` is zero.
+    fn spawn_with_batched_callback<F>(
+        mut self,
+        cancellation_token: CancellationToken,
+        options: SpawnOptions,
+        batch_size: usize,
+        max_delay: Duration,
+        callback: F,
+    ) -> impl Future<Output = CancellableHandle<Self>> + Send
+    where
+        Self: Sized + Send + 'static,
+        Self::Result: 'static,
+        Self::Error: 'static,
+        F: FnMut(Vec<Self::Result>) -> CallbackResult<Self::Error> + Send + 'static,
+    {
+        let (callback, batches) = batched(batch_size, max_delay, options.clock.clone(), callback);
+
+        // The handle is a future itself, but it's meant to be returned, not awaited.
+        #[allow(clippy::async_yields_async)]
+        async move {
+            let inner = self.new_handle().await;
+            spawn_work_loop(
+                self,
+                cancellation_token,
+                options,
+                callback,
+                inner,
+                batches,
+                Tracking::None,
+            )
+        }
+    }
+
+    /// Consumes the service and spawns its work loop.
+    ///
+    /// Values yielded by the service are passed to the callback wrapped in
+    /// [`Ack`], which has to be acknowledged once the value has been
+    /// processed, e.g. after an asynchronous side effect of the value has
+    /// completed elsewhere. The values dropped without being acknowledged are
+    /// passed to [`Self::on_item_dropped`], so the service can queue them
+    /// again, or diverted to the dead letters, see
+    /// [`SpawnOptions::dead_letters`].
+    ///
+    /// Once the work loop has completed, e.g. because the service has been
+    /// cancelled, the service waits until each of its values has been either
+    /// acknowledged or dropped, before it completes.
+    fn spawn_with_acks<F>(
+        mut self,
+        cancellation_token: CancellationToken,
+        options: SpawnOptions,
+        mut callback: F,
+    ) -> impl Future<Output = CancellableHandle<Self>> + Send
+    where
+        Self: Sized + Send + 'static,
+        Self::Result: 'static,
+        F: FnMut(Ack<Self::Result>) -> CallbackResult<Self::Error> + Send + 'static,
+    {
+        let acks = AckTracker::new();
+
+        // The handle is a future itself, but it's meant to be returned, not awaited.
+        #[allow(clippy::async_yields_async)]
+        async move {
+            let inner = self.new_handle().await;
+            let tracker = acks.clone();
+            spawn_work_loop(
+                self,
+                cancellation_token,
+                options,
+                move |item| callback(tracker.track(item)).widen(),
+                inner,
+                async {},
+                Tracking::Acks(acks),
+            )
+        }
+    }
+
+    /// Consumes the service and spawns its work loop.
+    ///
+    /// Values yielded by the service are dispatched by `router` to the
+    /// callbacks of their routes. See [`Router`].
+    fn spawn_with_router<K>(
+        self,
+        cancellation_token: CancellationToken,
+        router: Router<K, Self::Result, Self::Error>,
+    ) -> impl Future<Output = CancellableHandle<Self>> + Send
+    where
+        Self: Sized + Send + 'static,
+        Self::Result: 'static,
+        Self::Error: 'static,
+        K: Eq + Hash + Send + 'static,
+    {
+        let mut callback = router.into_callback();
+        self.spawn_with_callback(cancellation_token, move |item| callback(item).widen())
+    }
+
+    /// Consumes the service and spawns its work loop, unless its handle
+    /// couldn't be constructed.
+    ///
+    /// It's equivalent to [`Self::spawn`], but the handle is constructed with
+    /// [`Self::try_new_handle`], whose error is returned instead of the handle.
+    fn try_spawn(
+        self,
+        cancellation_token: CancellationToken,
+    ) -> impl Future<Output = Result<CancellableHandle<Self>, Self::Error>> + Send
+    where
+        Self: Sized + Send + 'static,
+    {
+        self.try_spawn_with_options(cancellation_token, SpawnOptions::default(), |_| {
+            CallbackResult::Continue
+        })
+    }
+
+    /// Consumes the service and spawns its work loop, unless its handle
+    /// couldn't be constructed.
+    ///
+    /// It's equivalent to [`Self::spawn_with_options`], but the handle is
+    /// constructed with [`Self::try_new_handle`], whose error is returned
+    /// instead of the handle.
+    fn try_spawn_with_options<F>(
+        mut self,
+        cancellation_token: CancellationToken,
+        options: SpawnOptions,
+        callback: F,
+    ) -> impl Future<Output = Result<CancellableHandle<Self>, Self::Error>> + Send
+    where
+        Self: Sized + Send + 'static,
+        F: FnMut(Self::Result) -> CallbackResult<Self::Error, Self::Result> + Send + 'static,
+    {
+        async move {
+            let inner = self.try_new_handle().await?;
+            Ok(spawn_work_loop(
+                self,
+                cancellation_token,
+                options,
+                callback,
+                inner,
+                async {},
+                Tracking::None,
+            ))
+        }
+    }
+}
+
+/// Spawns the work loop of `service`, whose handle is `inner`.
+///
+/// The service's task completes once both the work loop and `companion` have
+/// completed. The values passed to `callback` are tracked with `tracking`.
+fn spawn_work_loop<T, F, C>(
+    service: T,
+    cancellation_token: CancellationToken,
+    options: SpawnOptions,
+    callback: F,
+    inner: T::Handle,
+    companion: C,
+    tracking: Tracking<T::Result>,
+) -> CancellableHandle<T>
+where
+    T: Cancellable + Send + 'static,
+    F: FnMut(T::Result) -> CallbackResult<T::Error, T::Result> + Send + 'static,
+    C: Future<Output = ()> + Send + 'static,
+{
+    let inner_cancellable_token = cancellation_token.child_token();
+
+    #[cfg(feature = "tracing")]
+    let span = tracing::info_span!("service", name = service.name());
+
+    let runtime = options.runtime.clone();
+    let clock = options.clock.clone();
+    let retain_state = options.retain_state;
+    let (dead_letter_sender, dead_letter_receiver) =
+        options.dead_letters.map(mpsc::channel).unzip();
+    let mut work_loop = WorkLoop::new(service, inner_cancellable_token.clone(), options, callback)
+        .with_tracking(tracking);
+    if let Some(sender) = dead_letter_sender {
+        work_loop = work_loop.with_dead_letters(sender);
+    }
+    let health = work_loop.health();
+    let cancel_reason = work_loop.cancel_reason();
+    let completion = work_loop.completion();
+    let children = work_loop.children();
+    let stats = work_loop.stats();
+    let state = StateSlot::default();
+    let work = {
+        let state = Arc::clone(&state);
+        async move {
+            let (result, service) = work_loop.run_with_service().await;
+            if retain_state {
+                *state.lock().expect("lock not to be poisoned") = Some(Box::new(service));
+            }
+            result
+        }
+    };
+    let future = async move {
+        let (result, ()) = tokio::join!(work, companion);
+        result
+    };
+
+    #[cfg(feature = "tracing")]
+    let future = tracing::Instrument::instrument(future, span);
+
+    let join_handle = match runtime {
+        Some(runtime) => runtime.spawn(future),
+        None => tokio::spawn(future),
+    };
+
+    let handle = CancellableHandle::<T>::new(join_handle, inner_cancellable_token, inner)
+        .with_health(health)
+        .with_cancel_reason(cancel_reason)
+        .with_completion(completion)
+        .with_children(children)
+        .with_stats(stats)
+        .with_state(state)
+        .with_clock(clock);
+
+    match dead_letter_receiver {
+        Some(receiver) => handle.with_dead_letters(receiver),
+        None => handle,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use tokio::time::timeout;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{
+        clock::InstantClock, CallbackResult, Cancellable, CancellableHandle, CancellationResult,
+        ErrorDirective, Health, SpawnOptions,
+    };
+
+    struct MockCancellable {
+        flag: Arc<AtomicBool>,
+    }
+
+    impl MockCancellable {
+        fn new(flag: bool) -> Self {
+            Self {
+                flag: Arc::new(AtomicBool::new(flag)),
+            }
+        }
+    }
+
+    impl Cancellable for MockCancellable {
+        type Result = Arc<AtomicBool>;
+        type Handle = ();
+        type Error = std::io::Error;
+        type Output = ();
+
+        async fn run(
+            &mut self,
+        ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+            let () = std::future::pending().await;
+            self.flag.store(true, Ordering::Relaxed);
+            Ok(CancellationResult::Break)
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+    }
+
+    #[tokio::test]
+    async fn should_finish_when_cancelled() {
+        // Arrange
+        let flag = Arc::new(AtomicBool::new(false));
+        let flag_clone = Arc::clone(&flag);
+
+        let cancellable = MockCancellable::new(false);
+        let cancellation_token = CancellationToken::new();
+
+        // Act
+        let handle = cancellable
+            .spawn_with_callback(cancellation_token.clone(), move |_| {
+                flag_clone.store(true, Ordering::SeqCst);
+                CallbackResult::Continue
+            })
+            .await;
+
+        cancellation_token.cancel();
+
+        // Assert
+        assert!(!flag.load(Ordering::SeqCst));
+        handle.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_cancel_detached_service_through_handle() {
+        // Arrange
+        let handle = MockCancellable::new(false).spawn_detached().await;
+
+        // Act
+        handle.cancel();
+
+        // Assert
+        let result = timeout(Duration::from_secs(1), handle.join()).await;
+        assert!(result.unwrap().is_ok());
+    }
+
+    struct ErrorCancellable {}
+
+    impl Cancellable for ErrorCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            Err(anyhow::anyhow!("ErrorCancellable error"))
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+    }
+
+    #[tokio::test]
+    async fn should_propagate_error_from_task() {
+        // Arrange
+        let cancellable = ErrorCancellable {};
+        let cancellation_token = CancellationToken::new();
+
+        // Act
+        let handle = cancellable.spawn(cancellation_token).await;
+
+        // Assert
+        let result = handle.await.unwrap();
+        assert!(result.is_err());
+    }
+
+    struct BreakCancellable {}
+
+    impl Cancellable for BreakCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            Ok(CancellationResult::Break)
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+    }
+
+    #[tokio::test]
+    async fn should_finish_future_when_breaks() {
+        // Arrange
+        let cancellable = BreakCancellable {};
+        let cancellation_token = CancellationToken::new();
+
+        // Act
+        let handle = cancellable.spawn(cancellation_token).await;
+
+        // Assert
+        let result = handle.await.unwrap();
+        assert!(result.is_ok());
+    }
+
+    struct BreakWithCancellable {}
+
+    impl Cancellable for BreakWithCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = usize;
+
+        async fn run(&mut self) -> Result<CancellationResult<(), usize>, Self::Error> {
+            Ok(CancellationResult::BreakWith(42))
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+    }
+
+    #[tokio::test]
+    async fn should_return_final_value_when_breaks_with() {
+        // Arrange
+        let cancellable = BreakWithCancellable {};
+        let cancellation_token = CancellationToken::new();
+
+        // Act
+        let handle = cancellable.spawn(cancellation_token).await;
+
+        // Assert
+        assert_eq!(Some(42), handle.await.unwrap().unwrap());
+    }
+
+    struct ItemsCancellable {
+        batches: Vec<Vec<usize>>,
+    }
+
+    impl Cancellable for ItemsCancellable {
+        type Result = usize;
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn run(&mut self) -> Result<CancellationResult<usize>, Self::Error> {
+            match self.batches.pop() {
+                Some(batch) => Ok(CancellationResult::items(batch)),
+                None => Ok(CancellationResult::Break),
+            }
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+    }
+
+    #[tokio::test]
+    async fn should_pass_each_of_yielded_items_to_callback() {
+        // Arrange
+        let cancellable = ItemsCancellable {
+            batches: vec![vec![4], vec![1, 2, 3]],
+        };
+        let items = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let items_clone = Arc::clone(&items);
+
+        // Act
+        let handle = cancellable
+            .spawn_with_callback(CancellationToken::new(), move |item| {
+                items_clone.lock().unwrap().push(item);
+                CallbackResult::Continue
+            })
+            .await;
+        handle.await.unwrap().unwrap();
+
+        // Assert
+        assert_eq!(vec![1, 2, 3, 4], *items.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn should_discard_remaining_items_when_callback_rejects() {
+        // Arrange
+        let cancellable = ItemsCancellable {
+            batches: vec![vec![4], vec![1, 2, 3]],
+        };
+        let items = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let items_clone = Arc::clone(&items);
+
+        // Act
+        let handle = cancellable
+            .spawn_with_callback(CancellationToken::new(), move |item| {
+                if item == 2 {
+                    return CallbackResult::Break;
+                }
+                items_clone.lock().unwrap().push(item);
+                CallbackResult::Continue
+            })
+            .await;
+        handle.await.unwrap().unwrap();
+
+        // Assert
+        assert_eq!(vec![1], *items.lock().unwrap());
+    }
+
+    async fn spawn_panicking_callback(
+        policy: crate::CallbackPanicPolicy,
+    ) -> (Vec<usize>, CancellableHandle<ItemsCancellable>) {
+        // The first batch is the last one popped.
+        let cancellable = ItemsCancellable {
+            batches: vec![vec![4], vec![1, 2, 3]],
+        };
+        let items = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let items_clone = Arc::clone(&items);
+        let options = SpawnOptions::new().callback_panic(policy).dead_letters(4);
+
+        let handle = cancellable
+            .spawn_with_options(CancellationToken::new(), options, move |item| {
+                assert_ne!(2, item, "Callback panic");
+                items_clone.lock().unwrap().push(item);
+                CallbackResult::Continue
+            })
+            .await;
+        while !handle.is_finished() {
+            tokio::task::yield_now().await;
+        }
+
+        let items = items.lock().unwrap().clone();
+        (items, handle)
+    }
+
+    #[tokio::test]
+    async fn should_skip_item_when_callback_panics() {
+        // Act
+        let (items, handle) = spawn_panicking_callback(crate::CallbackPanicPolicy::Skip).await;
+
+        // Assert
+        assert_eq!(vec![1, 3, 4], items);
+        assert!(handle.cancel_reason().is_none());
+        let mut dead_letters = handle.take_dead_letters().unwrap();
+        assert!(handle.await.unwrap().is_ok());
+        assert_eq!(None, dead_letters.recv().await);
+    }
+
+    #[tokio::test]
+    async fn should_cancel_service_and_drop_rest_of_batch_when_callback_panics() {
+        // Act
+        let (items, handle) = spawn_panicking_callback(crate::CallbackPanicPolicy::Cancel).await;
+
+        // Assert
+        assert_eq!(vec![1], items);
+        let mut dead_letters = handle.take_dead_letters().unwrap();
+        assert_eq!(Some(3), dead_letters.recv().await);
+        assert_eq!(None, dead_letters.recv().await);
+        let reason = handle.cancel_reason().expect("service to be cancelled");
+        let panicked = reason
+            .downcast_ref::<crate::CallbackPanicked>()
+            .expect("callback panic to be the reason");
+        assert!(panicked.message().unwrap().contains("Callback panic"));
+        assert!(handle.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn should_propagate_error_from_callback() {
+        // Arrange
+        let cancellable = ItemsCancellable {
+            batches: vec![vec![1]],
+        };
+
+        // Act
+        let handle = cancellable
+            .spawn_with_callback(CancellationToken::new(), |_| {
+                CallbackResult::Fail(anyhow::anyhow!("Callback error"))
+            })
+            .await;
+
+        // Assert
+        let result = handle.await.unwrap();
+        assert!(result.is_err());
+    }
+
+    struct StuckCancellable {
+        timeouts: Arc<AtomicUsize>,
+    }
+
+    impl Cancellable for StuckCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = usize;
+
+        async fn run(&mut self) -> Result<CancellationResult<(), usize>, Self::Error> {
+            std::future::pending().await
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn on_timeout(&mut self) -> Result<CancellationResult<(), usize>, Self::Error> {
+            match self.timeouts.fetch_add(1, Ordering::SeqCst) {
+                0 => Ok(CancellationResult::Continue),
+                timeouts => Ok(CancellationResult::BreakWith(timeouts + 1)),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn should_call_timeout_hook_when_iteration_times_out() {
+        // Arrange
+        let timeouts = Arc::new(AtomicUsize::new(0));
+        let cancellable = StuckCancellable {
+            timeouts: Arc::clone(&timeouts),
+        };
+        let options = SpawnOptions::new().iteration_timeout(Duration::from_millis(10));
+
+        // Act
+        let handle = cancellable
+            .spawn_with_options(CancellationToken::new(), options, |_| {
+                CallbackResult::Continue
+            })
+            .await;
+
+        // Assert
+        let result = timeout(Duration::from_millis(150), handle).await;
+        assert_eq!(Some(2), result.unwrap().unwrap().unwrap());
+    }
+
+    #[test]
+    fn should_name_service_after_its_type() {
+        // Arrange
+        let cancellable = BreakCancellable {};
+
+        // Act
+        let name = cancellable.name();
+
+        // Assert
+        assert!(name.ends_with("BreakCancellable"));
+    }
+
+    struct PanicCancellable {
+        convert: bool,
+    }
+
+    impl Cancellable for PanicCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            panic!("PanicCancellable panic");
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn on_panic(
+            &mut self,
+            panic: Box<dyn std::any::Any + Send>,
+        ) -> Result<CancellationResult<()>, Self::Error> {
+            if self.convert {
+                Err(anyhow::anyhow!("PanicCancellable has panicked"))
+            } else {
+                std::panic::resume_unwind(panic)
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn should_convert_panic_into_error() {
+        // Arrange
+        let cancellable = PanicCancellable { convert: true };
+
+        // Act
+        let handle = cancellable.spawn(CancellationToken::new()).await;
+
+        // Assert
+        let result = handle.await.unwrap();
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn should_resume_panic_by_default() {
+        // Arrange
+        let cancellable = PanicCancellable { convert: false };
+
+        // Act
+        let handle = cancellable.spawn(CancellationToken::new()).await;
+
+        // Assert
+        let result = handle.await;
+        assert!(result.unwrap_err().is_panic());
+    }
+
+    struct ContinueCancellable {}
+
+    impl Cancellable for ContinueCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            tokio::task::yield_now().await;
+            Ok(CancellationResult::Continue)
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+    }
+
+    #[tokio::test]
+    async fn should_pending_when_continues() {
+        // Arrange
+        let cancellable = ContinueCancellable {};
+        let cancellation_token = CancellationToken::new();
+
+        // Act
+        let handle = cancellable.spawn(cancellation_token.clone()).await;
+
+        // Assert
+        let t = timeout(Duration::from_millis(150), handle).await;
+        assert!(t.is_err());
+    }
+
+    #[derive(Default)]
+    struct HookCalls {
+        on_start: AtomicUsize,
+        on_stop: AtomicUsize,
+        on_cancel: AtomicUsize,
+    }
+
+    struct HookCancellable {
+        calls: Arc<HookCalls>,
+        result: fn() -> Result<CancellationResult<()>, anyhow::Error>,
+    }
+
+    impl Cancellable for HookCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            (self.result)()
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn on_start(&mut self) -> Result<(), Self::Error> {
+            self.calls.on_start.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        async fn on_stop(&mut self) {
+            self.calls.on_stop.fetch_add(1, Ordering::SeqCst);
+        }
+
+        async fn on_cancel(&mut self) {
+            self.calls.on_cancel.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_call_start_and_stop_hooks_when_breaks() {
+        // Arrange
+        let calls = Arc::new(HookCalls::default());
+        let cancellable = HookCancellable {
+            calls: Arc::clone(&calls),
+            result: || Ok(CancellationResult::Break),
+        };
+
+        // Act
+        let handle = cancellable.spawn(CancellationToken::new()).await;
+        handle.await.unwrap().unwrap();
+
+        // Assert
+        assert_eq!(1, calls.on_start.load(Ordering::SeqCst));
+        assert_eq!(1, calls.on_stop.load(Ordering::SeqCst));
+        assert_eq!(0, calls.on_cancel.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn should_call_cancel_hook_when_cancelled() {
+        // Arrange
+        let calls = Arc::new(HookCalls::default());
+        let cancellable = HookCancellable {
+            calls: Arc::clone(&calls),
+            result: || Ok(CancellationResult::Continue),
+        };
+        let handle = cancellable.spawn(CancellationToken::new()).await;
+
+        // Act
+        handle.cancel();
+        handle.await.unwrap().unwrap();
+
+        // Assert
+        assert_eq!(1, calls.on_start.load(Ordering::SeqCst));
+        assert_eq!(1, calls.on_stop.load(Ordering::SeqCst));
+        assert_eq!(1, calls.on_cancel.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn should_skip_stop_hook_when_fails() {
+        // Arrange
+        let calls = Arc::new(HookCalls::default());
+        let cancellable = HookCancellable {
+            calls: Arc::clone(&calls),
+            result: || Err(anyhow::anyhow!("HookCancellable error")),
+        };
+
+        // Act
+        let handle = cancellable.spawn(CancellationToken::new()).await;
+
+        // Assert
+        assert!(handle.await.unwrap().is_err());
+        assert_eq!(0, calls.on_stop.load(Ordering::SeqCst));
+        assert_eq!(0, calls.on_cancel.load(Ordering::SeqCst));
+    }
+
+    struct PendingDrainCancellable {}
+
+    impl Cancellable for PendingDrainCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            std::future::pending().await
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn drain(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn should_complete_when_drain_times_out() {
+        // Arrange
+        let cancellable = PendingDrainCancellable {};
+        let options = SpawnOptions::new().drain_timeout(Duration::from_millis(50));
+        let handle = cancellable
+            .spawn_with_options(CancellationToken::new(), options, |_| {
+                CallbackResult::Continue
+            })
+            .await;
+
+        // Act
+        handle.cancel();
+
+        // Assert
+        let result = timeout(Duration::from_millis(150), handle).await;
+        assert!(result.unwrap().unwrap().is_ok());
+    }
+
+    struct ThreadNameCancellable {}
+
+    impl Cancellable for ThreadNameCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = Option<String>;
+
+        async fn run(&mut self) -> Result<CancellationResult<(), Option<String>>, Self::Error> {
+            let name = std::thread::current().name().map(String::from);
+            Ok(CancellationResult::BreakWith(name))
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+    }
+
+    #[test]
+    fn should_run_on_given_runtime() {
+        // Arrange
+        let dedicated = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("dedicated-runtime")
+            .enable_all()
+            .build()
+            .unwrap();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let cancellable = ThreadNameCancellable {};
+
+        // Act
+        let output = runtime.block_on(async {
+            cancellable
+                .spawn_on(dedicated.handle(), CancellationToken::new())
+                .await
+                .join()
+                .await
+        });
+
+        // Assert
+        assert_eq!(output.unwrap(), Some(Some("dedicated-runtime".to_owned())));
+    }
+
+    #[tokio::test]
+    async fn should_run_on_dedicated_thread() {
+        // Arrange
+        let cancellable = ThreadNameCancellable {};
+
+        // Act
+        let output = cancellable
+            .spawn_on_dedicated_thread(CancellationToken::new())
+            .await
+            .join()
+            .await;
+
+        // Assert
+        assert_eq!(
+            output.unwrap(),
+            Some(Some(
+                std::any::type_name::<ThreadNameCancellable>().to_owned()
+            ))
+        );
+    }
+
+    struct ContextCancellable {
+        token: Arc<std::sync::Mutex<Option<CancellationToken>>>,
+    }
+
+    impl Cancellable for ContextCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            let context = crate::RunContext::current().unwrap();
+            *self.token.lock().unwrap() = Some(context.cancellation_token().clone());
+            std::future::pending().await
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+    }
+
+    #[tokio::test]
+    async fn should_cancel_context_token_when_cancelled() {
+        // Arrange
+        let token = Arc::new(std::sync::Mutex::new(None));
+        let cancellable = ContextCancellable {
+            token: Arc::clone(&token),
+        };
+        let handle = cancellable.spawn(CancellationToken::new()).await;
+        tokio::task::yield_now().await;
+
+        // Act
+        handle.cancel();
+        handle.await.unwrap().unwrap();
+
+        // Assert
+        let token = token.lock().unwrap().take().unwrap();
+        assert!(token.is_cancelled());
+    }
+
+    struct CooperativeCancellable {
+        finished: Arc<AtomicBool>,
+    }
+
+    impl Cancellable for CooperativeCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            let context = crate::RunContext::current().unwrap();
+            context.cancellation_token().cancelled().await;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            self.finished.store(true, Ordering::SeqCst);
+            Ok(CancellationResult::Cancelled)
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+    }
+
+    #[tokio::test]
+    async fn should_let_service_finish_iteration_when_cooperative() {
+        // Arrange
+        let finished = Arc::new(AtomicBool::new(false));
+        let cancellable = CooperativeCancellable {
+            finished: Arc::clone(&finished),
+        };
+        let options = SpawnOptions::new().cooperative_cancellation();
+        let handle = cancellable
+            .spawn_with_options(CancellationToken::new(), options, |_| {
+                CallbackResult::Continue
+            })
+            .await;
+        tokio::task::yield_now().await;
+
+        // Act
+        handle.cancel();
+
+        // Assert
+        assert!(handle.await.unwrap().is_ok());
+        assert!(finished.load(Ordering::SeqCst));
+    }
+
+    struct BusyCancellable {
+        runs: Arc<AtomicUsize>,
+    }
+
+    impl Cancellable for BusyCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            self.runs.fetch_add(1, Ordering::SeqCst);
+            Ok(CancellationResult::Continue)
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+    }
+
+    #[tokio::test]
+    async fn should_yield_to_other_tasks_between_iterations() {
+        // Arrange
+        let runs = Arc::new(AtomicUsize::new(0));
+        let cancellable = BusyCancellable {
+            runs: Arc::clone(&runs),
+        };
+        let options = SpawnOptions::new().yield_every(1);
+
+        // Act
+        let handle = cancellable
+            .spawn_with_options(CancellationToken::new(), options, |_| {
+                CallbackResult::Continue
+            })
+            .await;
+        let observed_runs = Arc::clone(&runs);
+        let observed = tokio::spawn(async move { observed_runs.load(Ordering::SeqCst) })
+            .await
+            .unwrap();
+
+        // Assert
+        assert!(observed <= 2, "service has run {observed} times in a row");
+        handle.cancel();
+        assert!(handle.join().await.is_ok());
+    }
+
+    struct DelayCancellable {
+        delay: Duration,
+        delayed: bool,
+    }
+
+    impl Cancellable for DelayCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            if std::mem::replace(&mut self.delayed, true) {
+                Ok(CancellationResult::Break)
+            } else {
+                Ok(CancellationResult::Delay(self.delay))
+            }
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+    }
+
+    #[tokio::test]
+    async fn should_delay_next_iteration() {
+        // Arrange
+        let cancellable = DelayCancellable {
+            delay: Duration::from_millis(20),
+            delayed: false,
+        };
+        let started = tokio::time::Instant::now();
+
+        // Act
+        let handle = cancellable.spawn(CancellationToken::new()).await;
+
+        // Assert
+        assert!(handle.await.unwrap().is_ok());
+        assert!(started.elapsed() >= Duration::from_millis(20));
+    }
+
+    #[tokio::test]
+    async fn should_cancel_during_delay() {
+        // Arrange
+        let cancellable = DelayCancellable {
+            delay: Duration::from_secs(3600),
+            delayed: false,
+        };
+        let handle = cancellable.spawn(CancellationToken::new()).await;
+        tokio::task::yield_now().await;
+
+        // Act
+        handle.cancel();
+
+        // Assert
+        let result = timeout(Duration::from_millis(100), handle).await;
+        assert!(result.unwrap().unwrap().is_ok());
+    }
+
+    struct LastItemCancellable {}
+
+    impl Cancellable for LastItemCancellable {
+        type Result = i32;
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn run(&mut self) -> Result<CancellationResult<i32>, Self::Error> {
+            Ok(CancellationResult::LastItem(42))
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+    }
+
+    #[tokio::test]
+    async fn should_deliver_last_item_and_complete() {
+        // Arrange
+        let cancellable = LastItemCancellable {};
+        let items = Arc::new(AtomicUsize::new(0));
+        let items_clone = Arc::clone(&items);
+
+        // Act
+        let handle = cancellable
+            .spawn_with_callback(CancellationToken::new(), move |item| {
+                assert_eq!(42, item);
+                items_clone.fetch_add(1, Ordering::SeqCst);
+                CallbackResult::Continue
+            })
+            .await;
+
+        // Assert
+        assert!(handle.await.unwrap().is_ok());
+        assert_eq!(1, items.load(Ordering::SeqCst));
+    }
+
+    struct FallibleHandleCancellable {}
+
+    impl Cancellable for FallibleHandleCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            Ok(CancellationResult::Break)
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {
+            panic!("FallibleHandleCancellable error")
+        }
+
+        async fn try_new_handle(&mut self) -> Result<Self::Handle, Self::Error> {
+            Err(anyhow::anyhow!("FallibleHandleCancellable error"))
+        }
+    }
+
+    #[tokio::test]
+    async fn should_surface_handle_construction_error() {
+        // Arrange
+        let cancellable = FallibleHandleCancellable {};
+
+        // Act
+        let result = cancellable.try_spawn(CancellationToken::new()).await;
+
+        // Assert
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "FallibleHandleCancellable error")]
+    async fn should_panic_when_spawning_service_whose_handle_fails() {
+        // Arrange
+        let cancellable = FallibleHandleCancellable {};
+
+        // Act
+        drop(cancellable.spawn(CancellationToken::new()).await);
+    }
+
+    #[tokio::test]
+    async fn should_construct_handle_with_new_handle_when_trying() {
+        // Arrange
+        let cancellable = MockCancellable::new(false);
+
+        // Act
+        let handle = cancellable.try_spawn(CancellationToken::new()).await;
+
+        // Assert
+        assert!(!handle.unwrap().is_finished());
+    }
+
+    struct HealthCancellable {}
+
+    impl Cancellable for HealthCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            let context = crate::RunContext::current().unwrap();
+            context.report_health(Health::Degraded("HealthCancellable".to_owned()));
+            std::future::pending().await
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+    }
+
+    #[tokio::test]
+    async fn should_observe_reported_health() {
+        // Arrange
+        let cancellable = HealthCancellable {};
+        let handle = cancellable.spawn(CancellationToken::new()).await;
+        let mut health = handle.watch_health();
+
+        // Act
+        let result = timeout(Duration::from_secs(1), health.changed()).await;
+
+        // Assert
+        assert!(result.unwrap().is_ok());
+        assert_eq!(
+            Health::Degraded("HealthCancellable".to_owned()),
+            handle.health()
+        );
+    }
+
+    struct CountingCancellable {
+        next: i32,
+    }
+
+    impl Cancellable for CountingCancellable {
+        type Result = i32;
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn run(&mut self) -> Result<CancellationResult<i32>, Self::Error> {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            self.next += 1;
+            match self.next {
+                next if next > 3 => Ok(CancellationResult::Break),
+                next => Ok(CancellationResult::Item(next)),
+            }
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn should_cancel_service_exceeding_time_budget() {
+        // Arrange
+        let cancellable = CountingCancellable { next: 0 };
+        let budget = crate::TimeBudget::cancel(Duration::from_millis(15), Duration::from_secs(1));
+        let options = SpawnOptions::new().time_budget(budget);
+
+        // Act
+        let handle = cancellable
+            .spawn_with_options(CancellationToken::new(), options, |_| {
+                CallbackResult::Continue
+            })
+            .await;
+        while !handle.is_finished() {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        let stats = handle.stats();
+        let (result, reason) = handle.join_with_reason().await;
+
+        // Assert
+        assert!(result.is_ok());
+        assert!(reason.unwrap().is::<crate::BudgetExceeded>());
+        assert_eq!(2, stats.iterations());
+        assert_eq!(Duration::from_millis(20), stats.run_time());
+    }
+
+    #[tokio::test]
+    async fn should_broadcast_items_to_all_subscribers() {
+        // Arrange
+        let cancellable = CountingCancellable { next: 0 };
+        let (handle, subscribers) = cancellable
+            .spawn_broadcast(CancellationToken::new(), 4)
+            .await;
+        let mut first = subscribers.subscribe(crate::LagPolicy::Skip);
+        let mut second = subscribers.subscribe(crate::LagPolicy::Close);
+        drop(subscribers);
+
+        // Act
+        let mut items = Vec::new();
+        while let Some(item) = first.recv().await {
+            items.push((item, second.recv().await));
+        }
+
+        // Assert
+        assert_eq!(vec![(1, Some(1)), (2, Some(2)), (3, Some(3))], items);
+        assert!(handle.join().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn should_publish_latest_item() {
+        // Arrange
+        let cancellable = CountingCancellable { next: 0 };
+
+        // Act
+        let (handle, mut latest) = cancellable.spawn_watch(CancellationToken::new()).await;
+        handle.join().await.unwrap();
+
+        // Assert
+        assert_eq!(Some(3), latest.latest());
+        assert_eq!(Some(3), latest.changed().await);
+        assert_eq!(None, latest.changed().await);
+    }
+
+    #[tokio::test]
+    async fn should_deliver_items_in_batches() {
+        // Arrange
+        let cancellable = CountingCancellable { next: 0 };
+        let batches = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let batches_clone = Arc::clone(&batches);
+
+        // Act
+        let handle = cancellable
+            .spawn_with_batched_callback(
+                CancellationToken::new(),
+                SpawnOptions::default(),
+                2,
+                Duration::from_secs(1),
+                move |batch| {
+                    batches_clone.lock().unwrap().push(batch);
+                    CallbackResult::Continue
+                },
+            )
+            .await;
+        handle.join().await.unwrap();
+
+        // Assert
+        assert_eq!(vec![vec![1, 2], vec![3]], *batches.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn should_deliver_batch_after_max_delay() {
+        // Arrange
+        let cancellable = CountingCancellable { next: 0 };
+        let batches = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let batches_clone = Arc::clone(&batches);
+
+        // Act
+        let handle = cancellable
+            .spawn_with_batched_callback(
+                CancellationToken::new(),
+                SpawnOptions::default(),
+                10,
+                Duration::from_millis(1),
+                move |batch| {
+                    batches_clone.lock().unwrap().push(batch);
+                    CallbackResult::Continue
+                },
+            )
+            .await;
+        handle.join().await.unwrap();
+
+        // Assert
+        assert_eq!(vec![vec![1], vec![2], vec![3]], *batches.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn should_wait_for_batch_with_service_clock() {
+        // Arrange
+        let items =
+            futures::StreamExt::chain(futures::stream::iter([1]), futures::stream::pending());
+        let cancellable = crate::from_stream(items);
+        let options = SpawnOptions::new().clock(Arc::new(InstantClock));
+        let (sender, mut batches) = tokio::sync::mpsc::unbounded_channel();
+
+        // Act
+        let handle = cancellable
+            .spawn_with_batched_callback(
+                CancellationToken::new(),
+                options,
+                10,
+                Duration::from_secs(3600),
+                move |batch| {
+                    sender.send(batch).unwrap();
+                    CallbackResult::Continue
+                },
+            )
+            .await;
+
+        // Assert
+        let batch = timeout(Duration::from_secs(1), batches.recv()).await;
+        assert_eq!(Some(vec![1]), batch.unwrap());
+        handle.cancel();
+        assert!(handle.join().await.is_ok());
+    }
+
+    struct BadFrameCancellable {
+        frames: std::vec::IntoIter<Result<i32, &'static str>>,
+    }
+
+    impl Cancellable for BadFrameCancellable {
+        type Result = i32;
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn run(&mut self) -> Result<CancellationResult<i32>, Self::Error> {
+            match self.frames.next() {
+                Some(Ok(frame)) => Ok(CancellationResult::Item(frame)),
+                Some(Err(e)) => Err(anyhow::anyhow!(e)),
+                None => Ok(CancellationResult::Break),
+            }
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn on_error(&mut self, error: Self::Error) -> ErrorDirective<Self::Error> {
+            match error.to_string().as_str() {
+                "bad frame" => ErrorDirective::Continue,
+                "closed" => ErrorDirective::Break,
+                _ => ErrorDirective::Fail(error),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn should_skip_recoverable_errors() {
+        // Arrange
+        let cancellable = BadFrameCancellable {
+            frames: vec![Ok(1), Err("bad frame"), Ok(2), Err("closed"), Ok(3)].into_iter(),
+        };
+
+        // Act
+        let (handle, items) = cancellable.spawn_stream(CancellationToken::new()).await;
+
+        // Assert
+        assert_eq!(
+            vec![1, 2],
+            futures::StreamExt::collect::<Vec<_>>(items).await
+        );
+        assert!(handle.join().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn should_count_iterations_items_and_tolerated_errors() {
+        // Arrange
+        let cancellable = BadFrameCancellable {
+            frames: vec![Ok(1), Err("bad frame"), Ok(2), Err("closed"), Ok(3)].into_iter(),
+        };
+
+        // Act
+        let handle = cancellable
+            .spawn_with_callback(CancellationToken::new(), |_| CallbackResult::Continue)
+            .await;
+        while !handle.is_finished() {
+            tokio::task::yield_now().await;
+        }
+        let stats = handle.stats();
 
-/// Defines an interface for a cancellable service with an optional callback.
-#[async_trait]
-pub trait Cancellable {
-    /// Type of values that _can_ be yielded by the service.
-    type Result;
+        // Assert
+        assert_eq!(4, stats.iterations());
+        assert_eq!(2, stats.items());
+        assert_eq!(1, stats.tolerated_errors());
+        assert_eq!(0, stats.restarts());
+        assert!(stats.last_activity().is_some());
+    }
 
-    /// Type of a handle for communicating with the service.
-    type Handle: std::fmt::Debug;
+    #[derive(Default)]
+    struct RecordingObserver {
+        events: std::sync::Mutex<Vec<String>>,
+    }
 
-    /// Error returned by [`Self::run`] method.
-    type Error: std::fmt::Debug + std::fmt::Display + Send;
+    impl crate::CancellableObserver for RecordingObserver {
+        fn on_start(&self, service: &str) {
+            self.record(format!("{service} started"));
+        }
 
-    /// Performs a single unit of work.
-    ///
-    /// The return value of this method controls whether the service will
-    /// continue to loop. If the returned value is either
-    /// `Ok(CancellableResult::Break)` or `Err(Self::Error)`, then the service
-    /// will complete.
-    ///
-    /// See [CancellationResult::Break].
-    ///
-    /// # Returns
-    ///
-    /// Returned value controls whether the work loop will continue. If the
-    /// returned value is `Err(Self::Error)` or `Ok(CancellationResult::Break)`,
-    /// then the work loop finishes. The method can optionally yield a value by
-    /// returning [`CancellationResult::Item`].
-    ///
-    /// [CancellableResult::Break]: crate::CancellationResult#variant.Break
-    /// [`CancellationResult::Item`]: crate::CancellationResult#variant.Item
-    async fn run(&mut self) -> Result<CancellationResult<Self::Result>, Self::Error>;
+        fn on_item(&self, service: &str) {
+            self.record(format!("{service} item"));
+        }
 
-    /// Constructs a new handle for communicating with the service.
-    ///
-    /// This method is intended to be called only once. If it's called more than
-    /// once, then the behavior is undefined.
-    async fn new_handle(&mut self) -> Self::Handle;
+        fn on_error(&self, service: &str, error: &dyn std::fmt::Display) {
+            self.record(format!("{service} error: {error}"));
+        }
 
-    /// Consumes the service and spawns its work loop.
-    ///
-    /// It's equivalent to [`Self::spawn_with_callback`] in every way, besides
-    /// the callback.
-    async fn spawn(mut self, cancellation_token: CancellationToken) -> CancellableHandle<Self>
-    where
-        Self: Sized + Send + 'static,
-    {
-        self.spawn_with_callback(cancellation_token, |_| Ok(()))
-            .await
+        fn on_finish(&self, service: &str, reason: crate::CompletionReason) {
+            self.record(format!("{service} finished: {reason:?}"));
+        }
     }
 
-    /// Consumes the service and spawns its work loop.
-    ///
-    /// Schedules a new background task, that repetitively calls [`Self::run`]
-    /// and performs its work. If `cancellation_token` is cancelled, then the
-    /// service completes instantly.
-    ///
-    /// # Arguments
-    ///
-    /// * `cancellation_token` - provides a way of cancelling the service mid
-    /// work.
-    /// * `callback` - if the service yields a new value, then it's passed to
-    /// the callback. If the callback returns `Err`, then service completes with
-    /// the same error.
-    ///
-    /// # Returns
-    ///
-    /// Handle that can be used to await for the service to complete.
-    async fn spawn_with_callback<F>(
-        mut self,
-        cancellation_token: CancellationToken,
-        mut callback: F,
-    ) -> CancellableHandle<Self>
-    where
-        Self: Sized + Send + 'static,
-        F: FnMut(Self::Result) -> Result<(), Self::Result> + Send + 'static,
-    {
-        let inner_cancellable_token = CancellationToken::new();
-        let inner_cancellable_token_child = inner_cancellable_token.child_token();
-        let inner = self.new_handle().await;
+    impl RecordingObserver {
+        fn record(&self, event: String) {
+            self.events
+                .lock()
+                .expect("lock not to be poisoned")
+                .push(event);
+        }
+    }
 
-        let join_handle = tokio::spawn(async move {
-            loop {
-                tokio::select! {
-                    _ = cancellation_token.cancelled() => {
-                        break
-                    }
-                    _ = inner_cancellable_token_child.cancelled() => {
-                        break
-                    }
-                    result = self.run() => {
-                        match result {
-                            Ok(CancellationResult::Item(result)) => {
-                                if let Err(_result) = callback(result) {
-                                    break
-                                }
-                            },
-                            Ok(CancellationResult::Continue) => {}
-                            Ok(CancellationResult::Break) => break,
-                            Err(e) => return Err(e)
-                        }
-                    }
-                }
-            }
+    #[tokio::test]
+    async fn should_notify_each_observer_of_lifecycle_events() {
+        // Arrange
+        let cancellable = BadFrameCancellable {
+            frames: vec![Ok(1), Err("bad frame"), Err("closed")].into_iter(),
+        };
+        let first = Arc::new(RecordingObserver::default());
+        let second = Arc::new(RecordingObserver::default());
+        let options = SpawnOptions::new()
+            .observer(first.clone())
+            .observer(second.clone());
 
-            Ok(())
-        });
+        // Act
+        let handle = cancellable
+            .spawn_with_options(CancellationToken::new(), options, |_| {
+                CallbackResult::Continue
+            })
+            .await;
+        assert!(handle.join().await.is_ok());
 
-        CancellableHandle::new(join_handle, inner_cancellable_token, inner)
+        // Assert
+        let name = std::any::type_name::<BadFrameCancellable>();
+        let expected = vec![
+            format!("{name} started"),
+            format!("{name} item"),
+            format!("{name} error: bad frame"),
+            format!("{name} error: closed"),
+            format!("{name} finished: Completed"),
+        ];
+        for observer in [first, second] {
+            assert_eq!(expected, *observer.events.lock().unwrap());
+        }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::{
-        sync::{
-            atomic::{AtomicBool, Ordering},
-            Arc,
-        },
-        time::Duration,
-    };
+    #[tokio::test]
+    async fn should_fail_on_unrecoverable_error() {
+        // Arrange
+        let cancellable = BadFrameCancellable {
+            frames: vec![Ok(1), Err("fatal")].into_iter(),
+        };
 
-    use tokio::time::timeout;
-    use tokio_util::sync::CancellationToken;
+        // Act
+        let handle = cancellable.spawn(CancellationToken::new()).await;
 
-    use crate::{Cancellable, CancellationResult};
+        // Assert
+        assert!(handle.join().await.is_err());
+    }
 
-    struct MockCancellable {
-        flag: Arc<AtomicBool>,
+    #[derive(Debug, Clone, PartialEq)]
+    enum Shutdown {
+        Fatal(String),
     }
 
-    impl MockCancellable {
-        fn new(flag: bool) -> Self {
-            Self {
-                flag: Arc::new(AtomicBool::new(flag)),
-            }
-        }
+    struct ReasonCancellable {
+        reason: Arc<std::sync::Mutex<Option<Shutdown>>>,
     }
 
-    #[async_trait::async_trait]
-    impl Cancellable for MockCancellable {
-        type Result = Arc<AtomicBool>;
+    impl Cancellable for ReasonCancellable {
+        type Result = ();
         type Handle = ();
-        type Error = std::io::Error;
+        type Error = anyhow::Error;
+        type Output = ();
 
-        async fn run(&mut self) -> Result<CancellationResult<Self::Result>, Self::Error> {
-            let () = std::future::pending().await;
-            self.flag.store(true, Ordering::Relaxed);
-            Ok(CancellationResult::Break)
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            std::future::pending().await
         }
 
         async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn on_cancel(&mut self) {
+            let context = crate::RunContext::current().unwrap();
+            *self.reason.lock().unwrap() = context
+                .cancel_reason()
+                .and_then(|reason| reason.downcast_ref::<Shutdown>().cloned());
+        }
     }
 
     #[tokio::test]
-    async fn should_finish_when_cancelled() {
+    async fn should_pass_cancel_reason_to_service() {
         // Arrange
-        let flag = Arc::new(AtomicBool::new(false));
-        let flag_clone = Arc::clone(&flag);
+        let reason = Arc::new(std::sync::Mutex::new(None));
+        let cancellable = ReasonCancellable {
+            reason: Arc::clone(&reason),
+        };
+        let handle = cancellable.spawn(CancellationToken::new()).await;
 
-        let cancellable = MockCancellable::new(false);
+        // Act
+        handle.cancel_with_reason(Shutdown::Fatal("disk is full".into()));
+        let (result, cancel_reason) = handle.join_with_reason().await;
+
+        // Assert
+        assert!(result.is_ok());
+        let expected = Shutdown::Fatal("disk is full".into());
+        assert_eq!(Some(&expected), reason.lock().unwrap().as_ref());
+        assert_eq!(
+            Some(&expected),
+            cancel_reason.unwrap().downcast_ref::<Shutdown>()
+        );
+    }
+
+    #[tokio::test]
+    async fn should_have_no_cancel_reason_when_parent_is_cancelled() {
+        // Arrange
         let cancellation_token = CancellationToken::new();
+        let cancellable = ReasonCancellable {
+            reason: Arc::new(std::sync::Mutex::new(None)),
+        };
+        let handle = cancellable.spawn(cancellation_token.clone()).await;
+
+        // Act
+        cancellation_token.cancel();
+        let (result, cancel_reason) = handle.join_with_reason().await;
+
+        // Assert
+        assert!(result.is_ok());
+        assert!(cancel_reason.is_none());
+    }
+
+    struct GeneratorCancellable {
+        rejected: Arc<AtomicUsize>,
+    }
+
+    impl Cancellable for GeneratorCancellable {
+        type Result = usize;
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = u64;
+
+        async fn run(&mut self) -> Result<CancellationResult<usize, u64>, Self::Error> {
+            let context = crate::RunContext::current().unwrap();
+            for item in 1..=3_usize {
+                if context.yield_item(item).await.is_err() {
+                    self.rejected.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+
+            match context.iteration() {
+                2 => Ok(CancellationResult::BreakWith(context.iteration())),
+                _ => Ok(CancellationResult::Continue),
+            }
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+    }
+
+    #[tokio::test]
+    async fn should_deliver_items_yielded_during_iteration() {
+        // Arrange
+        let rejected = Arc::new(AtomicUsize::new(0));
+        let cancellable = GeneratorCancellable {
+            rejected: Arc::clone(&rejected),
+        };
+
+        // Act
+        let (handle, items) = cancellable.spawn_stream(CancellationToken::new()).await;
+
+        // Assert
+        assert_eq!(
+            vec![1, 2, 3, 1, 2, 3],
+            futures::StreamExt::collect::<Vec<_>>(items).await
+        );
+        assert_eq!(Some(2), handle.join().await.unwrap());
+        assert_eq!(0, rejected.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn should_reject_yielded_items_once_callback_breaks() {
+        // Arrange
+        let rejected = Arc::new(AtomicUsize::new(0));
+        let cancellable = GeneratorCancellable {
+            rejected: Arc::clone(&rejected),
+        };
+        let mut items = Vec::new();
 
         // Act
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
         let handle = cancellable
-            .spawn_with_callback(cancellation_token.clone(), move |_| {
-                flag_clone.store(true, Ordering::SeqCst);
-                Ok(())
+            .spawn_with_callback(CancellationToken::new(), move |item| {
+                sender.send(item).unwrap();
+                match item {
+                    1 => CallbackResult::Continue,
+                    _ => CallbackResult::Break,
+                }
             })
             .await;
-
-        cancellation_token.cancel();
+        let output = handle.join().await.unwrap();
+        while let Ok(item) = receiver.try_recv() {
+            items.push(item);
+        }
 
         // Assert
-        assert!(!flag.load(Ordering::SeqCst));
-        handle.await.unwrap().unwrap();
+        assert_eq!(vec![1, 2], items);
+        assert_eq!(None, output);
+        assert_eq!(1, rejected.load(Ordering::SeqCst));
     }
 
-    struct ErrorCancellable {}
+    struct BurstCancellable {
+        dropped: Arc<std::sync::Mutex<Vec<usize>>>,
+    }
 
-    #[async_trait::async_trait]
-    impl Cancellable for ErrorCancellable {
-        type Result = ();
+    impl Cancellable for BurstCancellable {
+        type Result = usize;
         type Handle = ();
         type Error = anyhow::Error;
+        type Output = ();
 
-        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
-            Err(anyhow::anyhow!("ErrorCancellable error"))
+        async fn run(&mut self) -> Result<CancellationResult<usize>, Self::Error> {
+            Ok(CancellationResult::items([1, 2, 3, 4]))
+        }
+
+        fn on_item_dropped(&mut self, item: Self::Result) {
+            self.dropped.lock().unwrap().push(item);
         }
 
         async fn new_handle(&mut self) -> Self::Handle {}
     }
 
     #[tokio::test]
-    async fn should_propagate_error_from_task() {
+    async fn should_pass_undelivered_items_to_service() {
         // Arrange
-        let cancellable = ErrorCancellable {};
-        let cancellation_token = CancellationToken::new();
+        let dropped = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let cancellable = BurstCancellable {
+            dropped: Arc::clone(&dropped),
+        };
 
         // Act
-        let handle = cancellable.spawn(cancellation_token).await;
+        let handle = cancellable
+            .spawn_with_callback(CancellationToken::new(), |item| match item {
+                2 => CallbackResult::Break,
+                _ => CallbackResult::Continue,
+            })
+            .await;
 
         // Assert
-        let result = handle.await.unwrap();
-        assert!(result.is_err());
+        assert!(handle.join().await.is_ok());
+        assert_eq!(vec![3, 4], *dropped.lock().unwrap());
     }
 
-    struct BreakCancellable {}
+    #[tokio::test]
+    async fn should_divert_undelivered_items_to_dead_letters() {
+        // Arrange
+        let dropped = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let cancellable = BurstCancellable {
+            dropped: Arc::clone(&dropped),
+        };
+        let options = SpawnOptions::new().dead_letters(1);
 
-    #[async_trait::async_trait]
-    impl Cancellable for BreakCancellable {
-        type Result = ();
+        // Act
+        let handle = cancellable
+            .spawn_with_options(CancellationToken::new(), options, |item| match item {
+                2 => CallbackResult::Break,
+                _ => CallbackResult::Continue,
+            })
+            .await;
+        let mut dead_letters = handle.take_dead_letters().unwrap();
+        let result = handle.join().await;
+
+        // Assert
+        assert!(result.is_ok());
+        assert_eq!(Some(3), dead_letters.recv().await);
+        assert_eq!(None, dead_letters.recv().await);
+        assert_eq!(vec![4], *dropped.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn should_divert_rejected_items_to_dead_letters() {
+        // Arrange
+        let dropped = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let cancellable = BurstCancellable {
+            dropped: Arc::clone(&dropped),
+        };
+        let options = SpawnOptions::new().dead_letters(4);
+
+        // Act
+        let handle = cancellable
+            .spawn_with_options(CancellationToken::new(), options, |item| match item {
+                1 | 3 => CallbackResult::Reject(item),
+                4 => CallbackResult::Break,
+                _ => CallbackResult::Continue,
+            })
+            .await;
+        let mut dead_letters = handle.take_dead_letters().unwrap();
+        let result = handle.join().await;
+
+        // Assert
+        assert!(result.is_ok());
+        assert_eq!(Some(1), dead_letters.recv().await);
+        assert_eq!(Some(3), dead_letters.recv().await);
+        assert_eq!(None, dead_letters.recv().await);
+        assert!(dropped.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn should_drop_unacknowledged_items_and_wait_for_pending_acks() {
+        // Arrange
+        let dropped = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let cancellable = BurstCancellable {
+            dropped: Arc::clone(&dropped),
+        };
+        let (sender, mut held) = tokio::sync::mpsc::unbounded_channel();
+        let handle = cancellable
+            .spawn_with_acks(CancellationToken::new(), SpawnOptions::new(), move |item| {
+                match *item {
+                    1 => drop(item.ack()),
+                    2 => item.nack(),
+                    3 => drop(sender.send(item)),
+                    _ => return CallbackResult::Break,
+                }
+                CallbackResult::Continue
+            })
+            .await;
+        let held = held.recv().await.unwrap();
+
+        // Act
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let finished_before_ack = handle.is_finished();
+        held.ack();
+        let result = handle.join().await;
+
+        // Assert
+        assert!(!finished_before_ack);
+        assert!(result.is_ok());
+        assert_eq!(vec![2, 4], *dropped.lock().unwrap());
+    }
+
+    struct EndlessCancellable {
+        runs: Arc<AtomicUsize>,
+    }
+
+    impl Cancellable for EndlessCancellable {
+        type Result = usize;
         type Handle = ();
         type Error = anyhow::Error;
+        type Output = ();
 
-        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
-            Ok(CancellationResult::Break)
+        async fn run(&mut self) -> Result<CancellationResult<usize>, Self::Error> {
+            Ok(CancellationResult::Item(
+                self.runs.fetch_add(1, Ordering::SeqCst),
+            ))
         }
 
         async fn new_handle(&mut self) -> Self::Handle {}
     }
 
     #[tokio::test]
-    async fn should_finish_future_when_breaks() {
+    async fn should_wait_for_stream_consumer_to_catch_up() {
         // Arrange
-        let cancellable = BreakCancellable {};
-        let cancellation_token = CancellationToken::new();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let cancellable = EndlessCancellable {
+            runs: Arc::clone(&runs),
+        };
+        let options = SpawnOptions::new().max_in_flight(2);
+        let (handle, mut items) = cancellable
+            .spawn_stream_with_options(CancellationToken::new(), options)
+            .await;
 
         // Act
-        let handle = cancellable.spawn(cancellation_token).await;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let runs_before = runs.load(Ordering::SeqCst);
+        let first = futures::StreamExt::next(&mut items).await;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        let runs_after = runs.load(Ordering::SeqCst);
 
         // Assert
-        let result = handle.await.unwrap();
-        assert!(result.is_ok());
+        assert_eq!(2, runs_before);
+        assert_eq!(Some(0), first);
+        assert_eq!(3, runs_after);
+        drop(items);
+        assert!(handle.join().await.is_ok());
     }
 
-    struct ContinueCancellable {}
+    struct LastWordCancellable {
+        started: Arc<tokio::sync::Notify>,
+        resume: Arc<tokio::sync::Notify>,
+    }
 
-    #[async_trait::async_trait]
-    impl Cancellable for ContinueCancellable {
+    impl Cancellable for LastWordCancellable {
         type Result = ();
         type Handle = ();
         type Error = anyhow::Error;
+        type Output = ();
 
         async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
-            tokio::task::yield_now().await;
-            Ok(CancellationResult::Continue)
+            self.started.notify_one();
+            self.resume.notified().await;
+            Ok(CancellationResult::item(()))
         }
 
         async fn new_handle(&mut self) -> Self::Handle {}
     }
 
+    /// Cancels `attempts` services right as their iterations complete, and
+    /// returns the number of items passed to the callback.
+    async fn race_cancellation(attempts: usize, options: SpawnOptions) -> usize {
+        let delivered = Arc::new(AtomicUsize::new(0));
+
+        for _ in 0..attempts {
+            let started = Arc::new(tokio::sync::Notify::new());
+            let resume = Arc::new(tokio::sync::Notify::new());
+            let cancellable = LastWordCancellable {
+                started: Arc::clone(&started),
+                resume: Arc::clone(&resume),
+            };
+            let delivered = Arc::clone(&delivered);
+            let handle = cancellable
+                .spawn_with_options(CancellationToken::new(), options.clone(), move |()| {
+                    delivered.fetch_add(1, Ordering::SeqCst);
+                    CallbackResult::Continue
+                })
+                .await;
+            started.notified().await;
+
+            resume.notify_one();
+            handle.cancel();
+            assert!(handle.join().await.is_ok());
+        }
+
+        delivered.load(Ordering::SeqCst)
+    }
+
     #[tokio::test]
-    async fn should_pending_when_continues() {
+    async fn should_deliver_item_produced_while_cancelled() {
+        // Act
+        let delivered = race_cancellation(16, SpawnOptions::new()).await;
+
+        // Assert
+        assert_eq!(16, delivered);
+    }
+
+    #[tokio::test]
+    async fn should_drop_item_produced_while_cancelled_with_cancellation_priority() {
         // Arrange
-        let cancellable = ContinueCancellable {};
-        let cancellation_token = CancellationToken::new();
+        let options =
+            SpawnOptions::new().cancellation_priority(crate::CancellationPriority::Cancellation);
 
         // Act
-        let handle = cancellable.spawn(cancellation_token.clone()).await;
+        let delivered = race_cancellation(16, options).await;
 
         // Assert
-        let t = timeout(Duration::from_millis(150), handle).await;
-        assert!(t.is_err());
+        assert_eq!(0, delivered);
     }
 }
@@ -0,0 +1,93 @@
+use tokio::task::JoinError;
+
+/// Error of a service that hasn't completed successfully.
+///
+/// See [`CancellableHandle::join`].
+///
+/// [`CancellableHandle::join`]: crate::CancellableHandle::join
+#[derive(Debug)]
+pub enum CancellableError<E> {
+    /// The service's task has been aborted before it completed.
+    Cancelled,
+
+    /// The service's task has panicked.
+    Panicked(JoinError),
+
+    /// The service has completed with an error.
+    Service(E),
+}
+
+impl<E> CancellableError<E> {
+    /// Returns the service's error, if the service has completed with one.
+    pub fn into_service_error(self) -> Option<E> {
+        match self {
+            Self::Service(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl<E> From<JoinError> for CancellableError<E> {
+    fn from(value: JoinError) -> Self {
+        if value.is_cancelled() {
+            Self::Cancelled
+        } else {
+            Self::Panicked(value)
+        }
+    }
+}
+
+impl<E> std::fmt::Display for CancellableError<E>
+where
+    E: std::fmt::Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Cancelled => f.write_str("service has been aborted"),
+            Self::Panicked(e) => write!(f, "service has panicked: {e}"),
+            Self::Service(e) => write!(f, "service has failed: {e}"),
+        }
+    }
+}
+
+impl<E> std::error::Error for CancellableError<E>
+where
+    E: std::fmt::Debug + std::fmt::Display,
+{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Panicked(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::CancellableError;
+
+    #[tokio::test]
+    async fn should_convert_aborted_task_into_cancelled() {
+        // Arrange
+        let task = tokio::spawn(std::future::pending::<()>());
+        task.abort();
+
+        // Act
+        let error = CancellableError::<()>::from(task.await.unwrap_err());
+
+        // Assert
+        assert!(matches!(error, CancellableError::Cancelled));
+    }
+
+    #[tokio::test]
+    async fn should_convert_panicked_task_into_panicked() {
+        // Arrange
+        let task = tokio::spawn(async { panic!("CancellableError panic") });
+
+        // Act
+        let error = CancellableError::<()>::from(task.await.unwrap_err());
+
+        // Assert
+        assert!(matches!(error, CancellableError::Panicked(_)));
+    }
+}
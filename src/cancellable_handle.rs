@@ -1,83 +1,596 @@
 use std::{
+    any::Any,
+    convert::Infallible,
+    future::Future,
     ops::{Deref, DerefMut},
-    task::Poll,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Wake, Waker},
+    time::Duration,
 };
 
 use pin_project::pin_project;
-use tokio::task::{JoinError, JoinHandle};
+use tokio::{
+    sync::{mpsc, watch},
+    task::{JoinError, JoinHandle},
+};
 use tokio_util::sync::CancellationToken;
 
-use crate::Cancellable;
+use crate::{
+    cancel_reason::ReasonSlot, clock::SharedClock, completion_reason::CompletionSlot,
+    service_stats::StatsSlot, CancelReason, CancellableError, CompletionReason, Health,
+    LocalCancellable, Scope, ServiceStats,
+};
+
+/// Join handle of a spawned service's task.
+pub(crate) type ServiceJoinHandle<T> = JoinHandle<ServiceResult<T>>;
+
+/// Result of a spawned service's task.
+pub(crate) type ServiceResult<T> =
+    Result<Option<<T as LocalCancellable>::Output>, <T as LocalCancellable>::Error>;
+
+/// Slot for the service itself, filled by its task once the work loop
+/// completes, if the service retains its state.
+///
+/// The service is type-erased, so the handle of a service which isn't `Send`
+/// stays `Send`.
+pub(crate) type StateSlot = Arc<Mutex<Option<Box<dyn Any + Send>>>>;
+
+/// Receiver of the service's dead letters, if it has been spawned with them.
+///
+/// The receiver is type-erased, so the handle doesn't depend on the type of
+/// the service's values.
+type DeadLetterSlot = Mutex<Option<Box<dyn Any + Send>>>;
 
 /// Service handle that allows to await for the service to join after it has
 /// been cancelled.
 ///
+/// The handle resolves to the final value of the service, if the service has
+/// completed with [`CancellationResult::BreakWith`].
+///
 /// Awaiting this future does not guarantee that the service will ever join. It
 /// the callers responsibility to ensure that the service either has been
 /// cancelled, or it will join on its own.
+///
+/// The handle dereferences to `H`, the service's handle for communicating with
+/// it. See [`Self::into_parts`] to split the two apart.
+///
+/// [`CancellationResult::BreakWith`]: crate::CancellationResult#variant.BreakWith
 #[pin_project]
-#[derive(Debug)]
-pub struct CancellableHandle<T>
+pub struct CancellableHandle<T, H = <T as LocalCancellable>::Handle>
 where
-    T: Cancellable,
+    T: LocalCancellable,
 {
     #[pin]
-    join_handle: JoinHandle<Result<(), <T as Cancellable>::Error>>,
+    join_handle: ServiceJoinHandle<T>,
     cancellation_token: CancellationToken,
-    inner: <T as Cancellable>::Handle,
+    health: watch::Receiver<Health>,
+    cancel_reason: ReasonSlot,
+    completion: CompletionSlot,
+    state: StateSlot,
+    dead_letters: DeadLetterSlot,
+    stats: StatsSlot,
+    children: Scope,
+    clock: SharedClock,
+    inner: H,
 }
 
 impl<T> CancellableHandle<T>
 where
-    T: Cancellable,
+    T: LocalCancellable,
 {
     pub(crate) fn new(
-        join_handle: JoinHandle<Result<(), <T as Cancellable>::Error>>,
+        join_handle: ServiceJoinHandle<T>,
         cancellation_token: CancellationToken,
-        inner: <T as Cancellable>::Handle,
+        inner: <T as LocalCancellable>::Handle,
     ) -> Self {
+        let (_, health) = watch::channel(Health::default());
+        let children = Scope::new(cancellation_token.child_token());
+
         Self {
             join_handle,
             cancellation_token,
+            health,
+            cancel_reason: ReasonSlot::default(),
+            completion: CompletionSlot::default(),
+            state: StateSlot::default(),
+            dead_letters: DeadLetterSlot::default(),
+            stats: StatsSlot::default(),
+            children,
+            clock: SharedClock::default(),
             inner,
         }
     }
 
+    pub(crate) fn with_health(mut self, health: watch::Receiver<Health>) -> Self {
+        self.health = health;
+        self
+    }
+
+    pub(crate) fn with_cancel_reason(mut self, cancel_reason: ReasonSlot) -> Self {
+        self.cancel_reason = cancel_reason;
+        self
+    }
+
+    pub(crate) fn with_completion(mut self, completion: CompletionSlot) -> Self {
+        self.completion = completion;
+        self
+    }
+
+    pub(crate) fn with_children(mut self, children: Scope) -> Self {
+        self.children = children;
+        self
+    }
+
+    pub(crate) fn with_state(mut self, state: StateSlot) -> Self {
+        self.state = state;
+        self
+    }
+
+    pub(crate) fn with_stats(mut self, stats: StatsSlot) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    pub(crate) fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub(crate) fn with_dead_letters<R>(mut self, dead_letters: mpsc::Receiver<R>) -> Self
+    where
+        R: Send + 'static,
+    {
+        self.dead_letters = Mutex::new(Some(Box::new(dead_letters)));
+        self
+    }
+}
+
+impl<T, H> CancellableHandle<T, H>
+where
+    T: LocalCancellable,
+{
+    pub(crate) fn into_raw_parts(self) -> (ServiceJoinHandle<T>, CancellationToken, H) {
+        (self.join_handle, self.cancellation_token, self.inner)
+    }
+
+    /// Splits the handle into its join side and the service's handle.
+    ///
+    /// The returned join side can still cancel and join the service, e.g. in
+    /// a supervising task, while the service's handle is moved elsewhere.
+    pub fn into_parts(self) -> (CancellableHandle<T, ()>, H) {
+        let join = CancellableHandle {
+            join_handle: self.join_handle,
+            cancellation_token: self.cancellation_token,
+            health: self.health,
+            cancel_reason: self.cancel_reason,
+            completion: self.completion,
+            state: self.state,
+            dead_letters: self.dead_letters,
+            stats: self.stats,
+            children: self.children,
+            clock: self.clock,
+            inner: (),
+        };
+
+        (join, self.inner)
+    }
+
+    /// Returns a reference to the service's handle.
+    pub fn handle(&self) -> &H {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the service's handle.
+    pub fn handle_mut(&mut self) -> &mut H {
+        &mut self.inner
+    }
+
     /// Cancels the service from which this handle has been spawned.
     ///
     /// When a service is cancelled it completes immediately. This operation is
     /// not reversible.
+    ///
+    /// The handle owns a child of the token the service has been spawned with,
+    /// so it cancels only this service, whether the token is shared with other
+    /// services or not. See [`Cancellable::spawn_detached`].
+    ///
+    /// [`Cancellable::spawn_detached`]: crate::Cancellable::spawn_detached
     pub fn cancel(&self) {
         self.cancellation_token.cancel();
     }
+
+    /// Returns a token for a sub-task of the service, e.g. a helper task
+    /// spawned alongside it.
+    ///
+    /// The token is cancelled with [`Self::cancel_children`], when the service
+    /// is cancelled, or once it completes. Cancelling the token itself doesn't
+    /// cancel the service. See [`RunContext::child_token`].
+    ///
+    /// [`RunContext::child_token`]: crate::RunContext::child_token
+    pub fn child_token(&self) -> CancellationToken {
+        self.children.child_token()
+    }
+
+    /// Cancels the service's sub-tasks, while the service itself keeps
+    /// running.
+    ///
+    /// It cancels the tokens returned by [`Self::child_token`] and
+    /// [`RunContext::child_token`] so far, along with the services spawned
+    /// with [`RunContext::spawn_child`]. The tokens and children created
+    /// afterwards aren't affected.
+    ///
+    /// [`RunContext::child_token`]: crate::RunContext::child_token
+    /// [`RunContext::spawn_child`]: crate::RunContext::spawn_child
+    pub fn cancel_children(&self) {
+        self.children.cancel_spawned();
+    }
+
+    /// Cancels the service with the given reason.
+    ///
+    /// The reason is observable from within the service with
+    /// [`RunContext::cancel_reason`], and from the handle with
+    /// [`Self::cancel_reason`] and [`Self::join_with_reason`]. Only the first
+    /// reason is kept, if the service is cancelled more than once.
+    ///
+    /// [`RunContext::cancel_reason`]: crate::RunContext::cancel_reason
+    pub fn cancel_with_reason<R>(&self, reason: R)
+    where
+        R: std::any::Any + Send + Sync,
+    {
+        let _ = self.cancel_reason.set(CancelReason::new(reason));
+        self.cancel();
+    }
+
+    /// Returns the reason the service has been cancelled with.
+    ///
+    /// See [`Self::cancel_with_reason`].
+    pub fn cancel_reason(&self) -> Option<CancelReason> {
+        self.cancel_reason.get().cloned()
+    }
+
+    /// Returns the health most recently reported by the service.
+    ///
+    /// See [`RunContext::report_health`].
+    ///
+    /// [`RunContext::report_health`]: crate::RunContext::report_health
+    pub fn health(&self) -> Health {
+        self.health.borrow().clone()
+    }
+
+    /// Returns a snapshot of the service's counters, e.g. the number of its
+    /// iterations, or the time it has spent working so far.
+    ///
+    /// See [`ServiceStats`].
+    pub fn stats(&self) -> ServiceStats {
+        self.stats.snapshot()
+    }
+
+    /// Returns a receiver of the health reported by the service.
+    ///
+    /// The receiver is notified every time the service reports its health.
+    pub fn watch_health(&self) -> watch::Receiver<Health> {
+        self.health.clone()
+    }
+
+    /// Aborts the service's task.
+    ///
+    /// Unlike [`Self::cancel`], the service is stopped at its next await
+    /// point, without running any of its hooks, e.g. [`on_stop`]. Awaiting the
+    /// handle afterwards results in [`CancellableError::Cancelled`], unless the
+    /// service has already completed.
+    ///
+    /// [`on_stop`]: crate::Cancellable::on_stop
+    pub fn abort(&self) {
+        self.join_handle.abort();
+    }
+
+    /// Detaches the service's task, returning the service's handle.
+    ///
+    /// The service keeps running in the background, but it can no longer be
+    /// cancelled or joined through this handle.
+    pub fn detach(self) -> H {
+        self.inner
+    }
+
+    /// Waits for the service to complete.
+    ///
+    /// It's equivalent to awaiting the handle itself, but flattens the nested
+    /// result into a single [`CancellableError`].
+    pub async fn join(
+        self,
+    ) -> Result<
+        Option<<T as LocalCancellable>::Output>,
+        CancellableError<<T as LocalCancellable>::Error>,
+    > {
+        self.await?.map_err(CancellableError::Service)
+    }
+
+    /// Waits for the service to complete, just like [`Self::join`], and
+    /// returns its result along with the reason it has been cancelled with.
+    ///
+    /// See [`Self::cancel_with_reason`].
+    pub async fn join_with_reason(
+        self,
+    ) -> (
+        Result<
+            Option<<T as LocalCancellable>::Output>,
+            CancellableError<<T as LocalCancellable>::Error>,
+        >,
+        Option<CancelReason>,
+    ) {
+        let cancel_reason = Arc::clone(&self.cancel_reason);
+        let result = self.join().await;
+
+        (result, cancel_reason.get().cloned())
+    }
+
+    /// Waits for the service to complete, just like [`Self::join`], and
+    /// returns its result along with the reason it has completed.
+    ///
+    /// See [`CompletionReason`].
+    pub async fn join_with_completion(
+        self,
+    ) -> (
+        Result<
+            Option<<T as LocalCancellable>::Output>,
+            CancellableError<<T as LocalCancellable>::Error>,
+        >,
+        CompletionReason,
+    ) {
+        let completion = Arc::clone(&self.completion);
+        let result = self.join().await;
+
+        let reason = match &result {
+            Err(CancellableError::Panicked(_)) => CompletionReason::Panicked,
+            Err(CancellableError::Cancelled) => CompletionReason::Aborted,
+            Err(CancellableError::Service(_)) => completion
+                .get()
+                .copied()
+                .unwrap_or(CompletionReason::Failed),
+            Ok(_) => completion
+                .get()
+                .copied()
+                .unwrap_or(CompletionReason::Completed),
+        };
+        (result, reason)
+    }
+
+    /// Waits for the service to complete, just like [`Self::join`], and
+    /// returns its result along with the service itself.
+    ///
+    /// The service is returned only if it has been spawned with
+    /// [`SpawnOptions::retain_state`], and its task hasn't panicked or been
+    /// aborted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cancellable::{
+    ///     CallbackResult, Cancellable, CancellationResult, CancellationToken, SpawnOptions,
+    /// };
+    ///
+    /// struct Counter {
+    ///     count: u32,
+    /// }
+    ///
+    /// impl Cancellable for Counter {
+    ///     type Result = ();
+    ///     type Handle = ();
+    ///     type Error = std::io::Error;
+    ///     type Output = ();
+    ///
+    ///     async fn new_handle(&mut self) -> Self::Handle {}
+    ///
+    ///     async fn run(&mut self) -> Result<CancellationResult<(), ()>, Self::Error> {
+    ///         self.count += 1;
+    ///         match self.count {
+    ///             3 => Ok(CancellationResult::Break),
+    ///             _ => Ok(CancellationResult::Continue),
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let options = SpawnOptions::new().retain_state();
+    /// let handle = Counter { count: 0 }
+    ///     .spawn_with_options(CancellationToken::new(), options, |_| {
+    ///         CallbackResult::Continue
+    ///     })
+    ///     .await;
+    ///
+    /// let (result, counter) = handle.join_with_state().await;
+    /// assert!(result.is_ok());
+    /// assert_eq!(3, counter.unwrap().count);
+    /// # }
+    /// ```
+    ///
+    /// [`SpawnOptions::retain_state`]: crate::SpawnOptions::retain_state
+    pub async fn join_with_state(
+        self,
+    ) -> (
+        Result<
+            Option<<T as LocalCancellable>::Output>,
+            CancellableError<<T as LocalCancellable>::Error>,
+        >,
+        Option<T>,
+    )
+    where
+        T: 'static,
+    {
+        let state = Arc::clone(&self.state);
+        let result = self.join().await;
+        let service = state
+            .lock()
+            .expect("lock not to be poisoned")
+            .take()
+            .and_then(|service| service.downcast().ok())
+            .map(|service| *service);
+
+        (result, service)
+    }
+
+    /// Takes the receiver of the values which couldn't be delivered, if the
+    /// service has been spawned with [`SpawnOptions::dead_letters`].
+    ///
+    /// The receiver can be taken only once. It keeps receiving the dead
+    /// letters while the service runs, and holds the remaining ones after it
+    /// has completed, so they can be inspected or replayed.
+    ///
+    /// [`SpawnOptions::dead_letters`]: crate::SpawnOptions::dead_letters
+    pub fn take_dead_letters(&self) -> Option<mpsc::Receiver<<T as LocalCancellable>::Result>>
+    where
+        T: 'static,
+    {
+        self.dead_letters
+            .lock()
+            .expect("lock not to be poisoned")
+            .take()
+            .and_then(|receiver| receiver.downcast().ok())
+            .map(|receiver| *receiver)
+    }
+
+    /// Cancels the service and waits for it to complete for at most
+    /// `timeout`, measured with the service's clock, see
+    /// [`SpawnOptions::clock`].
+    ///
+    /// If the service doesn't complete in time, then its task is aborted. See
+    /// [`Self::abort`].
+    ///
+    /// # Returns
+    ///
+    /// The flattened result of the service, just like [`Self::join`], or
+    /// `None` if the service had to be aborted.
+    ///
+    /// [`SpawnOptions::clock`]: crate::SpawnOptions::clock
+    pub async fn cancel_with_timeout(
+        mut self,
+        timeout: Duration,
+    ) -> Option<
+        Result<
+            Option<<T as LocalCancellable>::Output>,
+            CancellableError<<T as LocalCancellable>::Error>,
+        >,
+    > {
+        self.cancel();
+        let clock = self.clock.clone();
+        match clock.timeout(timeout, &mut self.join_handle).await {
+            Some(Ok(result)) => Some(result.map_err(CancellableError::Service)),
+            Some(Err(e)) => Some(Err(e.into())),
+            None => {
+                self.abort();
+                None
+            }
+        }
+    }
+
+    /// Checks if the service has completed.
+    ///
+    /// This method doesn't wait for the service to complete.
+    pub fn is_finished(&self) -> bool {
+        self.join_handle.is_finished()
+    }
+
+    /// Returns the output of the service, if it has already completed.
+    ///
+    /// This method doesn't wait for the service to complete. Once it returns
+    /// `Some`, the output has been taken and the handle must not be awaited
+    /// anymore.
+    pub fn try_join(&mut self) -> Option<<Self as Future>::Output> {
+        if !self.is_finished() {
+            return None;
+        }
+
+        let waker = Waker::from(Arc::new(NoopWaker));
+        let mut cx = Context::from_waker(&waker);
+        match Pin::new(&mut self.join_handle).poll(&mut cx) {
+            Poll::Ready(output) => Some(output),
+            Poll::Pending => None,
+        }
+    }
 }
 
-impl<T> std::future::Future for CancellableHandle<T>
+impl<T, H> CancellableHandle<T, H>
 where
-    T: Cancellable,
+    T: LocalCancellable,
+    H: Clone,
 {
-    type Output = Result<Result<(), <T as Cancellable>::Error>, JoinError>;
+    /// Returns a new handle for communicating with the service.
+    ///
+    /// The returned handle is independent of this one, so it can be moved to
+    /// another task, e.g. to one of many producers feeding the service.
+    pub fn clone_handle(&self) -> H {
+        self.inner.clone()
+    }
+}
+
+impl<T, H> CancellableHandle<T, H>
+where
+    T: LocalCancellable<Error = Infallible>,
+{
+    /// Waits for a service which cannot fail to complete.
+    ///
+    /// It's equivalent to [`Self::join`], but since the service's error is
+    /// [`Infallible`], the only error left is the one of its task, i.e. the
+    /// task has panicked or has been aborted.
+    pub async fn join_infallible(
+        self,
+    ) -> Result<Option<<T as LocalCancellable>::Output>, JoinError> {
+        match self.await? {
+            Ok(output) => Ok(output),
+            Err(never) => match never {},
+        }
+    }
+}
 
-    fn poll(self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
+impl<T, H> Future for CancellableHandle<T, H>
+where
+    T: LocalCancellable,
+{
+    type Output = Result<ServiceResult<T>, JoinError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         let this = self.project();
         this.join_handle.poll(cx)
     }
 }
 
-impl<T> Deref for CancellableHandle<T>
+/// Waker of a single poll, whose result doesn't need to be awaited.
+struct NoopWaker;
+
+impl Wake for NoopWaker {
+    fn wake(self: Arc<Self>) {}
+}
+
+impl<T, H> std::fmt::Debug for CancellableHandle<T, H>
+where
+    T: LocalCancellable,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // The service's handle isn't required to implement `Debug`.
+        f.debug_struct("CancellableHandle")
+            .field("join_handle", &self.join_handle)
+            .field("cancellation_token", &self.cancellation_token)
+            .field("health", &self.health)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, H> Deref for CancellableHandle<T, H>
 where
-    T: Cancellable,
+    T: LocalCancellable,
 {
-    type Target = <T as Cancellable>::Handle;
+    type Target = H;
 
     fn deref(&self) -> &Self::Target {
         &self.inner
     }
 }
 
-impl<T> DerefMut for CancellableHandle<T>
+impl<T, H> DerefMut for CancellableHandle<T, H>
 where
-    T: Cancellable,
+    T: LocalCancellable,
 {
     fn deref_mut(&mut self) -> &mut Self::Target {
         &mut self.inner
@@ -86,21 +599,28 @@ where
 
 #[cfg(test)]
 mod tests {
-    use std::time::Duration;
+    use std::{sync::Arc, time::Duration};
 
+    use futures::StreamExt;
+    use tokio::time::timeout;
     use tokio_util::sync::CancellationToken;
 
-    use crate::{Cancellable, CancellableHandle, CancellationResult};
+    use crate::{
+        clock::InstantClock, CallbackResult, Cancellable, CancellableError, CancellableHandle,
+        CancellationResult, SenderHandle, SpawnOptions,
+    };
 
     struct MockCancellable {}
 
-    #[async_trait::async_trait]
     impl Cancellable for MockCancellable {
         type Result = ();
         type Handle = ();
         type Error = anyhow::Error;
+        type Output = ();
 
-        async fn run(&mut self) -> Result<CancellationResult<Self::Result>, Self::Error> {
+        async fn run(
+            &mut self,
+        ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
             Ok(CancellationResult::Continue)
         }
 
@@ -132,7 +652,7 @@ mod tests {
     async fn should_cancel_token_when_call_cancel() {
         // Arrange
         let cancellation_token = CancellationToken::new();
-        let task = tokio::spawn(async { Ok(()) });
+        let task = tokio::spawn(async { Ok(None) });
         let handle =
             CancellableHandle::<MockCancellable>::new(task, cancellation_token.child_token(), ());
 
@@ -142,4 +662,294 @@ mod tests {
         // Assert
         assert!(handle.cancellation_token.is_cancelled());
     }
+
+    #[tokio::test]
+    async fn should_not_be_finished_when_running() {
+        // Arrange
+        let task = tokio::spawn(std::future::pending());
+        let mut handle =
+            CancellableHandle::<MockCancellable>::new(task, CancellationToken::new(), ());
+
+        // Act
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // Assert
+        assert!(!handle.is_finished());
+        assert!(handle.try_join().is_none());
+    }
+
+    #[tokio::test]
+    async fn should_return_output_when_finished() {
+        // Arrange
+        let task = tokio::spawn(async { Ok(None) });
+        let mut handle =
+            CancellableHandle::<MockCancellable>::new(task, CancellationToken::new(), ());
+
+        // Act
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // Assert
+        assert!(handle.is_finished());
+        assert!(handle.try_join().unwrap().unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn should_clone_inner_handle() {
+        // Arrange
+        let service =
+            crate::from_channel(2, |item: i32| async move { Ok::<_, anyhow::Error>(item) });
+        let (handle, items) = service.spawn_stream(CancellationToken::new()).await;
+        let producers = [handle.clone_handle(), handle.clone_handle()];
+        drop(handle);
+
+        // Act
+        for (item, producer) in producers.into_iter().enumerate() {
+            tokio::spawn(async move { producer.send(item as i32).await });
+        }
+
+        // Assert
+        let mut items = items.collect::<Vec<_>>().await;
+        items.sort();
+        assert_eq!(vec![0, 1], items);
+    }
+
+    #[tokio::test]
+    async fn should_split_into_join_side_and_inner_handle() {
+        // Arrange
+        let service =
+            crate::from_channel(1, |item: i32| async move { Ok::<_, anyhow::Error>(item) });
+        let (handle, mut items) = service.spawn_stream(CancellationToken::new()).await;
+
+        // Act
+        let (join, sender) = handle.into_parts();
+        let producer = tokio::spawn(async move { sender.send(42).await });
+
+        // Assert
+        assert_eq!(Some(42), items.next().await);
+        assert!(producer.await.unwrap().is_ok());
+        join.cancel();
+        assert!(join.join().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn should_fail_with_cancelled_when_aborted() {
+        // Arrange
+        let task = tokio::spawn(std::future::pending());
+        let handle = CancellableHandle::<MockCancellable>::new(task, CancellationToken::new(), ());
+
+        // Act
+        handle.abort();
+
+        // Assert
+        assert!(matches!(
+            handle.join().await,
+            Err(CancellableError::Cancelled)
+        ));
+    }
+
+    #[tokio::test]
+    async fn should_keep_running_when_detached() {
+        // Arrange
+        let cancellation_token = CancellationToken::new();
+        let cancellation_token_clone = cancellation_token.clone();
+        let task = tokio::spawn(async move {
+            cancellation_token_clone.cancel();
+            Ok(None)
+        });
+        let handle = CancellableHandle::<MockCancellable>::new(task, CancellationToken::new(), ());
+
+        // Act
+        handle.detach();
+
+        // Assert
+        cancellation_token.cancelled().await;
+    }
+
+    #[tokio::test]
+    async fn should_join_when_cancelled_with_timeout() {
+        // Arrange
+        let cancellation_token = CancellationToken::new();
+        let cancelled = cancellation_token.clone();
+        let task = tokio::spawn(async move {
+            cancelled.cancelled().await;
+            Ok(None)
+        });
+        let handle = CancellableHandle::<MockCancellable>::new(task, cancellation_token, ());
+
+        // Act
+        let result = handle.cancel_with_timeout(Duration::from_secs(1)).await;
+
+        // Assert
+        assert!(matches!(result, Some(Ok(None))));
+    }
+
+    #[tokio::test]
+    async fn should_abort_when_cancel_times_out() {
+        // Arrange
+        let task = tokio::spawn(std::future::pending());
+        let handle = CancellableHandle::<MockCancellable>::new(task, CancellationToken::new(), ());
+
+        // Act
+        let result = handle.cancel_with_timeout(Duration::from_millis(50)).await;
+
+        // Assert
+        assert!(result.is_none());
+    }
+
+    struct StuckCancellable {}
+
+    impl Cancellable for StuckCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<(), ()>, Self::Error> {
+            std::future::pending().await
+        }
+
+        async fn on_cancel(&mut self) {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn should_measure_cancel_timeout_with_service_clock() {
+        // Arrange
+        let options = SpawnOptions::new().clock(Arc::new(InstantClock));
+        let handle = StuckCancellable {}
+            .spawn_with_options(CancellationToken::new(), options, |_| {
+                CallbackResult::Continue
+            })
+            .await;
+
+        // Act
+        let result = timeout(
+            Duration::from_secs(1),
+            handle.cancel_with_timeout(Duration::from_secs(3600)),
+        )
+        .await;
+
+        // Assert
+        assert!(result.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn should_flatten_service_error_when_joined() {
+        // Arrange
+        let task = tokio::spawn(async { Err(anyhow::anyhow!("MockCancellable error")) });
+        let handle = CancellableHandle::<MockCancellable>::new(task, CancellationToken::new(), ());
+
+        // Act
+        let result = handle.join().await;
+
+        // Assert
+        assert!(matches!(result, Err(CancellableError::Service(_))));
+    }
+
+    struct OpaqueHandle;
+
+    struct OpaqueCancellable {}
+
+    impl Cancellable for OpaqueCancellable {
+        type Result = ();
+        type Handle = OpaqueHandle;
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn run(
+            &mut self,
+        ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+            Ok(CancellationResult::Break)
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {
+            OpaqueHandle
+        }
+    }
+
+    #[tokio::test]
+    async fn should_format_handle_without_debug_inner_handle() {
+        // Arrange
+        let task = tokio::spawn(async { Ok(None) });
+        let handle = CancellableHandle::<OpaqueCancellable>::new(
+            task,
+            CancellationToken::new(),
+            OpaqueHandle,
+        );
+
+        // Act
+        let formatted = format!("{handle:?}");
+
+        // Assert
+        assert!(formatted.starts_with("CancellableHandle {"));
+        assert!(formatted.ends_with(", .. }"));
+    }
+
+    #[tokio::test]
+    async fn should_join_infallible_service() {
+        // Arrange
+        let service = crate::from_stream(futures::stream::iter([1, 2, 3]));
+        let (handle, items) = service.spawn_stream(CancellationToken::new()).await;
+
+        // Act
+        let items = items.collect::<Vec<_>>().await;
+        let result = handle.join_infallible().await;
+
+        // Assert
+        assert_eq!(vec![1, 2, 3], items);
+        assert!(matches!(result, Ok(None)));
+    }
+
+    #[tokio::test]
+    async fn should_return_service_when_state_is_retained() {
+        // Arrange
+        let options = SpawnOptions::new().retain_state();
+        let handle = MockCancellable {}
+            .spawn_with_options(CancellationToken::new(), options, |_| {
+                CallbackResult::Continue
+            })
+            .await;
+
+        // Act
+        handle.cancel();
+        let (result, service) = handle.join_with_state().await;
+
+        // Assert
+        assert!(result.is_ok());
+        assert!(service.is_some());
+    }
+
+    #[tokio::test]
+    async fn should_drop_service_by_default() {
+        // Arrange
+        let handle = MockCancellable {}.spawn(CancellationToken::new()).await;
+
+        // Act
+        handle.cancel();
+        let (result, service) = handle.join_with_state().await;
+
+        // Assert
+        assert!(result.is_ok());
+        assert!(service.is_none());
+    }
+
+    #[tokio::test]
+    async fn should_cancel_children_without_cancelling_service() {
+        // Arrange
+        let handle = MockCancellable {}.spawn(CancellationToken::new()).await;
+        let child_token = handle.child_token();
+
+        // Act
+        handle.cancel_children();
+
+        // Assert
+        assert!(child_token.is_cancelled());
+        assert!(!handle.child_token().is_cancelled());
+        assert!(!handle.is_finished());
+        handle.cancel();
+        assert!(handle.join().await.is_ok());
+    }
 }
@@ -0,0 +1,254 @@
+use tokio::task::{AbortHandle, JoinSet};
+use tokio_util::sync::CancellationToken;
+
+use crate::{cancellable_handle::ServiceResult, Cancellable, CancellableError};
+
+/// Aborts the service's task when the task joining it is dropped or aborted.
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// Collection of homogeneous services spawned dynamically.
+///
+/// It's similar to [`JoinSet`], but it preserves the types of the services'
+/// results. All services of the set are spawned with child tokens of the
+/// set's token, so they can be cancelled at once with [`Self::cancel`].
+/// Dropping the set aborts all of its services.
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{
+///     Cancellable, CancellableSet, CancellationResult, CancellationToken,
+/// };
+///
+/// struct Connection;
+///
+/// impl Cancellable for Connection {
+///     type Result = ();
+///     type Handle = ();
+///     type Error = std::io::Error;
+///     type Output = ();
+///
+///     async fn new_handle(&mut self) -> Self::Handle {}
+///
+///     async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+///         Ok(CancellationResult::Break)
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mut connections = CancellableSet::new(CancellationToken::new());
+/// connections.spawn(Connection).await;
+/// connections.spawn(Connection).await;
+///
+/// while let Some(result) = connections.join_next().await {
+///     result.unwrap();
+/// }
+/// # }
+/// ```
+pub struct CancellableSet<T>
+where
+    T: Cancellable,
+{
+    join_set: JoinSet<Result<ServiceResult<T>, CancellableError<T::Error>>>,
+    cancellation_token: CancellationToken,
+}
+
+impl<T> CancellableSet<T>
+where
+    T: Cancellable + 'static,
+    T::Output: 'static,
+    T::Error: 'static,
+{
+    /// Constructs a new, empty set, whose services are cancelled together with
+    /// `cancellation_token`.
+    pub fn new(cancellation_token: CancellationToken) -> Self {
+        Self {
+            join_set: JoinSet::new(),
+            cancellation_token,
+        }
+    }
+
+    /// Spawns `service` into the set.
+    ///
+    /// # Returns
+    ///
+    /// The handle for communicating with the service.
+    pub async fn spawn(&mut self, service: T) -> T::Handle {
+        let handle = service.spawn(self.cancellation_token.child_token()).await;
+        let (join_handle, _, inner) = handle.into_raw_parts();
+
+        let guard = AbortOnDrop(join_handle.abort_handle());
+        self.join_set.spawn(async move {
+            let _guard = guard;
+            join_handle.await.map_err(CancellableError::from)
+        });
+
+        inner
+    }
+
+    /// Waits for any of the services to complete.
+    ///
+    /// # Returns
+    ///
+    /// The result of the completed service, or `None` if the set is empty.
+    pub async fn join_next(
+        &mut self,
+    ) -> Option<Result<Option<T::Output>, CancellableError<T::Error>>> {
+        let result = self.join_set.join_next().await?;
+
+        Some(match result {
+            Ok(Ok(result)) => result.map_err(CancellableError::Service),
+            Ok(Err(e)) => Err(e),
+            Err(e) => Err(e.into()),
+        })
+    }
+
+    /// Cancels all services of the set.
+    ///
+    /// Services spawned into the set afterwards are cancelled immediately.
+    pub fn cancel(&self) {
+        self.cancellation_token.cancel();
+    }
+
+    /// Aborts all services of the set.
+    ///
+    /// See [`CancellableHandle::abort`].
+    ///
+    /// [`CancellableHandle::abort`]: crate::CancellableHandle::abort
+    pub fn abort_all(&mut self) {
+        self.join_set.abort_all();
+    }
+
+    /// Cancels all services of the set and waits for them to complete.
+    pub async fn shutdown(&mut self) {
+        self.cancel();
+        while self.join_set.join_next().await.is_some() {}
+    }
+
+    /// Returns the number of services in the set, including the completed
+    /// ones which haven't been joined yet.
+    pub fn len(&self) -> usize {
+        self.join_set.len()
+    }
+
+    /// Checks if the set has no services.
+    pub fn is_empty(&self) -> bool {
+        self.join_set.is_empty()
+    }
+}
+
+impl<T> std::fmt::Debug for CancellableSet<T>
+where
+    T: Cancellable,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CancellableSet")
+            .field("len", &self.join_set.len())
+            .field("cancellation_token", &self.cancellation_token)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{Cancellable, CancellableError, CancellableSet, CancellationResult};
+
+    struct EchoCancellable {
+        value: i32,
+    }
+
+    impl Cancellable for EchoCancellable {
+        type Result = ();
+        type Handle = i32;
+        type Error = anyhow::Error;
+        type Output = i32;
+
+        async fn new_handle(&mut self) -> Self::Handle {
+            self.value
+        }
+
+        async fn run(&mut self) -> Result<CancellationResult<(), i32>, Self::Error> {
+            match self.value {
+                0 => std::future::pending().await,
+                value if value < 0 => Err(anyhow::anyhow!("EchoCancellable error")),
+                value => Ok(CancellationResult::BreakWith(value)),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn should_join_all_services() {
+        // Arrange
+        let mut set = CancellableSet::new(CancellationToken::new());
+        let handles = [
+            set.spawn(EchoCancellable { value: 1 }).await,
+            set.spawn(EchoCancellable { value: 2 }).await,
+        ];
+
+        // Act
+        let mut outputs = Vec::new();
+        while let Some(result) = set.join_next().await {
+            outputs.push(result.unwrap().unwrap());
+        }
+        outputs.sort();
+
+        // Assert
+        assert_eq!([1, 2], handles);
+        assert_eq!(vec![1, 2], outputs);
+        assert!(set.is_empty());
+    }
+
+    #[tokio::test]
+    async fn should_return_service_error() {
+        // Arrange
+        let mut set = CancellableSet::new(CancellationToken::new());
+        set.spawn(EchoCancellable { value: -1 }).await;
+
+        // Act
+        let result = set.join_next().await.unwrap();
+
+        // Assert
+        assert!(matches!(result, Err(CancellableError::Service(_))));
+    }
+
+    #[tokio::test]
+    async fn should_cancel_all_services() {
+        // Arrange
+        let mut set = CancellableSet::new(CancellationToken::new());
+        set.spawn(EchoCancellable { value: 0 }).await;
+        set.spawn(EchoCancellable { value: 0 }).await;
+
+        // Act
+        set.cancel();
+
+        // Assert
+        assert!(set.join_next().await.unwrap().unwrap().is_none());
+        assert!(set.join_next().await.unwrap().unwrap().is_none());
+        assert!(set.join_next().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn should_abort_all_services() {
+        // Arrange
+        let mut set = CancellableSet::new(CancellationToken::new());
+        set.spawn(EchoCancellable { value: 0 }).await;
+
+        // Act
+        set.abort_all();
+
+        // Assert
+        assert!(matches!(
+            set.join_next().await.unwrap(),
+            Err(CancellableError::Cancelled)
+        ));
+    }
+}
@@ -0,0 +1,22 @@
+/// Priority of a service's cancellation over the iteration in flight.
+///
+/// The work loop never starts a new iteration once the service has been
+/// cancelled. The priority decides only about a call to
+/// [`Cancellable::run`] which has completed by the time the work loop observes
+/// the cancellation, so the outcome is deterministic either way.
+///
+/// See [`SpawnOptions::cancellation_priority`].
+///
+/// [`Cancellable::run`]: crate::Cancellable::run
+/// [`SpawnOptions::cancellation_priority`]: crate::SpawnOptions::cancellation_priority
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CancellationPriority {
+    /// The result of the completed iteration is handled first, e.g. its
+    /// values are passed to the callback.
+    #[default]
+    Iteration,
+
+    /// The cancellation is handled first, and the result of the completed
+    /// iteration is dropped.
+    Cancellation,
+}
@@ -1,18 +1,62 @@
+use std::time::Duration;
+
 /// Result of a single iteration of the service loop.
+///
+/// `O` is the type of the final value the service can complete with. See
+/// [`Cancellable::Output`].
+///
+/// [`Cancellable::Output`]: crate::Cancellable::Output
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
-pub enum CancellationResult<T> {
+pub enum CancellationResult<T, O = ()> {
     /// Indicates that the loop should continue and wraps value yielded by the
     /// finished iteration.
     Item(T),
 
+    /// Indicates that the loop should continue and wraps values yielded by the
+    /// finished iteration.
+    ///
+    /// The values are passed to the callback one by one, in order. If the
+    /// callback rejects any of them, then the remaining values are discarded.
+    Items(Vec<T>),
+
+    /// Indicates that the loop should end and wraps the last value yielded by
+    /// the service.
+    ///
+    /// The value is passed to the callback before the loop ends.
+    LastItem(T),
+
     /// Indicates that the loop should continue.
     Continue,
 
+    /// Indicates that the loop should continue after the given delay.
+    ///
+    /// The delay is cancellation-aware, i.e. if the service is cancelled while
+    /// it's waiting, then it's cancelled immediately.
+    Delay(Duration),
+
     /// Indicates that the loop should end.
     Break,
+
+    /// Indicates that the loop should end and wraps the final value of the
+    /// service.
+    BreakWith(O),
+
+    /// Acknowledges that the service has been cancelled.
+    ///
+    /// The loop ends as if the service has been cancelled between iterations,
+    /// i.e. [`Cancellable::on_cancel`] is called and the service is drained,
+    /// if graceful shutdown is enabled. It's meant to be returned by services
+    /// spawned with [`SpawnOptions::cooperative_cancellation`], which observe
+    /// the cancellation through [`RunContext`] and finish their current unit
+    /// of work first.
+    ///
+    /// [`Cancellable::on_cancel`]: crate::Cancellable::on_cancel
+    /// [`SpawnOptions::cooperative_cancellation`]: crate::SpawnOptions::cooperative_cancellation
+    /// [`RunContext`]: crate::RunContext
+    Cancelled,
 }
 
-impl<T> CancellationResult<T> {
+impl<T, O> CancellationResult<T, O> {
     /// Constructs a new `CancellationResult::Item`.
     ///
     /// # Examples
@@ -27,9 +71,24 @@ impl<T> CancellationResult<T> {
     pub fn item(t: impl Into<T>) -> Self {
         Self::Item(t.into())
     }
+
+    /// Constructs a new `CancellationResult::Items`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cancellable::CancellationResult;
+    ///
+    /// fn construct_result() -> CancellationResult<u8> {
+    ///     CancellationResult::items(b"foo".iter().copied())
+    /// }
+    /// ```
+    pub fn items(items: impl IntoIterator<Item = T>) -> Self {
+        Self::Items(items.into_iter().collect())
+    }
 }
 
-impl<T> From<T> for CancellationResult<T> {
+impl<T, O> From<T> for CancellationResult<T, O> {
     fn from(value: T) -> Self {
         Self::Item(value)
     }
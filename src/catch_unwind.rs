@@ -0,0 +1,68 @@
+use std::{
+    any::Any,
+    future::Future,
+    panic::AssertUnwindSafe,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use pin_project::pin_project;
+
+/// Future that catches panics of the wrapped future.
+#[pin_project]
+pub(crate) struct CatchUnwind<F> {
+    #[pin]
+    future: F,
+}
+
+impl<F> CatchUnwind<F> {
+    pub(crate) fn new(future: F) -> Self {
+        Self { future }
+    }
+}
+
+impl<F> Future for CatchUnwind<F>
+where
+    F: Future,
+{
+    type Output = Result<F::Output, Box<dyn Any + Send>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let future = self.project().future;
+        match std::panic::catch_unwind(AssertUnwindSafe(|| future.poll(cx))) {
+            Ok(Poll::Ready(output)) => Poll::Ready(Ok(output)),
+            Ok(Poll::Pending) => Poll::Pending,
+            Err(panic) => Poll::Ready(Err(panic)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CatchUnwind;
+
+    #[tokio::test]
+    async fn should_return_output_when_not_panicked() {
+        // Arrange
+        let future = CatchUnwind::new(async { 42 });
+
+        // Act
+        let result = future.await;
+
+        // Assert
+        assert_eq!(42, result.unwrap());
+    }
+
+    #[tokio::test]
+    async fn should_return_payload_when_panicked() {
+        // Arrange
+        let future = CatchUnwind::new(async { panic!("CatchUnwind panic") });
+
+        // Act
+        let result: Result<(), _> = future.await;
+
+        // Assert
+        let panic = result.unwrap_err();
+        assert_eq!(Some(&"CatchUnwind panic"), panic.downcast_ref::<&str>());
+    }
+}
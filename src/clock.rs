@@ -0,0 +1,209 @@
+use std::{future::Future, pin::Pin, sync::Arc, time::Duration};
+
+use tokio::time::Instant;
+
+use crate::RunContext;
+
+/// Source of time for a service's work loop.
+///
+/// The work loop measures time and waits with the clock of the service, e.g.
+/// for the restart backoff, the delays requested by the service, the
+/// iteration and drain timeouts, the rate limit and the watchdog. So do the
+/// adapters running within the work loop, e.g.
+/// [`Cancellable::with_error_budget`], and [`IntervalCancellable`], as well as
+/// [`CancellableHandle::cancel_with_timeout`].
+///
+/// By default, it's [`TokioClock`], which follows `tokio::time::pause`, so
+/// tests can skip hours of backoff instantly. A custom clock is set with
+/// [`SpawnOptions::clock`].
+///
+/// # Examples
+///
+/// ```
+/// use std::{future::Future, pin::Pin, time::Duration};
+///
+/// use cancellable::Clock;
+/// use tokio::time::Instant;
+///
+/// /// Clock whose sleeps complete right away.
+/// struct InstantClock;
+///
+/// impl Clock for InstantClock {
+///     fn now(&self) -> Instant {
+///         Instant::now()
+///     }
+///
+///     fn sleep(&self, _duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+///         Box::pin(std::future::ready(()))
+///     }
+/// }
+/// ```
+///
+/// [`Cancellable::with_error_budget`]: crate::Cancellable::with_error_budget
+/// [`IntervalCancellable`]: crate::IntervalCancellable
+/// [`CancellableHandle::cancel_with_timeout`]: crate::CancellableHandle::cancel_with_timeout
+/// [`SpawnOptions::clock`]: crate::SpawnOptions::clock
+pub trait Clock: Send + Sync {
+    /// Returns the current instant.
+    fn now(&self) -> Instant;
+
+    /// Returns a future completing once `duration` has elapsed.
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>>;
+}
+
+/// Clock of the tokio runtime.
+///
+/// It follows `tokio::time::pause` and `tokio::time::advance`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioClock;
+
+impl Clock for TokioClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// Shared clock of a service.
+#[derive(Clone)]
+pub(crate) struct SharedClock(pub(crate) Arc<dyn Clock>);
+
+impl SharedClock {
+    /// Returns the clock of the service currently driven by the work loop, or
+    /// [`TokioClock`] if there's none.
+    pub(crate) fn current() -> Self {
+        RunContext::current()
+            .map(|context| Self(context.clock()))
+            .unwrap_or_default()
+    }
+
+    /// See [`Clock::now`].
+    pub(crate) fn now(&self) -> Instant {
+        self.0.now()
+    }
+
+    /// See [`Clock::sleep`].
+    pub(crate) fn sleep(&self, duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        self.0.sleep(duration)
+    }
+
+    /// Awaits `future` for at most `duration`.
+    ///
+    /// Returns `None` if `duration` has elapsed first.
+    pub(crate) async fn timeout<F>(&self, duration: Duration, future: F) -> Option<F::Output>
+    where
+        F: Future,
+    {
+        tokio::select! {
+            biased;
+            output = future => Some(output),
+            _ = self.sleep(duration) => None,
+        }
+    }
+}
+
+impl Default for SharedClock {
+    fn default() -> Self {
+        Self(Arc::new(TokioClock))
+    }
+}
+
+impl std::fmt::Debug for SharedClock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SharedClock")
+    }
+}
+
+/// Clock whose sleeps complete right away.
+#[cfg(test)]
+pub(crate) struct InstantClock;
+
+#[cfg(test)]
+impl Clock for InstantClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, _duration: Duration) -> Pin<Box<dyn Future<Output = ()> + Send>> {
+        Box::pin(std::future::ready(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use tokio::time::{timeout, Instant};
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{
+        clock::InstantClock, CallbackResult, Cancellable, CancellationResult, RestartPolicy,
+        SpawnOptions,
+    };
+
+    struct FlakyCancellable {
+        failures: usize,
+    }
+
+    impl Cancellable for FlakyCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<(), ()>, Self::Error> {
+            match self.failures.checked_sub(1) {
+                Some(failures) => {
+                    self.failures = failures;
+                    Err(anyhow::anyhow!("FlakyCancellable error"))
+                }
+                None => Ok(CancellationResult::Break),
+            }
+        }
+    }
+
+    fn hourly_restarts() -> SpawnOptions {
+        SpawnOptions::new().restart_policy(RestartPolicy::fixed(Duration::from_secs(3600)))
+    }
+
+    #[tokio::test]
+    async fn should_wait_with_custom_clock() {
+        // Arrange
+        let service = FlakyCancellable { failures: 2 };
+        let options = hourly_restarts().clock(Arc::new(InstantClock));
+
+        // Act
+        let handle = service
+            .spawn_with_options(CancellationToken::new(), options, |_| {
+                CallbackResult::Continue
+            })
+            .await;
+
+        // Assert
+        let result = timeout(Duration::from_secs(1), handle.join()).await;
+        assert!(result.unwrap().is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn should_follow_paused_tokio_time() {
+        // Arrange
+        let service = FlakyCancellable { failures: 2 };
+        let started = Instant::now();
+
+        // Act
+        let handle = service
+            .spawn_with_options(CancellationToken::new(), hourly_restarts(), |_| {
+                CallbackResult::Continue
+            })
+            .await;
+
+        // Assert
+        assert!(handle.join().await.is_ok());
+        assert!(started.elapsed() >= Duration::from_secs(2 * 3600));
+    }
+}
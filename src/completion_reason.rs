@@ -0,0 +1,184 @@
+use std::sync::{Arc, OnceLock};
+
+/// Slot for the reason of a service's completion, shared by its handle and
+/// its work loop.
+pub(crate) type CompletionSlot = Arc<OnceLock<CompletionReason>>;
+
+/// Reason of a service's completion.
+///
+/// It's returned along with the service's result by
+/// [`CancellableHandle::join_with_completion`], so the caller can tell, e.g.
+/// whether the service should be restarted, without inspecting its error.
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{CallbackResult, Cancellable, CancellationToken, CompletionReason};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let service = cancellable::from_stream(futures::stream::iter([1, 2, 3]));
+///
+/// let handle = service
+///     .spawn_with_callback(CancellationToken::new(), |item| match item {
+///         2 => CallbackResult::Break,
+///         _ => CallbackResult::Continue,
+///     })
+///     .await;
+///
+/// let (result, reason) = handle.join_with_completion().await;
+/// assert!(result.is_ok());
+/// assert_eq!(CompletionReason::Rejected, reason);
+/// # }
+/// ```
+///
+/// [`CancellableHandle::join_with_completion`]: crate::CancellableHandle::join_with_completion
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompletionReason {
+    /// The service has broken out of the loop on its own, e.g. with
+    /// [`CancellationResult::Break`] or [`ErrorDirective::Break`].
+    ///
+    /// [`CancellationResult::Break`]: crate::CancellationResult#variant.Break
+    /// [`ErrorDirective::Break`]: crate::ErrorDirective#variant.Break
+    Completed,
+
+    /// The service has been cancelled with its cancellation token.
+    Cancelled,
+
+    /// The callback has broken the loop, or failed, on one of the service's
+    /// items.
+    Rejected,
+
+    /// The service has failed with an error.
+    Failed,
+
+    /// The service's task has panicked.
+    Panicked,
+
+    /// The service's task has been aborted.
+    Aborted,
+
+    /// The service has been cancelled after being idle for too long.
+    ///
+    /// See [`SpawnOptions::idle_timeout`].
+    ///
+    /// [`SpawnOptions::idle_timeout`]: crate::SpawnOptions::idle_timeout
+    Idle,
+
+    /// The service's deadline has passed.
+    ///
+    /// See [`Cancellable::with_deadline`].
+    ///
+    /// [`Cancellable::with_deadline`]: crate::Cancellable::with_deadline
+    Deadline,
+}
+
+impl CompletionReason {
+    /// Checks if the service has been stopped from the outside, i.e. it has
+    /// been cancelled or aborted.
+    pub fn is_stopped(&self) -> bool {
+        matches!(self, Self::Cancelled | Self::Aborted | Self::Idle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{
+        CallbackResult, Cancellable, CancellableError, CancellationResult, CompletionReason,
+        SpawnOptions,
+    };
+
+    struct StepCancellable {
+        step: u32,
+    }
+
+    impl Cancellable for StepCancellable {
+        type Result = u32;
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<u32>, Self::Error> {
+            self.step += 1;
+            match self.step {
+                3 => Err(anyhow::anyhow!("StepCancellable error")),
+                step => Ok(CancellationResult::item(step)),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn should_report_failure_and_rejection() {
+        // Arrange
+        let failing = StepCancellable { step: 0 };
+        let rejected = StepCancellable { step: 0 };
+
+        // Act
+        let failing = failing.spawn(CancellationToken::new()).await;
+        let rejected = rejected
+            .spawn_with_callback(CancellationToken::new(), |_| {
+                CallbackResult::Fail(anyhow::anyhow!("rejected"))
+            })
+            .await;
+
+        // Assert
+        let (result, reason) = failing.join_with_completion().await;
+        assert!(matches!(result, Err(CancellableError::Service(_))));
+        assert_eq!(CompletionReason::Failed, reason);
+        let (result, reason) = rejected.join_with_completion().await;
+        assert!(result.is_err());
+        assert_eq!(CompletionReason::Rejected, reason);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn should_report_cancellation_and_idle_timeout() {
+        // Arrange
+        let options = SpawnOptions::new().idle_timeout(Duration::from_secs(60));
+        let idle = crate::from_stream(futures::stream::pending::<()>());
+        let cancelled = crate::from_stream(futures::stream::pending::<()>());
+
+        // Act
+        let idle = idle
+            .spawn_with_options(CancellationToken::new(), options, |_| {
+                CallbackResult::Continue
+            })
+            .await;
+        let cancelled = cancelled.spawn(CancellationToken::new()).await;
+        cancelled.cancel();
+
+        // Assert
+        assert_eq!(CompletionReason::Idle, idle.join_with_completion().await.1);
+        assert_eq!(
+            CompletionReason::Cancelled,
+            cancelled.join_with_completion().await.1
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn should_report_deadline_and_abort() {
+        // Arrange
+        let deadline = crate::from_stream(futures::stream::pending::<()>())
+            .with_timeout(Duration::from_secs(60));
+        let aborted = crate::from_stream(futures::stream::pending::<()>());
+
+        // Act
+        let deadline = deadline.spawn(CancellationToken::new()).await;
+        let aborted = aborted.spawn(CancellationToken::new()).await;
+        aborted.abort();
+
+        // Assert
+        let (result, reason) = deadline.join_with_completion().await;
+        assert!(result.is_err());
+        assert_eq!(CompletionReason::Deadline, reason);
+        assert_eq!(
+            CompletionReason::Aborted,
+            aborted.join_with_completion().await.1
+        );
+    }
+}
@@ -0,0 +1,210 @@
+use std::{future::Future, sync::Arc};
+
+use tokio::task::JoinSet;
+
+use crate::{Cancellable, CancellationResult, RunContext};
+
+/// Result of a single call to [`ConcurrentCancellable::run`].
+type RunResult<S> = Result<
+    CancellationResult<<S as ConcurrentCancellable>::Result, <S as ConcurrentCancellable>::Output>,
+    <S as ConcurrentCancellable>::Error,
+>;
+
+/// Defines an interface for a service whose units of work are independent of
+/// each other, so they can be performed concurrently.
+///
+/// Unlike [`Cancellable::run`], [`Self::run`] borrows the service immutably.
+/// The service is wrapped with [`Concurrent`] to be spawned.
+pub trait ConcurrentCancellable: Send + Sync + 'static {
+    /// Type of values that _can_ be yielded by the service.
+    ///
+    /// See [`Cancellable::Result`].
+    type Result: Send + 'static;
+
+    /// Error returned by [`Self::run`] method.
+    ///
+    /// See [`Cancellable::Error`].
+    type Error: std::fmt::Debug + std::fmt::Display + Send + 'static;
+
+    /// Type of the final value the service _can_ complete with.
+    ///
+    /// See [`Cancellable::Output`].
+    type Output: std::fmt::Debug + Send + 'static;
+
+    /// Performs a single unit of work.
+    ///
+    /// See [`Cancellable::run`].
+    fn run(
+        &self,
+    ) -> impl Future<Output = Result<CancellationResult<Self::Result, Self::Output>, Self::Error>> + Send;
+}
+
+/// Service performing up to a given number of units of work of a
+/// [`ConcurrentCancellable`] concurrently.
+///
+/// Each unit of work runs in its own task. Values yielded by the units are
+/// passed to the callback in the order of their completion. As soon as any
+/// unit breaks or fails, the service completes and the remaining units are
+/// aborted.
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{
+///     Cancellable, CancellationResult, CancellationToken, Concurrent, ConcurrentCancellable,
+/// };
+///
+/// struct Acceptor;
+///
+/// impl ConcurrentCancellable for Acceptor {
+///     type Result = ();
+///     type Error = std::io::Error;
+///     type Output = ();
+///
+///     async fn run(&self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+///         // Accept and handle a single connection...
+///         Ok(CancellationResult::Break)
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let handle = Concurrent::new(Acceptor, 16)
+///     .spawn(CancellationToken::new())
+///     .await;
+/// # }
+/// ```
+pub struct Concurrent<S>
+where
+    S: ConcurrentCancellable,
+{
+    service: Arc<S>,
+    limit: usize,
+    in_flight: JoinSet<RunResult<S>>,
+}
+
+impl<S> Concurrent<S>
+where
+    S: ConcurrentCancellable,
+{
+    /// Constructs a service performing up to `limit` units of work of
+    /// `service` concurrently.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `limit` is zero.
+    pub fn new(service: S, limit: usize) -> Self {
+        assert!(limit > 0, "concurrency limit must be positive");
+
+        Self {
+            service: Arc::new(service),
+            limit,
+            in_flight: JoinSet::new(),
+        }
+    }
+}
+
+impl<S> std::fmt::Debug for Concurrent<S>
+where
+    S: ConcurrentCancellable,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Concurrent")
+            .field("limit", &self.limit)
+            .field("in_flight", &self.in_flight.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> Cancellable for Concurrent<S>
+where
+    S: ConcurrentCancellable,
+{
+    type Result = S::Result;
+    type Handle = ();
+    type Error = S::Error;
+    type Output = S::Output;
+
+    async fn new_handle(&mut self) -> Self::Handle {}
+
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        while self.in_flight.len() < self.limit {
+            let service = Arc::clone(&self.service);
+            let unit = async move { service.run().await };
+            match RunContext::current() {
+                Some(context) => self.in_flight.spawn(context.enter(unit)),
+                None => self.in_flight.spawn(unit),
+            };
+        }
+
+        match self.in_flight.join_next().await {
+            Some(Ok(result)) => result,
+            Some(Err(e)) if e.is_panic() => std::panic::resume_unwind(e.into_panic()),
+            _ => Ok(CancellationResult::Continue),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+
+    use futures::StreamExt;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{Cancellable, CancellationResult, Concurrent, ConcurrentCancellable};
+
+    struct CountingCancellable {
+        running: AtomicUsize,
+        max_running: AtomicUsize,
+        started: AtomicUsize,
+    }
+
+    impl ConcurrentCancellable for CountingCancellable {
+        type Result = usize;
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn run(&self) -> Result<CancellationResult<usize>, Self::Error> {
+            let id = self.started.fetch_add(1, Ordering::SeqCst);
+            if id >= 8 {
+                return std::future::pending().await;
+            }
+
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_running.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+
+            Ok(CancellationResult::Item(id))
+        }
+    }
+
+    #[tokio::test]
+    async fn should_run_units_concurrently() {
+        // Arrange
+        let service = Concurrent::new(
+            CountingCancellable {
+                running: AtomicUsize::new(0),
+                max_running: AtomicUsize::new(0),
+                started: AtomicUsize::new(0),
+            },
+            4,
+        );
+        let service_ref = std::sync::Arc::clone(&service.service);
+
+        // Act
+        let (handle, items) = service.spawn_stream(CancellationToken::new()).await;
+        let mut items = items.take(8).collect::<Vec<_>>().await;
+        handle.cancel();
+        handle.join().await.unwrap();
+
+        // Assert
+        items.sort();
+        assert_eq!((0..8).collect::<Vec<_>>(), items);
+        assert_eq!(4, service_ref.max_running.load(Ordering::SeqCst));
+    }
+}
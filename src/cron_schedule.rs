@@ -0,0 +1,332 @@
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use chrono::{DateTime, TimeZone, Utc};
+
+/// Names of the days of the week, in the standard cron numbering.
+const DAYS_OF_WEEK: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+/// Time a fire can be late by, before it's considered missed.
+const MISSED_FIRE_TOLERANCE: Duration = Duration::from_secs(1);
+
+/// Maximum number of missed fires fired with [`CatchUp::All`] at once.
+const MAX_CAUGHT_UP_FIRES: usize = 1000;
+
+/// Returns the first fire of a schedule strictly after the given instant.
+type NextAfter = dyn Fn(&cron::Schedule, DateTime<Utc>) -> Option<DateTime<Utc>> + Send + Sync;
+
+/// Policy for the fires of a [`CronSchedule`] which have been missed, e.g.
+/// because the machine has been suspended.
+///
+/// A fire is missed if it's more than a second late. The fires on time are
+/// always fired.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CatchUp {
+    /// The missed fires are skipped.
+    Skip,
+
+    /// The missed fires are coalesced into a single one.
+    #[default]
+    Once,
+
+    /// Each of the missed fires is fired, up to a thousand of them, and the
+    /// rest are skipped.
+    All,
+}
+
+/// Recurrence of a job described by a cron expression, see
+/// [`SchedulerHandle::schedule_cron`].
+///
+/// Both the standard five-field expressions, e.g. `30 9 * * Mon-Fri`, and the
+/// ones with the seconds and the years fields are accepted, as are the
+/// shorthands, e.g. `@daily`. In the five-field expressions the days of the
+/// week are numbered as in the standard cron, i.e. from 0 for Sunday to 6 for
+/// Saturday, with 7 being Sunday as well, whereas the longer ones follow the
+/// numbering of the `cron` crate, i.e. from 1 for Sunday to 7 for Saturday.
+/// The expression is evaluated in UTC, unless a
+/// different timezone is set with [`Self::with_timezone`].
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{CatchUp, CronSchedule};
+/// use chrono::FixedOffset;
+///
+/// let schedule = "30 9 * * Mon-Fri"
+///     .parse::<CronSchedule>()
+///     .unwrap()
+///     .with_timezone(FixedOffset::east_opt(2 * 3600).unwrap())
+///     .catch_up(CatchUp::Skip);
+/// ```
+///
+/// [`SchedulerHandle::schedule_cron`]: crate::SchedulerHandle::schedule_cron
+#[derive(Clone)]
+pub struct CronSchedule {
+    schedule: Arc<cron::Schedule>,
+    next_after: Arc<NextAfter>,
+    catch_up: CatchUp,
+}
+
+impl CronSchedule {
+    /// Parses the cron `expression`.
+    pub fn parse(expression: &str) -> Result<Self, cron::error::Error> {
+        let fields = expression.split_whitespace().collect::<Vec<_>>();
+        let schedule = if let [minute, hour, day, month, day_of_week] = fields[..] {
+            // The cron crate expects the seconds field first, and numbers the
+            // days of the week from Sunday being 1.
+            let day_of_week = standard_day_of_week(day_of_week)?;
+            cron::Schedule::from_str(&format!("0 {minute} {hour} {day} {month} {day_of_week}"))?
+        } else {
+            cron::Schedule::from_str(expression)?
+        };
+
+        Ok(Self {
+            schedule: Arc::new(schedule),
+            next_after: Arc::new(|schedule, after| schedule.after(&after).next()),
+            catch_up: CatchUp::default(),
+        })
+    }
+
+    /// Evaluates the expression in `timezone`, e.g. `chrono::Local`.
+    pub fn with_timezone<Tz>(mut self, timezone: Tz) -> Self
+    where
+        Tz: TimeZone + Send + Sync + 'static,
+    {
+        self.next_after = Arc::new(move |schedule, after| {
+            schedule
+                .after(&after.with_timezone(&timezone))
+                .next()
+                .map(|next| next.with_timezone(&Utc))
+        });
+        self
+    }
+
+    /// Sets the policy for the missed fires.
+    ///
+    /// It's [`CatchUp::Once`] by default.
+    pub fn catch_up(mut self, catch_up: CatchUp) -> Self {
+        self.catch_up = catch_up;
+        self
+    }
+
+    /// Returns the first fire strictly after `after`, if there's any.
+    pub(crate) fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        (self.next_after)(&self.schedule, after)
+    }
+
+    /// Returns the number of times to fire at `now`, given the `next` fire
+    /// which is due, along with the first fire after `now`.
+    ///
+    /// At most [`MAX_CAUGHT_UP_FIRES`] fires are visited, however many of
+    /// them have been missed.
+    pub(crate) fn catch_up_fires(
+        &self,
+        next: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) -> (usize, Option<DateTime<Utc>>) {
+        if next > now {
+            return (0, Some(next));
+        }
+
+        let first = match self.catch_up {
+            // Only the fires on time are fired, so the missed ones before them
+            // aren't visited at all.
+            CatchUp::Skip if next < now - MISSED_FIRE_TOLERANCE => {
+                self.next_after(now - MISSED_FIRE_TOLERANCE * 2)
+            }
+            CatchUp::Skip | CatchUp::All => Some(next),
+            CatchUp::Once => return (1, self.next_after(now)),
+        };
+
+        let mut count = 0;
+        let mut upcoming = first;
+        while let Some(fire) = upcoming.filter(|fire| *fire <= now) {
+            if count == MAX_CAUGHT_UP_FIRES {
+                // The rest of the missed fires are skipped.
+                return (count, self.next_after(now));
+            }
+            let on_time = (now - fire).to_std().unwrap_or_default() <= MISSED_FIRE_TOLERANCE;
+            if on_time || self.catch_up == CatchUp::All {
+                count += 1;
+            }
+            upcoming = self.next_after(fire);
+        }
+        (count, upcoming)
+    }
+}
+
+/// Translates the day of the week field in the standard cron numbering to the
+/// names of the days, which the cron crate understands unambiguously.
+///
+/// The numeric ranges are expanded into the lists of the days, so the ones
+/// ending on Sunday as 7, e.g. `5-7`, don't wrap around.
+fn standard_day_of_week(field: &str) -> Result<String, cron::error::Error> {
+    let invalid = || {
+        cron::error::Error::from(cron::error::ErrorKind::Expression(format!(
+            "invalid day of the week: {field}"
+        )))
+    };
+
+    let mut elements = Vec::new();
+    for element in field.split(',') {
+        let (range, step) = match element.split_once('/') {
+            Some((range, step)) => (range, Some(step)),
+            None => (element, None),
+        };
+        if !range.bytes().any(|byte| byte.is_ascii_digit()) {
+            // Names and wildcards mean the same in both numberings.
+            elements.push(element.to_owned());
+            continue;
+        }
+
+        let day = |day: &str| day.parse::<usize>().ok().filter(|day| *day <= 7);
+        let (first, last) = match range.split_once('-') {
+            Some((first, last)) => (day(first), day(last)),
+            // A single day with a step runs until the end of the week.
+            None if step.is_some() => (day(range), Some(7)),
+            None => (day(range), day(range)),
+        };
+        let (Some(first), Some(last)) = (first, last) else {
+            return Err(invalid());
+        };
+        let step = match step.map(str::parse::<usize>) {
+            Some(Ok(step)) if step > 0 => step,
+            Some(_) => return Err(invalid()),
+            None => 1,
+        };
+        if first > last {
+            return Err(invalid());
+        }
+
+        for day in (first..=last).step_by(step) {
+            let name = DAYS_OF_WEEK[day % 7];
+            if !elements.iter().any(|element| element == name) {
+                elements.push(name.to_owned());
+            }
+        }
+    }
+
+    Ok(elements.join(","))
+}
+
+impl FromStr for CronSchedule {
+    type Err = cron::error::Error;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        Self::parse(expression)
+    }
+}
+
+impl std::fmt::Debug for CronSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CronSchedule")
+            .field("expression", &self.schedule.source())
+            .field("catch_up", &self.catch_up)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, FixedOffset, Utc};
+
+    use super::MAX_CAUGHT_UP_FIRES;
+    use crate::{CatchUp, CronSchedule};
+
+    fn at(time: &str) -> DateTime<Utc> {
+        time.parse().unwrap()
+    }
+
+    #[test]
+    fn should_evaluate_standard_expression_in_timezone() {
+        // Arrange
+        let schedule = CronSchedule::parse("30 9 * * *")
+            .unwrap()
+            .with_timezone(FixedOffset::east_opt(2 * 3600).unwrap());
+
+        // Act
+        let next = schedule.next_after(at("2024-01-01T07:00:00Z"));
+
+        // Assert
+        assert_eq!(Some(at("2024-01-01T07:30:00Z")), next);
+    }
+
+    #[test]
+    fn should_number_days_of_week_from_sunday_as_zero() {
+        // Arrange
+        let monday = at("2024-01-01T00:00:00Z");
+        let friday = at("2024-01-05T10:00:00Z");
+
+        // Act
+        let next = |expression: &str, after| {
+            CronSchedule::parse(expression)
+                .unwrap()
+                .next_after(after)
+                .unwrap()
+        };
+
+        // Assert
+        assert_eq!(at("2024-01-07T09:00:00Z"), next("0 9 * * 0", monday));
+        assert_eq!(at("2024-01-01T09:00:00Z"), next("0 9 * * 1", monday));
+        assert_eq!(at("2024-01-07T09:00:00Z"), next("0 9 * * 7", monday));
+        assert_eq!(at("2024-01-08T09:00:00Z"), next("0 9 * * 1-5", friday));
+        assert_eq!(at("2024-01-06T09:00:00Z"), next("0 9 * * 5-7", friday));
+        assert_eq!(at("2024-01-06T09:00:00Z"), next("0 9 * * 6-7", monday));
+    }
+
+    #[test]
+    fn should_reject_invalid_days_of_week() {
+        // Act
+        let results =
+            ["0 9 * * 8", "0 9 * * 5-1", "0 9 * * 1-5/0", "0 9 * * Mon-5"].map(CronSchedule::parse);
+
+        // Assert
+        assert!(results.iter().all(Result::is_err));
+    }
+
+    #[test]
+    fn should_count_fires_according_to_catch_up_policy() {
+        // Arrange
+        let next = at("2024-01-01T00:01:00Z");
+        let now = at("2024-01-01T00:03:00Z");
+        let schedule = CronSchedule::parse("* * * * *").unwrap();
+
+        // Act
+        let fires = [CatchUp::Skip, CatchUp::Once, CatchUp::All].map(|catch_up| {
+            schedule
+                .clone()
+                .catch_up(catch_up)
+                .catch_up_fires(next, now)
+        });
+
+        // Assert
+        let upcoming = Some(at("2024-01-01T00:04:00Z"));
+        assert_eq!([(1, upcoming), (1, upcoming), (3, upcoming)], fires);
+    }
+
+    #[test]
+    fn should_bound_fires_caught_up_after_long_suspend() {
+        // Arrange
+        let next = at("2024-01-01T00:00:00Z");
+        let now = at("2024-01-31T00:00:00Z");
+        let schedule = CronSchedule::parse("* * * * * * *").unwrap();
+
+        // Act
+        let fires = [CatchUp::Skip, CatchUp::Once, CatchUp::All].map(|catch_up| {
+            schedule
+                .clone()
+                .catch_up(catch_up)
+                .catch_up_fires(next, now)
+        });
+
+        // Assert
+        let upcoming = Some(at("2024-01-31T00:00:01Z"));
+        assert_eq!(
+            [
+                (2, upcoming),
+                (1, upcoming),
+                (MAX_CAUGHT_UP_FIRES, upcoming)
+            ],
+            fires
+        );
+    }
+}
@@ -0,0 +1,265 @@
+use std::{any::Any, future::Future, pin::Pin, time::Duration};
+
+use tokio::time::Instant;
+
+use crate::{
+    clock::SharedClock, Cancellable, CancellationResult, CompletionReason, ErrorDirective,
+    RunContext,
+};
+
+/// Error of a service with a deadline.
+///
+/// See [`Cancellable::with_deadline`] and [`Cancellable::with_timeout`].
+#[derive(Debug)]
+pub enum DeadlineError<E> {
+    /// The deadline has passed before the service has completed.
+    Elapsed,
+
+    /// The service has failed.
+    Service(E),
+}
+
+impl<E> DeadlineError<E> {
+    /// Checks if the deadline has passed.
+    pub fn is_elapsed(&self) -> bool {
+        matches!(self, Self::Elapsed)
+    }
+
+    /// Returns the service's error, if the service has failed on its own.
+    pub fn into_service_error(self) -> Option<E> {
+        match self {
+            Self::Elapsed => None,
+            Self::Service(e) => Some(e),
+        }
+    }
+}
+
+impl<E> std::fmt::Display for DeadlineError<E>
+where
+    E: std::fmt::Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Elapsed => f.write_str("deadline has elapsed"),
+            Self::Service(e) => e.fmt(f),
+        }
+    }
+}
+
+impl<E> std::error::Error for DeadlineError<E> where E: std::fmt::Debug + std::fmt::Display {}
+
+/// Point in time at which a service's deadline passes.
+#[derive(Debug, Clone, Copy)]
+enum Limit {
+    At(Instant),
+    AfterSpawn(Duration),
+}
+
+/// Service failing with [`DeadlineError::Elapsed`] once its deadline passes.
+///
+/// See [`Cancellable::with_deadline`] and [`Cancellable::with_timeout`].
+pub struct Deadline<S> {
+    service: S,
+    limit: Limit,
+    timer: Option<Pin<Box<dyn Future<Output = ()> + Send>>>,
+    elapsed: bool,
+}
+
+impl<S> Deadline<S> {
+    pub(crate) fn at(service: S, deadline: Instant) -> Self {
+        Self::new(service, Limit::At(deadline))
+    }
+
+    pub(crate) fn after_spawn(service: S, timeout: Duration) -> Self {
+        Self::new(service, Limit::AfterSpawn(timeout))
+    }
+
+    fn new(service: S, limit: Limit) -> Self {
+        Self {
+            service,
+            limit,
+            timer: None,
+            elapsed: false,
+        }
+    }
+}
+
+impl<S> std::fmt::Debug for Deadline<S>
+where
+    S: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Deadline")
+            .field("service", &self.service)
+            .field("limit", &self.limit)
+            .field("elapsed", &self.elapsed)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> Cancellable for Deadline<S>
+where
+    S: Cancellable + Send,
+{
+    type Result = S::Result;
+    type Handle = S::Handle;
+    type Error = DeadlineError<S::Error>;
+    type Output = S::Output;
+
+    fn name(&self) -> &str {
+        self.service.name()
+    }
+
+    async fn new_handle(&mut self) -> Self::Handle {
+        self.service.new_handle().await
+    }
+
+    async fn try_new_handle(&mut self) -> Result<Self::Handle, Self::Error> {
+        self.service
+            .try_new_handle()
+            .await
+            .map_err(DeadlineError::Service)
+    }
+
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        if self.elapsed {
+            return Err(DeadlineError::Elapsed);
+        }
+
+        let limit = self.limit;
+        let timer = self.timer.get_or_insert_with(|| {
+            let clock = SharedClock::current();
+            let remaining = match limit {
+                Limit::At(deadline) => deadline.saturating_duration_since(clock.now()),
+                Limit::AfterSpawn(timeout) => {
+                    let elapsed = RunContext::current().map(|context| context.elapsed());
+                    timeout.saturating_sub(elapsed.unwrap_or_default())
+                }
+            };
+            clock.sleep(remaining)
+        });
+
+        let error = tokio::select! {
+            biased;
+            _ = timer.as_mut() => {
+                self.elapsed = true;
+                if let Some(context) = RunContext::current() {
+                    context.complete_with(CompletionReason::Deadline);
+                }
+                return Err(DeadlineError::Elapsed);
+            }
+            result = self.service.run() => match result {
+                Ok(result) => return Ok(result),
+                Err(e) => e,
+            },
+        };
+
+        match self.service.on_error(error).await {
+            ErrorDirective::Continue => Ok(CancellationResult::Continue),
+            ErrorDirective::Retry { delay } => Ok(CancellationResult::Delay(delay)),
+            ErrorDirective::Break => Ok(CancellationResult::Break),
+            ErrorDirective::Fail(e) => Err(DeadlineError::Service(e)),
+        }
+    }
+
+    async fn on_start(&mut self) -> Result<(), Self::Error> {
+        self.service
+            .on_start()
+            .await
+            .map_err(DeadlineError::Service)
+    }
+
+    async fn on_stop(&mut self) {
+        self.service.on_stop().await
+    }
+
+    async fn on_cancel(&mut self) {
+        self.service.on_cancel().await
+    }
+
+    fn cancelled_output(&mut self) -> Option<Self::Output> {
+        self.service.cancelled_output()
+    }
+
+    async fn drain(
+        &mut self,
+    ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        self.service.drain().await.map_err(DeadlineError::Service)
+    }
+
+    async fn on_timeout(
+        &mut self,
+    ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        self.service
+            .on_timeout()
+            .await
+            .map_err(DeadlineError::Service)
+    }
+
+    async fn on_panic(
+        &mut self,
+        panic: Box<dyn Any + Send>,
+    ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        self.service
+            .on_panic(panic)
+            .await
+            .map_err(DeadlineError::Service)
+    }
+
+    async fn restart(&mut self, error: Self::Error) -> Result<(), Self::Error> {
+        match error {
+            DeadlineError::Elapsed => Err(DeadlineError::Elapsed),
+            DeadlineError::Service(e) => self
+                .service
+                .restart(e)
+                .await
+                .map_err(DeadlineError::Service),
+        }
+    }
+
+    fn on_item_dropped(&mut self, item: Self::Result) {
+        self.service.on_item_dropped(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{Cancellable, CancellableError, DeadlineError};
+
+    #[tokio::test(start_paused = true)]
+    async fn should_fail_when_timeout_elapses() {
+        // Arrange
+        let service = crate::from_stream(futures::stream::pending::<i32>())
+            .with_timeout(Duration::from_secs(60));
+        let started = Instant::now();
+
+        // Act
+        let handle = service.spawn(CancellationToken::new()).await;
+
+        // Assert
+        let result = handle.join().await;
+        assert!(matches!(
+            result,
+            Err(CancellableError::Service(DeadlineError::Elapsed))
+        ));
+        assert!(started.elapsed() >= Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn should_complete_before_deadline() {
+        // Arrange
+        let service = crate::from_stream(futures::stream::iter([1, 2, 3]))
+            .with_deadline(Instant::now() + Duration::from_secs(60));
+
+        // Act
+        let handle = service.spawn(CancellationToken::new()).await;
+
+        // Assert
+        assert!(handle.join().await.is_ok());
+    }
+}
@@ -0,0 +1,274 @@
+use std::{any::Any, collections::VecDeque, time::Duration};
+
+use tokio::time::Instant;
+
+use crate::{clock::SharedClock, Cancellable, CancellationResult, ErrorDirective};
+
+/// Result of a single call to [`Cancellable::run`] of a service with an error
+/// budget.
+type RunResult<S> = Result<
+    CancellationResult<<S as Cancellable>::Result, <S as Cancellable>::Output>,
+    ServiceErrors<<S as Cancellable>::Error>,
+>;
+
+/// Errors collected by a service with an error budget.
+///
+/// See [`Cancellable::with_error_budget`].
+#[derive(Debug)]
+pub struct ServiceErrors<E> {
+    errors: Vec<E>,
+}
+
+impl<E> ServiceErrors<E> {
+    fn single(error: E) -> Self {
+        Self {
+            errors: vec![error],
+        }
+    }
+
+    /// Returns the collected errors, from the oldest to the most recent one.
+    pub fn errors(&self) -> &[E] {
+        &self.errors
+    }
+
+    /// Returns the collected errors, from the oldest to the most recent one.
+    pub fn into_errors(self) -> Vec<E> {
+        self.errors
+    }
+}
+
+impl<E> std::fmt::Display for ServiceErrors<E>
+where
+    E: std::fmt::Display,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} error(s)", self.errors.len())?;
+        for (i, error) in self.errors.iter().enumerate() {
+            let separator = if i == 0 { ": " } else { "; " };
+            write!(f, "{separator}{error}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<E> std::error::Error for ServiceErrors<E> where E: std::fmt::Debug + std::fmt::Display {}
+
+/// Service tolerating a bounded number of errors of another service within a
+/// time window.
+///
+/// See [`Cancellable::with_error_budget`].
+#[derive(Debug)]
+pub struct ErrorBudget<S>
+where
+    S: Cancellable,
+{
+    service: S,
+    max_errors: usize,
+    window: Duration,
+    errors: VecDeque<(Instant, S::Error)>,
+}
+
+impl<S> ErrorBudget<S>
+where
+    S: Cancellable,
+{
+    pub(crate) fn new(service: S, max_errors: usize, window: Duration) -> Self {
+        Self {
+            service,
+            max_errors,
+            window,
+            errors: VecDeque::new(),
+        }
+    }
+
+    /// Records `error`, failing the service if the budget has been exceeded.
+    fn record(&mut self, error: S::Error) -> RunResult<S> {
+        let now = SharedClock::current().now();
+        while self
+            .errors
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) >= self.window)
+        {
+            self.errors.pop_front();
+        }
+
+        self.errors.push_back((now, error));
+        if self.errors.len() <= self.max_errors {
+            return Ok(CancellationResult::Continue);
+        }
+
+        Err(ServiceErrors {
+            errors: self.errors.drain(..).map(|(_, error)| error).collect(),
+        })
+    }
+}
+
+impl<S> Cancellable for ErrorBudget<S>
+where
+    S: Cancellable + Send,
+    S::Error: Sync,
+{
+    type Result = S::Result;
+    type Handle = S::Handle;
+    type Error = ServiceErrors<S::Error>;
+    type Output = S::Output;
+
+    fn name(&self) -> &str {
+        self.service.name()
+    }
+
+    async fn new_handle(&mut self) -> Self::Handle {
+        self.service.new_handle().await
+    }
+
+    async fn try_new_handle(&mut self) -> Result<Self::Handle, Self::Error> {
+        self.service
+            .try_new_handle()
+            .await
+            .map_err(ServiceErrors::single)
+    }
+
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        let error = match self.service.run().await {
+            Ok(result) => return Ok(result),
+            Err(e) => e,
+        };
+
+        match self.service.on_error(error).await {
+            ErrorDirective::Continue => Ok(CancellationResult::Continue),
+            ErrorDirective::Retry { delay } => Ok(CancellationResult::Delay(delay)),
+            ErrorDirective::Break => Ok(CancellationResult::Break),
+            ErrorDirective::Fail(e) => self.record(e),
+        }
+    }
+
+    async fn on_start(&mut self) -> Result<(), Self::Error> {
+        self.service.on_start().await.map_err(ServiceErrors::single)
+    }
+
+    async fn on_stop(&mut self) {
+        self.service.on_stop().await
+    }
+
+    async fn on_cancel(&mut self) {
+        self.service.on_cancel().await
+    }
+
+    fn cancelled_output(&mut self) -> Option<Self::Output> {
+        self.service.cancelled_output()
+    }
+
+    async fn drain(
+        &mut self,
+    ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        self.service.drain().await.map_err(ServiceErrors::single)
+    }
+
+    async fn on_timeout(
+        &mut self,
+    ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        self.service
+            .on_timeout()
+            .await
+            .map_err(ServiceErrors::single)
+    }
+
+    async fn on_panic(
+        &mut self,
+        panic: Box<dyn Any + Send>,
+    ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        self.service
+            .on_panic(panic)
+            .await
+            .map_err(ServiceErrors::single)
+    }
+
+    async fn restart(&mut self, error: Self::Error) -> Result<(), Self::Error> {
+        for error in error.into_errors() {
+            self.service
+                .restart(error)
+                .await
+                .map_err(ServiceErrors::single)?;
+        }
+
+        Ok(())
+    }
+
+    fn on_item_dropped(&mut self, item: Self::Result) {
+        self.service.on_item_dropped(item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{Cancellable, CancellationResult};
+
+    struct FlakyCancellable {
+        calls: usize,
+        failing: fn(usize) -> bool,
+    }
+
+    impl Cancellable for FlakyCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = String;
+        type Output = usize;
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<(), usize>, Self::Error> {
+            self.calls += 1;
+            if self.calls > 10 {
+                return Ok(CancellationResult::BreakWith(self.calls));
+            }
+
+            match (self.failing)(self.calls) {
+                true => Err(format!("error {}", self.calls)),
+                false => Ok(CancellationResult::Continue),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn should_tolerate_errors_within_budget() {
+        // Arrange
+        let service = FlakyCancellable {
+            calls: 0,
+            failing: |call| call % 5 == 0,
+        }
+        .with_error_budget(2, Duration::from_secs(60));
+
+        // Act
+        let handle = service.spawn(CancellationToken::new()).await;
+
+        // Assert
+        assert_eq!(Some(11), handle.join().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn should_fail_with_collected_errors_when_budget_is_exceeded() {
+        // Arrange
+        let service = FlakyCancellable {
+            calls: 0,
+            failing: |call| call > 2,
+        }
+        .with_error_budget(2, Duration::from_secs(60));
+
+        // Act
+        let handle = service.spawn(CancellationToken::new()).await;
+
+        // Assert
+        let errors = handle
+            .join()
+            .await
+            .unwrap_err()
+            .into_service_error()
+            .unwrap();
+        assert_eq!(["error 3", "error 4", "error 5"], errors.errors());
+    }
+}
@@ -0,0 +1,37 @@
+use std::time::Duration;
+
+/// Decision on how the work loop should handle an error returned by
+/// [`Cancellable::run`].
+///
+/// See [`Cancellable::on_error`].
+///
+/// [`Cancellable::run`]: crate::Cancellable::run
+/// [`Cancellable::on_error`]: crate::Cancellable::on_error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorDirective<E> {
+    /// Skips the error and continues with the next iteration right away.
+    Continue,
+
+    /// Skips the error and continues with the next iteration after `delay`.
+    ///
+    /// The service completes right away if it's cancelled in the meantime.
+    Retry {
+        /// Time to wait before the next iteration.
+        delay: Duration,
+    },
+
+    /// Ends the service as if [`Cancellable::run`] has returned
+    /// [`CancellationResult::Break`].
+    ///
+    /// [`Cancellable::run`]: crate::Cancellable::run
+    /// [`CancellationResult::Break`]: crate::CancellationResult#variant.Break
+    Break,
+
+    /// Fails the service with the wrapped error.
+    ///
+    /// The service is still restarted, if a restart policy has been set with
+    /// [`SpawnOptions::restart_policy`].
+    ///
+    /// [`SpawnOptions::restart_policy`]: crate::SpawnOptions::restart_policy
+    Fail(E),
+}
@@ -0,0 +1,205 @@
+use std::{path::PathBuf, time::Duration};
+
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use tokio::sync::mpsc;
+
+use crate::{clock::SharedClock, Cancellable, CancellationResult};
+
+/// Service yielding the filesystem events of the watched paths.
+///
+/// The paths are watched with the platform's recommended `notify` watcher,
+/// which is set up once the service starts. If a path cannot be watched, e.g.
+/// it doesn't exist, then the service fails to start.
+///
+/// By default, every event is yielded on its own. With [`Self::debounce`], the
+/// events are collected until none arrive for the debounce window, and they're
+/// yielded at once, with duplicates coalesced.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::time::Duration;
+///
+/// use cancellable::{Cancellable, CancellationToken, FileWatcher};
+/// use futures::StreamExt;
+/// use notify::RecursiveMode;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let watcher = FileWatcher::new()
+///     .watch("config", RecursiveMode::Recursive)
+///     .debounce(Duration::from_millis(100));
+///
+/// let (handle, mut events) = watcher.spawn_stream(CancellationToken::new()).await;
+/// while let Some(event) = events.next().await {
+///     println!("{:?} {:?}", event.kind, event.paths);
+/// }
+/// # }
+/// ```
+pub struct FileWatcher {
+    paths: Vec<(PathBuf, RecursiveMode)>,
+    debounce: Option<Duration>,
+    watcher: Option<RecommendedWatcher>,
+    sender: mpsc::UnboundedSender<notify::Result<Event>>,
+    events: mpsc::UnboundedReceiver<notify::Result<Event>>,
+}
+
+impl FileWatcher {
+    /// Constructs a new watcher without any paths.
+    pub fn new() -> Self {
+        let (sender, events) = mpsc::unbounded_channel();
+
+        Self {
+            paths: Vec::new(),
+            debounce: None,
+            watcher: None,
+            sender,
+            events,
+        }
+    }
+
+    /// Adds `path` to the watched paths.
+    pub fn watch(mut self, path: impl Into<PathBuf>, mode: RecursiveMode) -> Self {
+        self.paths.push((path.into(), mode));
+        self
+    }
+
+    /// Debounces the events with `window`.
+    ///
+    /// Once an event arrives, the following ones are collected as long as each
+    /// of them arrives within `window` of the previous one. They're yielded
+    /// together with [`CancellationResult::Items`], so the callback sees a
+    /// burst of changes, e.g. an editor saving a file, at once.
+    ///
+    /// [`CancellationResult::Items`]: crate::CancellationResult#variant.Items
+    pub fn debounce(mut self, window: Duration) -> Self {
+        self.debounce = Some(window);
+        self
+    }
+}
+
+impl Default for FileWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Cancellable for FileWatcher {
+    type Result = Event;
+    type Handle = ();
+    type Error = notify::Error;
+    type Output = ();
+
+    async fn new_handle(&mut self) -> Self::Handle {}
+
+    async fn on_start(&mut self) -> Result<(), Self::Error> {
+        let sender = self.sender.clone();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            // The service has completed, if no one receives the event.
+            let _ = sender.send(event);
+        })?;
+        for (path, mode) in &self.paths {
+            watcher.watch(path, *mode)?;
+        }
+
+        self.watcher = Some(watcher);
+        Ok(())
+    }
+
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        let Some(event) = self.events.recv().await else {
+            return Ok(CancellationResult::Break);
+        };
+        let Some(window) = self.debounce else {
+            return Ok(CancellationResult::Item(event?));
+        };
+
+        let clock = SharedClock::current();
+        let mut events = vec![event?];
+        while let Some(Some(event)) = clock.timeout(window, self.events.recv()).await {
+            let event = event?;
+            if !events.contains(&event) {
+                events.push(event);
+            }
+        }
+
+        Ok(CancellationResult::Items(events))
+    }
+}
+
+impl std::fmt::Debug for FileWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileWatcher")
+            .field("paths", &self.paths)
+            .field("debounce", &self.debounce)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashSet, path::PathBuf, time::Duration};
+
+    use futures::StreamExt;
+    use notify::RecursiveMode;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{Cancellable, CancellableError, FileWatcher};
+
+    /// Creates an empty directory for a single test.
+    fn directory(name: &str) -> PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("cancellable-{}-{name}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+        // Canonicalized, so the paths match the ones reported by the watcher.
+        directory.canonicalize().unwrap()
+    }
+
+    #[tokio::test]
+    async fn should_yield_debounced_events_of_watched_directory() {
+        // Arrange
+        let directory = directory("debounced");
+        let watcher = FileWatcher::new()
+            .watch(&directory, RecursiveMode::NonRecursive)
+            .debounce(Duration::from_millis(50));
+        let (handle, mut events) = watcher.spawn_stream(CancellationToken::new()).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // Act
+        let files = ["a.txt", "b.txt"].map(|name| directory.join(name));
+        for file in &files {
+            std::fs::write(file, "changed").unwrap();
+        }
+        let mut changed = HashSet::new();
+        let all_changed = async {
+            while let Some(event) = events.next().await {
+                changed.extend(event.paths);
+                if files.iter().all(|file| changed.contains(file)) {
+                    break;
+                }
+            }
+        };
+        let result = tokio::time::timeout(Duration::from_secs(5), all_changed).await;
+
+        // Assert
+        assert!(result.is_ok());
+        handle.cancel();
+        assert!(handle.join().await.is_ok());
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn should_fail_to_start_for_missing_path() {
+        // Arrange
+        let directory = directory("missing");
+        let watcher = FileWatcher::new().watch(directory.join("missing"), RecursiveMode::Recursive);
+
+        // Act
+        let result = watcher.spawn(CancellationToken::new()).await.join().await;
+
+        // Assert
+        assert!(matches!(result, Err(CancellableError::Service(_))));
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
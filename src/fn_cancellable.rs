@@ -0,0 +1,129 @@
+use std::{convert::Infallible, future::Future};
+
+use crate::{Cancellable, CancellationResult, RunContext};
+
+/// Service performing its units of work with a closure.
+///
+/// See [`from_fn`].
+pub struct FnCancellable<F> {
+    f: F,
+}
+
+impl<F> FnCancellable<F> {
+    /// Constructs a new service performing its units of work with `f`.
+    pub fn new(f: F) -> Self {
+        Self { f }
+    }
+}
+
+impl<F> std::fmt::Debug for FnCancellable<F> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FnCancellable").finish_non_exhaustive()
+    }
+}
+
+impl<F, Fut, T, O> Cancellable for FnCancellable<F>
+where
+    F: FnMut(RunContext) -> Fut + Send,
+    Fut: Future<Output = CancellationResult<T, O>> + Send,
+    T: Send,
+    O: std::fmt::Debug + Send,
+{
+    type Result = T;
+    type Handle = ();
+    type Error = Infallible;
+    type Output = O;
+
+    async fn new_handle(&mut self) -> Self::Handle {}
+
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        let context = RunContext::current().expect("to be called by the work loop");
+
+        Ok((self.f)(context).await)
+    }
+}
+
+/// Constructs a service performing its units of work with `f`.
+///
+/// Each iteration of the service calls `f` with the [`RunContext`] of the
+/// service and awaits the returned future. It's meant for one-off services,
+/// for which defining a type implementing [`Cancellable`] is overkill.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use cancellable::{Cancellable, CancellationResult, CancellationToken};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let handle = cancellable::from_fn(|context| async move {
+///     if context.iteration() == 3 {
+///         return CancellationResult::<u64>::Break;
+///     }
+///
+///     tokio::time::sleep(Duration::from_millis(10)).await;
+///     CancellationResult::item(context.iteration())
+/// })
+/// .spawn(CancellationToken::new())
+/// .await;
+///
+/// handle.join().await.unwrap();
+/// # }
+/// ```
+pub fn from_fn<F, Fut, T, O>(f: F) -> FnCancellable<F>
+where
+    F: FnMut(RunContext) -> Fut,
+    Fut: Future<Output = CancellationResult<T, O>>,
+{
+    FnCancellable::new(f)
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{Cancellable, CancellationResult};
+
+    #[tokio::test]
+    async fn should_yield_closure_results() {
+        // Arrange
+        let mut numbers = vec![3, 2, 1];
+        let service = crate::from_fn(move |_| {
+            let number = numbers.pop();
+            async move {
+                match number {
+                    Some(number) => CancellationResult::Item(number),
+                    None => CancellationResult::BreakWith("done"),
+                }
+            }
+        });
+
+        // Act
+        let (handle, items) = service.spawn_stream(CancellationToken::new()).await;
+
+        // Assert
+        assert_eq!(vec![1, 2, 3], items.collect::<Vec<_>>().await);
+        assert_eq!(Some("done"), handle.join_infallible().await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn should_pass_run_context_to_closure() {
+        // Arrange
+        let service = crate::from_fn(|context| async move {
+            match context.iteration() {
+                3 => CancellationResult::<u64>::Break,
+                iteration => CancellationResult::Item(iteration),
+            }
+        });
+
+        // Act
+        let (handle, items) = service.spawn_stream(CancellationToken::new()).await;
+
+        // Assert
+        assert_eq!(vec![1, 2], items.collect::<Vec<_>>().await);
+        assert!(handle.join().await.is_ok());
+    }
+}
@@ -0,0 +1,47 @@
+/// Health of a spawned service, as reported by the service itself.
+///
+/// Services report their health with [`RunContext::report_health`], while
+/// spawners observe it with [`CancellableHandle::health`] and
+/// [`CancellableHandle::watch_health`]. Every service is [`Health::Healthy`]
+/// until it reports otherwise.
+///
+/// [`RunContext::report_health`]: crate::RunContext::report_health
+/// [`CancellableHandle::health`]: crate::CancellableHandle::health
+/// [`CancellableHandle::watch_health`]: crate::CancellableHandle::watch_health
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Health {
+    /// The service works as expected.
+    #[default]
+    Healthy,
+
+    /// The service works, but with reduced capabilities.
+    Degraded(String),
+
+    /// The service doesn't work.
+    Unhealthy(String),
+}
+
+impl Health {
+    /// Checks if the service is [`Health::Healthy`].
+    pub fn is_healthy(&self) -> bool {
+        matches!(self, Self::Healthy)
+    }
+
+    /// Returns the message describing the service's health, if there's any.
+    pub fn message(&self) -> Option<&str> {
+        match self {
+            Self::Healthy => None,
+            Self::Degraded(message) | Self::Unhealthy(message) => Some(message),
+        }
+    }
+}
+
+impl std::fmt::Display for Health {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Healthy => f.write_str("healthy"),
+            Self::Degraded(message) => write!(f, "degraded: {message}"),
+            Self::Unhealthy(message) => write!(f, "unhealthy: {message}"),
+        }
+    }
+}
@@ -0,0 +1,210 @@
+use std::{io, net::SocketAddr, time::Duration};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    sync::watch,
+};
+
+use crate::{clock::SharedClock, Cancellable, CancellationResult, Health};
+
+/// Maximum size of a request's head read by the server.
+const MAX_REQUEST_SIZE: usize = 1024;
+
+/// Time a client has to send its request and receive the response.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Service serving the liveness and readiness of other services over HTTP.
+///
+/// The server answers `GET` requests of two paths:
+///
+/// * `/livez` - always `200 OK`, as long as the server is running.
+/// * `/readyz` - `200 OK` if none of the watched services is
+///   [`Health::Unhealthy`], or `503 Service Unavailable` otherwise. The body
+///   lists the health of every watched service.
+///
+/// The services are watched with [`Self::watch`], e.g. with the receivers
+/// returned by [`CancellableHandle::watch_health`]. Requests are answered one
+/// at a time, and the errors of single connections are ignored.
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{Cancellable, CancellationToken, HealthServer};
+///
+/// # #[tokio::main]
+/// # async fn main() -> std::io::Result<()> {
+/// let worker = cancellable::from_stream(futures::stream::pending::<()>())
+///     .spawn(CancellationToken::new())
+///     .await;
+///
+/// let server = HealthServer::bind("127.0.0.1:0")
+///     .await?
+///     .watch("worker", worker.watch_health());
+/// println!("Serving health on {}", server.local_addr()?);
+///
+/// let server = server.spawn(CancellationToken::new()).await;
+/// # Ok(())
+/// # }
+/// ```
+///
+/// [`CancellableHandle::watch_health`]: crate::CancellableHandle::watch_health
+pub struct HealthServer {
+    listener: TcpListener,
+    services: Vec<(String, watch::Receiver<Health>)>,
+}
+
+impl HealthServer {
+    /// Constructs a new server accepting connections on `listener`.
+    pub fn new(listener: TcpListener) -> Self {
+        Self {
+            listener,
+            services: Vec::new(),
+        }
+    }
+
+    /// Constructs a new server listening on `addr`.
+    pub async fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Self::new(TcpListener::bind(addr).await?))
+    }
+
+    /// Adds the service called `name` to the ones whose health is served.
+    pub fn watch(mut self, name: impl Into<String>, health: watch::Receiver<Health>) -> Self {
+        self.services.push((name.into(), health));
+        self
+    }
+
+    /// Returns the address the server is listening on.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Returns the status and the body of the response to `path`.
+    fn respond(&self, path: &str) -> (&'static str, String) {
+        match path {
+            "/livez" => ("200 OK", "ok\n".to_owned()),
+            "/readyz" => {
+                let mut ready = true;
+                let mut body = String::new();
+                for (name, health) in &self.services {
+                    let health = health.borrow();
+                    ready &= !matches!(*health, Health::Unhealthy(_));
+                    body.push_str(&format!("{name}: {}\n", *health));
+                }
+
+                if ready {
+                    ("200 OK", body)
+                } else {
+                    ("503 Service Unavailable", body)
+                }
+            }
+            _ => ("404 Not Found", "not found\n".to_owned()),
+        }
+    }
+
+    async fn serve(&self, mut stream: TcpStream) -> io::Result<()> {
+        let mut request = Vec::new();
+        let mut buffer = [0; 256];
+        while !request.windows(4).any(|window| window == b"\r\n\r\n") {
+            let read = stream.read(&mut buffer).await?;
+            if read == 0 || request.len() + read > MAX_REQUEST_SIZE {
+                break;
+            }
+            request.extend_from_slice(&buffer[..read]);
+        }
+
+        let request = String::from_utf8_lossy(&request);
+        let mut request_line = request.lines().next().unwrap_or_default().split(' ');
+        let (status, body) = match (request_line.next(), request_line.next()) {
+            (Some("GET"), Some(path)) => self.respond(path),
+            _ => ("400 Bad Request", "bad request\n".to_owned()),
+        };
+
+        let response = format!(
+            "HTTP/1.1 {status}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await
+    }
+}
+
+impl Cancellable for HealthServer {
+    type Result = ();
+    type Handle = ();
+    type Error = io::Error;
+    type Output = ();
+
+    async fn new_handle(&mut self) -> Self::Handle {}
+
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        let (stream, _) = self.listener.accept().await?;
+        // A single misbehaving client shouldn't take the server down.
+        let _ = SharedClock::current()
+            .timeout(REQUEST_TIMEOUT, self.serve(stream))
+            .await;
+
+        Ok(CancellationResult::Continue)
+    }
+}
+
+impl std::fmt::Debug for HealthServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HealthServer")
+            .field("listener", &self.listener)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpStream,
+        sync::watch,
+    };
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{Cancellable, Health, HealthServer};
+
+    async fn get(addr: SocketAddr, path: &str) -> String {
+        let mut stream = TcpStream::connect(addr).await.unwrap();
+        let request = format!("GET {path} HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn should_serve_liveness_and_readiness() {
+        // Arrange
+        let (health, receiver) = watch::channel(Health::Healthy);
+        let server = HealthServer::bind("127.0.0.1:0")
+            .await
+            .unwrap()
+            .watch("worker", receiver);
+        let addr = server.local_addr().unwrap();
+        let handle = server.spawn(CancellationToken::new()).await;
+
+        // Act
+        let live = get(addr, "/livez").await;
+        let ready = get(addr, "/readyz").await;
+        health.send_replace(Health::Unhealthy("disk is full".to_owned()));
+        let unready = get(addr, "/readyz").await;
+        let missing = get(addr, "/metrics").await;
+
+        // Assert
+        assert!(live.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(ready.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(ready.ends_with("\r\n\r\nworker: healthy\n"));
+        assert!(unready.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+        assert!(unready.ends_with("worker: unhealthy: disk is full\n"));
+        assert!(missing.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        handle.cancel();
+        assert!(handle.join().await.is_ok());
+    }
+}
@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+
+use crate::{cancel_reason::ReasonSlot, clock::SharedClock, trace::event, CancelReason};
+
+/// Reason of the cancellation of a service which has been idle for too long.
+///
+/// See [`SpawnOptions::idle_timeout`].
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use cancellable::{
+///     CallbackResult, Cancellable, CancellationToken, Idle, SpawnOptions,
+/// };
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let connection = cancellable::from_stream(futures::stream::pending::<Vec<u8>>());
+/// let options = SpawnOptions::new().idle_timeout(Duration::from_millis(10));
+///
+/// let handle = connection
+///     .spawn_with_options(CancellationToken::new(), options, |_| {
+///         CallbackResult::Continue
+///     })
+///     .await;
+///
+/// let (result, reason) = handle.join_with_reason().await;
+/// assert!(result.is_ok());
+/// assert!(reason.unwrap().is::<Idle>());
+/// # }
+/// ```
+///
+/// [`SpawnOptions::idle_timeout`]: crate::SpawnOptions::idle_timeout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Idle;
+
+/// Monitors the `activity` of a service, and cancels the service with
+/// [`Idle`] once there's none for `timeout`.
+///
+/// The returned future never completes.
+pub(crate) async fn monitor(
+    timeout: Duration,
+    mut activity: watch::Receiver<()>,
+    cancellation_token: CancellationToken,
+    cancel_reason: ReasonSlot,
+    clock: SharedClock,
+) {
+    loop {
+        match clock.timeout(timeout, activity.changed()).await {
+            Some(Ok(())) => continue,
+            Some(Err(_)) => break,
+            None => {
+                event!(debug, ?timeout, "Service has been idle, cancelling");
+                let _ = cancel_reason.set(CancelReason::new(Idle));
+                cancellation_token.cancel();
+                break;
+            }
+        }
+    }
+
+    std::future::pending().await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{CallbackResult, Cancellable, CancellationResult, Idle, RunContext, SpawnOptions};
+
+    struct QuietCancellable {
+        keep_alive: bool,
+    }
+
+    impl Cancellable for QuietCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            if self.keep_alive {
+                RunContext::current().unwrap().keep_alive();
+            }
+            Ok(CancellationResult::Continue)
+        }
+    }
+
+    fn idle_after_minute() -> SpawnOptions {
+        SpawnOptions::new().idle_timeout(Duration::from_secs(60))
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn should_cancel_idle_service() {
+        // Arrange
+        let service = QuietCancellable { keep_alive: false };
+        let started = Instant::now();
+
+        // Act
+        let handle = service
+            .spawn_with_options(CancellationToken::new(), idle_after_minute(), |_| {
+                CallbackResult::Continue
+            })
+            .await;
+
+        // Assert
+        let (result, reason) = handle.join_with_reason().await;
+        assert!(result.is_ok());
+        assert_eq!(Some(&Idle), reason.unwrap().downcast_ref::<Idle>());
+        assert!(started.elapsed() >= Duration::from_secs(60));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn should_keep_active_service_running() {
+        // Arrange
+        let service = QuietCancellable { keep_alive: true };
+
+        // Act
+        let handle = service
+            .spawn_with_options(CancellationToken::new(), idle_after_minute(), |_| {
+                CallbackResult::Continue
+            })
+            .await;
+        tokio::time::sleep(Duration::from_secs(300)).await;
+
+        // Assert
+        assert!(!handle.is_finished());
+        handle.cancel();
+        assert!(handle.join().await.is_ok());
+    }
+}
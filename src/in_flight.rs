@@ -0,0 +1,46 @@
+use std::sync::Arc;
+
+use tokio::sync::watch;
+
+/// Number of values passed to the callback, which haven't been processed by
+/// their consumer yet.
+///
+/// It's shared by the work loop and the consumer, so that the loop can wait
+/// for the consumer to catch up, see [`SpawnOptions::max_in_flight`].
+///
+/// [`SpawnOptions::max_in_flight`]: crate::SpawnOptions::max_in_flight
+#[derive(Debug, Clone)]
+pub(crate) struct InFlight(Arc<watch::Sender<usize>>);
+
+impl InFlight {
+    pub(crate) fn new() -> Self {
+        Self(Arc::new(watch::channel(0).0))
+    }
+
+    /// Records a value passed to the consumer.
+    pub(crate) fn acquire(&self) {
+        self.0.send_modify(|count| *count += 1);
+    }
+
+    /// Records a value processed by the consumer.
+    pub(crate) fn release(&self) {
+        self.0.send_modify(|count| *count = count.saturating_sub(1));
+    }
+
+    /// Forgets the values which haven't been processed, e.g. once the consumer
+    /// is gone.
+    pub(crate) fn reset(&self) {
+        self.0.send_replace(0);
+    }
+
+    /// Returns a receiver of the number of values in flight.
+    pub(crate) fn subscribe(&self) -> watch::Receiver<usize> {
+        self.0.subscribe()
+    }
+
+    /// Waits until there are fewer than `limit` values in flight.
+    pub(crate) async fn wait_below(&self, limit: usize) {
+        // The sender is owned by `self`, so the receiver cannot be closed.
+        let _ = self.subscribe().wait_for(|count| *count < limit).await;
+    }
+}
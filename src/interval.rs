@@ -0,0 +1,221 @@
+use std::{future::Future, time::Duration};
+
+use tokio::time::{Instant, MissedTickBehavior};
+
+use crate::{clock::SharedClock, Cancellable, CancellationResult};
+
+/// Service calling an asynchronous function periodically.
+///
+/// The function is called for the first time right after the service has
+/// been spawned and then once per every `period`, measured with the service's
+/// clock, see [`SpawnOptions::clock`]. Each value returned by the function is
+/// yielded by the service, while an error completes the service.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use cancellable::{Cancellable, CancellationToken, IntervalCancellable};
+/// use tokio::time::MissedTickBehavior;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let service = IntervalCancellable::new(Duration::from_secs(5), || async {
+///     println!("Tick");
+///     Ok::<_, std::io::Error>(())
+/// })
+/// .missed_tick_behavior(MissedTickBehavior::Skip);
+///
+/// let handle = service.spawn(CancellationToken::new()).await;
+/// handle.cancel();
+/// # }
+/// ```
+///
+/// [`SpawnOptions::clock`]: crate::SpawnOptions::clock
+#[derive(Debug)]
+pub struct IntervalCancellable<F> {
+    period: Duration,
+    missed_tick_behavior: MissedTickBehavior,
+    next_tick: Option<Instant>,
+    f: F,
+}
+
+impl<F> IntervalCancellable<F> {
+    /// Constructs a new service calling `f` once per every `period`.
+    ///
+    /// # Panics
+    ///
+    /// The service panics when it's spawned, if `period` is zero.
+    pub fn new(period: Duration, f: F) -> Self {
+        Self {
+            period,
+            missed_tick_behavior: MissedTickBehavior::default(),
+            next_tick: None,
+            f,
+        }
+    }
+
+    /// Sets the behavior of the service when it misses a tick, e.g. because a
+    /// call to the function has taken longer than `period`.
+    ///
+    /// Defaults to [`MissedTickBehavior::Burst`].
+    pub fn missed_tick_behavior(mut self, behavior: MissedTickBehavior) -> Self {
+        self.missed_tick_behavior = behavior;
+        self
+    }
+}
+
+impl<F, Fut, T, E> Cancellable for IntervalCancellable<F>
+where
+    F: FnMut() -> Fut + Send,
+    Fut: Future<Output = Result<T, E>> + Send,
+    T: Send,
+    E: std::fmt::Debug + std::fmt::Display + Send,
+{
+    type Result = T;
+    type Handle = ();
+    type Error = E;
+    type Output = ();
+
+    async fn new_handle(&mut self) -> Self::Handle {}
+
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        let clock = SharedClock::current();
+        let tick = match self.next_tick {
+            Some(tick) => tick,
+            None => {
+                assert!(!self.period.is_zero(), "period must be positive");
+                clock.now()
+            }
+        };
+        let wait = tick.saturating_duration_since(clock.now());
+        if !wait.is_zero() {
+            clock.sleep(wait).await;
+        }
+        self.next_tick = Some(next_tick(
+            tick,
+            clock.now(),
+            self.period,
+            self.missed_tick_behavior,
+        ));
+
+        (self.f)().await.map(CancellationResult::Item)
+    }
+}
+
+/// Returns the instant of the tick following the one scheduled at `tick`,
+/// which has happened at `now`.
+fn next_tick(
+    tick: Instant,
+    now: Instant,
+    period: Duration,
+    behavior: MissedTickBehavior,
+) -> Instant {
+    match behavior {
+        MissedTickBehavior::Burst => tick + period,
+        MissedTickBehavior::Delay => now + period,
+        MissedTickBehavior::Skip => {
+            let missed = now.duration_since(tick).as_nanos() % period.as_nanos();
+            // The remainder is smaller than `period`, so it fits.
+            now + period - Duration::from_nanos(missed as u64)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use tokio::time::{timeout, Instant, MissedTickBehavior};
+    use tokio_util::sync::CancellationToken;
+
+    use super::next_tick;
+    use crate::{
+        clock::InstantClock, CallbackResult, Cancellable, IntervalCancellable, SpawnOptions,
+    };
+
+    #[tokio::test]
+    async fn should_call_function_periodically() {
+        // Arrange
+        let mut calls = 0;
+        let service = IntervalCancellable::new(Duration::from_millis(20), move || {
+            calls += 1;
+            async move { Ok::<_, anyhow::Error>(calls) }
+        });
+        let start = Instant::now();
+
+        // Act
+        let handle = service
+            .spawn_with_callback(CancellationToken::new(), |calls| {
+                if calls == 3 {
+                    CallbackResult::Break
+                } else {
+                    CallbackResult::Continue
+                }
+            })
+            .await;
+
+        // Assert
+        assert!(handle.join().await.is_ok());
+        assert!(start.elapsed() >= Duration::from_millis(40));
+    }
+
+    #[tokio::test]
+    async fn should_fail_when_function_fails() {
+        // Arrange
+        let service = IntervalCancellable::new(Duration::from_millis(20), || async {
+            Err::<(), _>(anyhow::anyhow!("IntervalCancellable error"))
+        });
+
+        // Act
+        let handle = service.spawn(CancellationToken::new()).await;
+
+        // Assert
+        assert!(handle.join().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn should_wait_for_ticks_with_service_clock() {
+        // Arrange
+        let mut calls = 0;
+        let service = IntervalCancellable::new(Duration::from_secs(3600), move || {
+            calls += 1;
+            async move { Ok::<_, anyhow::Error>(calls) }
+        });
+        let options = SpawnOptions::new().clock(Arc::new(InstantClock));
+
+        // Act
+        let handle = service
+            .spawn_with_options(CancellationToken::new(), options, |calls| {
+                if calls == 3 {
+                    CallbackResult::Break
+                } else {
+                    CallbackResult::Continue
+                }
+            })
+            .await;
+
+        // Assert
+        let result = timeout(Duration::from_secs(1), handle.join()).await;
+        assert!(result.unwrap().is_ok());
+    }
+
+    #[test]
+    fn should_schedule_next_tick_according_to_missed_tick_behavior() {
+        // Arrange
+        let tick = Instant::now();
+        let period = Duration::from_secs(10);
+        let now = tick + Duration::from_secs(25);
+
+        // Act
+        let burst = next_tick(tick, now, period, MissedTickBehavior::Burst);
+        let delay = next_tick(tick, now, period, MissedTickBehavior::Delay);
+        let skip = next_tick(tick, now, period, MissedTickBehavior::Skip);
+
+        // Assert
+        assert_eq!(tick + Duration::from_secs(10), burst);
+        assert_eq!(tick + Duration::from_secs(35), delay);
+        assert_eq!(tick + Duration::from_secs(30), skip);
+    }
+}
@@ -0,0 +1,91 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_core::Stream;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::in_flight::InFlight;
+
+/// Stream of values yielded by a service spawned with
+/// [`Cancellable::spawn_stream`].
+///
+/// The stream ends when the service completes. Dropping the stream causes the
+/// service to complete as soon as it yields its next value.
+///
+/// [`Cancellable::spawn_stream`]: crate::Cancellable::spawn_stream
+#[derive(Debug)]
+pub struct ItemStream<T> {
+    receiver: UnboundedReceiver<T>,
+    in_flight: Option<InFlight>,
+}
+
+impl<T> ItemStream<T> {
+    pub(crate) fn new(receiver: UnboundedReceiver<T>) -> Self {
+        Self {
+            receiver,
+            in_flight: None,
+        }
+    }
+
+    /// Releases each value taken from the stream from `in_flight`.
+    pub(crate) fn with_in_flight(mut self, in_flight: InFlight) -> Self {
+        self.in_flight = Some(in_flight);
+        self
+    }
+
+    /// Receives the next value yielded by the service.
+    pub(crate) async fn recv(&mut self) -> Option<T> {
+        std::future::poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    fn poll_recv(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        let poll = self.receiver.poll_recv(cx);
+        if let (Poll::Ready(Some(_)), Some(in_flight)) = (&poll, &self.in_flight) {
+            in_flight.release();
+        }
+        poll
+    }
+}
+
+impl<T> Stream for ItemStream<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.poll_recv(cx)
+    }
+}
+
+impl<T> Drop for ItemStream<T> {
+    fn drop(&mut self) {
+        // Nobody is going to take the values, so the service mustn't wait for
+        // it. It completes once it yields its next value.
+        if let Some(in_flight) = &self.in_flight {
+            in_flight.reset();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    use crate::ItemStream;
+
+    #[tokio::test]
+    async fn should_yield_sent_items() {
+        // Arrange
+        let (sender, receiver) = unbounded_channel();
+        let stream = ItemStream::new(receiver);
+
+        // Act
+        sender.send(1).unwrap();
+        sender.send(2).unwrap();
+        drop(sender);
+
+        // Assert
+        assert_eq!(vec![1, 2], stream.collect::<Vec<_>>().await);
+    }
+}
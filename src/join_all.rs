@@ -0,0 +1,188 @@
+use crate::{CancellableError, CancellableHandle, LocalCancellable};
+
+/// Flattened result of a single service, see [`CancellableHandle::join`].
+type JoinResult<T> = Result<
+    Option<<T as LocalCancellable>::Output>,
+    CancellableError<<T as LocalCancellable>::Error>,
+>;
+
+/// Outcome of joining a collection of services with [`join_all`].
+///
+/// The results are kept in the order of the joined handles.
+pub struct JoinReport<T>
+where
+    T: LocalCancellable,
+{
+    results: Vec<JoinResult<T>>,
+}
+
+impl<T> JoinReport<T>
+where
+    T: LocalCancellable,
+{
+    /// Checks if all services have completed successfully.
+    pub fn is_ok(&self) -> bool {
+        self.results.iter().all(Result::is_ok)
+    }
+
+    /// Returns the results of the services, in the order of their handles.
+    // Spelled out, so the documentation doesn't refer to a private alias.
+    #[allow(clippy::type_complexity)]
+    pub fn results(&self) -> &[Result<Option<T::Output>, CancellableError<T::Error>>] {
+        &self.results
+    }
+
+    /// Returns the results of the services, in the order of their handles.
+    #[allow(clippy::type_complexity)]
+    pub fn into_results(self) -> Vec<Result<Option<T::Output>, CancellableError<T::Error>>> {
+        self.results
+    }
+
+    /// Returns the indices and errors of the services which have completed
+    /// with an error.
+    pub fn failures(&self) -> impl Iterator<Item = (usize, &T::Error)> {
+        self.results
+            .iter()
+            .enumerate()
+            .filter_map(|(index, result)| match result {
+                Err(CancellableError::Service(e)) => Some((index, e)),
+                _ => None,
+            })
+    }
+
+    /// Returns the indices of the services whose tasks have panicked.
+    pub fn panicked(&self) -> impl Iterator<Item = usize> + '_ {
+        self.indices_where(|e| matches!(e, CancellableError::Panicked(_)))
+    }
+
+    /// Returns the indices of the services whose tasks have been aborted.
+    pub fn aborted(&self) -> impl Iterator<Item = usize> + '_ {
+        self.indices_where(|e| matches!(e, CancellableError::Cancelled))
+    }
+
+    fn indices_where<F>(&self, predicate: F) -> impl Iterator<Item = usize> + '_
+    where
+        F: Fn(&CancellableError<T::Error>) -> bool + 'static,
+    {
+        self.results
+            .iter()
+            .enumerate()
+            .filter(move |(_, result)| result.as_ref().is_err_and(&predicate))
+            .map(|(index, _)| index)
+    }
+}
+
+impl<T> std::fmt::Debug for JoinReport<T>
+where
+    T: LocalCancellable,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JoinReport")
+            .field("results", &self.results)
+            .finish()
+    }
+}
+
+/// Waits for all services of `handles` to complete.
+///
+/// It doesn't cancel the services, so it's meant to be called once they have
+/// been cancelled, or they will complete on their own.
+///
+/// # Returns
+///
+/// The report of the services' results, classified into successes, service
+/// errors, panics and aborts.
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{Cancellable, CancellationToken};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mut handles = Vec::new();
+/// for items in [vec![1, 2], vec![3]] {
+///     let service = cancellable::from_stream(futures::stream::iter(items));
+///     handles.push(service.spawn(CancellationToken::new()).await);
+/// }
+///
+/// let report = cancellable::join_all(handles).await;
+///
+/// assert!(report.is_ok());
+/// assert_eq!(0, report.failures().count());
+/// # }
+/// ```
+pub async fn join_all<T, H, I>(handles: I) -> JoinReport<T>
+where
+    T: LocalCancellable,
+    I: IntoIterator<Item = CancellableHandle<T, H>>,
+{
+    let mut results = Vec::new();
+    // The services run on their own, so awaiting them one by one takes as long
+    // as awaiting the slowest one.
+    for handle in handles {
+        results.push(handle.join().await);
+    }
+
+    JoinReport { results }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{Cancellable, CancellationResult};
+
+    struct OutcomeCancellable {
+        value: i32,
+    }
+
+    impl Cancellable for OutcomeCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = i32;
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<(), i32>, Self::Error> {
+            match self.value {
+                0 => panic!("OutcomeCancellable panic"),
+                value if value < 0 => Err(anyhow::anyhow!("OutcomeCancellable error")),
+                value => Ok(CancellationResult::BreakWith(value)),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn should_classify_results() {
+        // Arrange
+        let mut handles = Vec::new();
+        for value in [1, -1, 0, 2] {
+            let service = OutcomeCancellable { value };
+            handles.push(service.spawn(CancellationToken::new()).await);
+        }
+        let pending = crate::from_stream(futures::stream::pending::<()>())
+            .spawn(CancellationToken::new())
+            .await;
+        pending.abort();
+
+        // Act
+        let report = crate::join_all(handles).await;
+        let aborted = crate::join_all([pending]).await;
+
+        // Assert
+        assert!(!report.is_ok());
+        assert_eq!(
+            vec![1],
+            report
+                .failures()
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(vec![2], report.panicked().collect::<Vec<_>>());
+        assert_eq!(Some(1), *report.results()[0].as_ref().unwrap());
+        assert_eq!(Some(2), *report.results()[3].as_ref().unwrap());
+        assert_eq!(vec![0], aborted.aborted().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,54 @@
+use tokio::sync::watch;
+
+/// Handle for observing the latest value yielded by a service spawned with
+/// [`Cancellable::spawn_watch`].
+///
+/// Unlike [`ItemStream`], values which haven't been observed before the next
+/// one is yielded are overwritten.
+///
+/// [`Cancellable::spawn_watch`]: crate::Cancellable::spawn_watch
+/// [`ItemStream`]: crate::ItemStream
+#[derive(Debug)]
+pub struct LatestHandle<T> {
+    receiver: watch::Receiver<Option<T>>,
+}
+
+impl<T> LatestHandle<T> {
+    pub(crate) fn new(receiver: watch::Receiver<Option<T>>) -> Self {
+        Self { receiver }
+    }
+
+    /// Returns the latest value yielded by the service, or `None` if it
+    /// hasn't yielded any value yet.
+    pub fn latest(&self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.receiver.borrow().clone()
+    }
+
+    /// Returns a receiver notified every time the service yields a value.
+    pub fn subscribe(&self) -> watch::Receiver<Option<T>> {
+        self.receiver.clone()
+    }
+
+    /// Waits for the service to yield a value that hasn't been observed by
+    /// this handle yet.
+    ///
+    /// # Returns
+    ///
+    /// The yielded value, or `None` if the service has completed.
+    pub async fn changed(&mut self) -> Option<T>
+    where
+        T: Clone,
+    {
+        self.receiver.changed().await.ok()?;
+        self.receiver.borrow_and_update().clone()
+    }
+}
+
+impl<T> Clone for LatestHandle<T> {
+    fn clone(&self) -> Self {
+        Self::new(self.receiver.clone())
+    }
+}
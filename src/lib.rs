@@ -1,15 +1,15 @@
 //! This crate provides a way of defining an interface for a background service.
 //!
-//! The main entrypoint of this create is [`cancellable::Cancellable`] trait. It
-//! is an [`async-trait`](https://docs.rs/async-trait/latest/async_trait/) that
-//! depends on [tokio](https://tokio.rs).
+//! The main entrypoint of this create is [`cancellable::Cancellable`] trait. Its
+//! methods are implemented with native `async fn`s and the services it defines
+//! are driven by [tokio](https://tokio.rs).
 //!
 //! # Examples
 //!
 //! ```
 //! use std::{error::Error, net::SocketAddr};
 //!
-//! use cancellable::{async_trait, Cancellable, CancellationResult};
+//! use cancellable::{Cancellable, CancellationResult};
 //! use tokio::net::{TcpListener, TcpStream};
 //!
 //! struct Listener {
@@ -24,30 +24,291 @@
 //!     }
 //! }
 //!
-//! #[async_trait]
 //! impl Cancellable for Listener {
 //!     type Result = (TcpStream, SocketAddr);
 //!     type Handle = ();
 //!     type Error = std::io::Error;
+//!     type Output = ();
 //!
 //!     async fn new_handle(&mut self) -> Self::Handle {}
 //!
-//!     async fn run(&mut self) -> Result<CancellationResult<Self::Result>, Self::Error> {
+//!     async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
 //!         let (addr, stream) = self.tcp_listener.accept().await?;
 //!
 //!         Ok(CancellationResult::item((addr, stream)))
 //!     }
 //! }
 //! ```
+//!
+//! # Features
+//!
+//! * `async-trait` (enabled by default) - re-exports the `async_trait`
+//!   attribute, which `Cancellable` used to require. It's deprecated and
+//!   will be removed in the next release.
+//! * `cron` - enables `CronSchedule`, which schedules the jobs of a `Scheduler`
+//!   with cron expressions.
+//! * `file-watcher` - enables `FileWatcher`, which yields the filesystem
+//!   events of the watched paths.
+//! * `health-server` - enables `HealthServer`, which serves the health of
+//!   services over HTTP.
+//! * `listener` - enables `UnixSocketListener` on Unix and `NamedPipeListener`
+//!   on Windows, which accept local connections.
+//! * `macros` - enables the `service` attribute macro, which generates the
+//!   handle plumbing of a service.
+//! * `process` - enables `ProcessService`, which supervises a child process.
+//! * `signal` - enables the `shutdown` module, which cancels services on
+//!   the operating system's shutdown signals.
+//! * `sink` - enables `SenderSink`, which implements `futures::Sink` for
+//!   sender handles.
+//! * `testing` - enables the `testing` module, which provides utilities for
+//!   testing services and their consumers.
+//! * `tower` - enables the `tower` module, which adapts services to and from
+//!   `tower::Service`.
+//! * `tracing` - instruments spawned services with
+//!   [`tracing`](https://docs.rs/tracing/latest/tracing/) spans and events.
+//! * `udp` - enables `UdpService`, which receives and sends datagrams of a
+//!   UDP socket.
 
 #![warn(missing_docs)]
 
+mod ack;
+mod actor;
+mod adapters;
+mod batch;
+mod blocking;
+mod broadcast;
+mod callback_panic;
+mod callback_result;
+mod cancel_reason;
 mod cancellable;
+mod cancellable_error;
 mod cancellable_handle;
+mod cancellable_set;
+mod cancellation_priority;
 mod cancellation_result;
+mod catch_unwind;
+mod clock;
+mod completion_reason;
+mod concurrent;
+#[cfg(feature = "cron")]
+mod cron_schedule;
+mod deadline;
+mod error_budget;
+mod error_directive;
+#[cfg(feature = "file-watcher")]
+mod file_watcher;
+mod fn_cancellable;
+mod health;
+#[cfg(feature = "health-server")]
+mod health_server;
+mod idle;
+mod in_flight;
+mod interval;
+mod item_stream;
+mod join_all;
+mod latest;
+#[cfg(feature = "listener")]
+mod listener;
+mod local_cancellable;
+mod metrics;
+mod observer;
+mod pipe;
+mod priority_handle;
+#[cfg(feature = "process")]
+mod process_service;
+mod race;
+mod rate_limiter;
+mod receiver_cancellable;
+mod registry;
+mod request_handle;
+mod restart_policy;
+mod router;
+mod run_context;
+mod scheduler;
+mod scope;
+mod sender_handle;
+#[cfg(feature = "sink")]
+mod sender_sink;
+mod service_group;
+mod service_stats;
+#[cfg(feature = "signal")]
+pub mod shutdown;
+mod shutdown_coordinator;
+mod simple_cancellable;
+mod spawn_options;
+mod stop_phase;
+mod stream_cancellable;
+mod supervisor;
+#[cfg(feature = "testing")]
+pub mod testing;
+mod time_budget;
+#[cfg(feature = "tower")]
+pub mod tower;
+mod trace;
+#[cfg(feature = "udp")]
+mod udp_service;
+mod watchdog;
+mod work_loop;
+mod work_queue;
+mod worker_pool;
+mod yielder;
+
+#[cfg(feature = "macros")]
+#[doc(hidden)]
+pub mod __private;
 
+pub use crate::ack::Ack;
+pub use crate::actor::{Actor, ActorService, Mailbox, Reply};
+pub use crate::adapters::{
+    Debounce, Filter, FilterMap, Inspect, InspectErr, Map, Take, TakeUntil, Throttle,
+};
+pub use crate::blocking::{Blocking, BlockingCancellable};
+pub use crate::broadcast::{LagPolicy, SubscriberHandle, Subscription};
+pub use crate::callback_panic::{CallbackPanicPolicy, CallbackPanicked};
+pub use crate::callback_result::CallbackResult;
+pub use crate::cancel_reason::CancelReason;
 pub use crate::cancellable::Cancellable;
+pub use crate::cancellable_error::CancellableError;
 pub use crate::cancellable_handle::CancellableHandle;
+pub use crate::cancellable_set::CancellableSet;
+pub use crate::cancellation_priority::CancellationPriority;
 pub use crate::cancellation_result::CancellationResult;
-pub use async_trait::async_trait;
+pub use crate::clock::{Clock, TokioClock};
+pub use crate::completion_reason::CompletionReason;
+pub use crate::concurrent::{Concurrent, ConcurrentCancellable};
+#[cfg(feature = "cron")]
+pub use crate::cron_schedule::{CatchUp, CronSchedule};
+pub use crate::deadline::{Deadline, DeadlineError};
+pub use crate::error_budget::{ErrorBudget, ServiceErrors};
+pub use crate::error_directive::ErrorDirective;
+#[cfg(feature = "file-watcher")]
+pub use crate::file_watcher::FileWatcher;
+pub use crate::fn_cancellable::{from_fn, FnCancellable};
+pub use crate::health::Health;
+#[cfg(feature = "health-server")]
+pub use crate::health_server::HealthServer;
+pub use crate::idle::Idle;
+pub use crate::interval::IntervalCancellable;
+pub use crate::item_stream::ItemStream;
+pub use crate::join_all::{join_all, JoinReport};
+pub use crate::latest::LatestHandle;
+#[cfg(all(windows, feature = "listener"))]
+pub use crate::listener::NamedPipeListener;
+#[cfg(all(unix, feature = "listener"))]
+pub use crate::listener::UnixSocketListener;
+pub use crate::local_cancellable::LocalCancellable;
+pub use crate::metrics::CancellableMetrics;
+pub use crate::observer::CancellableObserver;
+pub use crate::pipe::PipeHandle;
+pub use crate::priority_handle::{PriorityReceiver, PrioritySenderHandle};
+#[cfg(feature = "process")]
+pub use crate::process_service::{ProcessLine, ProcessService};
+pub use crate::race::race;
+pub use crate::receiver_cancellable::{from_channel, from_receiver, ReceiverCancellable};
+pub use crate::registry::Registry;
+pub use crate::request_handle::{CallError, RequestHandle, RequestHandler, RequestService};
+pub use crate::restart_policy::RestartPolicy;
+pub use crate::router::Router;
+pub use crate::run_context::RunContext;
+pub use crate::scheduler::{JobId, Scheduler, SchedulerHandle};
+pub use crate::scope::{scope, Scope};
+pub use crate::sender_handle::{MpscSenderHandle, SenderHandle, UnboundedSenderHandle};
+#[cfg(feature = "sink")]
+pub use crate::sender_sink::SenderSink;
+pub use crate::service_group::{BoxError, GroupReport, ServiceGroup};
+pub use crate::service_stats::ServiceStats;
+pub use crate::shutdown_coordinator::{PhaseReport, ShutdownCoordinator};
+pub use crate::simple_cancellable::SimpleCancellable;
+pub use crate::spawn_options::SpawnOptions;
+pub use crate::stop_phase::StopPhase;
+pub use crate::stream_cancellable::{from_stream, StreamCancellable};
+pub use crate::supervisor::{
+    Respawnable, SupervisedHandle, SupervisionStrategy, Supervisor, SupervisorError,
+    SupervisorEvent,
+};
+pub use crate::time_budget::{BudgetExceeded, TimeBudget};
+#[cfg(feature = "udp")]
+pub use crate::udp_service::UdpService;
+pub use crate::watchdog::{Stalled, Watchdog};
+pub use crate::work_queue::{
+    Delivery, FileBackend, Journaled, MemoryBackend, QueueBackend, WorkQueueHandle,
+    WorkQueueReceiver,
+};
+pub use crate::worker_pool::{spawn_pool, PoolHandle, SharedReceiver};
+pub use crate::yielder::Yielder;
+/// Generates the handle plumbing of a service.
+///
+/// When applied to a struct, the macro looks for the field providing the
+/// service's handle:
+///
+/// * `#[handle_receiver(capacity = N)]` marks a `tokio::sync::mpsc::Receiver`
+///   the service receives its work from. The handle is an
+///   [`MpscSenderHandle`] of the channel with capacity `N`, and the macro
+///   generates a `new` constructor taking the struct's remaining fields and
+///   creating the channel. A `tokio::sync::mpsc::UnboundedReceiver` is marked
+///   with `#[handle_receiver]`, and its handle is an
+///   [`UnboundedSenderHandle`].
+/// * `#[handle_sender]` marks a field holding the handle itself. If the field
+///   is an `Option`, the handle is taken out of it, so it can be obtained only
+///   once. Otherwise, the handle is cloned.
+///
+/// A struct without such a field gets `()` as its handle.
+///
+/// When applied to the struct's [`Cancellable`] or [`LocalCancellable`] impl,
+/// it fills in [`Cancellable::Handle`] and [`Cancellable::new_handle`], unless
+/// they're defined by the impl.
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{
+///     service, Cancellable, CancellationResult, CancellationToken, SenderHandle,
+/// };
+/// use tokio::sync::mpsc::Receiver;
+///
+/// #[service]
+/// struct Printer {
+///     prefix: String,
+///     #[handle_receiver(capacity = 16)]
+///     lines: Receiver<String>,
+/// }
+///
+/// #[service]
+/// impl Cancellable for Printer {
+///     type Result = ();
+///     type Error = std::io::Error;
+///     type Output = ();
+///
+///     async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+///         match self.lines.recv().await {
+///             Some(line) => println!("{}{line}", self.prefix),
+///             None => return Ok(CancellationResult::Break),
+///         }
+///
+///         Ok(CancellationResult::Continue)
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let printer = Printer::new("> ".into());
+/// let handle = printer.spawn(CancellationToken::new()).await;
+/// handle.send("Hello, world!".into()).await.unwrap();
+/// # }
+/// ```
+#[cfg(feature = "macros")]
+pub use cancellable_macros::service;
 pub use tokio_util::sync::CancellationToken;
+
+/// Re-export of the [`async-trait`](https://docs.rs/async-trait/latest/async_trait/)
+/// attribute, which [`Cancellable`] used to require.
+///
+/// [`Cancellable`] is implemented with native `async fn`s now, so the
+/// attribute should be removed from its implementations. The re-export is
+/// deprecated and will be removed in the next release, along with the
+/// `async-trait` feature.
+#[cfg(feature = "async-trait")]
+#[deprecated(
+    note = "`Cancellable` is implemented with native `async fn`s, remove `#[async_trait]` from its implementations"
+)]
+pub use async_trait::async_trait;
@@ -0,0 +1,272 @@
+#[cfg(unix)]
+pub use self::unix::UnixSocketListener;
+#[cfg(windows)]
+pub use self::windows::NamedPipeListener;
+
+#[cfg(unix)]
+mod unix {
+    use std::{
+        io,
+        path::{Path, PathBuf},
+    };
+
+    use tokio::net::{unix::SocketAddr, UnixListener, UnixStream};
+
+    use crate::{Cancellable, CancellationResult};
+
+    /// Service accepting connections on a Unix domain socket.
+    ///
+    /// Each accepted connection is yielded along with the address of its peer.
+    /// By default, the socket's file is removed once the service stops, so the
+    /// path can be bound again, see [`Self::unlink_on_stop`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cancellable::{Cancellable, CancellationToken, UnixSocketListener};
+    /// use futures::StreamExt;
+    /// use tokio::net::UnixStream;
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> std::io::Result<()> {
+    /// let path = std::env::temp_dir().join(format!("example-{}.sock", std::process::id()));
+    /// let listener = UnixSocketListener::bind(&path)?;
+    ///
+    /// let (handle, mut connections) = listener.spawn_stream(CancellationToken::new()).await;
+    /// let _client = UnixStream::connect(&path).await?;
+    /// let (_stream, _peer) = connections.next().await.unwrap();
+    ///
+    /// handle.cancel();
+    /// handle.join().await.unwrap();
+    /// assert!(!path.exists());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub struct UnixSocketListener {
+        listener: UnixListener,
+        path: Option<PathBuf>,
+        unlink_on_stop: bool,
+    }
+
+    impl UnixSocketListener {
+        /// Constructs a new service accepting connections on `listener`.
+        pub fn new(listener: UnixListener) -> Self {
+            let path = listener
+                .local_addr()
+                .ok()
+                .and_then(|addr| addr.as_pathname().map(Path::to_owned));
+
+            Self {
+                listener,
+                path,
+                unlink_on_stop: true,
+            }
+        }
+
+        /// Constructs a new service with a socket bound to `path`.
+        ///
+        /// # Panics
+        ///
+        /// This function panics if it's called outside of a Tokio runtime.
+        pub fn bind(path: impl AsRef<Path>) -> io::Result<Self> {
+            Ok(Self::new(UnixListener::bind(path)?))
+        }
+
+        /// Sets whether the socket's file is removed once the service stops.
+        ///
+        /// It's enabled by default. It has no effect on unnamed sockets.
+        pub fn unlink_on_stop(mut self, unlink: bool) -> Self {
+            self.unlink_on_stop = unlink;
+            self
+        }
+
+        /// Returns the address the socket is bound to.
+        pub fn local_addr(&self) -> io::Result<SocketAddr> {
+            self.listener.local_addr()
+        }
+    }
+
+    impl Cancellable for UnixSocketListener {
+        type Result = (UnixStream, SocketAddr);
+        type Handle = ();
+        type Error = io::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn on_stop(&mut self) {
+            if let (true, Some(path)) = (self.unlink_on_stop, &self.path) {
+                // The file might have been removed by someone else already.
+                let _ = std::fs::remove_file(path);
+            }
+        }
+
+        async fn run(
+            &mut self,
+        ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+            Ok(CancellationResult::item(self.listener.accept().await?))
+        }
+    }
+
+    impl std::fmt::Debug for UnixSocketListener {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("UnixSocketListener")
+                .field("listener", &self.listener)
+                .field("unlink_on_stop", &self.unlink_on_stop)
+                .finish_non_exhaustive()
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use std::path::PathBuf;
+
+        use futures::StreamExt;
+        use tokio::{
+            io::{AsyncReadExt, AsyncWriteExt},
+            net::UnixStream,
+        };
+        use tokio_util::sync::CancellationToken;
+
+        use crate::{Cancellable, UnixSocketListener};
+
+        /// Returns a path of a socket for a single test.
+        fn socket_path(name: &str) -> PathBuf {
+            let path = std::env::temp_dir()
+                .join(format!("cancellable-{}-{name}.sock", std::process::id()));
+            let _ = std::fs::remove_file(&path);
+            path
+        }
+
+        #[tokio::test]
+        async fn should_accept_connections_and_unlink_socket_on_stop() {
+            // Arrange
+            let path = socket_path("unlinked");
+            let listener = UnixSocketListener::bind(&path).unwrap();
+            let (handle, mut connections) = listener.spawn_stream(CancellationToken::new()).await;
+
+            // Act
+            let mut client = UnixStream::connect(&path).await.unwrap();
+            let (mut stream, _) = connections.next().await.unwrap();
+            client.write_all(b"ping").await.unwrap();
+            let mut buffer = [0; 4];
+            stream.read_exact(&mut buffer).await.unwrap();
+            handle.cancel();
+            let result = handle.join().await;
+
+            // Assert
+            assert_eq!(b"ping", &buffer);
+            assert!(result.is_ok());
+            assert!(!path.exists());
+        }
+
+        #[tokio::test]
+        async fn should_keep_socket_when_unlinking_is_disabled() {
+            // Arrange
+            let path = socket_path("kept");
+            let listener = UnixSocketListener::bind(&path)
+                .unwrap()
+                .unlink_on_stop(false);
+            let handle = listener.spawn(CancellationToken::new()).await;
+
+            // Act
+            handle.cancel();
+            let result = handle.join().await;
+
+            // Assert
+            assert!(result.is_ok());
+            assert!(path.exists());
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::{
+        ffi::{OsStr, OsString},
+        io,
+    };
+
+    use tokio::net::windows::named_pipe::{NamedPipeServer, ServerOptions};
+
+    use crate::{Cancellable, CancellationResult};
+
+    /// Service accepting connections on a Windows named pipe.
+    ///
+    /// Each connection is yielded as the pipe's instance the client has
+    /// connected to, and a new instance is created right away for the next
+    /// client. Named pipes don't outlive their instances, so there's nothing
+    /// to clean up once the service stops.
+    pub struct NamedPipeListener {
+        name: OsString,
+        options: ServerOptions,
+        server: NamedPipeServer,
+    }
+
+    impl NamedPipeListener {
+        /// Constructs a new service creating instances of the pipe called
+        /// `name` with `options`, e.g. `\\.\pipe\my-service`.
+        ///
+        /// The first instance is created right away.
+        ///
+        /// # Panics
+        ///
+        /// This function panics if it's called outside of a Tokio runtime.
+        pub fn new(name: impl AsRef<OsStr>, options: ServerOptions) -> io::Result<Self> {
+            let name = name.as_ref().to_owned();
+            let server = options.create(&name)?;
+
+            Ok(Self {
+                name,
+                options,
+                server,
+            })
+        }
+
+        /// Constructs a new service with the default options, failing if the
+        /// pipe called `name` already exists.
+        ///
+        /// See [`Self::new`].
+        pub fn bind(name: impl AsRef<OsStr>) -> io::Result<Self> {
+            let mut options = ServerOptions::new();
+            let server = options.first_pipe_instance(true).create(name.as_ref())?;
+            options.first_pipe_instance(false);
+
+            Ok(Self {
+                name: name.as_ref().to_owned(),
+                options,
+                server,
+            })
+        }
+    }
+
+    impl Cancellable for NamedPipeListener {
+        type Result = NamedPipeServer;
+        type Handle = ();
+        type Error = io::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(
+            &mut self,
+        ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+            self.server.connect().await?;
+            let next = self.options.create(&self.name)?;
+
+            Ok(CancellationResult::item(std::mem::replace(
+                &mut self.server,
+                next,
+            )))
+        }
+    }
+
+    impl std::fmt::Debug for NamedPipeListener {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("NamedPipeListener")
+                .field("name", &self.name)
+                .finish_non_exhaustive()
+        }
+    }
+}
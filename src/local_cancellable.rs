@@ -0,0 +1,401 @@
+use std::{any::Any, future::Future};
+
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    cancellation_result::CancellationResult, work_loop::WorkLoop, CallbackResult, Cancellable,
+    CancellableHandle, ErrorDirective, SpawnOptions,
+};
+
+/// Defines an interface for a cancellable service that isn't [`Send`].
+///
+/// It's the counterpart of [`Cancellable`] for services holding resources
+/// which cannot be moved across threads, e.g. [`Rc`]. Such a service is
+/// spawned with [`tokio::task::spawn_local`], so it must be spawned within a
+/// [`LocalSet`]. Apart from that, it behaves exactly like [`Cancellable`] and
+/// its methods have the same meaning.
+///
+/// Every [`Cancellable`] implements this trait as well.
+///
+/// [`Rc`]: std::rc::Rc
+/// [`LocalSet`]: tokio::task::LocalSet
+pub trait LocalCancellable {
+    /// Type of values that _can_ be yielded by the service.
+    type Result;
+
+    /// Type of a handle for communicating with the service.
+    type Handle;
+
+    /// Error returned by [`Self::run`] method.
+    type Error: std::fmt::Debug + std::fmt::Display;
+
+    /// Type of the final value the service _can_ complete with.
+    type Output: std::fmt::Debug;
+
+    /// Performs a single unit of work.
+    ///
+    /// See [`Cancellable::run`].
+    fn run(
+        &mut self,
+    ) -> impl Future<Output = Result<CancellationResult<Self::Result, Self::Output>, Self::Error>>;
+
+    /// Returns the name of the service.
+    ///
+    /// See [`Cancellable::name`].
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Constructs a new handle for communicating with the service.
+    ///
+    /// See [`Cancellable::new_handle`].
+    fn new_handle(&mut self) -> impl Future<Output = Self::Handle>;
+
+    /// Called once, before the first call to [`Self::run`].
+    ///
+    /// See [`Cancellable::on_start`].
+    fn on_start(&mut self) -> impl Future<Output = Result<(), Self::Error>> {
+        async { Ok(()) }
+    }
+
+    /// Called once, after the work loop has completed without an error.
+    ///
+    /// See [`Cancellable::on_stop`].
+    fn on_stop(&mut self) -> impl Future<Output = ()> {
+        async {}
+    }
+
+    /// Called once, when the service has been cancelled.
+    ///
+    /// See [`Cancellable::on_cancel`].
+    fn on_cancel(&mut self) -> impl Future<Output = ()> {
+        async {}
+    }
+
+    /// Returns the final value of the service once it has been cancelled.
+    ///
+    /// See [`Cancellable::cancelled_output`].
+    fn cancelled_output(&mut self) -> Option<Self::Output> {
+        None
+    }
+
+    /// Performs a single unit of work after the service has been cancelled.
+    ///
+    /// See [`Cancellable::drain`].
+    fn drain(
+        &mut self,
+    ) -> impl Future<Output = Result<CancellationResult<Self::Result, Self::Output>, Self::Error>>
+    {
+        async { Ok(CancellationResult::Break) }
+    }
+
+    /// Called when a single call to [`Self::run`] has exceeded the iteration
+    /// timeout.
+    ///
+    /// See [`Cancellable::on_timeout`].
+    fn on_timeout(
+        &mut self,
+    ) -> impl Future<Output = Result<CancellationResult<Self::Result, Self::Output>, Self::Error>>
+    {
+        async { Ok(CancellationResult::Continue) }
+    }
+
+    /// Called when a single call to [`Self::run`] has panicked.
+    ///
+    /// See [`Cancellable::on_panic`].
+    fn on_panic(
+        &mut self,
+        panic: Box<dyn Any + Send>,
+    ) -> impl Future<Output = Result<CancellationResult<Self::Result, Self::Output>, Self::Error>>
+    {
+        async move { std::panic::resume_unwind(panic) }
+    }
+
+    /// Called when [`Self::run`] has returned an error.
+    ///
+    /// See [`Cancellable::on_error`].
+    fn on_error(
+        &mut self,
+        error: Self::Error,
+    ) -> impl Future<Output = ErrorDirective<Self::Error>> {
+        async { ErrorDirective::Fail(error) }
+    }
+
+    /// Prepares the service to be run again after [`Self::run`] has failed.
+    ///
+    /// See [`Cancellable::restart`].
+    fn restart(&mut self, error: Self::Error) -> impl Future<Output = Result<(), Self::Error>> {
+        let _ = error;
+        async { Ok(()) }
+    }
+
+    /// Called with a value yielded by the service, which won't be passed to
+    /// the callback.
+    ///
+    /// See [`Cancellable::on_item_dropped`].
+    fn on_item_dropped(&mut self, item: Self::Result) {
+        let _ = item;
+    }
+
+    /// Consumes the service and spawns its work loop onto the current
+    /// [`LocalSet`].
+    ///
+    /// It's equivalent to [`Cancellable::spawn`].
+    ///
+    /// # Panics
+    ///
+    /// This method panics if called outside of a [`LocalSet`].
+    ///
+    /// [`LocalSet`]: tokio::task::LocalSet
+    fn spawn_local(
+        self,
+        cancellation_token: CancellationToken,
+    ) -> impl Future<Output = CancellableHandle<Self>>
+    where
+        Self: Sized + 'static,
+    {
+        self.spawn_local_with_callback(cancellation_token, |_| CallbackResult::Continue)
+    }
+
+    /// Consumes the service and spawns its work loop onto the current
+    /// [`LocalSet`].
+    ///
+    /// It's equivalent to [`Cancellable::spawn_with_callback`].
+    ///
+    /// # Panics
+    ///
+    /// This method panics if called outside of a [`LocalSet`].
+    ///
+    /// [`LocalSet`]: tokio::task::LocalSet
+    fn spawn_local_with_callback<F>(
+        self,
+        cancellation_token: CancellationToken,
+        callback: F,
+    ) -> impl Future<Output = CancellableHandle<Self>>
+    where
+        Self: Sized + 'static,
+        F: FnMut(Self::Result) -> CallbackResult<Self::Error, Self::Result> + 'static,
+    {
+        self.spawn_local_with_options(cancellation_token, SpawnOptions::default(), callback)
+    }
+
+    /// Consumes the service and spawns its work loop onto the current
+    /// [`LocalSet`].
+    ///
+    /// It's equivalent to [`Cancellable::spawn_with_options`].
+    ///
+    /// # Panics
+    ///
+    /// This method panics if called outside of a [`LocalSet`].
+    ///
+    /// [`LocalSet`]: tokio::task::LocalSet
+    fn spawn_local_with_options<F>(
+        mut self,
+        cancellation_token: CancellationToken,
+        options: SpawnOptions,
+        callback: F,
+    ) -> impl Future<Output = CancellableHandle<Self>>
+    where
+        Self: Sized + 'static,
+        F: FnMut(Self::Result) -> CallbackResult<Self::Error, Self::Result> + 'static,
+    {
+        // The handle is a future itself, but it's meant to be returned, not awaited.
+        #[allow(clippy::async_yields_async)]
+        async move {
+            let inner_cancellable_token = cancellation_token.child_token();
+            let inner = self.new_handle().await;
+
+            #[cfg(feature = "tracing")]
+            let span = tracing::info_span!("service", name = self.name());
+
+            let clock = options.clock.clone();
+            let work_loop = WorkLoop::new(self, inner_cancellable_token.clone(), options, callback);
+            let health = work_loop.health();
+            let cancel_reason = work_loop.cancel_reason();
+            let completion = work_loop.completion();
+            let children = work_loop.children();
+            let stats = work_loop.stats();
+            let future = work_loop.run();
+
+            #[cfg(feature = "tracing")]
+            let future = tracing::Instrument::instrument(future, span);
+
+            let join_handle = tokio::task::spawn_local(future);
+
+            CancellableHandle::<Self>::new(join_handle, inner_cancellable_token, inner)
+                .with_health(health)
+                .with_cancel_reason(cancel_reason)
+                .with_completion(completion)
+                .with_children(children)
+                .with_stats(stats)
+                .with_clock(clock)
+        }
+    }
+}
+
+impl<T> LocalCancellable for T
+where
+    T: Cancellable,
+{
+    type Result = <T as Cancellable>::Result;
+    type Handle = <T as Cancellable>::Handle;
+    type Error = <T as Cancellable>::Error;
+    type Output = <T as Cancellable>::Output;
+
+    fn run(
+        &mut self,
+    ) -> impl Future<Output = Result<CancellationResult<Self::Result, Self::Output>, Self::Error>>
+    {
+        Cancellable::run(self)
+    }
+
+    fn name(&self) -> &str {
+        Cancellable::name(self)
+    }
+
+    fn new_handle(&mut self) -> impl Future<Output = Self::Handle> {
+        Cancellable::new_handle(self)
+    }
+
+    fn on_start(&mut self) -> impl Future<Output = Result<(), Self::Error>> {
+        Cancellable::on_start(self)
+    }
+
+    fn on_stop(&mut self) -> impl Future<Output = ()> {
+        Cancellable::on_stop(self)
+    }
+
+    fn on_cancel(&mut self) -> impl Future<Output = ()> {
+        Cancellable::on_cancel(self)
+    }
+
+    fn cancelled_output(&mut self) -> Option<Self::Output> {
+        Cancellable::cancelled_output(self)
+    }
+
+    fn drain(
+        &mut self,
+    ) -> impl Future<Output = Result<CancellationResult<Self::Result, Self::Output>, Self::Error>>
+    {
+        Cancellable::drain(self)
+    }
+
+    fn on_timeout(
+        &mut self,
+    ) -> impl Future<Output = Result<CancellationResult<Self::Result, Self::Output>, Self::Error>>
+    {
+        Cancellable::on_timeout(self)
+    }
+
+    fn on_panic(
+        &mut self,
+        panic: Box<dyn Any + Send>,
+    ) -> impl Future<Output = Result<CancellationResult<Self::Result, Self::Output>, Self::Error>>
+    {
+        Cancellable::on_panic(self, panic)
+    }
+
+    fn on_error(
+        &mut self,
+        error: Self::Error,
+    ) -> impl Future<Output = ErrorDirective<Self::Error>> {
+        Cancellable::on_error(self, error)
+    }
+
+    fn restart(&mut self, error: Self::Error) -> impl Future<Output = Result<(), Self::Error>> {
+        Cancellable::restart(self, error)
+    }
+
+    fn on_item_dropped(&mut self, item: Self::Result) {
+        Cancellable::on_item_dropped(self, item)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use tokio::task::LocalSet;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{CallbackResult, CancellationResult, LocalCancellable};
+
+    struct RcCancellable {
+        counter: Rc<Cell<i32>>,
+    }
+
+    impl LocalCancellable for RcCancellable {
+        type Result = i32;
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = i32;
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(
+            &mut self,
+        ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+            let value = self.counter.get() + 1;
+            self.counter.set(value);
+
+            if value == 3 {
+                Ok(CancellationResult::BreakWith(value))
+            } else {
+                tokio::task::yield_now().await;
+                Ok(CancellationResult::Item(value))
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn should_complete_local_service_within_local_set() {
+        // Arrange
+        let counter = Rc::new(Cell::new(0));
+        let service = RcCancellable {
+            counter: counter.clone(),
+        };
+        let items = Rc::new(Cell::new(0));
+        let items_clone = items.clone();
+
+        // Act
+        let output = LocalSet::new()
+            .run_until(async move {
+                let handle = service
+                    .spawn_local_with_callback(CancellationToken::new(), move |_| {
+                        items_clone.set(items_clone.get() + 1);
+                        CallbackResult::Continue
+                    })
+                    .await;
+
+                handle.join().await
+            })
+            .await;
+
+        // Assert
+        assert_eq!(output.unwrap(), Some(3));
+        assert_eq!(counter.get(), 3);
+        assert_eq!(items.get(), 2);
+    }
+
+    #[tokio::test]
+    async fn should_cancel_local_service() {
+        // Arrange
+        let service = RcCancellable {
+            counter: Rc::new(Cell::new(i32::MIN)),
+        };
+        let cancellation_token = CancellationToken::new();
+
+        // Act
+        let output = LocalSet::new()
+            .run_until(async move {
+                let handle = service.spawn_local(cancellation_token.clone()).await;
+                cancellation_token.cancel();
+
+                handle.join().await
+            })
+            .await;
+
+        // Assert
+        assert_eq!(output.unwrap(), None);
+    }
+}
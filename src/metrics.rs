@@ -0,0 +1,47 @@
+use std::time::Duration;
+
+/// Hooks for collecting metrics of a spawned service.
+///
+/// All methods have empty default implementations, so only the relevant ones
+/// need to be implemented. The hooks are called from within the service's
+/// work loop, so they should return quickly.
+///
+/// See [`SpawnOptions::metrics`].
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// use cancellable::CancellableMetrics;
+///
+/// #[derive(Default)]
+/// struct ItemCounter {
+///     items: AtomicUsize,
+/// }
+///
+/// impl CancellableMetrics for ItemCounter {
+///     fn on_item(&self) {
+///         self.items.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+/// ```
+///
+/// [`SpawnOptions::metrics`]: crate::SpawnOptions::metrics
+pub trait CancellableMetrics: Send + Sync {
+    /// Called after every completed call to [`Cancellable::run`] with the time
+    /// it took.
+    ///
+    /// [`Cancellable::run`]: crate::Cancellable::run
+    fn on_iteration(&self, duration: Duration) {
+        let _ = duration;
+    }
+
+    /// Called for every value yielded by the service.
+    fn on_item(&self) {}
+
+    /// Called every time [`Cancellable::run`] returns an error.
+    ///
+    /// [`Cancellable::run`]: crate::Cancellable::run
+    fn on_error(&self) {}
+}
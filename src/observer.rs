@@ -0,0 +1,133 @@
+use std::{any::Any, fmt::Display, sync::Arc};
+
+use crate::CompletionReason;
+
+/// Hooks observing the lifecycle of spawned services.
+///
+/// Unlike [`CancellableMetrics`], every hook receives the name of the
+/// service, see [`Cancellable::name`], so a single observer can be attached to
+/// many services, e.g. to log or audit them uniformly. All methods have empty
+/// default implementations, so only the relevant ones need to be implemented.
+/// The hooks are called from within the service's work loop, so they should
+/// return quickly.
+///
+/// See [`SpawnOptions::observer`].
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{CancellableObserver, CompletionReason};
+///
+/// struct Logger;
+///
+/// impl CancellableObserver for Logger {
+///     fn on_error(&self, service: &str, error: &dyn std::fmt::Display) {
+///         eprintln!("{service} has failed: {error}");
+///     }
+///
+///     fn on_finish(&self, service: &str, reason: CompletionReason) {
+///         eprintln!("{service} has finished: {reason:?}");
+///     }
+/// }
+/// ```
+///
+/// [`CancellableMetrics`]: crate::CancellableMetrics
+/// [`Cancellable::name`]: crate::Cancellable::name
+/// [`SpawnOptions::observer`]: crate::SpawnOptions::observer
+pub trait CancellableObserver: Send + Sync {
+    /// Called once the service has started, i.e. after
+    /// [`Cancellable::on_start`] has succeeded.
+    ///
+    /// [`Cancellable::on_start`]: crate::Cancellable::on_start
+    fn on_start(&self, service: &str) {
+        let _ = service;
+    }
+
+    /// Called for every value passed to the callback.
+    fn on_item(&self, service: &str) {
+        let _ = service;
+    }
+
+    /// Called every time [`Cancellable::run`] returns an error, before it's
+    /// handled by [`Cancellable::on_error`].
+    ///
+    /// [`Cancellable::run`]: crate::Cancellable::run
+    /// [`Cancellable::on_error`]: crate::Cancellable::on_error
+    fn on_error(&self, service: &str, error: &dyn Display) {
+        let _ = (service, error);
+    }
+
+    /// Called every time the callback panics, if the panics are caught, see
+    /// [`SpawnOptions::callback_panic`].
+    ///
+    /// [`SpawnOptions::callback_panic`]: crate::SpawnOptions::callback_panic
+    fn on_callback_panic(&self, service: &str, panic: &(dyn Any + Send)) {
+        let _ = (service, panic);
+    }
+
+    /// Called once the service has been cancelled, before
+    /// [`Cancellable::on_cancel`].
+    ///
+    /// [`Cancellable::on_cancel`]: crate::Cancellable::on_cancel
+    fn on_cancelled(&self, service: &str) {
+        let _ = service;
+    }
+
+    /// Called once the service's work loop has completed, whatever the
+    /// reason.
+    fn on_finish(&self, service: &str, reason: CompletionReason) {
+        let _ = (service, reason);
+    }
+}
+
+/// Observers attached to a service.
+#[derive(Clone, Default)]
+pub(crate) struct Observers(Vec<Arc<dyn CancellableObserver>>);
+
+impl Observers {
+    pub(crate) fn push(&mut self, observer: Arc<dyn CancellableObserver>) {
+        self.0.push(observer);
+    }
+
+    pub(crate) fn on_start(&self, service: &str) {
+        self.0
+            .iter()
+            .for_each(|observer| observer.on_start(service));
+    }
+
+    pub(crate) fn on_item(&self, service: &str) {
+        self.0.iter().for_each(|observer| observer.on_item(service));
+    }
+
+    pub(crate) fn on_error(&self, service: &str, error: &dyn Display) {
+        self.0
+            .iter()
+            .for_each(|observer| observer.on_error(service, error));
+    }
+
+    pub(crate) fn on_callback_panic(&self, service: &str, panic: &(dyn Any + Send)) {
+        self.0
+            .iter()
+            .for_each(|observer| observer.on_callback_panic(service, panic));
+    }
+
+    pub(crate) fn on_cancelled(&self, service: &str) {
+        self.0
+            .iter()
+            .for_each(|observer| observer.on_cancelled(service));
+    }
+
+    pub(crate) fn on_finish(&self, service: &str, reason: CompletionReason) {
+        self.0
+            .iter()
+            .for_each(|observer| observer.on_finish(service, reason));
+    }
+}
+
+impl std::fmt::Debug for Observers {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Observers")
+            .field("len", &self.0.len())
+            .finish()
+    }
+}
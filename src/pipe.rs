@@ -0,0 +1,210 @@
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    cancellable_handle::ServiceJoinHandle, Cancellable, CancellableError, CancellableHandle,
+    SenderHandle,
+};
+
+/// Flattened result of a single service of a pipeline.
+type JoinResult<T> =
+    Result<Option<<T as Cancellable>::Output>, CancellableError<<T as Cancellable>::Error>>;
+
+/// Handle of a pipeline spawned with [`Cancellable::pipe`].
+///
+/// The pipeline consists of an upstream service, whose yielded values are
+/// sent to the downstream service through its [`SenderHandle`].
+#[derive(Debug)]
+pub struct PipeHandle<A, B>
+where
+    A: Cancellable,
+    B: Cancellable,
+{
+    upstream: CancellableHandle<A>,
+    forwarder: JoinHandle<()>,
+    downstream: ServiceJoinHandle<B>,
+}
+
+impl<A, B> PipeHandle<A, B>
+where
+    A: Cancellable + Send + 'static,
+    A::Result: 'static,
+    B: Cancellable + Send + 'static,
+    B::Handle: SenderHandle<Item = A::Result> + Send + 'static,
+{
+    pub(crate) async fn spawn(
+        upstream: A,
+        downstream: B,
+        cancellation_token: CancellationToken,
+    ) -> Self {
+        let (downstream, _, sender) = downstream
+            .spawn(cancellation_token.clone())
+            .await
+            .into_raw_parts();
+        let (upstream, mut items) = upstream.spawn_stream(cancellation_token).await;
+
+        let forwarder = tokio::spawn(async move {
+            while let Some(item) = items.recv().await {
+                if sender.send(item).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self {
+            upstream,
+            forwarder,
+            downstream,
+        }
+    }
+}
+
+impl<A, B> PipeHandle<A, B>
+where
+    A: Cancellable,
+    B: Cancellable,
+{
+    /// Shuts the pipeline down in order.
+    ///
+    /// Only the upstream service is cancelled. Once it completes and all of
+    /// its values have been sent, the downstream service's handle is dropped,
+    /// so the downstream service can complete after processing them.
+    pub fn cancel(&self) {
+        self.upstream.cancel();
+    }
+
+    /// Waits for both services of the pipeline to complete.
+    ///
+    /// # Returns
+    ///
+    /// Flattened results of the upstream and the downstream service.
+    pub async fn join(self) -> (JoinResult<A>, JoinResult<B>) {
+        let upstream = self.upstream.join().await;
+        // The forwarder only stops sending values, so there's nothing to report.
+        let _ = self.forwarder.await;
+        let downstream = match self.downstream.await {
+            Ok(result) => result.map_err(CancellableError::Service),
+            Err(e) => Err(e.into()),
+        };
+
+        (upstream, downstream)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::Receiver;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{Cancellable, CancellationResult, MpscSenderHandle};
+
+    struct Producer {
+        next: i32,
+    }
+
+    impl Cancellable for Producer {
+        type Result = i32;
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<i32>, Self::Error> {
+            self.next += 1;
+            match self.next {
+                next if next > 3 => Ok(CancellationResult::Break),
+                next => Ok(CancellationResult::Item(next)),
+            }
+        }
+    }
+
+    struct InfiniteProducer {}
+
+    impl Cancellable for InfiniteProducer {
+        type Result = i32;
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<i32>, Self::Error> {
+            tokio::task::yield_now().await;
+            Ok(CancellationResult::Item(1))
+        }
+    }
+
+    struct Collector {
+        receiver: Receiver<i32>,
+        handle: Option<MpscSenderHandle<i32>>,
+        items: Vec<i32>,
+    }
+
+    impl Collector {
+        fn new() -> Self {
+            let (handle, receiver) = MpscSenderHandle::channel(1);
+            Self {
+                receiver,
+                handle: Some(handle),
+                items: Vec::new(),
+            }
+        }
+    }
+
+    impl Cancellable for Collector {
+        type Result = ();
+        type Handle = MpscSenderHandle<i32>;
+        type Error = anyhow::Error;
+        type Output = Vec<i32>;
+
+        async fn new_handle(&mut self) -> Self::Handle {
+            self.handle.take().unwrap()
+        }
+
+        async fn run(&mut self) -> Result<CancellationResult<(), Vec<i32>>, Self::Error> {
+            match self.receiver.recv().await {
+                Some(item) => {
+                    self.items.push(item);
+                    Ok(CancellationResult::Continue)
+                }
+                None => Ok(CancellationResult::BreakWith(std::mem::take(
+                    &mut self.items,
+                ))),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn should_feed_downstream_with_upstream_values() {
+        // Arrange
+        let producer = Producer { next: 0 };
+
+        // Act
+        let handle = producer
+            .pipe(Collector::new(), CancellationToken::new())
+            .await;
+        let (upstream, downstream) = handle.join().await;
+
+        // Assert
+        assert_eq!(upstream.unwrap(), None);
+        assert_eq!(downstream.unwrap(), Some(vec![1, 2, 3]));
+    }
+
+    #[tokio::test]
+    async fn should_complete_downstream_after_upstream_is_cancelled() {
+        // Arrange
+        let producer = InfiniteProducer {};
+        let handle = producer
+            .pipe(Collector::new(), CancellationToken::new())
+            .await;
+
+        // Act
+        handle.cancel();
+        let (upstream, downstream) = handle.join().await;
+
+        // Assert
+        assert_eq!(upstream.unwrap(), None);
+        assert!(downstream.unwrap().is_some());
+    }
+}
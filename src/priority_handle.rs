@@ -0,0 +1,243 @@
+use std::{future::Future, time::Duration};
+
+use tokio::sync::mpsc::{
+    self,
+    error::{SendError, SendTimeoutError, TryRecvError, TrySendError},
+};
+
+use crate::{sender_handle::SharedSender, SenderHandle};
+
+/// Handle sending items to a service through a pair of bounded channels, one
+/// of which has a higher priority.
+///
+/// Items sent with [`Self::send_high`] overtake the items sent through
+/// [`SenderHandle`], since [`PriorityReceiver`] drains the high priority lane
+/// first. It's meant for services receiving both control messages and bulk
+/// data, so the former aren't stuck behind the latter. Each lane has a
+/// capacity of its own, so a full data lane doesn't hold back the control
+/// messages.
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{PrioritySenderHandle, SenderHandle};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let (handle, mut receiver) = PrioritySenderHandle::channel(16);
+///
+/// handle.send("chunk").await.unwrap();
+/// handle.send_high("flush").await.unwrap();
+///
+/// assert_eq!(Some("flush"), receiver.recv().await);
+/// assert_eq!(Some("chunk"), receiver.recv().await);
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct PrioritySenderHandle<T> {
+    high: SharedSender<mpsc::Sender<T>>,
+    normal: SharedSender<mpsc::Sender<T>>,
+}
+
+impl<T> PrioritySenderHandle<T> {
+    /// Creates a pair of bounded channels, each with the given `capacity`.
+    ///
+    /// The receiving side is meant to be owned by the service, while the
+    /// handle is returned by [`Cancellable::new_handle`].
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `capacity` is zero.
+    ///
+    /// [`Cancellable::new_handle`]: crate::Cancellable::new_handle
+    pub fn channel(capacity: usize) -> (Self, PriorityReceiver<T>) {
+        let (high, high_receiver) = mpsc::channel(capacity);
+        let (normal, normal_receiver) = mpsc::channel(capacity);
+
+        let handle = Self {
+            high: SharedSender::new(high),
+            normal: SharedSender::new(normal),
+        };
+        let receiver = PriorityReceiver {
+            high: high_receiver,
+            normal: normal_receiver,
+        };
+        (handle, receiver)
+    }
+
+    /// Sends `item` through the high priority lane.
+    ///
+    /// See [`SenderHandle::send`].
+    pub async fn send_high(&self, item: T) -> Result<(), SendError<T>> {
+        match self.high.get() {
+            Some(sender) => sender.send(item).await,
+            None => Err(SendError(item)),
+        }
+    }
+
+    /// Attempts to send `item` through the high priority lane without
+    /// waiting.
+    ///
+    /// See [`SenderHandle::try_send`].
+    pub fn try_send_high(&self, item: T) -> Result<(), TrySendError<T>> {
+        match self.high.get() {
+            Some(sender) => sender.try_send(item),
+            None => Err(TrySendError::Closed(item)),
+        }
+    }
+}
+
+impl<T> Clone for PrioritySenderHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            high: self.high.clone(),
+            normal: self.normal.clone(),
+        }
+    }
+}
+
+impl<T> SenderHandle for PrioritySenderHandle<T>
+where
+    T: Send,
+{
+    type Item = T;
+
+    fn send(
+        &self,
+        item: Self::Item,
+    ) -> impl Future<Output = Result<(), SendError<Self::Item>>> + Send {
+        let sender = self.normal.get();
+        async move {
+            match sender {
+                Some(sender) => sender.send(item).await,
+                None => Err(SendError(item)),
+            }
+        }
+    }
+
+    fn try_send(&self, item: Self::Item) -> Result<(), TrySendError<Self::Item>> {
+        match self.normal.get() {
+            Some(sender) => sender.try_send(item),
+            None => Err(TrySendError::Closed(item)),
+        }
+    }
+
+    fn send_timeout(
+        &self,
+        item: Self::Item,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<(), SendTimeoutError<Self::Item>>> + Send {
+        let sender = self.normal.get();
+        async move {
+            match sender {
+                Some(sender) => sender.send_timeout(item, timeout).await,
+                None => Err(SendTimeoutError::Closed(item)),
+            }
+        }
+    }
+
+    /// Closes both lanes.
+    fn close(&self) {
+        self.high.close();
+        self.normal.close();
+    }
+
+    fn is_closed(&self) -> bool {
+        self.normal.is_closed()
+    }
+}
+
+/// Receiving side of [`PrioritySenderHandle`].
+///
+/// Items of the high priority lane are always received before the items of
+/// the normal one.
+#[derive(Debug)]
+pub struct PriorityReceiver<T> {
+    high: mpsc::Receiver<T>,
+    normal: mpsc::Receiver<T>,
+}
+
+impl<T> PriorityReceiver<T> {
+    /// Receives the next item, preferring the high priority lane.
+    ///
+    /// Returns `None` once all handles have been dropped and both lanes have
+    /// been drained. It's cancel safe, just like [`mpsc::Receiver::recv`].
+    pub async fn recv(&mut self) -> Option<T> {
+        tokio::select! {
+            biased;
+            Some(item) = self.high.recv() => Some(item),
+            item = self.normal.recv() => match item {
+                Some(item) => Some(item),
+                // The lanes are closed at once, but the high one may still
+                // hold items.
+                None => self.high.recv().await,
+            },
+        }
+    }
+
+    /// Attempts to receive the next item without waiting, preferring the high
+    /// priority lane.
+    pub fn try_recv(&mut self) -> Result<T, TryRecvError> {
+        match self.high.try_recv() {
+            Ok(item) => Ok(item),
+            Err(high) => match (self.normal.try_recv(), high) {
+                (Ok(item), _) => Ok(item),
+                (Err(TryRecvError::Disconnected), TryRecvError::Disconnected) => {
+                    Err(TryRecvError::Disconnected)
+                }
+                (Err(_), _) => Err(TryRecvError::Empty),
+            },
+        }
+    }
+
+    /// Closes both lanes, without dropping the items they already hold.
+    ///
+    /// See [`mpsc::Receiver::close`].
+    pub fn close(&mut self) {
+        self.high.close();
+        self.normal.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::sync::mpsc::error::TryRecvError;
+
+    use crate::{PrioritySenderHandle, SenderHandle};
+
+    #[tokio::test]
+    async fn should_receive_high_priority_items_first() {
+        // Arrange
+        let (handle, mut receiver) = PrioritySenderHandle::channel(4);
+
+        // Act
+        handle.send(1).await.unwrap();
+        handle.send(2).await.unwrap();
+        handle.send_high(3).await.unwrap();
+        handle.try_send_high(4).unwrap();
+        drop(handle);
+
+        // Assert
+        let mut items = Vec::new();
+        while let Some(item) = receiver.recv().await {
+            items.push(item);
+        }
+        assert_eq!(vec![3, 4, 1, 2], items);
+    }
+
+    #[tokio::test]
+    async fn should_accept_high_priority_items_when_normal_lane_is_full() {
+        // Arrange
+        let (handle, mut receiver) = PrioritySenderHandle::channel(1);
+        handle.try_send(1).unwrap();
+
+        // Act
+        let result = handle.try_send_high(2);
+
+        // Assert
+        assert!(result.is_ok());
+        assert_eq!(Ok(2), receiver.try_recv());
+        assert_eq!(Ok(1), receiver.try_recv());
+        assert_eq!(Err(TryRecvError::Empty), receiver.try_recv());
+    }
+}
@@ -0,0 +1,317 @@
+use std::{io, process::ExitStatus, time::Duration};
+
+use tokio::{
+    io::{AsyncBufReadExt, AsyncRead, BufReader, Lines},
+    process::{Child, ChildStderr, ChildStdout, Command},
+};
+
+use crate::{Cancellable, CancellationResult};
+
+/// Time the child has to exit after being asked to, by default.
+const DEFAULT_GRACE_PERIOD: Duration = Duration::from_secs(5);
+
+/// Line printed by a child process.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProcessLine {
+    /// Line printed to the standard output.
+    Stdout(String),
+
+    /// Line printed to the standard error.
+    Stderr(String),
+}
+
+/// Service supervising a child process.
+///
+/// The child is spawned once the service starts, and each line it prints to
+/// its standard output or error is yielded as a [`ProcessLine`]. Once both
+/// streams are closed, the service waits for the child to exit, and completes
+/// with its exit status.
+///
+/// When the service is cancelled, then the child is asked to exit with
+/// `SIGTERM`, and killed with `SIGKILL` if it's still running after the grace
+/// period, see [`Self::grace_period`]. On Windows, the child is killed right
+/// away. Either way, the service completes with the exit status. If graceful
+/// shutdown is enabled, then the lines printed until the child has exited are
+/// yielded as well.
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{Cancellable, CancellationToken, ProcessLine, ProcessService};
+/// use futures::StreamExt;
+/// use tokio::process::Command;
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mut command = Command::new("rustc");
+/// command.arg("--version");
+///
+/// let (handle, mut lines) = ProcessService::new(command)
+///     .spawn_stream(CancellationToken::new())
+///     .await;
+/// while let Some(line) = lines.next().await {
+///     if let ProcessLine::Stdout(line) = line {
+///         println!("{line}");
+///     }
+/// }
+///
+/// let status = handle.join().await.unwrap();
+/// assert!(status.is_some_and(|status| status.success()));
+/// # }
+/// ```
+pub struct ProcessService {
+    command: Command,
+    grace_period: Duration,
+    child: Option<Child>,
+    stdout: Option<Lines<BufReader<ChildStdout>>>,
+    stderr: Option<Lines<BufReader<ChildStderr>>>,
+    status: Option<ExitStatus>,
+}
+
+impl ProcessService {
+    /// Constructs a new service supervising the child spawned with `command`.
+    ///
+    /// The child's standard output and error are piped to the service. The
+    /// child is killed if the service is dropped while it's running.
+    pub fn new(mut command: Command) -> Self {
+        command
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped())
+            .kill_on_drop(true);
+
+        Self {
+            command,
+            grace_period: DEFAULT_GRACE_PERIOD,
+            child: None,
+            stdout: None,
+            stderr: None,
+            status: None,
+        }
+    }
+
+    /// Sets the time the child has to exit after `SIGTERM`, before it's
+    /// killed.
+    ///
+    /// It's 5 seconds by default.
+    pub fn grace_period(mut self, grace_period: Duration) -> Self {
+        self.grace_period = grace_period;
+        self
+    }
+
+    fn child(&mut self) -> &mut Child {
+        self.child.as_mut().expect("child to be spawned")
+    }
+
+    /// Asks the child to exit, and kills it after the grace period.
+    async fn terminate(&mut self) -> io::Result<ExitStatus> {
+        #[cfg(unix)]
+        if let Some(pid) = self.child().id() {
+            // SAFETY: `kill` has no memory safety preconditions. The child
+            // hasn't been reaped yet, so its pid cannot have been reused.
+            unsafe { libc::kill(pid as libc::pid_t, libc::SIGTERM) };
+
+            let grace_period = self.grace_period;
+            if let Some(status) = crate::clock::SharedClock::current()
+                .timeout(grace_period, self.child().wait())
+                .await
+            {
+                return status;
+            }
+        }
+
+        self.child().kill().await?;
+        self.child().wait().await
+    }
+}
+
+/// Reads the next line of `lines`, and forgets them once they're closed.
+///
+/// It never completes if `lines` are already closed.
+async fn next_line<R>(lines: &mut Option<Lines<BufReader<R>>>) -> io::Result<Option<String>>
+where
+    R: AsyncRead + Unpin,
+{
+    let Some(reader) = lines else {
+        return std::future::pending().await;
+    };
+
+    let line = reader.next_line().await?;
+    if line.is_none() {
+        *lines = None;
+    }
+    Ok(line)
+}
+
+impl Cancellable for ProcessService {
+    type Result = ProcessLine;
+    type Handle = ();
+    type Error = io::Error;
+    type Output = ExitStatus;
+
+    async fn new_handle(&mut self) -> Self::Handle {}
+
+    async fn on_start(&mut self) -> Result<(), Self::Error> {
+        let mut child = self.command.spawn()?;
+        self.stdout = child.stdout.take().map(|out| BufReader::new(out).lines());
+        self.stderr = child.stderr.take().map(|err| BufReader::new(err).lines());
+        self.child = Some(child);
+
+        Ok(())
+    }
+
+    async fn on_cancel(&mut self) {
+        // The error means the child couldn't be waited for, so there's no
+        // status to report.
+        if let Ok(status) = self.terminate().await {
+            self.status = Some(status);
+        }
+    }
+
+    fn cancelled_output(&mut self) -> Option<Self::Output> {
+        self.status
+    }
+
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        if self.stdout.is_none() && self.stderr.is_none() {
+            let status = match self.status {
+                Some(status) => status,
+                None => self.child().wait().await?,
+            };
+            return Ok(CancellationResult::BreakWith(status));
+        }
+
+        let result = tokio::select! {
+            line = next_line(&mut self.stdout) => line?.map(ProcessLine::Stdout),
+            line = next_line(&mut self.stderr) => line?.map(ProcessLine::Stderr),
+        };
+
+        Ok(result.map_or(CancellationResult::Continue, CancellationResult::Item))
+    }
+
+    async fn drain(
+        &mut self,
+    ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        // The child has exited by now, so its streams are about to be closed.
+        self.run().await
+    }
+}
+
+impl std::fmt::Debug for ProcessService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProcessService")
+            .field("command", &self.command)
+            .field("grace_period", &self.grace_period)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{os::unix::process::ExitStatusExt, time::Duration};
+
+    use futures::StreamExt;
+    use tokio::{process::Command, sync::mpsc};
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{CallbackResult, Cancellable, ProcessLine, ProcessService, SpawnOptions};
+
+    fn shell(script: &str) -> ProcessService {
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(script);
+
+        ProcessService::new(command)
+    }
+
+    #[tokio::test]
+    async fn should_yield_lines_and_complete_with_exit_status() {
+        // Arrange
+        let service = shell("echo out; echo err >&2; exit 3");
+
+        // Act
+        let (handle, lines) = service.spawn_stream(CancellationToken::new()).await;
+        let mut lines = lines.collect::<Vec<_>>().await;
+        let status = handle.join().await.unwrap();
+
+        // Assert
+        lines.sort_by_key(|line| matches!(line, ProcessLine::Stderr(_)));
+        assert_eq!(
+            vec![
+                ProcessLine::Stdout("out".to_owned()),
+                ProcessLine::Stderr("err".to_owned()),
+            ],
+            lines
+        );
+        assert_eq!(Some(3), status.unwrap().code());
+    }
+
+    #[tokio::test]
+    async fn should_terminate_child_on_cancel() {
+        // Arrange
+        let service =
+            shell("trap 'echo terminated; exit 0' TERM; echo ready; while :; do sleep 0.01; done");
+        let (sender, mut lines) = mpsc::unbounded_channel();
+        let options = SpawnOptions::new().graceful_shutdown();
+        let handle = service
+            .spawn_with_options(CancellationToken::new(), options, move |line| {
+                let _ = sender.send(line);
+                CallbackResult::Continue
+            })
+            .await;
+        lines.recv().await;
+
+        // Act
+        handle.cancel();
+        let status = handle.join().await.unwrap();
+
+        // Assert
+        assert_eq!(
+            Some(ProcessLine::Stdout("terminated".to_owned())),
+            lines.recv().await
+        );
+        assert!(status.unwrap().success());
+    }
+
+    #[tokio::test]
+    async fn should_complete_with_exit_status_when_cancelled_without_draining() {
+        // Arrange
+        let service = shell("echo ready; while :; do sleep 0.01; done");
+        let (sender, mut lines) = mpsc::unbounded_channel();
+        let handle = service
+            .spawn_with_callback(CancellationToken::new(), move |line| {
+                let _ = sender.send(line);
+                CallbackResult::Continue
+            })
+            .await;
+        lines.recv().await;
+
+        // Act
+        handle.cancel();
+        let status = handle.join().await.unwrap();
+
+        // Assert
+        assert_eq!(Some(libc::SIGTERM), status.unwrap().signal());
+    }
+
+    #[tokio::test]
+    async fn should_kill_child_ignoring_sigterm_after_grace_period() {
+        // Arrange
+        let service = shell("trap '' TERM; echo ready; while :; do sleep 0.01; done")
+            .grace_period(Duration::from_millis(100));
+        let options = SpawnOptions::new().graceful_shutdown();
+        let (sender, mut lines) = mpsc::unbounded_channel();
+        let handle = service
+            .spawn_with_options(CancellationToken::new(), options, move |line| {
+                let _ = sender.send(line);
+                CallbackResult::Continue
+            })
+            .await;
+        lines.recv().await;
+
+        // Act
+        handle.cancel();
+        let status = handle.join().await.unwrap();
+
+        // Assert
+        assert_eq!(Some(libc::SIGKILL), status.unwrap().signal());
+    }
+}
@@ -0,0 +1,172 @@
+use std::{future::Future, pin::Pin, task::Poll};
+
+use tokio_util::sync::CancellationToken;
+
+use crate::{Cancellable, CancellableError, CancellableHandle};
+
+/// Spawns all `services` and waits for the first of them to complete.
+///
+/// As soon as one of the services completes, successfully or not, the rest of
+/// them are cancelled and joined. The services are spawned with child tokens
+/// of `cancellation_token`, and they're all cancelled if this function's
+/// future is dropped before it completes.
+///
+/// # Returns
+///
+/// The index of the service which has completed first, along with its
+/// flattened result (see [`CancellableHandle::join`]), or `None` if there were
+/// no services.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use cancellable::{Cancellable, CancellationResult, CancellationToken};
+///
+/// struct Fetcher {
+///     latency: Duration,
+/// }
+///
+/// impl Cancellable for Fetcher {
+///     type Result = ();
+///     type Handle = ();
+///     type Error = std::io::Error;
+///     type Output = Duration;
+///
+///     async fn new_handle(&mut self) -> Self::Handle {}
+///
+///     async fn run(&mut self) -> Result<CancellationResult<(), Duration>, Self::Error> {
+///         tokio::time::sleep(self.latency).await;
+///         Ok(CancellationResult::BreakWith(self.latency))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let fetchers = [Duration::from_secs(60), Duration::from_millis(1)]
+///     .map(|latency| Fetcher { latency });
+///
+/// let (winner, result) = cancellable::race(fetchers, CancellationToken::new())
+///     .await
+///     .unwrap();
+///
+/// assert_eq!(1, winner);
+/// assert_eq!(Some(Duration::from_millis(1)), result.unwrap());
+/// # }
+/// ```
+///
+/// [`CancellableHandle::join`]: crate::CancellableHandle::join
+pub async fn race<T, I>(
+    services: I,
+    cancellation_token: CancellationToken,
+) -> Option<(usize, Result<Option<T::Output>, CancellableError<T::Error>>)>
+where
+    I: IntoIterator<Item = T>,
+    T: Cancellable + Send + 'static,
+{
+    let race_token = cancellation_token.child_token();
+    // Cancels the services if the future is dropped before it completes.
+    let _guard = race_token.clone().drop_guard();
+
+    let mut handles: Vec<CancellableHandle<T, ()>> = Vec::new();
+    for service in services {
+        let handle = service.spawn(race_token.child_token()).await;
+        handles.push(handle.into_parts().0);
+    }
+    if handles.is_empty() {
+        return None;
+    }
+
+    let (winner, result) = std::future::poll_fn(|cx| {
+        for (index, handle) in handles.iter_mut().enumerate() {
+            if let Poll::Ready(result) = Pin::new(handle).poll(cx) {
+                return Poll::Ready((index, result));
+            }
+        }
+        Poll::Pending
+    })
+    .await;
+
+    race_token.cancel();
+    let losers = handles
+        .into_iter()
+        .enumerate()
+        .filter(|(index, _)| *index != winner);
+    for (_, handle) in losers {
+        let _ = handle.await;
+    }
+
+    let result = match result {
+        Ok(result) => result.map_err(CancellableError::Service),
+        Err(e) => Err(e.into()),
+    };
+    Some((winner, result))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{Cancellable, CancellationResult};
+
+    struct DelayedCancellable {
+        delay: Duration,
+        stopped: Arc<AtomicUsize>,
+    }
+
+    impl Cancellable for DelayedCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = Duration;
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<(), Duration>, Self::Error> {
+            tokio::time::sleep(self.delay).await;
+            Ok(CancellationResult::BreakWith(self.delay))
+        }
+
+        async fn on_stop(&mut self) {
+            self.stopped.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn should_cancel_and_join_losers() {
+        // Arrange
+        let stopped = Arc::new(AtomicUsize::new(0));
+        let services = [3600, 1, 60].map(|secs| DelayedCancellable {
+            delay: Duration::from_secs(secs),
+            stopped: Arc::clone(&stopped),
+        });
+
+        // Act
+        let (winner, result) = crate::race(services, CancellationToken::new())
+            .await
+            .unwrap();
+
+        // Assert
+        assert_eq!(1, winner);
+        assert_eq!(Some(Duration::from_secs(1)), result.unwrap());
+        assert_eq!(3, stopped.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn should_return_none_without_services() {
+        // Act
+        let result = crate::race(Vec::<DelayedCancellable>::new(), CancellationToken::new()).await;
+
+        // Assert
+        assert!(result.is_none());
+    }
+}
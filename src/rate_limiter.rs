@@ -0,0 +1,86 @@
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::clock::SharedClock;
+
+/// Token bucket limiting the rate of a service's iterations.
+///
+/// The bucket holds up to `max_per_second` tokens and starts full, so it
+/// allows bursts of up to `max_per_second` iterations.
+#[derive(Debug)]
+pub(crate) struct RateLimiter {
+    capacity: f64,
+    tokens: f64,
+    refilled: Instant,
+    clock: SharedClock,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(max_per_second: u32, clock: SharedClock) -> Self {
+        let capacity = f64::from(max_per_second);
+
+        Self {
+            capacity,
+            tokens: capacity,
+            refilled: clock.now(),
+            clock,
+        }
+    }
+
+    /// Takes a single token from the bucket.
+    ///
+    /// # Returns
+    ///
+    /// The time to wait before the token can be used, or `None` if it can be
+    /// used right away.
+    pub(crate) fn acquire(&mut self) -> Option<Duration> {
+        let now = self.clock.now();
+        let elapsed = now.duration_since(self.refilled).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.capacity).min(self.capacity);
+        self.refilled = now;
+
+        self.tokens -= 1.0;
+        if self.tokens >= 0.0 {
+            None
+        } else {
+            Some(Duration::from_secs_f64(-self.tokens / self.capacity))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::RateLimiter;
+    use crate::clock::SharedClock;
+
+    #[tokio::test(start_paused = true)]
+    async fn should_allow_burst_of_capacity() {
+        // Arrange
+        let mut limiter = RateLimiter::new(2, SharedClock::default());
+
+        // Act
+        let delays = [limiter.acquire(), limiter.acquire(), limiter.acquire()];
+
+        // Assert
+        assert_eq!([None, None, Some(Duration::from_millis(500))], delays);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn should_refill_over_time() {
+        // Arrange
+        let mut limiter = RateLimiter::new(10, SharedClock::default());
+        for _ in 0..10 {
+            limiter.acquire();
+        }
+
+        // Act
+        tokio::time::advance(Duration::from_millis(150)).await;
+
+        // Assert
+        assert_eq!(None, limiter.acquire());
+        assert!(limiter.acquire().is_some());
+    }
+}
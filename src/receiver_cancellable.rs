@@ -0,0 +1,177 @@
+use std::future::Future;
+
+use tokio::sync::mpsc::Receiver;
+
+use crate::{Cancellable, CancellationResult, MpscSenderHandle};
+
+/// Service applying an asynchronous function to every item received from a
+/// channel.
+///
+/// Each value returned by the function is yielded by the service, while an
+/// error completes the service. The service completes once all of the
+/// channel's senders have been dropped. When graceful shutdown is enabled,
+/// then the items already queued by the time the service is cancelled are
+/// still processed.
+///
+/// See [`from_receiver`] and [`from_channel`].
+#[derive(Debug)]
+pub struct ReceiverCancellable<T, F, H = ()> {
+    receiver: Receiver<T>,
+    handle: Option<H>,
+    f: F,
+}
+
+impl<T, F, H> ReceiverCancellable<T, F, H> {
+    async fn process<Fut, U, E>(&mut self, item: Option<T>) -> Result<CancellationResult<U>, E>
+    where
+        F: FnMut(T) -> Fut,
+        Fut: Future<Output = Result<U, E>>,
+    {
+        match item {
+            Some(item) => (self.f)(item).await.map(CancellationResult::Item),
+            None => Ok(CancellationResult::Break),
+        }
+    }
+}
+
+impl<T, F, H, Fut, U, E> Cancellable for ReceiverCancellable<T, F, H>
+where
+    T: Send,
+    F: FnMut(T) -> Fut + Send,
+    H: std::fmt::Debug + Send,
+    Fut: Future<Output = Result<U, E>> + Send,
+    U: Send,
+    E: std::fmt::Debug + std::fmt::Display + Send,
+{
+    type Result = U;
+    type Handle = H;
+    type Error = E;
+    type Output = ();
+
+    async fn new_handle(&mut self) -> Self::Handle {
+        self.handle.take().expect("handle to be present")
+    }
+
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        let item = self.receiver.recv().await;
+        self.process(item).await
+    }
+
+    async fn drain(
+        &mut self,
+    ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        let item = self.receiver.try_recv().ok();
+        self.process(item).await
+    }
+}
+
+/// Constructs a service applying `f` to every item received from `receiver`.
+///
+/// The items are sent to the service through the sender of `receiver`, so the
+/// service's handle is `()`. See [`from_channel`] for a service which creates
+/// its channel on its own.
+pub fn from_receiver<T, F>(receiver: Receiver<T>, f: F) -> ReceiverCancellable<T, F> {
+    ReceiverCancellable {
+        receiver,
+        handle: Some(()),
+        f,
+    }
+}
+
+/// Constructs a service applying `f` to every item received from a new
+/// bounded channel with the given `capacity`.
+///
+/// The service's handle is the [`MpscSenderHandle`] of the channel.
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{CallbackResult, Cancellable, CancellationToken, SenderHandle};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let service = cancellable::from_channel(16, |number: i32| async move {
+///     Ok::<_, std::io::Error>(number * 2)
+/// });
+///
+/// let handle = service
+///     .spawn_with_callback(CancellationToken::new(), |number| {
+///         println!("Received {number}.");
+///         CallbackResult::Continue
+///     })
+///     .await;
+///
+/// handle.send(21).await.unwrap();
+/// # }
+/// ```
+///
+/// # Panics
+///
+/// This function panics if `capacity` is zero.
+pub fn from_channel<T, F>(capacity: usize, f: F) -> ReceiverCancellable<T, F, MpscSenderHandle<T>> {
+    let (handle, receiver) = MpscSenderHandle::channel(capacity);
+
+    ReceiverCancellable {
+        receiver,
+        handle: Some(handle),
+        f,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use tokio::sync::mpsc;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{Cancellable, SenderHandle};
+
+    #[tokio::test]
+    async fn should_process_received_items() {
+        // Arrange
+        let (sender, receiver) = mpsc::channel(4);
+        let service = crate::from_receiver(receiver, |item: i32| async move {
+            Ok::<_, anyhow::Error>(item * 2)
+        });
+
+        // Act
+        sender.send(1).await.unwrap();
+        sender.send(2).await.unwrap();
+        drop(sender);
+        let (handle, items) = service.spawn_stream(CancellationToken::new()).await;
+
+        // Assert
+        assert_eq!(vec![2, 4], items.collect::<Vec<_>>().await);
+        assert!(handle.join().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn should_process_items_sent_through_handle() {
+        // Arrange
+        let service = crate::from_channel(1, |item: i32| async move {
+            Ok::<_, anyhow::Error>(item.to_string())
+        });
+        let (handle, mut items) = service.spawn_stream(CancellationToken::new()).await;
+
+        // Act
+        handle.send(42).await.unwrap();
+
+        // Assert
+        assert_eq!(Some("42".to_owned()), items.next().await);
+    }
+
+    #[tokio::test]
+    async fn should_fail_when_function_fails() {
+        // Arrange
+        let service = crate::from_channel(1, |_: i32| async move {
+            Err::<(), _>(anyhow::anyhow!("ReceiverCancellable error"))
+        });
+        let handle = service.spawn(CancellationToken::new()).await;
+
+        // Act
+        handle.send(42).await.unwrap();
+
+        // Assert
+        assert!(handle.join().await.is_err());
+    }
+}
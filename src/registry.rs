@@ -0,0 +1,147 @@
+use std::{
+    any::Any,
+    collections::HashMap,
+    sync::{OnceLock, RwLock},
+};
+
+/// Registry of services' handles, looked up by name.
+///
+/// Handles are registered under string keys and fetched by their type, so
+/// components can find the handles of the services they talk to without
+/// having them passed through constructors. Each lookup returns a clone of
+/// the registered handle.
+///
+/// A registry can be either constructed locally with [`Registry::new`], or
+/// shared by the whole process with [`Registry::global`].
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{MpscSenderHandle, Registry};
+///
+/// let registry = Registry::new();
+/// let (handle, _receiver) = MpscSenderHandle::<u32>::channel(16);
+/// registry.register("jobs", handle);
+///
+/// let handle = registry.get::<MpscSenderHandle<u32>>("jobs");
+/// assert!(handle.is_some());
+/// ```
+#[derive(Default)]
+pub struct Registry {
+    handles: RwLock<HashMap<String, Box<dyn Any + Send + Sync>>>,
+}
+
+impl Registry {
+    /// Constructs a new, empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the registry shared by the whole process.
+    pub fn global() -> &'static Registry {
+        static GLOBAL: OnceLock<Registry> = OnceLock::new();
+        GLOBAL.get_or_init(Registry::new)
+    }
+
+    /// Registers `handle` under the given name.
+    ///
+    /// # Returns
+    ///
+    /// `true` if a handle previously registered under the name has been
+    /// replaced.
+    pub fn register<H>(&self, name: impl Into<String>, handle: H) -> bool
+    where
+        H: Clone + Send + Sync + 'static,
+    {
+        self.handles
+            .write()
+            .unwrap()
+            .insert(name.into(), Box::new(handle))
+            .is_some()
+    }
+
+    /// Returns a clone of the handle registered under the given name.
+    ///
+    /// Returns `None` if there's no handle registered under the name, or if
+    /// the handle isn't of type `H`.
+    pub fn get<H>(&self, name: &str) -> Option<H>
+    where
+        H: Clone + 'static,
+    {
+        self.handles
+            .read()
+            .unwrap()
+            .get(name)?
+            .downcast_ref::<H>()
+            .cloned()
+    }
+
+    /// Removes the handle registered under the given name.
+    ///
+    /// # Returns
+    ///
+    /// `true` if there has been a handle registered under the name.
+    pub fn unregister(&self, name: &str) -> bool {
+        self.handles.write().unwrap().remove(name).is_some()
+    }
+
+    /// Checks if there's a handle registered under the given name.
+    pub fn contains(&self, name: &str) -> bool {
+        self.handles.read().unwrap().contains_key(name)
+    }
+}
+
+impl std::fmt::Debug for Registry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let handles = self.handles.read().unwrap();
+        let names: Vec<_> = handles.keys().collect();
+        f.debug_struct("Registry").field("names", &names).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{MpscSenderHandle, Registry, SenderHandle};
+
+    #[tokio::test]
+    async fn should_return_registered_handle() {
+        // Arrange
+        let registry = Registry::new();
+        let (handle, mut receiver) = MpscSenderHandle::channel(1);
+        registry.register("jobs", handle);
+
+        // Act
+        let handle = registry.get::<MpscSenderHandle<i32>>("jobs").unwrap();
+        handle.send(42).await.unwrap();
+
+        // Assert
+        assert_eq!(Some(42), receiver.recv().await);
+    }
+
+    #[test]
+    fn should_not_return_handle_of_other_type() {
+        // Arrange
+        let registry = Registry::new();
+        registry.register("jobs", 42_u32);
+
+        // Act
+        let handle = registry.get::<String>("jobs");
+
+        // Assert
+        assert!(handle.is_none());
+    }
+
+    #[test]
+    fn should_unregister_handle() {
+        // Arrange
+        let registry = Registry::global();
+        registry.register("should_unregister_handle", ());
+
+        // Act
+        let removed = registry.unregister("should_unregister_handle");
+
+        // Assert
+        assert!(removed);
+        assert!(!registry.contains("should_unregister_handle"));
+    }
+}
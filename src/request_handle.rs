@@ -0,0 +1,273 @@
+use std::{convert::Infallible, future::Future};
+
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{Cancellable, CancellationResult};
+
+/// Defines an interface for a service answering requests.
+///
+/// The handler is driven by [`RequestService`], which receives the requests
+/// sent with [`RequestHandle::call`] and replies with the responses returned
+/// by [`Self::handle_request`].
+pub trait RequestHandler {
+    /// Type of requests accepted by the handler.
+    type Request: Send;
+
+    /// Type of responses returned by the handler.
+    type Response: Send;
+
+    /// Answers a single request.
+    fn handle_request(
+        &mut self,
+        request: Self::Request,
+    ) -> impl Future<Output = Self::Response> + Send;
+}
+
+/// Request paired with the channel its response is sent through.
+pub(crate) struct Envelope<Req, Resp> {
+    pub(crate) request: Req,
+    pub(crate) reply: oneshot::Sender<Resp>,
+}
+
+/// Error returned by [`RequestHandle::call`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallError {
+    /// The service no longer accepts requests.
+    Closed,
+
+    /// The service has accepted the request, but it has completed without
+    /// answering it.
+    Dropped,
+}
+
+impl std::fmt::Display for CallError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Closed => f.write_str("service no longer accepts requests"),
+            Self::Dropped => f.write_str("service has completed without answering the request"),
+        }
+    }
+}
+
+impl std::error::Error for CallError {}
+
+/// Handle sending requests to a [`RequestService`] and awaiting for its
+/// responses.
+pub struct RequestHandle<Req, Resp> {
+    inner: mpsc::Sender<Envelope<Req, Resp>>,
+}
+
+impl<Req, Resp> RequestHandle<Req, Resp> {
+    /// Constructs a handle along with the receiver of its requests, at most
+    /// `capacity` of which can be queued.
+    pub(crate) fn channel(capacity: usize) -> (Self, mpsc::Receiver<Envelope<Req, Resp>>) {
+        let (sender, receiver) = mpsc::channel(capacity);
+
+        (Self { inner: sender }, receiver)
+    }
+
+    /// Sends `request` to the service and waits for its response.
+    ///
+    /// Sending waits until there's capacity in the service's queue.
+    pub async fn call(&self, request: Req) -> Result<Resp, CallError> {
+        let (reply, response) = oneshot::channel();
+        self.inner
+            .send(Envelope { request, reply })
+            .await
+            .map_err(|_| CallError::Closed)?;
+
+        response.await.map_err(|_| CallError::Dropped)
+    }
+}
+
+impl<Req, Resp> Clone for RequestHandle<Req, Resp> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<Req, Resp> std::fmt::Debug for RequestHandle<Req, Resp> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RequestHandle")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+/// Service answering requests with a [`RequestHandler`].
+///
+/// Its handle is a [`RequestHandle`]. The service completes once all of its
+/// handles have been dropped. When graceful shutdown is enabled, then the
+/// requests already queued by the time the service is cancelled are still
+/// answered.
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{Cancellable, CancellationToken, RequestHandler, RequestService};
+///
+/// struct Counter {
+///     value: u64,
+/// }
+///
+/// impl RequestHandler for Counter {
+///     type Request = u64;
+///     type Response = u64;
+///
+///     async fn handle_request(&mut self, request: Self::Request) -> Self::Response {
+///         self.value += request;
+///         self.value
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let service = RequestService::new(Counter { value: 0 }, 16);
+/// let handle = service.spawn(CancellationToken::new()).await;
+///
+/// assert_eq!(handle.call(2).await, Ok(2));
+/// assert_eq!(handle.call(3).await, Ok(5));
+/// # }
+/// ```
+pub struct RequestService<H>
+where
+    H: RequestHandler,
+{
+    handler: H,
+    receiver: mpsc::Receiver<Envelope<H::Request, H::Response>>,
+    handle: Option<RequestHandle<H::Request, H::Response>>,
+}
+
+impl<H> RequestService<H>
+where
+    H: RequestHandler,
+{
+    /// Constructs a new service answering requests with `handler`.
+    ///
+    /// At most `capacity` requests can be queued.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `capacity` is zero.
+    pub fn new(handler: H, capacity: usize) -> Self {
+        let (handle, receiver) = RequestHandle::channel(capacity);
+
+        Self {
+            handler,
+            receiver,
+            handle: Some(handle),
+        }
+    }
+
+    async fn answer(&mut self, envelope: Envelope<H::Request, H::Response>) {
+        let response = self.handler.handle_request(envelope.request).await;
+        // The caller might have stopped waiting for the response.
+        let _ = envelope.reply.send(response);
+    }
+}
+
+impl<H> Cancellable for RequestService<H>
+where
+    H: RequestHandler + Send,
+{
+    type Result = ();
+    type Handle = RequestHandle<H::Request, H::Response>;
+    type Error = Infallible;
+    type Output = ();
+
+    fn name(&self) -> &str {
+        std::any::type_name::<H>()
+    }
+
+    async fn new_handle(&mut self) -> Self::Handle {
+        self.handle.take().expect("handle to be present")
+    }
+
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        match self.receiver.recv().await {
+            Some(envelope) => {
+                self.answer(envelope).await;
+                Ok(CancellationResult::Continue)
+            }
+            None => Ok(CancellationResult::Break),
+        }
+    }
+
+    async fn drain(
+        &mut self,
+    ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        match self.receiver.try_recv() {
+            Ok(envelope) => {
+                self.answer(envelope).await;
+                Ok(CancellationResult::Continue)
+            }
+            Err(_) => Ok(CancellationResult::Break),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{CallError, Cancellable, RequestHandler, RequestService};
+
+    struct Echo {}
+
+    impl RequestHandler for Echo {
+        type Request = String;
+        type Response = String;
+
+        async fn handle_request(&mut self, request: Self::Request) -> Self::Response {
+            request
+        }
+    }
+
+    #[tokio::test]
+    async fn should_answer_request() {
+        // Arrange
+        let handle = RequestService::new(Echo {}, 1)
+            .spawn(CancellationToken::new())
+            .await;
+
+        // Act
+        let response = handle.call("hello".to_owned()).await;
+
+        // Assert
+        assert_eq!(response, Ok("hello".to_owned()));
+    }
+
+    #[tokio::test]
+    async fn should_fail_call_when_service_has_completed() {
+        // Arrange
+        let handle = RequestService::new(Echo {}, 1)
+            .spawn(CancellationToken::new())
+            .await;
+        let requests = (*handle).clone();
+        handle.cancel();
+        handle.await.unwrap().unwrap();
+
+        // Act
+        let response = requests.call("hello".to_owned()).await;
+
+        // Assert
+        assert_eq!(response, Err(CallError::Closed));
+    }
+
+    #[tokio::test]
+    async fn should_complete_when_handles_are_dropped() {
+        // Arrange
+        let handle = RequestService::new(Echo {}, 1)
+            .spawn(CancellationToken::new())
+            .await;
+        let (join_handle, _, requests) = handle.into_raw_parts();
+
+        // Act
+        drop(requests);
+
+        // Assert
+        assert!(join_handle.await.unwrap().is_ok());
+    }
+}
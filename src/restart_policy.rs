@@ -0,0 +1,158 @@
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::Duration,
+};
+
+/// Policy deciding whether and when a failed service should be restarted.
+///
+/// A service is restarted when [`Cancellable::run`] returns an error. The
+/// number of attempts is counted from the last successful iteration, so a
+/// service that recovers gets the full budget of attempts again.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use cancellable::RestartPolicy;
+///
+/// let policy = RestartPolicy::exponential(Duration::from_millis(100), Duration::from_secs(10))
+///     .with_jitter()
+///     .max_attempts(5);
+/// ```
+///
+/// [`Cancellable::run`]: crate::Cancellable::run
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestartPolicy {
+    backoff: Backoff,
+    jitter: bool,
+    max_attempts: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Backoff {
+    Fixed(Duration),
+    Exponential { initial: Duration, max: Duration },
+}
+
+impl RestartPolicy {
+    /// Constructs a policy that waits `delay` before every restart.
+    pub fn fixed(delay: Duration) -> Self {
+        Self {
+            backoff: Backoff::Fixed(delay),
+            jitter: false,
+            max_attempts: None,
+        }
+    }
+
+    /// Constructs a policy that doubles the delay with every consecutive
+    /// restart, starting with `initial` and never exceeding `max`.
+    pub fn exponential(initial: Duration, max: Duration) -> Self {
+        Self {
+            backoff: Backoff::Exponential { initial, max },
+            jitter: false,
+            max_attempts: None,
+        }
+    }
+
+    /// Randomizes each delay to be uniformly distributed between zero and the
+    /// delay computed by the policy.
+    pub fn with_jitter(mut self) -> Self {
+        self.jitter = true;
+        self
+    }
+
+    /// Limits the number of consecutive restarts. If the service fails once
+    /// more after the limit has been reached, then it completes with the
+    /// error.
+    pub fn max_attempts(mut self, max_attempts: usize) -> Self {
+        self.max_attempts = Some(max_attempts);
+        self
+    }
+
+    /// Returns the delay before the given restart attempt (counted from zero),
+    /// or `None` if the service shouldn't be restarted anymore.
+    pub(crate) fn delay(&self, attempt: usize) -> Option<Duration> {
+        if self.max_attempts.is_some_and(|max| attempt >= max) {
+            return None;
+        }
+
+        let delay = match self.backoff {
+            Backoff::Fixed(delay) => delay,
+            Backoff::Exponential { initial, max } => {
+                let factor = 1u32.checked_shl(attempt.try_into().unwrap_or(u32::MAX));
+                factor
+                    .and_then(|factor| initial.checked_mul(factor))
+                    .map_or(max, |delay| delay.min(max))
+            }
+        };
+
+        if self.jitter {
+            Some(delay.mul_f64(random_fraction()))
+        } else {
+            Some(delay)
+        }
+    }
+}
+
+/// Returns a pseudo-random number in range `[0, 1)`.
+fn random_fraction() -> f64 {
+    let hash = RandomState::new().build_hasher().finish();
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::RestartPolicy;
+
+    #[test]
+    fn should_return_fixed_delay() {
+        // Arrange
+        let policy = RestartPolicy::fixed(Duration::from_secs(1));
+
+        // Act
+        let delays: Vec<_> = (0..3).map(|attempt| policy.delay(attempt)).collect();
+
+        // Assert
+        assert_eq!(vec![Some(Duration::from_secs(1)); 3], delays);
+    }
+
+    #[test]
+    fn should_double_delay_up_to_max() {
+        // Arrange
+        let policy = RestartPolicy::exponential(Duration::from_secs(1), Duration::from_secs(5));
+
+        // Act
+        let delays: Vec<_> = [0, 1, 2, 3, 100]
+            .into_iter()
+            .map(|attempt| policy.delay(attempt).unwrap().as_secs())
+            .collect();
+
+        // Assert
+        assert_eq!(vec![1, 2, 4, 5, 5], delays);
+    }
+
+    #[test]
+    fn should_stop_after_max_attempts() {
+        // Arrange
+        let policy = RestartPolicy::fixed(Duration::ZERO).max_attempts(2);
+
+        // Act & Assert
+        assert!(policy.delay(1).is_some());
+        assert!(policy.delay(2).is_none());
+    }
+
+    #[test]
+    fn should_not_exceed_delay_with_jitter() {
+        // Arrange
+        let policy = RestartPolicy::fixed(Duration::from_secs(1)).with_jitter();
+
+        // Act & Assert
+        for attempt in 0..100 {
+            assert!(policy.delay(attempt).unwrap() <= Duration::from_secs(1));
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use std::{collections::HashMap, hash::Hash};
+
+use tokio::sync::mpsc::unbounded_channel;
+
+use crate::{CallbackResult, SenderHandle};
+
+/// Callback of a single route.
+type Route<T, E> = Box<dyn FnMut(T) -> CallbackResult<E> + Send>;
+
+/// Dispatcher of the values yielded by a service to per-key callbacks.
+///
+/// Every value is classified with the key function and passed to the callback
+/// of its key's route, or to the fallback if there's no such route. Values
+/// without a route are dropped, unless the fallback has been set.
+///
+/// If any of the callbacks doesn't return [`CallbackResult::Continue`], then
+/// the service completes accordingly, just like with a single callback.
+///
+/// See [`Cancellable::spawn_with_router`].
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{
+///     CallbackResult, Cancellable, CancellationToken, MpscSenderHandle, Router,
+/// };
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let (https, mut https_connections) = MpscSenderHandle::channel(16);
+///
+/// let router = Router::new(|(_, port): &(String, u16)| *port)
+///     .route_to(443, https)
+///     .route(80, |(host, _)| {
+///         println!("Redirecting {host} to HTTPS.");
+///         CallbackResult::Continue
+///     })
+///     .fallback(|(host, port)| {
+///         println!("Rejecting {host}:{port}.");
+///         CallbackResult::Continue
+///     });
+///
+/// let connections = futures::stream::iter([("example.com".to_string(), 443)]);
+/// let handle = cancellable::from_stream(connections)
+///     .spawn_with_router(CancellationToken::new(), router)
+///     .await;
+///
+/// assert!(https_connections.recv().await.is_some());
+/// handle.join().await.unwrap();
+/// # }
+/// ```
+///
+/// [`Cancellable::spawn_with_router`]: crate::Cancellable::spawn_with_router
+pub struct Router<K, T, E> {
+    key: Box<dyn FnMut(&T) -> K + Send>,
+    routes: HashMap<K, Route<T, E>>,
+    fallback: Option<Route<T, E>>,
+}
+
+impl<K, T, E> Router<K, T, E>
+where
+    K: Eq + Hash + Send + 'static,
+    T: Send + 'static,
+    E: 'static,
+{
+    /// Constructs a new router classifying values with `key`.
+    pub fn new<F>(key: F) -> Self
+    where
+        F: FnMut(&T) -> K + Send + 'static,
+    {
+        Self {
+            key: Box::new(key),
+            routes: HashMap::new(),
+            fallback: None,
+        }
+    }
+
+    /// Passes the values classified as `key` to `callback`.
+    ///
+    /// It replaces the previous route of `key`, if there's one.
+    pub fn route<F>(mut self, key: K, callback: F) -> Self
+    where
+        F: FnMut(T) -> CallbackResult<E> + Send + 'static,
+    {
+        self.routes.insert(key, Box::new(callback));
+        self
+    }
+
+    /// Sends the values classified as `key` through `handle`, e.g. to the
+    /// service handling them.
+    ///
+    /// The values are sent by a task of their own, so a route waiting for
+    /// capacity doesn't hold back the other routes. Once `handle` stops
+    /// accepting values, the service completes as soon as it yields the next
+    /// value of the route. The task completes once the service has completed
+    /// and all the values of the route have been sent.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if it's called outside of a tokio runtime.
+    pub fn route_to<H>(self, key: K, handle: H) -> Self
+    where
+        H: SenderHandle<Item = T> + Send + 'static,
+    {
+        let (sender, mut receiver) = unbounded_channel();
+        tokio::spawn(async move {
+            while let Some(item) = receiver.recv().await {
+                if handle.send(item).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        self.route(key, move |item| match sender.send(item) {
+            Ok(()) => CallbackResult::Continue,
+            Err(_) => CallbackResult::Break,
+        })
+    }
+
+    /// Passes the values without a route to `callback`.
+    pub fn fallback<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(T) -> CallbackResult<E> + Send + 'static,
+    {
+        self.fallback = Some(Box::new(callback));
+        self
+    }
+
+    /// Turns the router into a callback, e.g. to be passed to
+    /// [`Cancellable::spawn_with_options`].
+    ///
+    /// [`Cancellable::spawn_with_options`]: crate::Cancellable::spawn_with_options
+    pub fn into_callback(mut self) -> impl FnMut(T) -> CallbackResult<E> + Send + 'static {
+        move |item| {
+            let key = (self.key)(&item);
+            match self.routes.get_mut(&key).or(self.fallback.as_mut()) {
+                Some(route) => route(item),
+                None => CallbackResult::Continue,
+            }
+        }
+    }
+}
+
+impl<K, T, E> std::fmt::Debug for Router<K, T, E>
+where
+    K: std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Router")
+            .field("routes", &self.routes.keys().collect::<Vec<_>>())
+            .field("fallback", &self.fallback.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{CallbackResult, Cancellable, MpscSenderHandle, Router};
+
+    #[tokio::test]
+    async fn should_dispatch_values_by_key() {
+        // Arrange
+        let (evens, mut even_values) = MpscSenderHandle::channel(1);
+        let odds = Arc::new(Mutex::new(Vec::new()));
+        let odds_clone = Arc::clone(&odds);
+        let router = Router::new(|item: &i32| item % 2)
+            .route_to(0, evens)
+            .route(1, move |item| {
+                odds_clone.lock().unwrap().push(item);
+                CallbackResult::Continue
+            });
+
+        // Act
+        let handle = crate::from_stream(futures::stream::iter(1..=4))
+            .spawn_with_router(CancellationToken::new(), router)
+            .await;
+        handle.join().await.unwrap();
+
+        // Assert
+        assert_eq!(vec![1, 3], *odds.lock().unwrap());
+        assert_eq!(Some(2), even_values.recv().await);
+        assert_eq!(Some(4), even_values.recv().await);
+        assert_eq!(None, even_values.recv().await);
+    }
+
+    #[tokio::test]
+    async fn should_pass_values_without_route_to_fallback() {
+        // Arrange
+        let fallback = Arc::new(Mutex::new(Vec::new()));
+        let fallback_clone = Arc::clone(&fallback);
+        let router = Router::new(|item: &i32| *item)
+            .route(1, |_| CallbackResult::Continue)
+            .fallback(move |item| {
+                fallback_clone.lock().unwrap().push(item);
+                CallbackResult::Continue
+            });
+
+        // Act
+        let handle = crate::from_stream(futures::stream::iter(1..=3))
+            .spawn_with_router(CancellationToken::new(), router)
+            .await;
+        handle.join().await.unwrap();
+
+        // Assert
+        assert_eq!(vec![2, 3], *fallback.lock().unwrap());
+    }
+}
@@ -0,0 +1,627 @@
+use std::{
+    any::{Any, TypeId},
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tokio::{
+    sync::{mpsc, watch},
+    time::Instant,
+};
+use tokio_util::sync::{CancellationToken, DropGuard};
+
+use crate::{
+    cancel_reason::ReasonSlot, clock::SharedClock, completion_reason::CompletionSlot,
+    service_stats::StatsSlot, watchdog::Heartbeat, CancelReason, Cancellable, Clock,
+    CompletionReason, Health, Scope, StopPhase, Yielder,
+};
+
+tokio::task_local! {
+    static CONTEXT: RunContext;
+}
+
+/// Value yielded with [`RunContext::yield_item`].
+pub(crate) type YieldedItem = Box<dyn Any + Send>;
+
+/// Sender of the values yielded with [`RunContext::yield_item`].
+#[derive(Debug, Clone)]
+pub(crate) struct ItemSender {
+    sender: mpsc::Sender<YieldedItem>,
+    item_type: TypeId,
+}
+
+impl ItemSender {
+    /// Constructs a channel of values of type `R`.
+    pub(crate) fn channel<R>() -> (Self, mpsc::Receiver<YieldedItem>)
+    where
+        R: 'static,
+    {
+        // A single slot, so the service waits for each value to be delivered.
+        let (sender, receiver) = mpsc::channel(1);
+        let sender = Self {
+            sender,
+            item_type: TypeId::of::<R>(),
+        };
+
+        (sender, receiver)
+    }
+}
+
+/// Context of a spawned service.
+///
+/// The context is available from within every method of the service called
+/// by its work loop, e.g. [`Cancellable::run`], via [`RunContext::current`].
+/// It allows long-running work inside a single iteration to observe the
+/// service's cancellation, e.g. to abort a long await cooperatively, and to
+/// yield values before the iteration completes, with
+/// [`RunContext::yield_item`].
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{Cancellable, CancellationResult, RunContext};
+///
+/// struct Downloader;
+///
+/// impl Cancellable for Downloader {
+///     type Result = ();
+///     type Handle = ();
+///     type Error = std::io::Error;
+///     type Output = ();
+///
+///     async fn new_handle(&mut self) -> Self::Handle {}
+///
+///     async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+///         let context = RunContext::current().expect("to be called by the work loop");
+///         tokio::select! {
+///             _ = context.cancellation_token().cancelled() => {
+///                 // Clean up the partially completed work.
+///             }
+///             _ = std::future::pending::<()>() => {}
+///         }
+///
+///         Ok(CancellationResult::Break)
+///     }
+/// }
+/// ```
+///
+/// [`Cancellable::run`]: crate::Cancellable::run
+#[derive(Debug, Clone)]
+pub struct RunContext {
+    cancellation_token: CancellationToken,
+    soft_stop: CancellationToken,
+    health: Arc<watch::Sender<Health>>,
+    heartbeat: Heartbeat,
+    activity: Arc<watch::Sender<()>>,
+    stats: StatsSlot,
+    cancel_reason: ReasonSlot,
+    completion: CompletionSlot,
+    children: Scope,
+    iterations: Arc<AtomicU64>,
+    spawned_at: Instant,
+    items: ItemSender,
+    clock: SharedClock,
+}
+
+impl RunContext {
+    /// Returns the context of the service whose method is being called.
+    ///
+    /// Returns `None` if called outside of the service's work loop.
+    pub fn current() -> Option<Self> {
+        CONTEXT.try_with(Clone::clone).ok()
+    }
+
+    /// Returns a child token of the service's cancellation token.
+    ///
+    /// The token is cancelled as soon as the service is cancelled. Cancelling
+    /// the token itself doesn't cancel the service.
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.cancellation_token
+    }
+
+    /// Returns a token for a sub-task of the service.
+    ///
+    /// Unlike [`Self::cancellation_token`], the token is also cancelled with
+    /// [`CancellableHandle::cancel_children`], along with the services spawned
+    /// with [`Self::spawn_child`], while the service itself keeps running.
+    /// It's cancelled once the service completes as well.
+    ///
+    /// [`CancellableHandle::cancel_children`]: crate::CancellableHandle::cancel_children
+    pub fn child_token(&self) -> CancellationToken {
+        self.children.child_token()
+    }
+
+    /// Checks if the service has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation_token.is_cancelled()
+    }
+
+    /// Returns the number of the current iteration, i.e. the number of calls
+    /// to [`Cancellable::run`] made so far, including the current one.
+    pub fn iteration(&self) -> u64 {
+        self.iterations.load(Ordering::Relaxed)
+    }
+
+    /// Returns the time elapsed since the service's work loop has started.
+    pub fn elapsed(&self) -> Duration {
+        self.clock.now().duration_since(self.spawned_at)
+    }
+
+    /// Returns the clock the service's work loop measures time and waits
+    /// with.
+    ///
+    /// See [`SpawnOptions::clock`].
+    ///
+    /// [`SpawnOptions::clock`]: crate::SpawnOptions::clock
+    pub fn clock(&self) -> Arc<dyn Clock> {
+        Arc::clone(&self.clock.0)
+    }
+
+    /// Yields `item` to the service's consumer, e.g. its callback, without
+    /// returning from [`Cancellable::run`].
+    ///
+    /// It allows a single long-running call to [`Cancellable::run`] to emit
+    /// values as soon as they're produced. The future completes once the value
+    /// has been buffered, not once the consumer has received it. The buffer
+    /// holds a single value, so a slow consumer still slows the service down:
+    /// the next call waits until the buffered value has been taken. Values are
+    /// only taken while [`Cancellable::run`] is being called.
+    ///
+    /// The value is passed through the service's adapters, e.g.
+    /// [`Cancellable::map`] or [`Cancellable::take`], just like the values it
+    /// returns.
+    ///
+    /// # Errors
+    ///
+    /// Returns `item` back if it isn't of the type of the values yielded by
+    /// the service, or if the consumer has stopped accepting values, e.g.
+    /// its callback has returned [`CallbackResult::Break`] or
+    /// [`Cancellable::take`] has taken enough values. In the latter
+    /// case, the work loop completes as soon as the current call to
+    /// [`Cancellable::run`] returns.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cancellable::{Cancellable, CancellationResult, RunContext};
+    ///
+    /// struct Lines {
+    ///     document: String,
+    /// }
+    ///
+    /// impl Cancellable for Lines {
+    ///     type Result = String;
+    ///     type Handle = ();
+    ///     type Error = std::io::Error;
+    ///     type Output = ();
+    ///
+    ///     async fn new_handle(&mut self) -> Self::Handle {}
+    ///
+    ///     async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+    ///         let context = RunContext::current().expect("to be called by the work loop");
+    ///         for line in self.document.lines() {
+    ///             if context.yield_item(line.to_owned()).await.is_err() {
+    ///                 break;
+    ///             }
+    ///         }
+    ///
+    ///         Ok(CancellationResult::Break)
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`CallbackResult::Break`]: crate::CallbackResult::Break
+    pub async fn yield_item<R>(&self, item: R) -> Result<(), R>
+    where
+        R: Send + 'static,
+    {
+        match self.yielder() {
+            Some(yielder) => yielder.send(item).await,
+            None => Err(item),
+        }
+    }
+
+    /// Returns a [`Yielder`] of values of type `R`.
+    ///
+    /// Returns `None` if `R` isn't the type of the values yielded by the
+    /// service.
+    pub fn yielder<R>(&self) -> Option<Yielder<R>>
+    where
+        R: Send + 'static,
+    {
+        if TypeId::of::<R>() != self.items.item_type {
+            return None;
+        }
+
+        Some(Yielder::new(self.items.sender.clone()))
+    }
+
+    /// Returns the current phase of the service's two-phase shutdown.
+    ///
+    /// See [`SpawnOptions::soft_stop`].
+    ///
+    /// [`SpawnOptions::soft_stop`]: crate::SpawnOptions::soft_stop
+    pub fn stop_phase(&self) -> StopPhase {
+        if self.cancellation_token.is_cancelled() {
+            StopPhase::HardStop
+        } else if self.soft_stop.is_cancelled() {
+            StopPhase::SoftStop
+        } else {
+            StopPhase::Running
+        }
+    }
+
+    /// Waits until the service is asked to stop, either softly or hard.
+    ///
+    /// It's meant to be raced against accepting new work, e.g. new
+    /// connections, so the service stops accepting it as soon as the soft
+    /// stop begins.
+    pub async fn stop_requested(&self) {
+        tokio::select! {
+            _ = self.cancellation_token.cancelled() => {}
+            _ = self.soft_stop.cancelled() => {}
+        }
+    }
+
+    /// Returns the reason of the service's cancellation.
+    ///
+    /// Returns `None` if the service hasn't been cancelled with
+    /// [`CancellableHandle::cancel_with_reason`] or by its idle timeout, e.g.
+    /// if it has been cancelled through its parent token.
+    ///
+    /// [`CancellableHandle::cancel_with_reason`]: crate::CancellableHandle::cancel_with_reason
+    pub fn cancel_reason(&self) -> Option<CancelReason> {
+        self.cancel_reason.get().cloned()
+    }
+
+    /// Reports the service's health to its spawner.
+    ///
+    /// The health is observable with [`CancellableHandle::health`] and
+    /// [`CancellableHandle::watch_health`], e.g. to back liveness probes.
+    ///
+    /// [`CancellableHandle::health`]: crate::CancellableHandle::health
+    /// [`CancellableHandle::watch_health`]: crate::CancellableHandle::watch_health
+    pub fn report_health(&self, health: Health) {
+        self.health.send_replace(health);
+    }
+
+    /// Signals that the service is making progress.
+    ///
+    /// It's meant for services whose single call to [`Cancellable::run`] can
+    /// legitimately take longer than the interval of their [`Watchdog`].
+    /// Every completed call to [`Cancellable::run`] is a signal of progress
+    /// on its own.
+    ///
+    /// [`Cancellable::run`]: crate::Cancellable::run
+    /// [`Watchdog`]: crate::Watchdog
+    pub fn ping(&self) {
+        self.heartbeat.beat();
+    }
+
+    /// Signals that the service is active, e.g. that it has received a
+    /// message through its handle.
+    ///
+    /// It postpones the service's idle timeout. Every yielded value is a
+    /// signal of activity on its own. See [`SpawnOptions::idle_timeout`].
+    ///
+    /// [`SpawnOptions::idle_timeout`]: crate::SpawnOptions::idle_timeout
+    pub fn keep_alive(&self) {
+        self.activity.send_replace(());
+        self.stats.record_activity(self.clock.now());
+    }
+
+    /// Spawns `service` as a child of the service.
+    ///
+    /// The child is cancelled as soon as the service is cancelled, and it's
+    /// cancelled and joined once the service completes, whatever the reason
+    /// of its completion is.
+    ///
+    /// # Returns
+    ///
+    /// The handle for communicating with the child.
+    pub async fn spawn_child<T>(&self, service: T) -> T::Handle
+    where
+        T: Cancellable + 'static,
+        T::Output: 'static,
+        T::Error: 'static,
+    {
+        self.children.spawn(service).await
+    }
+
+    /// Provides this context to `f`, e.g. to a unit of work performed on a
+    /// blocking thread.
+    pub(crate) fn enter_sync<F, R>(self, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        CONTEXT.sync_scope(self, f)
+    }
+
+    /// Provides this context to `future`, e.g. to a unit of work spawned
+    /// onto another task.
+    pub(crate) fn enter<F>(self, future: F) -> impl Future<Output = F::Output>
+    where
+        F: Future,
+    {
+        CONTEXT.scope(self, future)
+    }
+
+    /// Constructs the context of a service cancelled with
+    /// `cancellation_token`.
+    pub(crate) fn new(
+        cancellation_token: &CancellationToken,
+        soft_stop: &CancellationToken,
+        health: Arc<watch::Sender<Health>>,
+        heartbeat: Heartbeat,
+        cancel_reason: ReasonSlot,
+        iterations: Arc<AtomicU64>,
+        items: ItemSender,
+    ) -> Self {
+        Self {
+            cancellation_token: cancellation_token.child_token(),
+            soft_stop: soft_stop.child_token(),
+            health,
+            heartbeat,
+            activity: Arc::new(watch::channel(()).0),
+            stats: StatsSlot::default(),
+            cancel_reason,
+            completion: CompletionSlot::default(),
+            children: Scope::new(cancellation_token.child_token()),
+            iterations,
+            spawned_at: Instant::now(),
+            items,
+            clock: SharedClock::default(),
+        }
+    }
+
+    /// Replaces the scope of the service's children, e.g. with the one shared
+    /// with the service's handle.
+    pub(crate) fn with_children(mut self, children: Scope) -> Self {
+        self.children = children;
+        self
+    }
+
+    /// Replaces the slot for the reason of the service's completion, e.g. with
+    /// the one shared with the service's handle.
+    pub(crate) fn with_completion(mut self, completion: CompletionSlot) -> Self {
+        self.completion = completion;
+        self
+    }
+
+    /// Records the reason of the service's completion, unless one has been
+    /// recorded already.
+    pub(crate) fn complete_with(&self, reason: CompletionReason) {
+        let _ = self.completion.set(reason);
+    }
+
+    /// Replaces the sender of the service's activity, e.g. with the one
+    /// monitored for the idle timeout.
+    pub(crate) fn with_activity(mut self, activity: Arc<watch::Sender<()>>) -> Self {
+        self.activity = activity;
+        self
+    }
+
+    /// Replaces the statistics of the service, e.g. with the ones of its work
+    /// loop.
+    pub(crate) fn with_stats(mut self, stats: StatsSlot) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    /// Replaces the sender of the values yielded by the service, e.g. with
+    /// the one of an adapter passing them through.
+    pub(crate) fn with_items(mut self, items: ItemSender) -> Self {
+        self.items = items;
+        self
+    }
+
+    pub(crate) fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.spawned_at = clock.now();
+        self.clock = clock;
+        self
+    }
+
+    /// Returns a guard cancelling the service's children when dropped, e.g.
+    /// when the service's task is aborted.
+    pub(crate) fn children_guard(&self) -> DropGuard {
+        self.children.cancellation_token().clone().drop_guard()
+    }
+
+    /// Cancels and joins the service's children.
+    ///
+    /// See [`Self::spawn_child`].
+    pub(crate) async fn close_children(&self) {
+        self.children.close().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use tokio::sync::watch;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{
+        cancel_reason::ReasonSlot, run_context::ItemSender, watchdog::Heartbeat, Cancellable,
+        CancellationResult, Health, RunContext, StopPhase,
+    };
+
+    #[test]
+    fn should_have_no_context_outside_of_service() {
+        // Act
+        let context = RunContext::current();
+
+        // Assert
+        assert!(context.is_none());
+    }
+
+    #[tokio::test]
+    async fn should_provide_child_token() {
+        // Arrange
+        let cancellation_token = CancellationToken::new();
+        let (health, _) = watch::channel(Health::default());
+
+        // Act
+        let context = RunContext::new(
+            &cancellation_token,
+            &CancellationToken::new(),
+            Arc::new(health),
+            Heartbeat::new(),
+            ReasonSlot::default(),
+            Arc::default(),
+            ItemSender::channel::<()>().0,
+        )
+        .enter(async { RunContext::current() })
+        .await;
+        cancellation_token.cancel();
+
+        // Assert
+        assert!(context.unwrap().cancellation_token().is_cancelled());
+    }
+
+    #[test]
+    fn should_report_stop_phase() {
+        // Arrange
+        let cancellation_token = CancellationToken::new();
+        let soft_stop = CancellationToken::new();
+        let (health, _) = watch::channel(Health::default());
+        let context = RunContext::new(
+            &cancellation_token,
+            &soft_stop,
+            Arc::new(health),
+            Heartbeat::new(),
+            ReasonSlot::default(),
+            Arc::default(),
+            ItemSender::channel::<()>().0,
+        );
+        let running = context.stop_phase();
+
+        // Act
+        soft_stop.cancel();
+        let soft = context.stop_phase();
+        cancellation_token.cancel();
+        let hard = context.stop_phase();
+
+        // Assert
+        assert_eq!(StopPhase::Running, running);
+        assert_eq!(StopPhase::SoftStop, soft);
+        assert_eq!(StopPhase::HardStop, hard);
+    }
+
+    struct ChildCancellable {
+        stopped: Arc<AtomicBool>,
+    }
+
+    impl Cancellable for ChildCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            std::future::pending().await
+        }
+
+        async fn on_stop(&mut self) {
+            self.stopped.store(true, Ordering::SeqCst);
+        }
+    }
+
+    struct ParentCancellable {
+        stopped: Arc<AtomicBool>,
+    }
+
+    impl Cancellable for ParentCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            let child = ChildCancellable {
+                stopped: Arc::clone(&self.stopped),
+            };
+            RunContext::current().unwrap().spawn_child(child).await;
+
+            Ok(CancellationResult::Break)
+        }
+    }
+
+    #[tokio::test]
+    async fn should_join_children_when_service_completes() {
+        // Arrange
+        let stopped = Arc::new(AtomicBool::new(false));
+        let service = ParentCancellable {
+            stopped: Arc::clone(&stopped),
+        };
+
+        // Act
+        let handle = service.spawn(CancellationToken::new()).await;
+        handle.join().await.unwrap();
+
+        // Assert
+        assert!(stopped.load(Ordering::SeqCst));
+    }
+
+    struct SpawningCancellable {
+        stopped: Arc<AtomicBool>,
+        spawned: bool,
+    }
+
+    impl Cancellable for SpawningCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            if !self.spawned {
+                self.spawned = true;
+                let child = ChildCancellable {
+                    stopped: Arc::clone(&self.stopped),
+                };
+                RunContext::current().unwrap().spawn_child(child).await;
+            }
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn should_cancel_children_while_service_keeps_running() {
+        // Arrange
+        let stopped = Arc::new(AtomicBool::new(false));
+        let service = SpawningCancellable {
+            stopped: Arc::clone(&stopped),
+            spawned: false,
+        };
+        let handle = service.spawn(CancellationToken::new()).await;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        // Act
+        handle.cancel_children();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        // Assert
+        assert!(stopped.load(Ordering::SeqCst));
+        assert!(!handle.is_finished());
+        handle.cancel();
+        assert!(handle.join().await.is_ok());
+    }
+}
@@ -0,0 +1,478 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+#[cfg(feature = "cron")]
+use chrono::{DateTime, Utc};
+use tokio::{sync::mpsc, time::Instant};
+
+#[cfg(feature = "cron")]
+use crate::CronSchedule;
+use crate::{clock::SharedClock, Cancellable, CancellationResult};
+
+/// Longest time the scheduler sleeps for, before it checks the wall clock for
+/// the cron jobs again.
+///
+/// The wall clock can jump, e.g. when the machine resumes from suspension,
+/// while the scheduler's clock doesn't.
+#[cfg(feature = "cron")]
+const WALL_CLOCK_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Identifier of a job scheduled with [`SchedulerHandle`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct JobId(u64);
+
+/// Timing of a job, as requested by the handle.
+///
+/// The delays are turned into instants by the scheduler, so they follow its
+/// clock.
+enum Timing {
+    At(Instant),
+    After(Duration),
+    Every(Duration),
+    #[cfg(feature = "cron")]
+    Cron(CronSchedule),
+}
+
+enum Command<J> {
+    Schedule { id: JobId, timing: Timing, job: J },
+    Cancel(JobId),
+}
+
+enum Recurrence {
+    Once,
+    Every(Duration),
+    #[cfg(feature = "cron")]
+    Cron {
+        schedule: CronSchedule,
+        next: DateTime<Utc>,
+    },
+}
+
+struct Job<J> {
+    job: J,
+    recurrence: Recurrence,
+}
+
+/// Service firing jobs at the scheduled instants.
+///
+/// The jobs are scheduled through its [`SchedulerHandle`], either once or
+/// repetitively, and each fired job is yielded along with its [`JobId`]. The
+/// jobs due at the same time are yielded together, in the order they were
+/// scheduled in. The scheduler waits with the clock of the service, see
+/// [`SpawnOptions::clock`].
+///
+/// The scheduler completes once all of its handles have been dropped and
+/// there are no jobs left to fire.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use cancellable::{Cancellable, CancellationToken, Scheduler};
+/// use futures::StreamExt;
+///
+/// # #[tokio::main(flavor = "current_thread", start_paused = true)]
+/// # async fn main() {
+/// let (handle, mut jobs) = Scheduler::new().spawn_stream(CancellationToken::new()).await;
+///
+/// handle.schedule_after(Duration::from_secs(60), "backup");
+/// let heartbeat = handle.schedule_every(Duration::from_secs(25), "heartbeat");
+///
+/// assert_eq!(Some((heartbeat, "heartbeat")), jobs.next().await);
+/// assert_eq!(Some((heartbeat, "heartbeat")), jobs.next().await);
+/// assert_eq!("backup", jobs.next().await.unwrap().1);
+/// # }
+/// ```
+///
+/// [`SpawnOptions::clock`]: crate::SpawnOptions::clock
+pub struct Scheduler<J> {
+    commands: mpsc::UnboundedReceiver<Command<J>>,
+    closed: bool,
+    queue: BTreeMap<(Instant, JobId), Job<J>>,
+    due: HashMap<JobId, Instant>,
+    handle: Option<SchedulerHandle<J>>,
+    #[cfg(feature = "cron")]
+    wall_clock: fn() -> DateTime<Utc>,
+}
+
+impl<J> Scheduler<J> {
+    /// Constructs a new scheduler without any jobs.
+    pub fn new() -> Self {
+        let (sender, commands) = mpsc::unbounded_channel();
+
+        Self {
+            commands,
+            closed: false,
+            queue: BTreeMap::new(),
+            due: HashMap::new(),
+            handle: Some(SchedulerHandle {
+                sender,
+                next_id: Arc::new(AtomicU64::new(0)),
+            }),
+            #[cfg(feature = "cron")]
+            wall_clock: Utc::now,
+        }
+    }
+
+    /// Replaces the wall clock the cron jobs are evaluated with.
+    #[cfg(all(test, feature = "cron"))]
+    fn with_wall_clock(mut self, wall_clock: fn() -> DateTime<Utc>) -> Self {
+        self.wall_clock = wall_clock;
+        self
+    }
+
+    fn insert(&mut self, at: Instant, id: JobId, job: Job<J>) {
+        self.queue.insert((at, id), job);
+        self.due.insert(id, at);
+    }
+
+    /// Inserts the cron `job` due at `next`, as seen by the wall clock at
+    /// `wall_now`.
+    #[cfg(feature = "cron")]
+    fn insert_cron(
+        &mut self,
+        now: Instant,
+        wall_now: DateTime<Utc>,
+        id: JobId,
+        job: J,
+        schedule: CronSchedule,
+        next: Option<DateTime<Utc>>,
+    ) {
+        let Some(next) = next else {
+            // The schedule has no fires left.
+            self.due.remove(&id);
+            return;
+        };
+
+        let delay = (next - wall_now).to_std().unwrap_or_default();
+        let job = Job {
+            job,
+            recurrence: Recurrence::Cron { schedule, next },
+        };
+        self.insert(now + delay.min(WALL_CLOCK_CHECK_INTERVAL), id, job);
+    }
+
+    fn apply(&mut self, command: Command<J>, now: Instant) {
+        match command {
+            Command::Schedule { id, timing, job } => {
+                let (at, recurrence) = match timing {
+                    Timing::At(at) => (at, Recurrence::Once),
+                    Timing::After(delay) => (now + delay, Recurrence::Once),
+                    Timing::Every(period) => (now + period, Recurrence::Every(period)),
+                    #[cfg(feature = "cron")]
+                    Timing::Cron(schedule) => {
+                        let wall_now = (self.wall_clock)();
+                        let next = schedule.next_after(wall_now);
+                        self.insert_cron(now, wall_now, id, job, schedule, next);
+                        return;
+                    }
+                };
+                self.insert(at, id, Job { job, recurrence });
+            }
+            Command::Cancel(id) => {
+                if let Some(at) = self.due.remove(&id) {
+                    self.queue.remove(&(at, id));
+                }
+            }
+        }
+    }
+
+    /// Removes the jobs due at `now`, rescheduling the recurring ones.
+    fn fire(&mut self, now: Instant) -> Vec<(JobId, J)>
+    where
+        J: Clone,
+    {
+        let mut fired = Vec::new();
+        let mut recurring = Vec::new();
+        #[cfg(feature = "cron")]
+        let mut cron = Vec::new();
+        while let Some(entry) = self.queue.first_entry() {
+            if entry.key().0 > now {
+                break;
+            }
+
+            let ((at, id), job) = entry.remove_entry();
+            match job.recurrence {
+                Recurrence::Once => {
+                    self.due.remove(&id);
+                    fired.push((id, job.job));
+                }
+                Recurrence::Every(every) => {
+                    fired.push((id, job.job.clone()));
+                    // The missed ticks are skipped, rather than fired at once.
+                    let mut next = at + every;
+                    if next <= now {
+                        next = now + every;
+                    }
+                    recurring.push(((next, id), job));
+                }
+                #[cfg(feature = "cron")]
+                Recurrence::Cron { schedule, next } => {
+                    let wall_now = (self.wall_clock)();
+                    let (count, upcoming) = schedule.catch_up_fires(next, wall_now);
+                    fired.extend(std::iter::repeat((id, job.job.clone())).take(count));
+                    cron.push((id, job.job, schedule, wall_now, upcoming));
+                }
+            }
+        }
+
+        for ((next, id), job) in recurring {
+            self.insert(next, id, job);
+        }
+        #[cfg(feature = "cron")]
+        for (id, job, schedule, wall_now, next) in cron {
+            self.insert_cron(now, wall_now, id, job, schedule, next);
+        }
+        fired
+    }
+}
+
+impl<J> Default for Scheduler<J> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<J> Cancellable for Scheduler<J>
+where
+    J: Clone + Send + 'static,
+{
+    type Result = (JobId, J);
+    type Handle = SchedulerHandle<J>;
+    type Error = std::convert::Infallible;
+    type Output = ();
+
+    async fn new_handle(&mut self) -> Self::Handle {
+        self.handle.take().expect("handle to be present")
+    }
+
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        let clock = SharedClock::current();
+        let now = clock.now();
+        let fired = self.fire(now);
+        if !fired.is_empty() {
+            return Ok(CancellationResult::Items(fired));
+        }
+
+        let next = self.queue.first_key_value().map(|((at, _), _)| *at);
+        if self.closed && next.is_none() {
+            return Ok(CancellationResult::Break);
+        }
+
+        let wait = async {
+            match next {
+                Some(at) => clock.sleep(at.saturating_duration_since(now)).await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            command = self.commands.recv(), if !self.closed => match command {
+                Some(command) => self.apply(command, clock.now()),
+                None => self.closed = true,
+            },
+            _ = wait => {}
+        }
+
+        Ok(CancellationResult::Continue)
+    }
+}
+
+impl<J> std::fmt::Debug for Scheduler<J> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Scheduler")
+            .field("jobs", &self.due.len())
+            .finish_non_exhaustive()
+    }
+}
+
+/// Handle scheduling jobs of a [`Scheduler`].
+///
+/// Scheduling has no effect once the scheduler has completed.
+pub struct SchedulerHandle<J> {
+    sender: mpsc::UnboundedSender<Command<J>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl<J> SchedulerHandle<J> {
+    fn schedule(&self, timing: Timing, job: J) -> JobId {
+        let id = JobId(self.next_id.fetch_add(1, Ordering::Relaxed));
+        // The scheduler has completed, if no one receives the command.
+        let _ = self.sender.send(Command::Schedule { id, timing, job });
+        id
+    }
+
+    /// Schedules `job` to be fired once, at `at`.
+    ///
+    /// If `at` has already passed, then the job is fired right away.
+    pub fn schedule_at(&self, at: Instant, job: J) -> JobId {
+        self.schedule(Timing::At(at), job)
+    }
+
+    /// Schedules `job` to be fired once, after `delay`.
+    pub fn schedule_after(&self, delay: Duration, job: J) -> JobId {
+        self.schedule(Timing::After(delay), job)
+    }
+
+    /// Schedules `job` to be fired every `period`, starting after the first
+    /// one.
+    ///
+    /// If the scheduler falls behind, e.g. because the callback is slow, then
+    /// the missed ticks are skipped.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `period` is zero.
+    pub fn schedule_every(&self, period: Duration, job: J) -> JobId {
+        assert!(!period.is_zero(), "period must be non-zero");
+        self.schedule(Timing::Every(period), job)
+    }
+
+    /// Schedules `job` to be fired at the times of the cron `schedule`.
+    ///
+    /// The times are evaluated with the wall clock, rather than the clock of
+    /// the service, so the fires follow the schedule's timezone, e.g. across
+    /// daylight saving time changes. The fires missed while the machine has
+    /// been suspended are handled according to [`CronSchedule::catch_up`].
+    #[cfg(feature = "cron")]
+    pub fn schedule_cron(&self, schedule: CronSchedule, job: J) -> JobId {
+        self.schedule(Timing::Cron(schedule), job)
+    }
+
+    /// Cancels the job `id`, so it's no longer fired.
+    ///
+    /// It has no effect if the job has already been fired for the last time.
+    pub fn cancel_job(&self, id: JobId) {
+        let _ = self.sender.send(Command::Cancel(id));
+    }
+}
+
+impl<J> Clone for SchedulerHandle<J> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            next_id: Arc::clone(&self.next_id),
+        }
+    }
+}
+
+impl<J> std::fmt::Debug for SchedulerHandle<J> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SchedulerHandle").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use futures::StreamExt;
+    use tokio::time::Instant;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{Cancellable, Scheduler};
+
+    #[tokio::test(start_paused = true)]
+    async fn should_fire_one_shot_jobs_in_order() {
+        // Arrange
+        let start = Instant::now();
+        let (handle, jobs) = Scheduler::new()
+            .spawn_stream(CancellationToken::new())
+            .await;
+
+        // Act
+        let last = handle.schedule_after(Duration::from_secs(3), "last");
+        let first = handle.schedule_at(start + Duration::from_secs(1), "first");
+        let cancelled = handle.schedule_after(Duration::from_secs(2), "cancelled");
+        handle.cancel_job(cancelled);
+        drop(handle);
+        let jobs = jobs.collect::<Vec<_>>().await;
+
+        // Assert
+        assert_eq!(vec![(first, "first"), (last, "last")], jobs);
+        assert_eq!(Duration::from_secs(3), start.elapsed());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn should_fire_recurring_job_until_cancelled() {
+        // Arrange
+        let start = Instant::now();
+        let (handle, mut jobs) = Scheduler::new()
+            .spawn_stream(CancellationToken::new())
+            .await;
+        let tick = handle.schedule_every(Duration::from_secs(10), "tick");
+
+        // Act
+        let mut fired = Vec::new();
+        for _ in 0..3 {
+            fired.push(jobs.next().await.unwrap());
+        }
+        handle.cancel_job(tick);
+        let once = handle.schedule_after(Duration::from_secs(15), "once");
+        let next = jobs.next().await;
+
+        // Assert
+        assert_eq!(vec![(tick, "tick"); 3], fired);
+        assert_eq!(Some((once, "once")), next);
+        assert_eq!(Duration::from_secs(45), start.elapsed());
+    }
+
+    #[cfg(feature = "cron")]
+    #[tokio::test(start_paused = true)]
+    async fn should_catch_up_missed_cron_fires() {
+        use std::sync::{
+            atomic::{AtomicU64, Ordering},
+            OnceLock,
+        };
+
+        use chrono::{DateTime, Utc};
+
+        use crate::{CatchUp, CronSchedule};
+
+        static START: OnceLock<Instant> = OnceLock::new();
+        static SUSPENDED: AtomicU64 = AtomicU64::new(0);
+
+        /// Wall clock starting at 00:00:30, which jumps by the time the
+        /// machine has been suspended for.
+        fn wall_clock() -> DateTime<Utc> {
+            let elapsed = START.get().unwrap().elapsed()
+                + Duration::from_secs(SUSPENDED.load(Ordering::SeqCst));
+            "2024-01-01T00:00:30Z".parse::<DateTime<Utc>>().unwrap() + elapsed
+        }
+
+        // Arrange
+        let start = *START.get_or_init(Instant::now);
+        let scheduler = Scheduler::new().with_wall_clock(wall_clock);
+        let (handle, mut jobs) = scheduler.spawn_stream(CancellationToken::new()).await;
+        let schedule = CronSchedule::parse("* * * * *")
+            .unwrap()
+            .catch_up(CatchUp::All);
+        let minutely = handle.schedule_cron(schedule, "minutely");
+        let end = handle.schedule_at(start + Duration::from_secs(100), "end");
+
+        // Act
+        let first = jobs.next().await;
+        let first_elapsed = start.elapsed();
+        SUSPENDED.store(330, Ordering::SeqCst);
+        let mut missed = Vec::new();
+        while let Some(job) = jobs.next().await {
+            missed.push(job);
+            if job == (end, "end") {
+                break;
+            }
+        }
+
+        // Assert
+        assert_eq!(Some((minutely, "minutely")), first);
+        assert_eq!(Duration::from_secs(30), first_elapsed);
+        let mut expected = vec![(minutely, "minutely"); 6];
+        expected.push((end, "end"));
+        assert_eq!(expected, missed);
+    }
+}
@@ -0,0 +1,258 @@
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+
+use crate::Cancellable;
+
+/// Scope of services spawned with [`scope`].
+///
+/// The scope can be cloned, e.g. to spawn services from other tasks, as long
+/// as the scope's future hasn't completed yet.
+#[derive(Clone)]
+pub struct Scope {
+    cancellation_token: CancellationToken,
+    generation: Arc<Mutex<CancellationToken>>,
+    joins: Arc<Mutex<JoinSet<()>>>,
+}
+
+impl Scope {
+    pub(crate) fn new(cancellation_token: CancellationToken) -> Self {
+        Self {
+            generation: Arc::new(Mutex::new(cancellation_token.child_token())),
+            cancellation_token,
+            joins: Arc::default(),
+        }
+    }
+
+    /// Spawns `service` within the scope.
+    ///
+    /// The service is cancelled as soon as the scope's future completes.
+    ///
+    /// # Returns
+    ///
+    /// The handle for communicating with the service.
+    pub async fn spawn<T>(&self, service: T) -> T::Handle
+    where
+        T: Cancellable + 'static,
+        T::Output: 'static,
+        T::Error: 'static,
+    {
+        let handle = service.spawn(self.child_token()).await;
+        let (join, inner) = handle.into_parts();
+        let mut joins = self.joins.lock().expect("lock not to be poisoned");
+        // The services which have completed already are forgotten, so a
+        // long-lived scope doesn't accumulate them.
+        while joins.try_join_next().is_some() {}
+        joins.spawn(async move {
+            let _ = join.await;
+        });
+
+        inner
+    }
+
+    /// Returns the token cancelled once the scope's future completes.
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.cancellation_token
+    }
+
+    /// Returns a token cancelled along with the services spawned within the
+    /// scope so far.
+    pub(crate) fn child_token(&self) -> CancellationToken {
+        self.generation
+            .lock()
+            .expect("lock not to be poisoned")
+            .child_token()
+    }
+
+    /// Cancels the services spawned within the scope so far, without closing
+    /// the scope, so the services spawned afterwards aren't cancelled.
+    pub(crate) fn cancel_spawned(&self) {
+        let next = self.cancellation_token.child_token();
+        let mut generation = self.generation.lock().expect("lock not to be poisoned");
+        std::mem::replace(&mut *generation, next).cancel();
+    }
+
+    /// Cancels and joins all services spawned within the scope.
+    pub(crate) async fn close(&self) {
+        self.cancellation_token.cancel();
+        let mut joins = std::mem::take(&mut *self.joins.lock().expect("lock not to be poisoned"));
+        while joins.join_next().await.is_some() {}
+    }
+}
+
+impl std::fmt::Debug for Scope {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Scope")
+            .field("cancellation_token", &self.cancellation_token)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Runs the future returned by `f` within a new [`Scope`].
+///
+/// Once the future completes, all services spawned within the scope are
+/// cancelled and joined before this function returns. If this function's
+/// future is dropped before it completes, e.g. because the higher-level
+/// operation has been aborted, then the services are still cancelled, but
+/// not joined.
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{Cancellable, CancellationResult};
+///
+/// struct Worker;
+///
+/// impl Cancellable for Worker {
+///     type Result = ();
+///     type Handle = ();
+///     type Error = std::io::Error;
+///     type Output = ();
+///
+///     async fn new_handle(&mut self) -> Self::Handle {}
+///
+///     async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+///         std::future::pending().await
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let result = cancellable::scope(|scope| async move {
+///     scope.spawn(Worker).await;
+///     scope.spawn(Worker).await;
+///     Err::<(), _>("bailing out early")
+/// })
+/// .await;
+///
+/// // Both workers have already been cancelled and joined.
+/// assert!(result.is_err());
+/// # }
+/// ```
+pub async fn scope<F, Fut>(f: F) -> Fut::Output
+where
+    F: FnOnce(Scope) -> Fut,
+    Fut: Future,
+{
+    let scope = Scope::new(CancellationToken::new());
+    // Cancels the services if the future is dropped before it completes.
+    let _guard = scope.cancellation_token.clone().drop_guard();
+
+    let output = f(scope.clone()).await;
+    scope.close().await;
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use tokio_util::sync::CancellationToken;
+
+    use super::Scope;
+    use crate::{Cancellable, CancellationResult};
+
+    struct StoppingCancellable {
+        stopped: Arc<AtomicUsize>,
+    }
+
+    impl Cancellable for StoppingCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            std::future::pending().await
+        }
+
+        async fn on_stop(&mut self) {
+            self.stopped.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn should_join_services_when_scope_completes() {
+        // Arrange
+        let stopped = Arc::new(AtomicUsize::new(0));
+
+        // Act
+        let output = crate::scope(|scope| {
+            let stopped = Arc::clone(&stopped);
+            async move {
+                for _ in 0..2 {
+                    let service = StoppingCancellable {
+                        stopped: Arc::clone(&stopped),
+                    };
+                    scope.spawn(service).await;
+                }
+                42
+            }
+        })
+        .await;
+
+        // Assert
+        assert_eq!(42, output);
+        assert_eq!(2, stopped.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn should_cancel_services_when_scope_is_dropped() {
+        // Arrange
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        let scope = crate::scope(|scope| async move {
+            sender.send(scope.cancellation_token().clone()).unwrap();
+            std::future::pending::<()>().await
+        });
+
+        // Act
+        let token = tokio::select! {
+            _ = scope => unreachable!(),
+            token = receiver => token.unwrap(),
+        };
+
+        // Assert
+        assert!(token.is_cancelled());
+    }
+
+    struct FinishedCancellable;
+
+    impl Cancellable for FinishedCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            Ok(CancellationResult::Break)
+        }
+    }
+
+    #[tokio::test]
+    async fn should_forget_services_which_have_completed() {
+        // Arrange
+        let scope = Scope::new(CancellationToken::new());
+        for _ in 0..10 {
+            scope.spawn(FinishedCancellable).await;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+
+        // Act
+        scope.spawn(FinishedCancellable).await;
+
+        // Assert
+        assert_eq!(1, scope.joins.lock().unwrap().len());
+    }
+}
@@ -0,0 +1,460 @@
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use tokio::sync::mpsc::{
+    self,
+    error::{SendError, SendTimeoutError, TrySendError},
+};
+
+/// Defines an interface for a handle that sends items to a service.
+///
+/// It's meant to be used as [`Cancellable::Handle`] of services which receive
+/// their work through a channel. See [`MpscSenderHandle`] and
+/// [`UnboundedSenderHandle`].
+///
+/// [`Cancellable::Handle`]: crate::Cancellable::Handle
+pub trait SenderHandle {
+    /// Type of items sent to the service.
+    type Item;
+
+    /// Sends `item` to the service.
+    ///
+    /// If the service's receiving side has been closed, then the item is
+    /// returned back in [`SendError`].
+    fn send(
+        &self,
+        item: Self::Item,
+    ) -> impl Future<Output = Result<(), SendError<Self::Item>>> + Send;
+
+    /// Attempts to send `item` to the service without waiting.
+    ///
+    /// If the service cannot accept the item immediately, or its receiving
+    /// side has been closed, then the item is returned back in
+    /// [`TrySendError`].
+    fn try_send(&self, item: Self::Item) -> Result<(), TrySendError<Self::Item>>;
+
+    /// Sends `item` to the service, waiting at most `timeout` for it to be
+    /// accepted.
+    ///
+    /// If the item isn't accepted within `timeout`, or the service's receiving
+    /// side has been closed, then the item is returned back in
+    /// [`SendTimeoutError`].
+    fn send_timeout(
+        &self,
+        item: Self::Item,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<(), SendTimeoutError<Self::Item>>> + Send;
+
+    /// Signals the end of input to the service.
+    ///
+    /// It closes the sending side of the channel for the handle and all its
+    /// clones, so once the service has received the items sent so far, it sees
+    /// the channel closed and can complete on its own, e.g. with
+    /// [`CancellationResult::Break`]. Unlike cancellation, it doesn't
+    /// interrupt the service. Sending fails afterwards.
+    ///
+    /// [`CancellationResult::Break`]: crate::CancellationResult::Break
+    fn close(&self);
+
+    /// Checks if the handle has been closed with [`Self::close`].
+    fn is_closed(&self) -> bool;
+}
+
+/// Sender shared by a handle and its clones, so that closing one of them
+/// closes them all.
+///
+/// The channel sees the closure once the in-flight sends, which hold clones of
+/// the sender, have completed.
+#[derive(Debug)]
+pub(crate) struct SharedSender<S>(Arc<Mutex<Option<S>>>);
+
+impl<S> SharedSender<S>
+where
+    S: Clone,
+{
+    pub(crate) fn new(sender: S) -> Self {
+        Self(Arc::new(Mutex::new(Some(sender))))
+    }
+
+    /// Returns a clone of the sender, unless it has been closed.
+    pub(crate) fn get(&self) -> Option<S> {
+        self.0.lock().expect("lock not to be poisoned").clone()
+    }
+
+    /// Drops the sender.
+    pub(crate) fn close(&self) {
+        self.0.lock().expect("lock not to be poisoned").take();
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.0.lock().expect("lock not to be poisoned").is_none()
+    }
+}
+
+impl<S> Clone for SharedSender<S> {
+    fn clone(&self) -> Self {
+        Self(Arc::clone(&self.0))
+    }
+}
+
+/// Handle sending items to a service through a bounded channel.
+///
+/// Sending waits until there's capacity in the channel, which gives the
+/// producers backpressure when the service is overwhelmed.
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{
+///     Cancellable, CancellationResult, CancellationToken, MpscSenderHandle, SenderHandle,
+/// };
+/// use tokio::sync::mpsc::Receiver;
+///
+/// struct Doubler {
+///     receiver: Receiver<i32>,
+///     handle: Option<MpscSenderHandle<i32>>,
+/// }
+///
+/// impl Cancellable for Doubler {
+///     type Result = i32;
+///     type Handle = MpscSenderHandle<i32>;
+///     type Error = std::io::Error;
+///     type Output = ();
+///
+///     async fn new_handle(&mut self) -> Self::Handle {
+///         self.handle.take().expect("handle to be present")
+///     }
+///
+///     async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+///         match self.receiver.recv().await {
+///             Some(number) => Ok(CancellationResult::item(number * 2)),
+///             None => Ok(CancellationResult::Break),
+///         }
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let (handle, receiver) = MpscSenderHandle::channel(16);
+/// let service = Doubler {
+///     receiver,
+///     handle: Some(handle),
+/// };
+///
+/// let handle = service.spawn(CancellationToken::new()).await;
+/// handle.send(21).await.unwrap();
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct MpscSenderHandle<T> {
+    inner: SharedSender<mpsc::Sender<T>>,
+}
+
+impl<T> MpscSenderHandle<T> {
+    /// Constructs a new handle wrapping `sender`.
+    ///
+    /// Closing the handle drops `sender`, so the service sees the channel
+    /// closed only if there are no other senders of the channel.
+    pub fn new(sender: mpsc::Sender<T>) -> Self {
+        Self {
+            inner: SharedSender::new(sender),
+        }
+    }
+
+    /// Creates a bounded channel with the given `capacity`.
+    ///
+    /// The receiving side is meant to be owned by the service, while the
+    /// handle is returned by [`Cancellable::new_handle`].
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `capacity` is zero.
+    ///
+    /// [`Cancellable::new_handle`]: crate::Cancellable::new_handle
+    pub fn channel(capacity: usize) -> (Self, mpsc::Receiver<T>) {
+        let (sender, receiver) = mpsc::channel(capacity);
+        (Self::new(sender), receiver)
+    }
+}
+
+impl<T> Clone for MpscSenderHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> SenderHandle for MpscSenderHandle<T>
+where
+    T: Send,
+{
+    type Item = T;
+
+    fn send(
+        &self,
+        item: Self::Item,
+    ) -> impl Future<Output = Result<(), SendError<Self::Item>>> + Send {
+        let sender = self.inner.get();
+        async move {
+            match sender {
+                Some(sender) => sender.send(item).await,
+                None => Err(SendError(item)),
+            }
+        }
+    }
+
+    fn try_send(&self, item: Self::Item) -> Result<(), TrySendError<Self::Item>> {
+        match self.inner.get() {
+            Some(sender) => sender.try_send(item),
+            None => Err(TrySendError::Closed(item)),
+        }
+    }
+
+    fn send_timeout(
+        &self,
+        item: Self::Item,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<(), SendTimeoutError<Self::Item>>> + Send {
+        let sender = self.inner.get();
+        async move {
+            match sender {
+                Some(sender) => sender.send_timeout(item, timeout).await,
+                None => Err(SendTimeoutError::Closed(item)),
+            }
+        }
+    }
+
+    fn close(&self) {
+        self.inner.close();
+    }
+
+    fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+}
+
+/// Handle sending items to a service through an unbounded channel.
+///
+/// Sending never waits, so the service's queue can grow without bounds. Hence
+/// [`SenderHandle::try_send`] and [`SenderHandle::send_timeout`] fail only if
+/// the channel has been closed. See [`MpscSenderHandle`] for a handle with
+/// backpressure.
+#[derive(Debug)]
+pub struct UnboundedSenderHandle<T> {
+    inner: SharedSender<mpsc::UnboundedSender<T>>,
+}
+
+impl<T> UnboundedSenderHandle<T> {
+    /// Constructs a new handle wrapping `sender`.
+    ///
+    /// See [`MpscSenderHandle::new`].
+    pub fn new(sender: mpsc::UnboundedSender<T>) -> Self {
+        Self {
+            inner: SharedSender::new(sender),
+        }
+    }
+
+    fn send_now(&self, item: T) -> Result<(), SendError<T>> {
+        match self.inner.get() {
+            Some(sender) => sender.send(item),
+            None => Err(SendError(item)),
+        }
+    }
+
+    /// Creates an unbounded channel.
+    ///
+    /// The receiving side is meant to be owned by the service, while the
+    /// handle is returned by [`Cancellable::new_handle`].
+    ///
+    /// [`Cancellable::new_handle`]: crate::Cancellable::new_handle
+    pub fn channel() -> (Self, mpsc::UnboundedReceiver<T>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (Self::new(sender), receiver)
+    }
+}
+
+impl<T> Clone for UnboundedSenderHandle<T> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+impl<T> SenderHandle for UnboundedSenderHandle<T>
+where
+    T: Send,
+{
+    type Item = T;
+
+    fn send(
+        &self,
+        item: Self::Item,
+    ) -> impl Future<Output = Result<(), SendError<Self::Item>>> + Send {
+        let result = self.send_now(item);
+        async { result }
+    }
+
+    fn try_send(&self, item: Self::Item) -> Result<(), TrySendError<Self::Item>> {
+        self.send_now(item)
+            .map_err(|SendError(item)| TrySendError::Closed(item))
+    }
+
+    fn send_timeout(
+        &self,
+        item: Self::Item,
+        timeout: Duration,
+    ) -> impl Future<Output = Result<(), SendTimeoutError<Self::Item>>> + Send {
+        let _ = timeout;
+        let result = self
+            .send_now(item)
+            .map_err(|SendError(item)| SendTimeoutError::Closed(item));
+        async { result }
+    }
+
+    fn close(&self) {
+        self.inner.close();
+    }
+
+    fn is_closed(&self) -> bool {
+        self.inner.is_closed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::sync::mpsc::error::{SendTimeoutError, TrySendError};
+
+    use crate::{MpscSenderHandle, SenderHandle, UnboundedSenderHandle};
+
+    #[tokio::test]
+    async fn should_send_through_bounded_channel() {
+        // Arrange
+        let (handle, mut receiver) = MpscSenderHandle::channel(1);
+
+        // Act
+        let result = handle.send(42).await;
+
+        // Assert
+        assert!(result.is_ok());
+        assert_eq!(Some(42), receiver.recv().await);
+    }
+
+    #[tokio::test]
+    async fn should_return_item_when_bounded_channel_is_closed() {
+        // Arrange
+        let (handle, receiver) = MpscSenderHandle::channel(1);
+        drop(receiver);
+
+        // Act
+        let result = handle.send(42).await;
+
+        // Assert
+        assert_eq!(42, result.unwrap_err().0);
+    }
+
+    #[tokio::test]
+    async fn should_send_through_unbounded_channel() {
+        // Arrange
+        let (handle, mut receiver) = UnboundedSenderHandle::channel();
+
+        // Act
+        let result = handle.clone().send(42).await;
+
+        // Assert
+        assert!(result.is_ok());
+        assert_eq!(Some(42), receiver.recv().await);
+    }
+
+    #[tokio::test]
+    async fn should_return_item_when_unbounded_channel_is_closed() {
+        // Arrange
+        let (handle, receiver) = UnboundedSenderHandle::channel();
+        drop(receiver);
+
+        // Act
+        let result = handle.send(42).await;
+
+        // Assert
+        assert_eq!(42, result.unwrap_err().0);
+    }
+
+    #[tokio::test]
+    async fn should_return_item_when_bounded_channel_is_full() {
+        // Arrange
+        let (handle, _receiver) = MpscSenderHandle::channel(1);
+        handle.try_send(13).unwrap();
+
+        // Act
+        let result = handle.try_send(42);
+
+        // Assert
+        assert!(matches!(result, Err(TrySendError::Full(42))));
+    }
+
+    #[tokio::test]
+    async fn should_time_out_when_bounded_channel_stays_full() {
+        // Arrange
+        let (handle, _receiver) = MpscSenderHandle::channel(1);
+        handle.try_send(13).unwrap();
+
+        // Act
+        let result = handle.send_timeout(42, Duration::from_millis(10)).await;
+
+        // Assert
+        assert!(matches!(result, Err(SendTimeoutError::Timeout(42))));
+    }
+
+    #[tokio::test]
+    async fn should_fail_with_closed_when_unbounded_channel_is_closed() {
+        // Arrange
+        let (handle, receiver) = UnboundedSenderHandle::channel();
+        drop(receiver);
+
+        // Act
+        let try_result = handle.try_send(13);
+        let timeout_result = handle.send_timeout(42, Duration::from_millis(10)).await;
+
+        // Assert
+        assert!(matches!(try_result, Err(TrySendError::Closed(13))));
+        assert!(matches!(timeout_result, Err(SendTimeoutError::Closed(42))));
+    }
+
+    #[tokio::test]
+    async fn should_close_channel_for_all_clones() {
+        // Arrange
+        let (handle, mut receiver) = MpscSenderHandle::channel(2);
+        let clone = handle.clone();
+        handle.send(42).await.unwrap();
+
+        // Act
+        clone.close();
+
+        // Assert
+        assert!(handle.is_closed());
+        assert!(matches!(handle.try_send(13), Err(TrySendError::Closed(13))));
+        assert_eq!(Some(42), receiver.recv().await);
+        assert_eq!(None, receiver.recv().await);
+    }
+
+    #[tokio::test]
+    async fn should_close_unbounded_channel() {
+        // Arrange
+        let (handle, mut receiver) = UnboundedSenderHandle::channel();
+        handle.send(42).await.unwrap();
+
+        // Act
+        handle.clone().close();
+
+        // Assert
+        assert_eq!(13, handle.send(13).await.unwrap_err().0);
+        assert_eq!(Some(42), receiver.recv().await);
+        assert_eq!(None, receiver.recv().await);
+    }
+}
@@ -0,0 +1,153 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use futures_sink::Sink;
+use tokio::sync::mpsc::error::SendError;
+
+use crate::SenderHandle;
+
+type PendingSend<T> = Pin<Box<dyn Future<Output = Result<(), SendError<T>>> + Send>>;
+
+/// Adapter implementing [`Sink`] for a [`SenderHandle`].
+///
+/// It allows to feed a service with the combinators of the `futures` crate,
+/// e.g. `SinkExt::send_all` or `StreamExt::forward`. Only a single item is
+/// sent at a time, so the sink is ready for the next item once the previous
+/// one has been accepted by the service.
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{MpscSenderHandle, SenderSink};
+/// use futures::{SinkExt, StreamExt};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let (handle, mut receiver) = MpscSenderHandle::channel(16);
+/// let mut sink = SenderSink::new(handle);
+///
+/// let mut items = futures::stream::iter([1, 2, 3]).map(Ok);
+/// sink.send_all(&mut items).await.unwrap();
+///
+/// assert_eq!(Some(1), receiver.recv().await);
+/// # }
+/// ```
+pub struct SenderSink<H>
+where
+    H: SenderHandle,
+{
+    handle: H,
+    pending: Option<PendingSend<H::Item>>,
+}
+
+impl<H> SenderSink<H>
+where
+    H: SenderHandle,
+{
+    /// Constructs a new sink sending items through `handle`.
+    pub fn new(handle: H) -> Self {
+        Self {
+            handle,
+            pending: None,
+        }
+    }
+
+    /// Returns the wrapped handle.
+    ///
+    /// The item currently being sent, if there's any, is dropped.
+    pub fn into_inner(self) -> H {
+        self.handle
+    }
+
+    fn poll_pending(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), SendError<H::Item>>> {
+        let Some(pending) = self.pending.as_mut() else {
+            return Poll::Ready(Ok(()));
+        };
+
+        let result = std::task::ready!(pending.as_mut().poll(cx));
+        self.pending = None;
+        Poll::Ready(result)
+    }
+}
+
+impl<H> Sink<H::Item> for SenderSink<H>
+where
+    H: SenderHandle + Clone + Send + Sync + Unpin + 'static,
+    H::Item: Send + 'static,
+{
+    type Error = SendError<H::Item>;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut().poll_pending(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: H::Item) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        let handle = this.handle.clone();
+        this.pending = Some(Box::pin(async move { handle.send(item).await }));
+
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut().poll_pending(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut().poll_pending(cx)
+    }
+}
+
+impl<H> std::fmt::Debug for SenderSink<H>
+where
+    H: SenderHandle + std::fmt::Debug,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SenderSink")
+            .field("handle", &self.handle)
+            .field("pending", &self.pending.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{SinkExt, StreamExt};
+
+    use crate::{MpscSenderHandle, SenderSink};
+
+    #[tokio::test]
+    async fn should_forward_stream_into_handle() {
+        // Arrange
+        let (handle, mut receiver) = MpscSenderHandle::channel(1);
+        let sink = SenderSink::new(handle);
+
+        // Act
+        let forward = tokio::spawn(futures::stream::iter([1, 2, 3]).map(Ok).forward(sink));
+        let mut items = Vec::new();
+        while let Some(item) = receiver.recv().await {
+            items.push(item);
+        }
+
+        // Assert
+        assert!(forward.await.unwrap().is_ok());
+        assert_eq!(vec![1, 2, 3], items);
+    }
+
+    #[tokio::test]
+    async fn should_fail_when_receiver_is_dropped() {
+        // Arrange
+        let (handle, receiver) = MpscSenderHandle::channel(1);
+        let mut sink = SenderSink::new(handle);
+
+        // Act
+        drop(receiver);
+        let result = sink.send(42).await;
+
+        // Assert
+        assert_eq!(42, result.unwrap_err().0);
+    }
+}
@@ -0,0 +1,336 @@
+use std::{error::Error, future::Future, pin::Pin, sync::Arc, time::Duration};
+
+use tokio::task::{AbortHandle, JoinSet};
+use tokio_util::sync::CancellationToken;
+
+use crate::{clock::SharedClock, Cancellable, CancellableError, CancellableHandle, Clock};
+
+/// Type-erased error of a service that hasn't completed successfully.
+pub type BoxError = Box<dyn Error + Send + 'static>;
+
+type MemberJoin = Pin<Box<dyn Future<Output = Result<(), BoxError>> + Send>>;
+
+/// Service added to a [`ServiceGroup`].
+struct Member {
+    name: String,
+    cancellation_token: CancellationToken,
+    abort_handle: AbortHandle,
+    join: MemberJoin,
+}
+
+/// Outcome of joining all services of a [`ServiceGroup`].
+#[derive(Debug, Default)]
+pub struct GroupReport {
+    failures: Vec<(String, BoxError)>,
+    timed_out: Vec<String>,
+}
+
+impl GroupReport {
+    /// Returns the names and errors of the services which have failed.
+    pub fn failures(&self) -> &[(String, BoxError)] {
+        &self.failures
+    }
+
+    /// Returns the names of the services which haven't completed before the
+    /// deadline, and so have been aborted.
+    pub fn timed_out(&self) -> &[String] {
+        &self.timed_out
+    }
+
+    /// Checks if all services have completed successfully before the deadline.
+    pub fn is_ok(&self) -> bool {
+        self.failures.is_empty() && self.timed_out.is_empty()
+    }
+}
+
+/// Group of services of different types, shut down together.
+///
+/// Services are tracked by their names and their errors are type-erased, so
+/// a single group can hold any services. See [`ShutdownCoordinator`] for
+/// shutting down groups of services in order.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use cancellable::{
+///     Cancellable, CancellationResult, CancellationToken, ServiceGroup,
+/// };
+///
+/// struct Worker;
+///
+/// impl Cancellable for Worker {
+///     type Result = ();
+///     type Handle = ();
+///     type Error = std::io::Error;
+///     type Output = ();
+///
+///     async fn new_handle(&mut self) -> Self::Handle {}
+///
+///     async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+///         std::future::pending().await
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let token = CancellationToken::new();
+/// let mut group = ServiceGroup::new();
+/// group.add("worker", Worker.spawn(token.clone()).await);
+///
+/// group.cancel_all();
+/// let report = group.join_all(Duration::from_secs(5)).await;
+/// assert!(report.is_ok());
+/// # }
+/// ```
+///
+/// [`ShutdownCoordinator`]: crate::ShutdownCoordinator
+#[derive(Default)]
+pub struct ServiceGroup {
+    members: Vec<Member>,
+    clock: SharedClock,
+}
+
+impl ServiceGroup {
+    /// Constructs a new, empty group.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the clock measuring the timeout of [`Self::join_all`].
+    ///
+    /// Defaults to [`TokioClock`].
+    ///
+    /// [`TokioClock`]: crate::TokioClock
+    pub fn clock(&mut self, clock: Arc<dyn Clock>) -> &mut Self {
+        self.clock = SharedClock(clock);
+        self
+    }
+
+    /// Adds the service of `handle` to the group under the given name.
+    ///
+    /// The service's inner handle is dropped.
+    pub fn add<T, H>(
+        &mut self,
+        name: impl Into<String>,
+        handle: CancellableHandle<T, H>,
+    ) -> &mut Self
+    where
+        T: Cancellable + 'static,
+        T::Output: 'static,
+        T::Error: 'static,
+    {
+        let (join_handle, cancellation_token, _) = handle.into_raw_parts();
+        let abort_handle = join_handle.abort_handle();
+        let join = Box::pin(async move {
+            match join_handle.await {
+                Ok(Ok(_)) => Ok(()),
+                Ok(Err(e)) => Err(Box::new(CancellableError::Service(e)) as BoxError),
+                Err(e) => Err(
+                    Box::new(CancellableError::<<T as Cancellable>::Error>::from(e)) as BoxError,
+                ),
+            }
+        });
+
+        self.members.push(Member {
+            name: name.into(),
+            cancellation_token,
+            abort_handle,
+            join,
+        });
+
+        self
+    }
+
+    /// Cancels the service with the given name.
+    ///
+    /// # Returns
+    ///
+    /// `true` if the group has a service with the given name.
+    pub fn cancel(&self, name: &str) -> bool {
+        let mut found = false;
+        for member in self.members.iter().filter(|member| member.name == name) {
+            member.cancellation_token.cancel();
+            found = true;
+        }
+
+        found
+    }
+
+    /// Cancels all services of the group.
+    pub fn cancel_all(&self) {
+        for member in &self.members {
+            member.cancellation_token.cancel();
+        }
+    }
+
+    /// Waits for all services of the group to complete.
+    ///
+    /// The services which haven't completed within `timeout` are aborted.
+    /// This method doesn't cancel the services, see [`Self::cancel_all`].
+    pub async fn join_all(self, timeout: Duration) -> GroupReport {
+        let mut join_set = JoinSet::new();
+        let mut pending = Vec::with_capacity(self.members.len());
+        for member in self.members {
+            let name = member.name;
+            let join = member.join;
+            pending.push((name.clone(), member.abort_handle));
+            join_set.spawn(async move { (name, join.await) });
+        }
+
+        let mut report = GroupReport::default();
+        let mut completed = Vec::with_capacity(pending.len());
+        let join_all = async {
+            while let Some(result) = join_set.join_next().await {
+                let Ok((name, result)) = result else {
+                    continue;
+                };
+                if let Err(e) = result {
+                    report.failures.push((name.clone(), e));
+                }
+                completed.push(name);
+            }
+        };
+
+        if self.clock.timeout(timeout, join_all).await.is_none() {
+            for (name, abort_handle) in pending {
+                if !completed.contains(&name) {
+                    abort_handle.abort();
+                    report.timed_out.push(name);
+                }
+            }
+        }
+
+        report
+    }
+
+    /// Returns the number of services in the group.
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Checks if the group has no services.
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+}
+
+impl std::fmt::Debug for ServiceGroup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let members: Vec<_> = self.members.iter().map(|member| &member.name).collect();
+        f.debug_struct("ServiceGroup")
+            .field("members", &members)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, time::Duration};
+
+    use tokio::time::timeout;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{clock::InstantClock, Cancellable, CancellationResult, ServiceGroup};
+
+    struct PendingCancellable {}
+
+    impl Cancellable for PendingCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            std::future::pending().await
+        }
+    }
+
+    struct FailingCancellable {}
+
+    impl Cancellable for FailingCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = std::io::Error;
+        type Output = i32;
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<(), i32>, Self::Error> {
+            Err(std::io::Error::other("FailingCancellable error"))
+        }
+    }
+
+    #[tokio::test]
+    async fn should_report_failed_services() {
+        // Arrange
+        let token = CancellationToken::new();
+        let mut group = ServiceGroup::new();
+        group
+            .add("pending", PendingCancellable {}.spawn(token.clone()).await)
+            .add("failing", FailingCancellable {}.spawn(token.clone()).await);
+
+        // Act
+        group.cancel("pending");
+        let report = group.join_all(Duration::from_secs(1)).await;
+
+        // Assert
+        assert_eq!(1, report.failures().len());
+        assert_eq!("failing", report.failures()[0].0);
+        assert!(report.timed_out().is_empty());
+    }
+
+    #[tokio::test]
+    async fn should_abort_services_after_deadline() {
+        // Arrange
+        let token = CancellationToken::new();
+        let mut group = ServiceGroup::new();
+        group.add("pending", PendingCancellable {}.spawn(token.clone()).await);
+
+        // Act
+        let report = group.join_all(Duration::from_millis(50)).await;
+
+        // Assert
+        assert_eq!(["pending"], report.timed_out());
+        assert!(!report.is_ok());
+    }
+
+    #[tokio::test]
+    async fn should_measure_deadline_with_group_clock() {
+        // Arrange
+        let token = CancellationToken::new();
+        let mut group = ServiceGroup::new();
+        group
+            .clock(Arc::new(InstantClock))
+            .add("pending", PendingCancellable {}.spawn(token.clone()).await);
+
+        // Act
+        let report = timeout(
+            Duration::from_secs(1),
+            group.join_all(Duration::from_secs(3600)),
+        )
+        .await;
+
+        // Assert
+        assert_eq!(["pending"], report.unwrap().timed_out());
+    }
+
+    #[tokio::test]
+    async fn should_cancel_service_by_name() {
+        // Arrange
+        let token = CancellationToken::new();
+        let mut group = ServiceGroup::new();
+        group.add("pending", PendingCancellable {}.spawn(token.clone()).await);
+
+        // Act
+        let found = group.cancel("pending");
+        let report = group.join_all(Duration::from_secs(1)).await;
+
+        // Assert
+        assert!(found);
+        assert!(report.is_ok());
+    }
+}
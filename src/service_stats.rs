@@ -0,0 +1,220 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tokio::time::Instant;
+
+/// Slot for the statistics of a service, shared by its handle and its work
+/// loop.
+pub(crate) type StatsSlot = Arc<StatsCounters>;
+
+/// Counters of a service, maintained by its work loop.
+///
+/// They're only ever read together as a snapshot, so relaxed ordering is
+/// enough.
+#[derive(Debug)]
+pub(crate) struct StatsCounters {
+    started: Instant,
+    iterations: AtomicU64,
+    items: AtomicU64,
+    tolerated_errors: AtomicU64,
+    restarts: AtomicU64,
+    run_nanos: AtomicU64,
+    callback_nanos: AtomicU64,
+    /// Nanoseconds since `started`, shifted by one, so zero means no activity.
+    last_activity: AtomicU64,
+}
+
+impl StatsCounters {
+    pub(crate) fn new(started: Instant) -> Self {
+        Self {
+            started,
+            iterations: AtomicU64::new(0),
+            items: AtomicU64::new(0),
+            tolerated_errors: AtomicU64::new(0),
+            restarts: AtomicU64::new(0),
+            run_nanos: AtomicU64::new(0),
+            callback_nanos: AtomicU64::new(0),
+            last_activity: AtomicU64::new(0),
+        }
+    }
+
+    /// Records a call to [`Cancellable::run`], which has completed at `now`.
+    ///
+    /// [`Cancellable::run`]: crate::Cancellable::run
+    pub(crate) fn record_iteration(&self, run_time: Duration, now: Instant) {
+        self.iterations.fetch_add(1, Ordering::Relaxed);
+        add_duration(&self.run_nanos, run_time);
+        self.record_activity(now);
+    }
+
+    /// Records a value passed to the callback, which has returned at `now`.
+    pub(crate) fn record_item(&self, callback_time: Duration, now: Instant) {
+        self.items.fetch_add(1, Ordering::Relaxed);
+        add_duration(&self.callback_nanos, callback_time);
+        self.record_activity(now);
+    }
+
+    /// Records an error the service has recovered from without a restart.
+    pub(crate) fn record_tolerated_error(&self) {
+        self.tolerated_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records a restart of the service.
+    pub(crate) fn record_restart(&self) {
+        self.restarts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Records the service's activity at `now`.
+    pub(crate) fn record_activity(&self, now: Instant) {
+        let since_started = now.saturating_duration_since(self.started);
+        let nanos = u64::try_from(since_started.as_nanos()).unwrap_or(u64::MAX - 1);
+        self.last_activity.fetch_max(nanos + 1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> ServiceStats {
+        let last_activity = match self.last_activity.load(Ordering::Relaxed) {
+            0 => None,
+            nanos => Some(self.started + Duration::from_nanos(nanos - 1)),
+        };
+
+        ServiceStats {
+            iterations: self.iterations.load(Ordering::Relaxed),
+            items: self.items.load(Ordering::Relaxed),
+            tolerated_errors: self.tolerated_errors.load(Ordering::Relaxed),
+            restarts: self.restarts.load(Ordering::Relaxed),
+            run_time: Duration::from_nanos(self.run_nanos.load(Ordering::Relaxed)),
+            callback_time: Duration::from_nanos(self.callback_nanos.load(Ordering::Relaxed)),
+            last_activity,
+        }
+    }
+}
+
+impl Default for StatsCounters {
+    fn default() -> Self {
+        Self::new(Instant::now())
+    }
+}
+
+fn add_duration(nanos: &AtomicU64, duration: Duration) {
+    let duration = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+    nanos.fetch_add(duration, Ordering::Relaxed);
+}
+
+/// Snapshot of the counters of a service, returned by
+/// [`CancellableHandle::stats`].
+///
+/// The times are measured with the service's clock, see
+/// [`SpawnOptions::clock`], so they include the time the service has spent
+/// waiting inside its iterations, e.g. for the network.
+///
+/// [`CancellableHandle::stats`]: crate::CancellableHandle::stats
+/// [`SpawnOptions::clock`]: crate::SpawnOptions::clock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ServiceStats {
+    iterations: u64,
+    items: u64,
+    tolerated_errors: u64,
+    restarts: u64,
+    run_time: Duration,
+    callback_time: Duration,
+    last_activity: Option<Instant>,
+}
+
+impl ServiceStats {
+    /// Returns the number of calls to [`Cancellable::run`].
+    ///
+    /// [`Cancellable::run`]: crate::Cancellable::run
+    pub fn iterations(&self) -> u64 {
+        self.iterations
+    }
+
+    /// Returns the number of values passed to the callback.
+    pub fn items(&self) -> u64 {
+        self.items
+    }
+
+    /// Returns the number of errors the service has recovered from without a
+    /// restart, see [`Cancellable::on_error`].
+    ///
+    /// [`Cancellable::on_error`]: crate::Cancellable::on_error
+    pub fn tolerated_errors(&self) -> u64 {
+        self.tolerated_errors
+    }
+
+    /// Returns the number of times the service has been restarted, see
+    /// [`SpawnOptions::restart_policy`].
+    ///
+    /// [`SpawnOptions::restart_policy`]: crate::SpawnOptions::restart_policy
+    pub fn restarts(&self) -> u64 {
+        self.restarts
+    }
+
+    /// Returns the time spent inside [`Cancellable::run`], excluding the
+    /// callback.
+    ///
+    /// [`Cancellable::run`]: crate::Cancellable::run
+    pub fn run_time(&self) -> Duration {
+        self.run_time
+    }
+
+    /// Returns the time spent in the callback.
+    pub fn callback_time(&self) -> Duration {
+        self.callback_time
+    }
+
+    /// Returns the time spent both inside [`Cancellable::run`] and in the
+    /// callback.
+    ///
+    /// [`Cancellable::run`]: crate::Cancellable::run
+    pub fn busy_time(&self) -> Duration {
+        self.run_time + self.callback_time
+    }
+
+    /// Returns the time of the service's latest activity, i.e. a completed
+    /// iteration, a value passed to the callback, or a call to
+    /// [`RunContext::keep_alive`], or `None` if there hasn't been any.
+    ///
+    /// [`RunContext::keep_alive`]: crate::RunContext::keep_alive
+    pub fn last_activity(&self) -> Option<Instant> {
+        self.last_activity
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::Instant;
+
+    use super::StatsCounters;
+
+    #[test]
+    fn should_snapshot_recorded_counters() {
+        // Arrange
+        let started = Instant::now();
+        let counters = StatsCounters::new(started);
+
+        // Act
+        counters.record_iteration(Duration::from_millis(3), started + Duration::from_secs(2));
+        counters.record_item(Duration::from_millis(1), started + Duration::from_secs(1));
+        counters.record_tolerated_error();
+        counters.record_restart();
+        let stats = counters.snapshot();
+
+        // Assert
+        assert_eq!(1, stats.iterations());
+        assert_eq!(1, stats.items());
+        assert_eq!(1, stats.tolerated_errors());
+        assert_eq!(1, stats.restarts());
+        assert_eq!(Duration::from_millis(4), stats.busy_time());
+        assert_eq!(
+            Some(started + Duration::from_secs(2)),
+            stats.last_activity()
+        );
+    }
+}
@@ -0,0 +1,149 @@
+//! Cancellation driven by the operating system's shutdown signals.
+//!
+//! The signals are:
+//!
+//! * `SIGINT` and `SIGTERM` on Unix,
+//! * `CTRL_C`, `CTRL_BREAK`, `CTRL_CLOSE` and `CTRL_SHUTDOWN` console events
+//!   on Windows.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use cancellable::shutdown;
+//!
+//! # #[tokio::main]
+//! # async fn main() -> std::io::Result<()> {
+//! let cancellation_token = shutdown::from_signals()?;
+//!
+//! // Spawn services with `cancellation_token`...
+//!
+//! cancellation_token.cancelled().await;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::io;
+
+use tokio_util::sync::CancellationToken;
+
+/// Listeners of the shutdown signals.
+struct Signals {
+    #[cfg(unix)]
+    interrupt: tokio::signal::unix::Signal,
+    #[cfg(unix)]
+    terminate: tokio::signal::unix::Signal,
+    #[cfg(windows)]
+    ctrl_c: tokio::signal::windows::CtrlC,
+    #[cfg(windows)]
+    ctrl_break: tokio::signal::windows::CtrlBreak,
+    #[cfg(windows)]
+    ctrl_close: tokio::signal::windows::CtrlClose,
+    #[cfg(windows)]
+    ctrl_shutdown: tokio::signal::windows::CtrlShutdown,
+}
+
+impl Signals {
+    #[cfg(unix)]
+    fn new() -> io::Result<Self> {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        Ok(Self {
+            interrupt: signal(SignalKind::interrupt())?,
+            terminate: signal(SignalKind::terminate())?,
+        })
+    }
+
+    #[cfg(windows)]
+    fn new() -> io::Result<Self> {
+        use tokio::signal::windows;
+
+        Ok(Self {
+            ctrl_c: windows::ctrl_c()?,
+            ctrl_break: windows::ctrl_break()?,
+            ctrl_close: windows::ctrl_close()?,
+            ctrl_shutdown: windows::ctrl_shutdown()?,
+        })
+    }
+
+    /// Waits for any of the signals.
+    #[cfg(unix)]
+    async fn recv(&mut self) {
+        tokio::select! {
+            _ = self.interrupt.recv() => {},
+            _ = self.terminate.recv() => {},
+        }
+    }
+
+    /// Waits for any of the signals.
+    #[cfg(windows)]
+    async fn recv(&mut self) {
+        tokio::select! {
+            _ = self.ctrl_c.recv() => {},
+            _ = self.ctrl_break.recv() => {},
+            _ = self.ctrl_close.recv() => {},
+            _ = self.ctrl_shutdown.recv() => {},
+        }
+    }
+}
+
+/// Cancels `cancellation_token` when any of the shutdown signals arrives.
+///
+/// The signal handlers are registered before this function returns, so no
+/// signal is missed. The listening task completes once the token has been
+/// cancelled, for whatever reason.
+///
+/// # Errors
+///
+/// This function fails if any of the signal handlers couldn't be registered.
+///
+/// # Panics
+///
+/// This function panics if called outside of a [`tokio`] runtime.
+pub fn cancel_on_signals(cancellation_token: CancellationToken) -> io::Result<()> {
+    let mut signals = Signals::new()?;
+
+    tokio::spawn(async move {
+        tokio::select! {
+            _ = cancellation_token.cancelled() => {},
+            _ = signals.recv() => cancellation_token.cancel(),
+        }
+    });
+
+    Ok(())
+}
+
+/// Constructs a new token, which is cancelled when any of the shutdown
+/// signals arrives.
+///
+/// See [`cancel_on_signals`].
+pub fn from_signals() -> io::Result<CancellationToken> {
+    let cancellation_token = CancellationToken::new();
+    cancel_on_signals(cancellation_token.clone())?;
+
+    Ok(cancellation_token)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::time::timeout;
+
+    #[tokio::test]
+    async fn should_cancel_token_on_sigterm() {
+        // Arrange
+        let cancellation_token = super::from_signals().unwrap();
+
+        // Act
+        let status = std::process::Command::new("kill")
+            .arg("-TERM")
+            .arg(std::process::id().to_string())
+            .status()
+            .unwrap();
+
+        // Assert
+        assert!(status.success());
+        let result = timeout(Duration::from_secs(1), cancellation_token.cancelled()).await;
+        assert!(result.is_ok());
+    }
+}
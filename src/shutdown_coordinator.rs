@@ -0,0 +1,364 @@
+use std::{future::Future, pin::Pin, sync::Arc, time::Duration};
+
+use tokio::task::AbortHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::{clock::SharedClock, Cancellable, CancellableHandle, Clock};
+
+type ServiceJoin = Pin<Box<dyn Future<Output = Result<(), String>> + Send>>;
+
+/// Service registered in a phase of a [`ShutdownCoordinator`].
+struct Registered {
+    cancellation_token: CancellationToken,
+    abort_handle: AbortHandle,
+    join: ServiceJoin,
+}
+
+/// Group of services shut down together.
+struct Phase {
+    name: String,
+    timeout: Option<Duration>,
+    services: Vec<Registered>,
+}
+
+/// Outcome of shutting down a single phase of a [`ShutdownCoordinator`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PhaseReport {
+    name: String,
+    timed_out: bool,
+    errors: Vec<String>,
+}
+
+impl PhaseReport {
+    /// Returns the name of the phase.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Checks if the phase's services haven't completed within the phase's
+    /// timeout, so the remaining ones have been aborted.
+    pub fn timed_out(&self) -> bool {
+        self.timed_out
+    }
+
+    /// Returns the errors of the phase's services which have failed.
+    pub fn errors(&self) -> &[String] {
+        &self.errors
+    }
+}
+
+/// Shuts down groups of interdependent services in order.
+///
+/// Services are registered into named phases. The phases are shut down in
+/// the order in which they have been declared: all services of a phase are
+/// cancelled and joined before the next phase is shut down. If the services
+/// of a phase don't complete within the phase's timeout, then the remaining
+/// ones are aborted.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use cancellable::{
+///     Cancellable, CancellationResult, CancellationToken, ShutdownCoordinator,
+/// };
+///
+/// struct Worker;
+///
+/// impl Cancellable for Worker {
+///     type Result = ();
+///     type Handle = ();
+///     type Error = std::io::Error;
+///     type Output = ();
+///
+///     async fn new_handle(&mut self) -> Self::Handle {}
+///
+///     async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+///         std::future::pending().await
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let mut coordinator = ShutdownCoordinator::new();
+/// coordinator.phase("frontends", Duration::from_secs(5));
+/// coordinator.phase("workers", Duration::from_secs(10));
+///
+/// let token = CancellationToken::new();
+/// coordinator.register("workers", Worker.spawn(token.clone()).await);
+/// coordinator.register("frontends", Worker.spawn(token.clone()).await);
+///
+/// let reports = coordinator.shutdown().await;
+/// assert!(reports.iter().all(|report| report.errors().is_empty()));
+/// # }
+/// ```
+#[derive(Default)]
+pub struct ShutdownCoordinator {
+    phases: Vec<Phase>,
+    clock: SharedClock,
+}
+
+impl ShutdownCoordinator {
+    /// Constructs a new coordinator without any phases.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the clock measuring the timeouts of the phases.
+    ///
+    /// Defaults to [`TokioClock`].
+    ///
+    /// [`TokioClock`]: crate::TokioClock
+    pub fn clock(&mut self, clock: Arc<dyn Clock>) -> &mut Self {
+        self.clock = SharedClock(clock);
+        self
+    }
+
+    /// Declares a phase with the given `name`, whose services must complete
+    /// within `timeout`.
+    ///
+    /// Phases are shut down in the order of their declaration. If the phase
+    /// has already been declared, then only its timeout is changed.
+    pub fn phase(&mut self, name: impl Into<String>, timeout: Duration) -> &mut Self {
+        let name = name.into();
+        match self.phases.iter_mut().find(|phase| phase.name == name) {
+            Some(phase) => phase.timeout = Some(timeout),
+            None => self.phases.push(Phase {
+                name,
+                timeout: Some(timeout),
+                services: Vec::new(),
+            }),
+        }
+
+        self
+    }
+
+    /// Registers the service of `handle` into the phase with the given name.
+    ///
+    /// If the phase hasn't been declared yet, then it's declared after all
+    /// other phases and its services can take any time to complete. The
+    /// service's inner handle is dropped.
+    pub fn register<T>(&mut self, phase: &str, handle: CancellableHandle<T>) -> &mut Self
+    where
+        T: Cancellable + 'static,
+        T::Output: 'static,
+        T::Error: 'static,
+    {
+        let (join_handle, cancellation_token, _) = handle.into_raw_parts();
+        let abort_handle = join_handle.abort_handle();
+        let join = Box::pin(async move {
+            match join_handle.await {
+                Ok(Ok(_)) => Ok(()),
+                Ok(Err(e)) => Err(e.to_string()),
+                Err(e) => Err(e.to_string()),
+            }
+        });
+
+        let index = match self.phases.iter().position(|p| p.name == phase) {
+            Some(index) => index,
+            None => {
+                self.phases.push(Phase {
+                    name: phase.to_owned(),
+                    timeout: None,
+                    services: Vec::new(),
+                });
+                self.phases.len() - 1
+            }
+        };
+        self.phases[index].services.push(Registered {
+            cancellation_token,
+            abort_handle,
+            join,
+        });
+
+        self
+    }
+
+    /// Shuts down all phases in order.
+    ///
+    /// # Returns
+    ///
+    /// Report of every phase, in the order in which they have been shut down.
+    pub async fn shutdown(self) -> Vec<PhaseReport> {
+        let mut reports = Vec::with_capacity(self.phases.len());
+        for phase in self.phases {
+            reports.push(Self::shutdown_phase(&self.clock, phase).await);
+        }
+
+        reports
+    }
+
+    async fn shutdown_phase(clock: &SharedClock, phase: Phase) -> PhaseReport {
+        for service in &phase.services {
+            service.cancellation_token.cancel();
+        }
+
+        let abort_handles: Vec<_> = phase
+            .services
+            .iter()
+            .map(|service| service.abort_handle.clone())
+            .collect();
+
+        let mut errors = Vec::new();
+        let join_all = async {
+            for service in phase.services {
+                if let Err(e) = service.join.await {
+                    errors.push(e);
+                }
+            }
+        };
+
+        let timed_out = match phase.timeout {
+            Some(timeout) => clock.timeout(timeout, join_all).await.is_none(),
+            None => {
+                join_all.await;
+                false
+            }
+        };
+        if timed_out {
+            abort_handles.iter().for_each(AbortHandle::abort);
+        }
+
+        PhaseReport {
+            name: phase.name,
+            timed_out,
+            errors,
+        }
+    }
+}
+
+impl std::fmt::Debug for ShutdownCoordinator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let phases: Vec<_> = self.phases.iter().map(|phase| &phase.name).collect();
+        f.debug_struct("ShutdownCoordinator")
+            .field("phases", &phases)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use tokio::time::timeout;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{clock::InstantClock, Cancellable, CancellationResult, ShutdownCoordinator};
+
+    struct RecordingCancellable {
+        name: &'static str,
+        log: Arc<Mutex<Vec<&'static str>>>,
+    }
+
+    impl Cancellable for RecordingCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            std::future::pending().await
+        }
+
+        async fn on_stop(&mut self) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            self.log.lock().unwrap().push(self.name);
+        }
+    }
+
+    struct StuckCancellable {}
+
+    impl Cancellable for StuckCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            std::future::pending().await
+        }
+
+        async fn on_stop(&mut self) {
+            std::future::pending().await
+        }
+    }
+
+    #[tokio::test]
+    async fn should_shut_down_phases_in_order() {
+        // Arrange
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let token = CancellationToken::new();
+        let mut coordinator = ShutdownCoordinator::new();
+        coordinator
+            .phase("frontends", Duration::from_secs(1))
+            .phase("storage", Duration::from_secs(1));
+
+        for name in ["storage", "frontends"] {
+            let service = RecordingCancellable {
+                name,
+                log: Arc::clone(&log),
+            };
+            coordinator.register(name, service.spawn(token.clone()).await);
+        }
+
+        // Act
+        let reports = coordinator.shutdown().await;
+
+        // Assert
+        assert_eq!(vec!["frontends", "storage"], *log.lock().unwrap());
+        assert_eq!(
+            vec!["frontends", "storage"],
+            reports.iter().map(|r| r.name()).collect::<Vec<_>>()
+        );
+    }
+
+    #[tokio::test]
+    async fn should_abort_phase_when_it_times_out() {
+        // Arrange
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let token = CancellationToken::new();
+        let mut coordinator = ShutdownCoordinator::new();
+        coordinator.phase("workers", Duration::from_millis(50));
+
+        let service = StuckCancellable {};
+        coordinator.register("workers", service.spawn(token.clone()).await);
+        let service = RecordingCancellable {
+            name: "storage",
+            log: Arc::clone(&log),
+        };
+        coordinator.register("storage", service.spawn(token.clone()).await);
+
+        // Act
+        let reports = coordinator.shutdown().await;
+
+        // Assert
+        assert!(reports[0].timed_out());
+        assert!(!reports[1].timed_out());
+        assert_eq!(vec!["storage"], *log.lock().unwrap());
+    }
+
+    #[tokio::test]
+    async fn should_measure_phase_timeout_with_coordinator_clock() {
+        // Arrange
+        let token = CancellationToken::new();
+        let mut coordinator = ShutdownCoordinator::new();
+        coordinator
+            .clock(Arc::new(InstantClock))
+            .phase("workers", Duration::from_secs(3600));
+        coordinator.register("workers", StuckCancellable {}.spawn(token.clone()).await);
+
+        // Act
+        let reports = timeout(Duration::from_secs(1), coordinator.shutdown()).await;
+
+        // Assert
+        assert!(reports.unwrap()[0].timed_out());
+    }
+}
@@ -0,0 +1,106 @@
+use std::future::Future;
+
+use crate::{Cancellable, CancellationResult};
+
+/// Defines an interface for a cancellable service without a handle.
+///
+/// It's a minimal counterpart of [`Cancellable`] for services which don't
+/// need to communicate with their spawners and don't need any of its hooks.
+/// Every `SimpleCancellable` implements [`Cancellable`] with `()` as its
+/// handle, so it's spawned in the same way.
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{Cancellable, CancellationResult, CancellationToken, SimpleCancellable};
+///
+/// struct Ticker;
+///
+/// impl SimpleCancellable for Ticker {
+///     type Result = ();
+///     type Error = std::io::Error;
+///     type Output = ();
+///
+///     async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+///         Ok(CancellationResult::Break)
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let handle = Ticker.spawn(CancellationToken::new()).await;
+/// # }
+/// ```
+pub trait SimpleCancellable: Send {
+    /// Type of values that _can_ be yielded by the service.
+    ///
+    /// See [`Cancellable::Result`].
+    type Result: Send;
+
+    /// Error returned by [`Self::run`] method.
+    ///
+    /// See [`Cancellable::Error`].
+    type Error: std::fmt::Debug + std::fmt::Display + Send;
+
+    /// Type of the final value the service _can_ complete with.
+    ///
+    /// See [`Cancellable::Output`].
+    type Output: std::fmt::Debug + Send;
+
+    /// Performs a single unit of work.
+    ///
+    /// See [`Cancellable::run`].
+    fn run(
+        &mut self,
+    ) -> impl Future<Output = Result<CancellationResult<Self::Result, Self::Output>, Self::Error>> + Send;
+}
+
+impl<T> Cancellable for T
+where
+    T: SimpleCancellable,
+{
+    type Result = <T as SimpleCancellable>::Result;
+    type Handle = ();
+    type Error = <T as SimpleCancellable>::Error;
+    type Output = <T as SimpleCancellable>::Output;
+
+    fn run(
+        &mut self,
+    ) -> impl Future<Output = Result<CancellationResult<Self::Result, Self::Output>, Self::Error>> + Send
+    {
+        SimpleCancellable::run(self)
+    }
+
+    async fn new_handle(&mut self) -> Self::Handle {}
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{Cancellable, CancellationResult, SimpleCancellable};
+
+    struct OnceCancellable {}
+
+    impl SimpleCancellable for OnceCancellable {
+        type Result = ();
+        type Error = anyhow::Error;
+        type Output = i32;
+
+        async fn run(&mut self) -> Result<CancellationResult<(), i32>, Self::Error> {
+            Ok(CancellationResult::BreakWith(42))
+        }
+    }
+
+    #[tokio::test]
+    async fn should_spawn_simple_service() {
+        // Arrange
+        let service = OnceCancellable {};
+
+        // Act
+        let handle = service.spawn(CancellationToken::new()).await;
+
+        // Assert
+        assert_eq!(Some(42), handle.join().await.unwrap());
+    }
+}
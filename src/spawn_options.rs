@@ -0,0 +1,403 @@
+use std::{sync::Arc, time::Duration};
+
+use tokio::runtime::Handle;
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    clock::SharedClock, observer::Observers, CallbackPanicPolicy, CancellableMetrics,
+    CancellableObserver, CancellationPriority, Clock, RestartPolicy, TimeBudget, Watchdog,
+};
+
+/// Options controlling the behavior of a spawned service.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use cancellable::SpawnOptions;
+///
+/// let options = SpawnOptions::new().drain_timeout(Duration::from_secs(5));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SpawnOptions {
+    pub(crate) graceful_shutdown: bool,
+    pub(crate) drain_timeout: Option<Duration>,
+    pub(crate) restart_policy: Option<RestartPolicy>,
+    pub(crate) iteration_timeout: Option<Duration>,
+    pub(crate) metrics: Option<Metrics>,
+    pub(crate) observers: Observers,
+    pub(crate) runtime: Option<Handle>,
+    pub(crate) cooperative_cancellation: bool,
+    pub(crate) cancellation_priority: CancellationPriority,
+    pub(crate) callback_panic: CallbackPanicPolicy,
+    pub(crate) watchdog: Option<Watchdog>,
+    pub(crate) rate_limit: Option<u32>,
+    pub(crate) soft_stop: Option<CancellationToken>,
+    pub(crate) clock: SharedClock,
+    pub(crate) retain_state: bool,
+    pub(crate) dead_letters: Option<usize>,
+    pub(crate) max_in_flight: Option<usize>,
+    pub(crate) time_budget: Option<TimeBudget>,
+    pub(crate) yield_every: Option<u64>,
+    pub(crate) idle_timeout: Option<Duration>,
+}
+
+/// Shared metrics hooks of a service.
+#[derive(Clone)]
+pub(crate) struct Metrics(pub(crate) Arc<dyn CancellableMetrics>);
+
+impl std::fmt::Debug for Metrics {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Metrics")
+    }
+}
+
+impl SpawnOptions {
+    /// Constructs a new `SpawnOptions` with the default values.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enables the graceful shutdown.
+    ///
+    /// When the service is cancelled, then [`Cancellable::drain`] will be
+    /// repetitively called until it returns `Ok(CancellationResult::Break)` or
+    /// an error. Values yielded while draining are passed to the callback.
+    ///
+    /// [`Cancellable::drain`]: crate::Cancellable::drain
+    pub fn graceful_shutdown(mut self) -> Self {
+        self.graceful_shutdown = true;
+        self
+    }
+
+    /// Enables the graceful shutdown and limits the time the service can spend
+    /// draining.
+    ///
+    /// If the service doesn't finish draining within `timeout`, then the
+    /// draining is aborted and the service completes.
+    pub fn drain_timeout(mut self, timeout: Duration) -> Self {
+        self.graceful_shutdown = true;
+        self.drain_timeout = Some(timeout);
+        self
+    }
+
+    /// Restarts the service according to `policy` when it fails, instead of
+    /// completing with the error.
+    ///
+    /// See [`Cancellable::restart`].
+    ///
+    /// [`Cancellable::restart`]: crate::Cancellable::restart
+    pub fn restart_policy(mut self, policy: RestartPolicy) -> Self {
+        self.restart_policy = Some(policy);
+        self
+    }
+
+    /// Limits the time a single call to [`Cancellable::run`] can take.
+    ///
+    /// If the call doesn't complete within `timeout`, then it's aborted and
+    /// [`Cancellable::on_timeout`] is called instead.
+    ///
+    /// [`Cancellable::run`]: crate::Cancellable::run
+    /// [`Cancellable::on_timeout`]: crate::Cancellable::on_timeout
+    pub fn iteration_timeout(mut self, timeout: Duration) -> Self {
+        self.iteration_timeout = Some(timeout);
+        self
+    }
+
+    /// Reports the metrics of the service to `metrics`.
+    ///
+    /// See [`CancellableMetrics`].
+    pub fn metrics(mut self, metrics: Arc<dyn CancellableMetrics>) -> Self {
+        self.metrics = Some(Metrics(metrics));
+        self
+    }
+
+    /// Reports the lifecycle events of the service to `observer`.
+    ///
+    /// It can be called multiple times to attach several observers, which are
+    /// notified in the order they have been attached. See
+    /// [`CancellableObserver`].
+    pub fn observer(mut self, observer: Arc<dyn CancellableObserver>) -> Self {
+        self.observers.push(observer);
+        self
+    }
+
+    /// Spawns the service onto the runtime of `handle`, instead of the
+    /// runtime the service is spawned from.
+    ///
+    /// This option is ignored by [`LocalCancellable::spawn_local_with_options`],
+    /// since a local service always runs on the current thread.
+    ///
+    /// [`LocalCancellable::spawn_local_with_options`]: crate::LocalCancellable::spawn_local_with_options
+    pub fn runtime(mut self, handle: Handle) -> Self {
+        self.runtime = Some(handle);
+        self
+    }
+
+    /// Lets the service handle its cancellation on its own.
+    ///
+    /// By default, calls to the service's methods are aborted as soon as the
+    /// service is cancelled. With this option the work loop only checks the
+    /// cancellation between iterations, whereas the service observes it
+    /// through [`RunContext::cancellation_token`] and acknowledges it by
+    /// returning [`CancellationResult::Cancelled`] once its current unit of
+    /// work is finished.
+    ///
+    /// [`RunContext::cancellation_token`]: crate::RunContext::cancellation_token
+    /// [`CancellationResult::Cancelled`]: crate::CancellationResult#variant.Cancelled
+    pub fn cooperative_cancellation(mut self) -> Self {
+        self.cooperative_cancellation = true;
+        self
+    }
+
+    /// Sets whether the cancellation or the iteration in flight goes first,
+    /// when both are ready at the same time.
+    ///
+    /// By default, a call to [`Cancellable::run`] which has completed by the
+    /// time the service is cancelled is still handled. With
+    /// [`CancellationPriority::Cancellation`], the service stops right away,
+    /// and the call's result is dropped. Either way, no new call is made once
+    /// the service has been cancelled, so even a service which is always
+    /// ready is stopped promptly. The option has no effect with
+    /// [`Self::cooperative_cancellation`].
+    ///
+    /// [`Cancellable::run`]: crate::Cancellable::run
+    pub fn cancellation_priority(mut self, priority: CancellationPriority) -> Self {
+        self.cancellation_priority = priority;
+        self
+    }
+
+    /// Decides what's done when the callback panics, instead of letting the
+    /// panic unwind the service's task.
+    ///
+    /// The panic is reported to the observers, see
+    /// [`CancellableObserver::on_callback_panic`]. Since the callback is
+    /// called again after it has panicked, it has to keep its state consistent
+    /// in that case.
+    ///
+    /// See [`CallbackPanicPolicy`].
+    pub fn callback_panic(mut self, policy: CallbackPanicPolicy) -> Self {
+        self.callback_panic = policy;
+        self
+    }
+
+    /// Monitors the service with `watchdog`, to detect when it stalls.
+    ///
+    /// See [`Watchdog`].
+    pub fn watchdog(mut self, watchdog: Watchdog) -> Self {
+        self.watchdog = Some(watchdog);
+        self
+    }
+
+    /// Limits the rate at which [`Cancellable::run`] is called to
+    /// `max_per_second`.
+    ///
+    /// The rate is enforced with a token bucket, which allows bursts of up to
+    /// `max_per_second` calls. Waiting for the next call is aborted as soon as
+    /// the service is cancelled.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `max_per_second` is zero.
+    ///
+    /// [`Cancellable::run`]: crate::Cancellable::run
+    pub fn rate_limit(mut self, max_per_second: u32) -> Self {
+        assert!(max_per_second > 0, "rate limit must be positive");
+        self.rate_limit = Some(max_per_second);
+        self
+    }
+
+    /// Limits the number of values passed to the callback, which haven't been
+    /// processed by their consumer yet, to `max_in_flight`.
+    ///
+    /// Once the limit has been reached, the work loop waits for the consumer
+    /// to catch up before it calls [`Cancellable::run`] again, so a slow
+    /// consumer slows the service down. The limit is checked before each
+    /// iteration, so an iteration yielding multiple values can exceed it.
+    /// Waiting is aborted as soon as the service is cancelled.
+    ///
+    /// A value is processed once it has been taken from the stream returned
+    /// by [`Cancellable::spawn_stream_with_options`], or once it has been
+    /// acknowledged or dropped, see [`Cancellable::spawn_with_acks`]. It has
+    /// no effect on the other ways of spawning the service, whose callbacks
+    /// process the values right away.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `max_in_flight` is zero.
+    ///
+    /// [`Cancellable::run`]: crate::Cancellable::run
+    /// [`Cancellable::spawn_stream_with_options`]: crate::Cancellable::spawn_stream_with_options
+    /// [`Cancellable::spawn_with_acks`]: crate::Cancellable::spawn_with_acks
+    pub fn max_in_flight(mut self, max_in_flight: usize) -> Self {
+        assert!(max_in_flight > 0, "in-flight limit must be positive");
+        self.max_in_flight = Some(max_in_flight);
+        self
+    }
+
+    /// Limits the time the service can spend working within a window, see
+    /// [`TimeBudget`].
+    ///
+    /// Waiting for the next window of a throttling budget is aborted as soon
+    /// as the service is cancelled. The time spent so far is returned by
+    /// [`CancellableHandle::stats`] regardless of the budget.
+    ///
+    /// [`CancellableHandle::stats`]: crate::CancellableHandle::stats
+    pub fn time_budget(mut self, budget: TimeBudget) -> Self {
+        self.time_budget = Some(budget);
+        self
+    }
+
+    /// Yields to the runtime after every `iterations` calls to
+    /// [`Cancellable::run`].
+    ///
+    /// The work loop calls the service back-to-back, so a service which
+    /// rarely waits on anything, e.g. one returning
+    /// [`CancellationResult::Continue`] in a tight loop, can starve the other
+    /// tasks of its runtime thread. With this option, the loop gives them a
+    /// chance to run with [`tokio::task::yield_now`].
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `iterations` is zero.
+    ///
+    /// [`Cancellable::run`]: crate::Cancellable::run
+    /// [`CancellationResult::Continue`]: crate::CancellationResult#variant.Continue
+    pub fn yield_every(mut self, iterations: u64) -> Self {
+        assert!(iterations > 0, "yield interval must be positive");
+        self.yield_every = Some(iterations);
+        self
+    }
+
+    /// Cancels the service once it has been idle for `timeout`.
+    ///
+    /// The service is active whenever it yields a value, or signals its
+    /// activity with [`RunContext::keep_alive`], e.g. when it receives a
+    /// message through its handle. Once it has been idle for `timeout`, it's
+    /// cancelled with [`Idle`] as the reason, just like with
+    /// [`CancellableHandle::cancel_with_reason`].
+    ///
+    /// [`RunContext::keep_alive`]: crate::RunContext::keep_alive
+    /// [`Idle`]: crate::Idle
+    /// [`CancellableHandle::cancel_with_reason`]: crate::CancellableHandle::cancel_with_reason
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Stops the service in two phases, with `token` starting the soft one.
+    ///
+    /// Cancelling `token` doesn't stop the work loop. Instead, the service
+    /// observes it through [`RunContext::stop_phase`] and
+    /// [`RunContext::stop_requested`], stops accepting new work, finishes the
+    /// work in flight, and then breaks on its own. Cancelling the service's
+    /// cancellation token stops it right away, e.g. once a grace period has
+    /// elapsed.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use cancellable::{
+    ///     CallbackResult, Cancellable, CancellationResult, RunContext, SpawnOptions, StopPhase,
+    /// };
+    /// use tokio_util::sync::CancellationToken;
+    ///
+    /// struct Server;
+    ///
+    /// impl Cancellable for Server {
+    ///     type Result = ();
+    ///     type Handle = ();
+    ///     type Error = std::io::Error;
+    ///     type Output = ();
+    ///
+    ///     async fn new_handle(&mut self) -> Self::Handle {}
+    ///
+    ///     async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+    ///         let context = RunContext::current().expect("to be called by the work loop");
+    ///         if context.stop_phase() == StopPhase::SoftStop {
+    ///             // Finish the requests in flight.
+    ///             return Ok(CancellationResult::Break);
+    ///         }
+    ///
+    ///         context.stop_requested().await;
+    ///         Ok(CancellationResult::Continue)
+    ///     }
+    /// }
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// let soft_stop = CancellationToken::new();
+    /// let options = SpawnOptions::new().soft_stop(soft_stop.clone());
+    /// let handle = Server
+    ///     .spawn_with_options(CancellationToken::new(), options, |_| CallbackResult::Continue)
+    ///     .await;
+    ///
+    /// soft_stop.cancel();
+    /// assert!(handle.join().await.is_ok());
+    /// # }
+    /// ```
+    ///
+    /// [`RunContext::stop_phase`]: crate::RunContext::stop_phase
+    /// [`RunContext::stop_requested`]: crate::RunContext::stop_requested
+    pub fn soft_stop(mut self, token: CancellationToken) -> Self {
+        self.soft_stop = Some(token);
+        self
+    }
+
+    /// Measures time and waits with `clock`, instead of the clock of the tokio
+    /// runtime.
+    ///
+    /// See [`Clock`].
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = SharedClock(clock);
+        self
+    }
+
+    /// Keeps the service once its work loop completes, so it can be recovered
+    /// with [`CancellableHandle::join_with_state`], e.g. along with the
+    /// buffers, connections or statistics it holds.
+    ///
+    /// By default, the service is dropped as soon as it completes. Services
+    /// spawned with [`LocalCancellable::spawn_local_with_options`] are always
+    /// dropped.
+    ///
+    /// [`CancellableHandle::join_with_state`]: crate::CancellableHandle::join_with_state
+    /// [`LocalCancellable::spawn_local_with_options`]: crate::LocalCancellable::spawn_local_with_options
+    pub fn retain_state(mut self) -> Self {
+        self.retain_state = true;
+        self
+    }
+
+    /// Diverts the values which cannot be delivered into a dead-letter channel
+    /// holding up to `capacity` of them, taken with
+    /// [`CancellableHandle::take_dead_letters`].
+    ///
+    /// The values the callback has rejected with [`CallbackResult::Reject`]
+    /// are diverted, as are the ones which cannot be delivered once the
+    /// callback has broken or failed, e.g. the rest of
+    /// [`CancellationResult::Items`], or the values yielded with
+    /// [`RunContext::yield_item`] in the meantime. The values the callback
+    /// hasn't acknowledged are diverted as well, see
+    /// [`Cancellable::spawn_with_acks`]. Once the channel is full, the values
+    /// are passed to [`Cancellable::on_item_dropped`], as they are by default.
+    /// Services spawned with [`LocalCancellable::spawn_local_with_options`]
+    /// have no dead letters.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if `capacity` is zero.
+    ///
+    /// [`CallbackResult::Reject`]: crate::CallbackResult#variant.Reject
+    /// [`CancellableHandle::take_dead_letters`]: crate::CancellableHandle::take_dead_letters
+    /// [`CancellationResult::Items`]: crate::CancellationResult#variant.Items
+    /// [`RunContext::yield_item`]: crate::RunContext::yield_item
+    /// [`Cancellable::spawn_with_acks`]: crate::Cancellable::spawn_with_acks
+    /// [`Cancellable::on_item_dropped`]: crate::Cancellable::on_item_dropped
+    /// [`LocalCancellable::spawn_local_with_options`]: crate::LocalCancellable::spawn_local_with_options
+    pub fn dead_letters(mut self, capacity: usize) -> Self {
+        assert!(capacity > 0, "dead letters capacity must be positive");
+        self.dead_letters = Some(capacity);
+        self
+    }
+}
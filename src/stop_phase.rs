@@ -0,0 +1,32 @@
+/// Phase of a service's two-phase shutdown.
+///
+/// A service spawned with [`SpawnOptions::soft_stop`] is stopped in two
+/// phases. Cancelling the soft stop token asks the service to stop accepting
+/// new work, but the work loop keeps running it, so it can finish the work
+/// that's already in flight. Cancelling the service's cancellation token
+/// stops it right away, just like for any other service.
+///
+/// The current phase is observable from within the service with
+/// [`RunContext::stop_phase`].
+///
+/// [`SpawnOptions::soft_stop`]: crate::SpawnOptions::soft_stop
+/// [`RunContext::stop_phase`]: crate::RunContext::stop_phase
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StopPhase {
+    /// The service hasn't been asked to stop.
+    Running,
+
+    /// The service should stop accepting new work and finish the work in
+    /// flight.
+    SoftStop,
+
+    /// The service should stop right away.
+    HardStop,
+}
+
+impl StopPhase {
+    /// Checks if the service has been asked to stop, either softly or hard.
+    pub fn is_stopping(&self) -> bool {
+        !matches!(self, Self::Running)
+    }
+}
@@ -0,0 +1,119 @@
+use std::{convert::Infallible, pin::Pin};
+
+use futures_core::Stream;
+
+use crate::{Cancellable, CancellationResult};
+
+/// Service yielding the items of a [`Stream`].
+///
+/// The service completes when the stream ends.
+///
+/// See [`from_stream`].
+pub struct StreamCancellable<S> {
+    stream: Pin<Box<S>>,
+}
+
+impl<S> StreamCancellable<S> {
+    /// Constructs a new service yielding the items of `stream`.
+    pub fn new(stream: S) -> Self {
+        Self {
+            stream: Box::pin(stream),
+        }
+    }
+}
+
+impl<S> std::fmt::Debug for StreamCancellable<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StreamCancellable").finish_non_exhaustive()
+    }
+}
+
+impl<S> Cancellable for StreamCancellable<S>
+where
+    S: Stream + Send,
+    S::Item: Send,
+{
+    type Result = S::Item;
+    type Handle = ();
+    type Error = Infallible;
+    type Output = ();
+
+    async fn new_handle(&mut self) -> Self::Handle {}
+
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        let item = std::future::poll_fn(|cx| self.stream.as_mut().poll_next(cx)).await;
+
+        match item {
+            Some(item) => Ok(CancellationResult::Item(item)),
+            None => Ok(CancellationResult::Break),
+        }
+    }
+}
+
+/// Constructs a service yielding the items of `stream`.
+///
+/// The service completes when the stream ends, or when it's cancelled.
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{CallbackResult, Cancellable, CancellationToken};
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let stream = futures::stream::iter([1, 2, 3]);
+///
+/// let handle = cancellable::from_stream(stream)
+///     .spawn_with_callback(CancellationToken::new(), |item| {
+///         println!("Received {item}.");
+///         CallbackResult::Continue
+///     })
+///     .await;
+///
+/// handle.join().await.unwrap();
+/// # }
+/// ```
+pub fn from_stream<S>(stream: S) -> StreamCancellable<S>
+where
+    S: Stream,
+{
+    StreamCancellable::new(stream)
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::Cancellable;
+
+    #[tokio::test]
+    async fn should_yield_stream_items() {
+        // Arrange
+        let stream = futures::stream::iter([1, 2, 3]);
+
+        // Act
+        let (handle, items) = crate::from_stream(stream)
+            .spawn_stream(CancellationToken::new())
+            .await;
+
+        // Assert
+        assert_eq!(vec![1, 2, 3], items.collect::<Vec<_>>().await);
+        assert!(handle.join().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn should_complete_when_cancelled() {
+        // Arrange
+        let stream = futures::stream::pending::<i32>();
+        let handle = crate::from_stream(stream)
+            .spawn(CancellationToken::new())
+            .await;
+
+        // Act
+        handle.cancel();
+
+        // Assert
+        assert!(handle.join().await.is_ok());
+    }
+}
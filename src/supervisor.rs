@@ -0,0 +1,827 @@
+use std::{future::Future, pin::Pin, sync::Arc, task::Poll};
+
+use tokio::sync::{Mutex, MutexGuard};
+use tokio_util::sync::{CancellationToken, DropGuard};
+
+use crate::{CallbackResult, Cancellable, CancellationResult, RunContext, SpawnOptions};
+
+type ChildJoin = Pin<Box<dyn Future<Output = Result<(), String>> + Send>>;
+
+/// Future spawning a new instance of a supervised service.
+type ChildStart<'a> = Pin<Box<dyn Future<Output = ChildJoin> + Send + 'a>>;
+
+/// Slot for the instance of a respawnable child that has completed.
+type Recovered<T> = Arc<std::sync::Mutex<Option<T>>>;
+
+/// Strategy used by a [`Supervisor`] to restart its children.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SupervisionStrategy {
+    /// Only the failed child is restarted.
+    OneForOne,
+
+    /// When a child fails, all other running children are cancelled and then
+    /// restarted along with the failed one. The children which have completed
+    /// without an error aren't restarted.
+    OneForAll,
+}
+
+/// Event yielded by a [`Supervisor`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SupervisorEvent {
+    /// The child with the given index has completed without an error and
+    /// won't be restarted.
+    Completed(usize),
+
+    /// The child with the given index has failed and has been restarted.
+    Restarted(usize),
+}
+
+/// Error returned by a [`Supervisor`] when a child has failed and it couldn't
+/// be restarted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SupervisorError {
+    child: usize,
+    message: String,
+}
+
+impl SupervisorError {
+    /// Returns the index of the child that has failed.
+    pub fn child(&self) -> usize {
+        self.child
+    }
+}
+
+impl std::fmt::Display for SupervisorError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "child {} has failed: {}", self.child, self.message)
+    }
+}
+
+impl std::error::Error for SupervisorError {}
+
+/// Handle for communicating with a service managed by a [`Supervisor`].
+///
+/// Whenever the service is restarted, the inner handle is replaced with the
+/// handle of the new instance.
+pub struct SupervisedHandle<T>
+where
+    T: Cancellable,
+{
+    inner: Arc<Mutex<<T as Cancellable>::Handle>>,
+}
+
+impl<T> SupervisedHandle<T>
+where
+    T: Cancellable,
+{
+    /// Locks the handle of the current instance of the service.
+    pub async fn lock(&self) -> MutexGuard<'_, <T as Cancellable>::Handle> {
+        self.inner.lock().await
+    }
+}
+
+impl<T> Clone for SupervisedHandle<T>
+where
+    T: Cancellable,
+{
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for SupervisedHandle<T>
+where
+    T: Cancellable,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SupervisedHandle").finish_non_exhaustive()
+    }
+}
+
+/// Service which can be spawned again once it has completed, without being
+/// constructed from scratch.
+///
+/// When it's added with [`Supervisor::add_respawnable`], the supervisor
+/// recovers the failed instance of the service, resets it and spawns it again,
+/// so expensive resources, e.g. connection pools or TLS contexts, survive the
+/// restart.
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{Cancellable, CancellationResult, Respawnable};
+///
+/// struct Worker {
+///     tls_config: std::sync::Arc<String>,
+///     pending: Vec<u8>,
+/// }
+///
+/// impl Cancellable for Worker {
+///     type Result = ();
+///     type Handle = ();
+///     type Error = std::io::Error;
+///     type Output = ();
+///
+///     async fn new_handle(&mut self) -> Self::Handle {}
+///
+///     async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+///         Ok(CancellationResult::Break)
+///     }
+/// }
+///
+/// impl Respawnable for Worker {
+///     fn reset(&mut self) {
+///         // The TLS config is kept, but the partial input is discarded.
+///         self.pending.clear();
+///     }
+/// }
+/// ```
+pub trait Respawnable: Cancellable {
+    /// Prepares the recovered service to be spawned again.
+    ///
+    /// By default, it does nothing, so the service is spawned again in the
+    /// state it has completed with.
+    fn reset(&mut self) {}
+}
+
+/// Type-erased factory of a supervised service.
+trait Child: Send {
+    /// Constructs and spawns a new instance of the service.
+    fn start(&mut self, cancellation_token: CancellationToken) -> ChildStart<'_>;
+}
+
+struct TypedChild<T, F>
+where
+    T: Cancellable,
+{
+    factory: F,
+    handle: Arc<Mutex<<T as Cancellable>::Handle>>,
+}
+
+impl<T, F> Child for TypedChild<T, F>
+where
+    T: Cancellable + Send + 'static,
+    T::Handle: Send,
+    F: FnMut() -> T + Send,
+{
+    fn start(&mut self, cancellation_token: CancellationToken) -> ChildStart<'_> {
+        // The join is a future itself, but it's meant to be returned, not awaited.
+        #[allow(clippy::async_yields_async)]
+        Box::pin(async move {
+            let (join_handle, _, inner) = (self.factory)()
+                .spawn(cancellation_token)
+                .await
+                .into_raw_parts();
+            *self.handle.lock().await = inner;
+
+            join(join_handle)
+        })
+    }
+}
+
+/// Child spawned again from its recovered state, if possible.
+struct RespawnableChild<T, F>
+where
+    T: Cancellable,
+{
+    factory: F,
+    recovered: Recovered<T>,
+    handle: Arc<Mutex<<T as Cancellable>::Handle>>,
+}
+
+impl<T, F> Child for RespawnableChild<T, F>
+where
+    T: Respawnable + Send + 'static,
+    T::Handle: Send,
+    F: FnMut() -> T + Send,
+{
+    fn start(&mut self, cancellation_token: CancellationToken) -> ChildStart<'_> {
+        // The join is a future itself, but it's meant to be returned, not awaited.
+        #[allow(clippy::async_yields_async)]
+        Box::pin(async move {
+            let recovered = self
+                .recovered
+                .lock()
+                .expect("lock not to be poisoned")
+                .take();
+            let service = match recovered {
+                Some(mut service) => {
+                    service.reset();
+                    service
+                }
+                None => (self.factory)(),
+            };
+
+            let (inner, join) =
+                respawn(service, cancellation_token, Arc::clone(&self.recovered)).await;
+            *self.handle.lock().await = inner;
+
+            join
+        })
+    }
+}
+
+/// Spawns `service`, whose instance is put into `recovered` once it completes.
+async fn respawn<T>(
+    service: T,
+    cancellation_token: CancellationToken,
+    recovered: Recovered<T>,
+) -> (T::Handle, ChildJoin)
+where
+    T: Respawnable + Send + 'static,
+{
+    let options = SpawnOptions::new().retain_state();
+    let (join_side, inner) = service
+        .spawn_with_options(cancellation_token, options, |_| CallbackResult::Continue)
+        .await
+        .into_parts();
+
+    let join = Box::pin(async move {
+        let (result, service) = join_side.join_with_state().await;
+        *recovered.lock().expect("lock not to be poisoned") = service;
+        result.map(|_| ()).map_err(|e| e.to_string())
+    });
+    (inner, join)
+}
+
+fn join<O, E>(join_handle: tokio::task::JoinHandle<Result<O, E>>) -> ChildJoin
+where
+    O: Send + 'static,
+    E: std::fmt::Display + Send + 'static,
+{
+    Box::pin(async move {
+        match join_handle.await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(e) => Err(e.to_string()),
+        }
+    })
+}
+
+struct ChildEntry {
+    child: Box<dyn Child>,
+    cancellation_token: CancellationToken,
+    join: Option<ChildJoin>,
+}
+
+/// Service that owns a group of other services and restarts them when they
+/// fail.
+///
+/// The children are spawned as soon as they're added. Spawning the supervisor
+/// itself starts the supervision, and from then on the children are
+/// cancelled along with the supervisor. When the supervisor completes, all of
+/// its children are cancelled and joined, so awaiting the supervisor's handle
+/// awaits all of them. The children are also cancelled if the supervisor is
+/// dropped, e.g. if it's never spawned or its task is aborted. Values yielded
+/// by the children are discarded.
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{
+///     Cancellable, CancellationResult, CancellationToken, SupervisionStrategy,
+///     Supervisor,
+/// };
+///
+/// struct Worker;
+///
+/// impl Cancellable for Worker {
+///     type Result = ();
+///     type Handle = ();
+///     type Error = std::io::Error;
+///     type Output = ();
+///
+///     async fn new_handle(&mut self) -> Self::Handle {}
+///
+///     async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+///         Ok(CancellationResult::Break)
+///     }
+/// }
+///
+/// # #[tokio::main(flavor = "current_thread")]
+/// # async fn main() {
+/// let mut supervisor = Supervisor::new(SupervisionStrategy::OneForOne).max_restarts(3);
+/// let _worker_handle = supervisor.add(|| Worker).await;
+///
+/// let handle = supervisor.spawn(CancellationToken::new()).await;
+/// assert!(handle.await.unwrap().is_ok());
+/// # }
+/// ```
+pub struct Supervisor {
+    strategy: SupervisionStrategy,
+    max_restarts: Option<usize>,
+    restarts: usize,
+    cancellation_token: CancellationToken,
+    children: Vec<ChildEntry>,
+    /// Cancels the children once the supervisor is dropped.
+    _guard: DropGuard,
+}
+
+impl Supervisor {
+    /// Constructs a new supervisor without any children.
+    pub fn new(strategy: SupervisionStrategy) -> Self {
+        let cancellation_token = CancellationToken::new();
+        Self {
+            strategy,
+            max_restarts: None,
+            restarts: 0,
+            _guard: cancellation_token.clone().drop_guard(),
+            cancellation_token,
+            children: Vec::new(),
+        }
+    }
+
+    /// Limits the total number of restarts. If a child fails once more after
+    /// the limit has been reached, then the supervisor fails.
+    pub fn max_restarts(mut self, max_restarts: usize) -> Self {
+        self.max_restarts = Some(max_restarts);
+        self
+    }
+
+    /// Spawns a new child constructed by `factory`.
+    ///
+    /// The factory is called again whenever the child needs to be restarted.
+    ///
+    /// # Returns
+    ///
+    /// Handle for communicating with the current instance of the child.
+    pub async fn add<T, F>(&mut self, mut factory: F) -> SupervisedHandle<T>
+    where
+        T: Cancellable + Send + 'static,
+        T::Handle: Send,
+        F: FnMut() -> T + Send + 'static,
+    {
+        let cancellation_token = self.cancellation_token.child_token();
+        let (join_handle, _, inner) = factory()
+            .spawn(cancellation_token.clone())
+            .await
+            .into_raw_parts();
+
+        let handle = Arc::new(Mutex::new(inner));
+        let child = TypedChild {
+            factory,
+            handle: Arc::clone(&handle),
+        };
+
+        self.children.push(ChildEntry {
+            child: Box::new(child),
+            cancellation_token,
+            join: Some(join(join_handle)),
+        });
+
+        SupervisedHandle { inner: handle }
+    }
+
+    /// Spawns a new child constructed by `factory`, which is spawned again
+    /// from its recovered state when it fails.
+    ///
+    /// The instance of the child that has failed is reset with
+    /// [`Respawnable::reset`] and spawned again. The factory is called only
+    /// for the first instance, and whenever the failed instance couldn't be
+    /// recovered, e.g. because it has panicked.
+    ///
+    /// # Returns
+    ///
+    /// Handle for communicating with the current instance of the child.
+    pub async fn add_respawnable<T, F>(&mut self, mut factory: F) -> SupervisedHandle<T>
+    where
+        T: Respawnable + Send + 'static,
+        T::Handle: Send,
+        F: FnMut() -> T + Send + 'static,
+    {
+        let cancellation_token = self.cancellation_token.child_token();
+        let recovered = Arc::default();
+        let (inner, join) = respawn(
+            factory(),
+            cancellation_token.clone(),
+            Arc::clone(&recovered),
+        )
+        .await;
+
+        let handle = Arc::new(Mutex::new(inner));
+        let child = RespawnableChild {
+            factory,
+            recovered,
+            handle: Arc::clone(&handle),
+        };
+
+        self.children.push(ChildEntry {
+            child: Box::new(child),
+            cancellation_token,
+            join: Some(join),
+        });
+
+        SupervisedHandle { inner: handle }
+    }
+
+    /// Waits for any running child to complete.
+    async fn next_exit(&mut self) -> (usize, Result<(), String>) {
+        std::future::poll_fn(|cx| {
+            for (index, entry) in self.children.iter_mut().enumerate() {
+                if let Some(join) = entry.join.as_mut() {
+                    if let Poll::Ready(result) = join.as_mut().poll(cx) {
+                        entry.join = None;
+                        return Poll::Ready((index, result));
+                    }
+                }
+            }
+            Poll::Pending
+        })
+        .await
+    }
+
+    /// Spawns a new instance of the child with the given index.
+    async fn restart_child(&mut self, index: usize) {
+        let entry = &mut self.children[index];
+        entry.cancellation_token = self.cancellation_token.child_token();
+        entry.join = Some(entry.child.start(entry.cancellation_token.clone()).await);
+    }
+
+    /// Cancels all running children and waits for them to complete.
+    async fn stop_children(&mut self) {
+        for entry in &mut self.children {
+            entry.cancellation_token.cancel();
+        }
+        for entry in &mut self.children {
+            if let Some(join) = entry.join.take() {
+                let _ = join.await;
+            }
+        }
+    }
+}
+
+impl Cancellable for Supervisor {
+    type Result = SupervisorEvent;
+    type Handle = ();
+    type Error = SupervisorError;
+    type Output = ();
+
+    async fn new_handle(&mut self) -> Self::Handle {}
+
+    async fn on_start(&mut self) -> Result<(), Self::Error> {
+        // The children have been spawned before the supervisor, so their
+        // token is linked to the supervisor's one here. The link is dropped
+        // once either token is cancelled, at the latest with the supervisor.
+        if let Some(context) = RunContext::current() {
+            let parent = context.cancellation_token().clone();
+            let cancellation_token = self.cancellation_token.clone();
+            tokio::spawn(async move {
+                tokio::select! {
+                    _ = parent.cancelled() => cancellation_token.cancel(),
+                    _ = cancellation_token.cancelled() => {}
+                }
+            });
+        }
+
+        Ok(())
+    }
+
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        if self.children.iter().all(|entry| entry.join.is_none()) {
+            return Ok(CancellationResult::Break);
+        }
+
+        let (index, result) = self.next_exit().await;
+        let Err(message) = result else {
+            return Ok(CancellationResult::item(SupervisorEvent::Completed(index)));
+        };
+
+        if self.max_restarts.is_some_and(|max| self.restarts >= max) {
+            self.stop_children().await;
+            return Err(SupervisorError {
+                child: index,
+                message,
+            });
+        }
+        self.restarts += 1;
+
+        match self.strategy {
+            SupervisionStrategy::OneForOne => self.restart_child(index).await,
+            SupervisionStrategy::OneForAll => {
+                // The children which have completed without an error stay
+                // completed.
+                let running: Vec<_> = (0..self.children.len())
+                    .filter(|&i| i == index || self.children[i].join.is_some())
+                    .collect();
+                self.stop_children().await;
+                for index in running {
+                    self.restart_child(index).await;
+                }
+            }
+        }
+
+        Ok(CancellationResult::item(SupervisorEvent::Restarted(index)))
+    }
+
+    async fn on_stop(&mut self) {
+        self.stop_children().await;
+    }
+
+    async fn on_cancel(&mut self) {
+        self.cancellation_token.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{Cancellable, CancellationResult, Respawnable, SupervisionStrategy, Supervisor};
+
+    struct FlakyCancellable {
+        starts: Arc<AtomicUsize>,
+        failures: usize,
+    }
+
+    impl FlakyCancellable {
+        fn new(starts: &Arc<AtomicUsize>, failures: usize) -> Self {
+            Self {
+                starts: Arc::clone(starts),
+                failures,
+            }
+        }
+    }
+
+    impl Cancellable for FlakyCancellable {
+        type Result = ();
+        type Handle = usize;
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            if self.starts.load(Ordering::SeqCst) <= self.failures {
+                return Err(anyhow::anyhow!("FlakyCancellable error"));
+            }
+            std::future::pending().await
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {
+            self.starts.fetch_add(1, Ordering::SeqCst) + 1
+        }
+    }
+
+    struct PendingCancellable {
+        cancelled: Arc<AtomicBool>,
+    }
+
+    impl Cancellable for PendingCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            std::future::pending().await
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn on_cancel(&mut self) {
+            self.cancelled.store(true, Ordering::SeqCst);
+        }
+    }
+
+    struct CompletingCancellable {
+        starts: Arc<AtomicUsize>,
+    }
+
+    impl Cancellable for CompletingCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            Ok(CancellationResult::Break)
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {
+            self.starts.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    struct ReusableCancellable {
+        resets: usize,
+        failures: usize,
+    }
+
+    impl Cancellable for ReusableCancellable {
+        type Result = ();
+        type Handle = usize;
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            if self.resets < self.failures {
+                return Err(anyhow::anyhow!("ReusableCancellable error"));
+            }
+            std::future::pending().await
+        }
+
+        async fn new_handle(&mut self) -> Self::Handle {
+            self.resets
+        }
+    }
+
+    impl Respawnable for ReusableCancellable {
+        fn reset(&mut self) {
+            self.resets += 1;
+        }
+    }
+
+    #[tokio::test]
+    async fn should_restart_failed_child() {
+        // Arrange
+        let starts = Arc::new(AtomicUsize::new(0));
+        let mut supervisor = Supervisor::new(SupervisionStrategy::OneForOne);
+        let child_starts = Arc::clone(&starts);
+        let child = supervisor
+            .add(move || FlakyCancellable::new(&child_starts, 2))
+            .await;
+
+        // Act
+        let handle = supervisor.spawn(CancellationToken::new()).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // Assert
+        assert_eq!(3, starts.load(Ordering::SeqCst));
+        assert_eq!(3, *child.lock().await);
+        handle.cancel();
+        assert!(handle.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn should_restart_all_children_when_one_fails() {
+        // Arrange
+        let flaky_starts = Arc::new(AtomicUsize::new(0));
+        let stable_starts = Arc::new(AtomicUsize::new(0));
+        let mut supervisor = Supervisor::new(SupervisionStrategy::OneForAll);
+        let child_starts = Arc::clone(&flaky_starts);
+        supervisor
+            .add(move || FlakyCancellable::new(&child_starts, 1))
+            .await;
+        let child_starts = Arc::clone(&stable_starts);
+        supervisor
+            .add(move || FlakyCancellable::new(&child_starts, 0))
+            .await;
+
+        // Act
+        let handle = supervisor.spawn(CancellationToken::new()).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // Assert
+        assert_eq!(2, flaky_starts.load(Ordering::SeqCst));
+        assert_eq!(2, stable_starts.load(Ordering::SeqCst));
+        handle.cancel();
+        assert!(handle.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn should_not_restart_completed_children_when_one_fails() {
+        // Arrange
+        let flaky_starts = Arc::new(AtomicUsize::new(0));
+        let completing_starts = Arc::new(AtomicUsize::new(0));
+        let mut supervisor = Supervisor::new(SupervisionStrategy::OneForAll);
+        let child_starts = Arc::clone(&completing_starts);
+        supervisor
+            .add(move || CompletingCancellable {
+                starts: Arc::clone(&child_starts),
+            })
+            .await;
+        let child_starts = Arc::clone(&flaky_starts);
+        supervisor
+            .add(move || FlakyCancellable::new(&child_starts, 1))
+            .await;
+
+        // Act
+        let handle = supervisor.spawn(CancellationToken::new()).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // Assert
+        assert_eq!(2, flaky_starts.load(Ordering::SeqCst));
+        assert_eq!(1, completing_starts.load(Ordering::SeqCst));
+        handle.cancel();
+        assert!(handle.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn should_fail_when_restart_limit_reached() {
+        // Arrange
+        let starts = Arc::new(AtomicUsize::new(0));
+        let mut supervisor = Supervisor::new(SupervisionStrategy::OneForOne).max_restarts(1);
+        let child_starts = Arc::clone(&starts);
+        supervisor
+            .add(move || FlakyCancellable::new(&child_starts, 5))
+            .await;
+
+        // Act
+        let handle = supervisor.spawn(CancellationToken::new()).await;
+
+        // Assert
+        let error = handle.await.unwrap().unwrap_err();
+        assert_eq!(0, error.child());
+        assert_eq!(2, starts.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn should_respawn_failed_child_from_its_state() {
+        // Arrange
+        let constructions = Arc::new(AtomicUsize::new(0));
+        let mut supervisor = Supervisor::new(SupervisionStrategy::OneForOne);
+        let child_constructions = Arc::clone(&constructions);
+        let child = supervisor
+            .add_respawnable(move || {
+                child_constructions.fetch_add(1, Ordering::SeqCst);
+                ReusableCancellable {
+                    resets: 0,
+                    failures: 2,
+                }
+            })
+            .await;
+
+        // Act
+        let handle = supervisor.spawn(CancellationToken::new()).await;
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // Assert
+        assert_eq!(1, constructions.load(Ordering::SeqCst));
+        assert_eq!(2, *child.lock().await);
+        handle.cancel();
+        assert!(handle.await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn should_cancel_children_when_dropped() {
+        // Arrange
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let mut supervisor = Supervisor::new(SupervisionStrategy::OneForOne);
+        let child_cancelled = Arc::clone(&cancelled);
+        supervisor
+            .add(move || PendingCancellable {
+                cancelled: Arc::clone(&child_cancelled),
+            })
+            .await;
+
+        // Act
+        drop(supervisor);
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        // Assert
+        assert!(cancelled.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn should_cancel_children_when_aborted() {
+        // Arrange
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let mut supervisor = Supervisor::new(SupervisionStrategy::OneForOne);
+        let child_cancelled = Arc::clone(&cancelled);
+        supervisor
+            .add(move || PendingCancellable {
+                cancelled: Arc::clone(&child_cancelled),
+            })
+            .await;
+        let handle = supervisor.spawn(CancellationToken::new()).await;
+
+        // Act
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        handle.abort();
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        // Assert
+        assert!(cancelled.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn should_cancel_children_with_spawn_token() {
+        // Arrange
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let mut supervisor = Supervisor::new(SupervisionStrategy::OneForOne);
+        let child_cancelled = Arc::clone(&cancelled);
+        supervisor
+            .add(move || PendingCancellable {
+                cancelled: Arc::clone(&child_cancelled),
+            })
+            .await;
+        let cancellation_token = CancellationToken::new();
+        let handle = supervisor.spawn(cancellation_token.clone()).await;
+
+        // Act
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        cancellation_token.cancel();
+
+        // Assert
+        assert!(handle.await.unwrap().is_ok());
+        assert!(cancelled.load(Ordering::SeqCst));
+    }
+}
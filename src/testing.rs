@@ -0,0 +1,374 @@
+//! Utilities for testing services and their consumers.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::time::Duration;
+//!
+//! use cancellable::{
+//!     testing::{self, MockCancellable, RecordingCallback},
+//!     Cancellable, CancellationResult, CancellationToken,
+//! };
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let service = MockCancellable::<i32, std::io::Error>::new()
+//!     .push_item(1)
+//!     .push_item(2)
+//!     .push(Ok(CancellationResult::Break));
+//! let recorder = RecordingCallback::new();
+//!
+//! let handle = service
+//!     .spawn_with_callback(CancellationToken::new(), recorder.callback())
+//!     .await;
+//!
+//! testing::assert_completes_within(handle, Duration::from_secs(1))
+//!     .await
+//!     .unwrap();
+//! assert_eq!(vec![1, 2], recorder.items());
+//! # }
+//! ```
+
+use std::{
+    collections::VecDeque,
+    pin::pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use futures_core::Stream;
+
+use crate::{
+    CallbackResult, Cancellable, CancellableError, CancellableHandle, CancellationResult,
+    LocalCancellable,
+};
+
+/// Result of a single call to [`MockCancellable`]'s [`Cancellable::run`].
+type Outcome<T, E, O> = Result<CancellationResult<T, O>, E>;
+
+/// Service returning predetermined outcomes from its units of work.
+///
+/// Each call to [`Cancellable::run`] returns the next outcome pushed to the
+/// service. Once the outcomes run out, the calls never complete, so the
+/// service keeps running until it's cancelled.
+///
+/// The service's handle, [`MockHandle`], counts the calls.
+pub struct MockCancellable<T, E, O = ()> {
+    outcomes: VecDeque<Outcome<T, E, O>>,
+    runs: Arc<AtomicUsize>,
+}
+
+impl<T, E, O> MockCancellable<T, E, O> {
+    /// Constructs a new service without any outcomes.
+    pub fn new() -> Self {
+        Self {
+            outcomes: VecDeque::new(),
+            runs: Arc::default(),
+        }
+    }
+
+    /// Pushes `outcome` to be returned by the next unscripted call to
+    /// [`Cancellable::run`].
+    pub fn push(mut self, outcome: Result<CancellationResult<T, O>, E>) -> Self {
+        self.outcomes.push_back(outcome);
+        self
+    }
+
+    /// Pushes an outcome yielding `item`.
+    pub fn push_item(self, item: T) -> Self {
+        self.push(Ok(CancellationResult::Item(item)))
+    }
+
+    /// Pushes an outcome failing with `error`.
+    pub fn push_error(self, error: E) -> Self {
+        self.push(Err(error))
+    }
+}
+
+impl<T, E, O> Default for MockCancellable<T, E, O> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, E, O> std::fmt::Debug for MockCancellable<T, E, O> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MockCancellable")
+            .field("outcomes", &self.outcomes.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T, E, O> Cancellable for MockCancellable<T, E, O>
+where
+    T: Send,
+    E: std::fmt::Debug + std::fmt::Display + Send,
+    O: std::fmt::Debug + Send,
+{
+    type Result = T;
+    type Handle = MockHandle;
+    type Error = E;
+    type Output = O;
+
+    async fn new_handle(&mut self) -> Self::Handle {
+        MockHandle {
+            runs: Arc::clone(&self.runs),
+        }
+    }
+
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        self.runs.fetch_add(1, Ordering::Relaxed);
+
+        match self.outcomes.pop_front() {
+            Some(outcome) => outcome,
+            None => std::future::pending().await,
+        }
+    }
+}
+
+/// Handle of a [`MockCancellable`].
+#[derive(Debug, Clone)]
+pub struct MockHandle {
+    runs: Arc<AtomicUsize>,
+}
+
+impl MockHandle {
+    /// Returns the number of calls to the service's [`Cancellable::run`] made
+    /// so far.
+    pub fn runs(&self) -> usize {
+        self.runs.load(Ordering::Relaxed)
+    }
+}
+
+/// Callback recording the values yielded by a service.
+///
+/// The recorder is shared with the callbacks it creates, so the values can be
+/// inspected after the callback has been moved to the work loop.
+pub struct RecordingCallback<T> {
+    items: Arc<Mutex<Vec<T>>>,
+}
+
+impl<T> RecordingCallback<T>
+where
+    T: Send + 'static,
+{
+    /// Constructs a new recorder.
+    pub fn new() -> Self {
+        Self {
+            items: Arc::default(),
+        }
+    }
+
+    /// Returns a callback recording the values passed to it.
+    ///
+    /// The callback never stops the service.
+    pub fn callback<E, R>(&self) -> impl FnMut(T) -> CallbackResult<E, R> + Send + 'static
+    where
+        E: 'static,
+        R: 'static,
+    {
+        let items = Arc::clone(&self.items);
+        move |item| {
+            items.lock().expect("lock not to be poisoned").push(item);
+            CallbackResult::Continue
+        }
+    }
+
+    /// Returns the values recorded so far.
+    pub fn items(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        self.items.lock().expect("lock not to be poisoned").clone()
+    }
+
+    /// Returns the number of values recorded so far.
+    pub fn len(&self) -> usize {
+        self.items.lock().expect("lock not to be poisoned").len()
+    }
+
+    /// Checks if no values have been recorded so far.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<T> Default for RecordingCallback<T>
+where
+    T: Send + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for RecordingCallback<T> {
+    fn clone(&self) -> Self {
+        Self {
+            items: Arc::clone(&self.items),
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for RecordingCallback<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecordingCallback").finish_non_exhaustive()
+    }
+}
+
+/// Asserts that the service completes within `timeout`.
+///
+/// # Returns
+///
+/// The flattened result of the service. See [`CancellableHandle::join`].
+///
+/// # Panics
+///
+/// Panics if the service doesn't complete in time.
+pub async fn assert_completes_within<T, H>(
+    handle: CancellableHandle<T, H>,
+    timeout: Duration,
+) -> Result<Option<T::Output>, CancellableError<T::Error>>
+where
+    T: LocalCancellable,
+{
+    match tokio::time::timeout(timeout, handle.join()).await {
+        Ok(result) => result,
+        Err(_) => panic!("service hasn't completed within {timeout:?}"),
+    }
+}
+
+/// Cancels the service and asserts that it completes successfully within
+/// `timeout`.
+///
+/// # Returns
+///
+/// The final value of the service, if it has completed with one.
+///
+/// # Panics
+///
+/// Panics if the service doesn't complete in time, or if it completes with an
+/// error. In the former case, the service's task is aborted.
+pub async fn assert_cancelled_within<T, H>(
+    handle: CancellableHandle<T, H>,
+    timeout: Duration,
+) -> Option<T::Output>
+where
+    T: LocalCancellable,
+{
+    match handle.cancel_with_timeout(timeout).await {
+        Some(Ok(output)) => output,
+        Some(Err(e)) => panic!("service has failed after being cancelled: {e:?}"),
+        None => panic!("service hasn't completed within {timeout:?} after being cancelled"),
+    }
+}
+
+/// Asserts that `stream` yields exactly the `expected` values, in order,
+/// before it ends.
+///
+/// It's meant to be used with the stream of a service spawned with
+/// [`Cancellable::spawn_stream`].
+///
+/// # Panics
+///
+/// Panics if the yielded values differ from the `expected` ones.
+pub async fn assert_yields<S>(stream: S, expected: &[S::Item])
+where
+    S: Stream,
+    S::Item: std::fmt::Debug + PartialEq,
+{
+    let mut stream = pin!(stream);
+    let mut items = Vec::with_capacity(expected.len());
+    while let Some(item) = std::future::poll_fn(|cx| stream.as_mut().poll_next(cx)).await {
+        items.push(item);
+    }
+
+    assert_eq!(
+        expected,
+        items.as_slice(),
+        "service has yielded different values"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{
+        testing::{self, MockCancellable, RecordingCallback},
+        Cancellable, CancellableError, CancellationResult,
+    };
+
+    #[tokio::test]
+    async fn should_return_pushed_outcomes() {
+        // Arrange
+        let service = MockCancellable::<i32, anyhow::Error, i32>::new()
+            .push_item(1)
+            .push(Ok(CancellationResult::Continue))
+            .push_item(2)
+            .push(Ok(CancellationResult::BreakWith(42)));
+
+        // Act
+        let (handle, items) = service.spawn_stream(CancellationToken::new()).await;
+        let runs = handle.clone_handle();
+
+        // Assert
+        testing::assert_yields(items, &[1, 2]).await;
+        let output = testing::assert_completes_within(handle, Duration::from_secs(1)).await;
+        assert_eq!(Some(42), output.unwrap());
+        assert_eq!(4, runs.runs());
+    }
+
+    #[tokio::test]
+    async fn should_fail_with_pushed_error() {
+        // Arrange
+        let service =
+            MockCancellable::<i32, anyhow::Error>::new().push_error(anyhow::anyhow!("boom"));
+
+        // Act
+        let handle = service.spawn(CancellationToken::new()).await;
+
+        // Assert
+        let result = testing::assert_completes_within(handle, Duration::from_secs(1)).await;
+        assert!(matches!(result, Err(CancellableError::Service(_))));
+    }
+
+    #[tokio::test]
+    async fn should_keep_running_when_outcomes_run_out() {
+        // Arrange
+        let service = MockCancellable::<i32, anyhow::Error>::new().push_item(1);
+        let recorder = RecordingCallback::new();
+
+        // Act
+        let handle = service
+            .spawn_with_callback(CancellationToken::new(), recorder.callback())
+            .await;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        // Assert
+        assert!(!handle.is_finished());
+        assert_eq!(
+            None,
+            testing::assert_cancelled_within(handle, Duration::from_secs(1)).await
+        );
+        assert_eq!(vec![1], recorder.items());
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "service hasn't completed within")]
+    async fn should_panic_when_service_does_not_complete_in_time() {
+        // Arrange
+        let service = MockCancellable::<i32, anyhow::Error>::new();
+
+        // Act
+        let handle = service.spawn(CancellationToken::new()).await;
+
+        // Assert
+        let _ = testing::assert_completes_within(handle, Duration::from_millis(10)).await;
+    }
+}
@@ -0,0 +1,174 @@
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+use crate::clock::SharedClock;
+
+/// Limit of the time a service can spend working within a window, see
+/// [`SpawnOptions::time_budget`].
+///
+/// The time spent both inside [`Cancellable::run`] and in the callback counts
+/// against the budget, as it does in [`ServiceStats::busy_time`]. The budget
+/// is checked before each iteration, so the iteration exceeding it always
+/// completes.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use cancellable::{SpawnOptions, TimeBudget};
+///
+/// // At most 100 ms of work per second.
+/// let budget = TimeBudget::throttle(Duration::from_millis(100), Duration::from_secs(1));
+/// let options = SpawnOptions::new().time_budget(budget);
+/// ```
+///
+/// [`SpawnOptions::time_budget`]: crate::SpawnOptions::time_budget
+/// [`Cancellable::run`]: crate::Cancellable::run
+/// [`ServiceStats::busy_time`]: crate::ServiceStats::busy_time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeBudget {
+    max: Duration,
+    window: Duration,
+    action: Action,
+}
+
+/// What's done once the budget has been exceeded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Action {
+    Throttle,
+    Cancel,
+}
+
+impl TimeBudget {
+    /// Constructs a new budget of `max` time per `window`, which pauses the
+    /// service until the window ends once the budget has been exceeded.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `window` is zero.
+    pub fn throttle(max: Duration, window: Duration) -> Self {
+        Self::new(max, window, Action::Throttle)
+    }
+
+    /// Constructs a new budget of `max` time per `window`, which cancels the
+    /// service with [`BudgetExceeded`] once it has been exceeded.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `window` is zero.
+    pub fn cancel(max: Duration, window: Duration) -> Self {
+        Self::new(max, window, Action::Cancel)
+    }
+
+    fn new(max: Duration, window: Duration, action: Action) -> Self {
+        assert!(!window.is_zero(), "budget window must be positive");
+
+        Self {
+            max,
+            window,
+            action,
+        }
+    }
+}
+
+/// Reason of the cancellation of a service which has exceeded its
+/// [`TimeBudget`].
+///
+/// See [`TimeBudget::cancel`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BudgetExceeded;
+
+/// Verdict of a [`BudgetTracker`] before an iteration.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Overrun {
+    /// The service has to wait for the given time before the iteration.
+    Throttle(Duration),
+
+    /// The service has to be cancelled.
+    Cancel,
+}
+
+/// Time spent by a service within the current window of its budget.
+#[derive(Debug)]
+pub(crate) struct BudgetTracker {
+    budget: TimeBudget,
+    window_started: Instant,
+    spent: Duration,
+    clock: SharedClock,
+}
+
+impl BudgetTracker {
+    pub(crate) fn new(budget: TimeBudget, clock: SharedClock) -> Self {
+        Self {
+            budget,
+            window_started: clock.now(),
+            spent: Duration::ZERO,
+            clock,
+        }
+    }
+
+    /// Records the time spent working.
+    pub(crate) fn record(&mut self, busy: Duration) {
+        self.roll();
+        self.spent += busy;
+    }
+
+    /// Checks if the budget of the current window has been exceeded.
+    pub(crate) fn check(&mut self) -> Option<Overrun> {
+        self.roll();
+        if self.spent <= self.budget.max {
+            return None;
+        }
+
+        Some(match self.budget.action {
+            Action::Throttle => {
+                let window_ends = self.window_started + self.budget.window;
+                Overrun::Throttle(window_ends.duration_since(self.clock.now()))
+            }
+            Action::Cancel => Overrun::Cancel,
+        })
+    }
+
+    /// Starts a new window if the current one has ended.
+    fn roll(&mut self) {
+        let now = self.clock.now();
+        if now.duration_since(self.window_started) >= self.budget.window {
+            self.window_started = now;
+            self.spent = Duration::ZERO;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{BudgetTracker, Overrun};
+    use crate::{clock::SharedClock, TimeBudget};
+
+    #[tokio::test(start_paused = true)]
+    async fn should_throttle_until_window_ends_once_budget_is_exceeded() {
+        // Arrange
+        let budget = TimeBudget::throttle(Duration::from_millis(100), Duration::from_secs(1));
+        let mut tracker = BudgetTracker::new(budget, SharedClock::default());
+
+        // Act
+        tracker.record(Duration::from_millis(100));
+        let within = tracker.check();
+        tokio::time::advance(Duration::from_millis(200)).await;
+        tracker.record(Duration::from_millis(1));
+        let exceeded = tracker.check();
+        tokio::time::advance(Duration::from_millis(800)).await;
+        let next_window = tracker.check();
+
+        // Assert
+        assert_eq!(None, within);
+        assert_eq!(
+            Some(Overrun::Throttle(Duration::from_millis(800))),
+            exceeded
+        );
+        assert_eq!(None, next_window);
+    }
+}
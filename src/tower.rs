@@ -0,0 +1,283 @@
+//! Interoperability with [`tower`](https://docs.rs/tower/latest/tower/)
+//! services.
+//!
+//! [`ServiceAdapter`] runs a `tower::Service` as a [`Cancellable`], answering
+//! the requests sent through its [`RequestHandle`]. Conversely, the
+//! [`RequestHandle`] of a spawned service implements `tower::Service`, so the
+//! service can be called through tower's middleware, e.g. timeouts or retries.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::{
+//!     convert::Infallible,
+//!     future::Ready,
+//!     task::{Context, Poll},
+//! };
+//!
+//! use cancellable::{tower::ServiceAdapter, Cancellable, CancellationToken};
+//! use tower_service::Service;
+//!
+//! struct Greeter;
+//!
+//! impl Service<String> for Greeter {
+//!     type Response = String;
+//!     type Error = Infallible;
+//!     type Future = Ready<Result<String, Infallible>>;
+//!
+//!     fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+//!         Poll::Ready(Ok(()))
+//!     }
+//!
+//!     fn call(&mut self, name: String) -> Self::Future {
+//!         std::future::ready(Ok(format!("Hello, {name}!")))
+//!     }
+//! }
+//!
+//! # #[tokio::main]
+//! # async fn main() {
+//! let handle = ServiceAdapter::new(Greeter, 16)
+//!     .spawn(CancellationToken::new())
+//!     .await;
+//!
+//! let mut greeter = (*handle).clone();
+//! let greeting = Service::call(&mut greeter, "world".to_owned()).await.unwrap();
+//!
+//! assert_eq!(Ok("Hello, world!".to_owned()), greeting);
+//! # }
+//! ```
+
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use tokio::sync::mpsc;
+use tower_service::Service;
+
+use crate::{request_handle::Envelope, CallError, Cancellable, CancellationResult, RequestHandle};
+
+/// Response of a `tower::Service`, or its error.
+type Reply<S, Req> = Result<<S as Service<Req>>::Response, <S as Service<Req>>::Error>;
+
+/// Service answering requests with a `tower::Service`.
+///
+/// Its handle is a [`RequestHandle`], which resolves to the response, or the
+/// error, returned by the wrapped service. The requests are answered one at a
+/// time, each once the wrapped service reports it's ready. If the wrapped
+/// service fails to become ready, then the adapter fails with its error.
+///
+/// Just like [`RequestService`], the adapter completes once all of its
+/// handles have been dropped, and it answers the requests already queued when
+/// it's cancelled with graceful shutdown enabled.
+///
+/// [`RequestService`]: crate::RequestService
+pub struct ServiceAdapter<S, Req>
+where
+    S: Service<Req>,
+{
+    service: S,
+    receiver: mpsc::Receiver<Envelope<Req, Reply<S, Req>>>,
+    handle: Option<RequestHandle<Req, Reply<S, Req>>>,
+}
+
+impl<S, Req> ServiceAdapter<S, Req>
+where
+    S: Service<Req>,
+{
+    /// Constructs a new adapter answering requests with `service`.
+    ///
+    /// At most `capacity` requests can be queued.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `capacity` is zero.
+    pub fn new(service: S, capacity: usize) -> Self {
+        let (handle, receiver) = RequestHandle::channel(capacity);
+
+        Self {
+            service,
+            receiver,
+            handle: Some(handle),
+        }
+    }
+
+    async fn answer(&mut self, envelope: Envelope<Req, Reply<S, Req>>) -> Result<(), S::Error> {
+        std::future::poll_fn(|cx| self.service.poll_ready(cx)).await?;
+        let response = self.service.call(envelope.request).await;
+        // The caller might have stopped waiting for the response.
+        let _ = envelope.reply.send(response);
+
+        Ok(())
+    }
+}
+
+impl<S, Req> Cancellable for ServiceAdapter<S, Req>
+where
+    S: Service<Req> + Send,
+    S::Response: Send,
+    S::Error: std::fmt::Debug + std::fmt::Display + Send,
+    S::Future: Send,
+    Req: Send,
+{
+    type Result = ();
+    type Handle = RequestHandle<Req, Result<S::Response, S::Error>>;
+    type Error = S::Error;
+    type Output = ();
+
+    fn name(&self) -> &str {
+        std::any::type_name::<S>()
+    }
+
+    async fn new_handle(&mut self) -> Self::Handle {
+        self.handle.take().expect("handle to be present")
+    }
+
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        match self.receiver.recv().await {
+            Some(envelope) => {
+                self.answer(envelope).await?;
+                Ok(CancellationResult::Continue)
+            }
+            None => Ok(CancellationResult::Break),
+        }
+    }
+
+    async fn drain(
+        &mut self,
+    ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        match self.receiver.try_recv() {
+            Ok(envelope) => {
+                self.answer(envelope).await?;
+                Ok(CancellationResult::Continue)
+            }
+            Err(_) => Ok(CancellationResult::Break),
+        }
+    }
+}
+
+impl<S, Req> std::fmt::Debug for ServiceAdapter<S, Req>
+where
+    S: Service<Req>,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ServiceAdapter").finish_non_exhaustive()
+    }
+}
+
+/// Calls the service behind the handle, see [`RequestHandle::call`].
+///
+/// The handle is always ready, as each call waits for capacity in the
+/// service's queue on its own.
+impl<Req, Resp> Service<Req> for RequestHandle<Req, Resp>
+where
+    Req: Send + 'static,
+    Resp: Send + 'static,
+{
+    type Response = Resp;
+    type Error = CallError;
+    type Future = Pin<Box<dyn Future<Output = Result<Resp, CallError>> + Send>>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: Req) -> Self::Future {
+        let handle = self.clone();
+        Box::pin(async move { handle.call(request).await })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        future::Ready,
+        task::{Context, Poll},
+    };
+
+    use tokio_util::sync::CancellationToken;
+    use tower_service::Service;
+
+    use crate::{tower::ServiceAdapter, CallError, Cancellable, CancellableError};
+
+    /// Service which becomes ready every other poll, and fails to become ready
+    /// once it has answered `limit` requests.
+    struct Doubler {
+        answered: u32,
+        limit: u32,
+        ready: bool,
+    }
+
+    impl Service<u32> for Doubler {
+        type Response = u32;
+        type Error = String;
+        type Future = Ready<Result<u32, String>>;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            if self.answered == self.limit {
+                return Poll::Ready(Err("limit reached".to_owned()));
+            }
+
+            self.ready = !self.ready;
+            if self.ready {
+                Poll::Ready(Ok(()))
+            } else {
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+
+        fn call(&mut self, request: u32) -> Self::Future {
+            self.answered += 1;
+            match request {
+                0 => std::future::ready(Err("zero".to_owned())),
+                request => std::future::ready(Ok(request * 2)),
+            }
+        }
+    }
+
+    fn doubler(limit: u32) -> ServiceAdapter<Doubler, u32> {
+        let service = Doubler {
+            answered: 0,
+            limit,
+            ready: false,
+        };
+
+        ServiceAdapter::new(service, 4)
+    }
+
+    #[tokio::test]
+    async fn should_answer_requests_through_tower_service() {
+        // Arrange
+        let handle = doubler(8).spawn(CancellationToken::new()).await;
+        let mut requests = (*handle).clone();
+
+        // Act
+        let doubled = Service::call(&mut requests, 21).await;
+        let failed = Service::call(&mut requests, 0).await;
+
+        // Assert
+        assert_eq!(Ok(Ok(42)), doubled);
+        assert_eq!(Ok(Err("zero".to_owned())), failed);
+    }
+
+    #[tokio::test]
+    async fn should_fail_when_service_fails_to_become_ready() {
+        // Arrange
+        let handle = doubler(1).spawn(CancellationToken::new()).await;
+        let mut requests = (*handle).clone();
+
+        // Act
+        let answered = Service::call(&mut requests, 1).await;
+        let dropped = Service::call(&mut requests, 2).await;
+
+        // Assert
+        assert_eq!(Ok(Ok(2)), answered);
+        assert_eq!(Err(CallError::Dropped), dropped);
+        assert!(matches!(
+            handle.join().await,
+            Err(CancellableError::Service(e)) if e == "limit reached"
+        ));
+    }
+}
@@ -0,0 +1,10 @@
+/// Emits a `tracing` event with the given level, if the `tracing` feature is
+/// enabled. Otherwise expands to nothing.
+macro_rules! event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!($($arg)+);
+    };
+}
+
+pub(crate) use event;
@@ -0,0 +1,187 @@
+use std::{io, net::SocketAddr};
+
+use bytes::Bytes;
+use tokio::{
+    net::{ToSocketAddrs, UdpSocket},
+    sync::mpsc,
+};
+
+use crate::{Cancellable, CancellationResult, MpscSenderHandle};
+
+/// Size of the buffer receiving a single datagram, enough for any IPv4 one.
+const MAX_DATAGRAM_SIZE: usize = 65_507;
+
+/// Service wrapping a [`UdpSocket`], which yields the datagrams it receives
+/// and sends the ones queued through its handle.
+///
+/// Each datagram is yielded along with the address of its sender. The handle
+/// is a [`MpscSenderHandle`] of outbound datagrams paired with their
+/// destination addresses. Both directions are driven by the same work loop,
+/// and waiting for them is cancellation safe, i.e. no received or queued
+/// datagram is lost when the service is cancelled while it waits.
+///
+/// The errors reported by the socket for the datagrams sent earlier, e.g.
+/// `ConnectionRefused` when the destination port is closed, are ignored. Any
+/// other error fails the service.
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{Cancellable, CancellationToken, SenderHandle, UdpService};
+/// use futures::StreamExt;
+///
+/// # #[tokio::main]
+/// # async fn main() -> std::io::Result<()> {
+/// let echo = UdpService::bind("127.0.0.1:0", 16).await?;
+/// let echo_addr = echo.local_addr()?;
+/// let client = UdpService::bind("127.0.0.1:0", 16).await?;
+///
+/// let (echo, mut requests) = echo.spawn_stream(CancellationToken::new()).await;
+/// let (client, mut responses) = client.spawn_stream(CancellationToken::new()).await;
+///
+/// client.send(("ping".into(), echo_addr)).await.unwrap();
+/// let (request, from) = requests.next().await.unwrap();
+/// echo.send((request, from)).await.unwrap();
+///
+/// let (response, _) = responses.next().await.unwrap();
+/// assert_eq!("ping", response);
+/// # Ok(())
+/// # }
+/// ```
+pub struct UdpService {
+    socket: UdpSocket,
+    buffer: Vec<u8>,
+    outbound: mpsc::Receiver<(Bytes, SocketAddr)>,
+    handle: Option<MpscSenderHandle<(Bytes, SocketAddr)>>,
+}
+
+impl UdpService {
+    /// Constructs a new service wrapping `socket`.
+    ///
+    /// At most `capacity` outbound datagrams can be queued.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `capacity` is zero.
+    pub fn new(socket: UdpSocket, capacity: usize) -> Self {
+        let (handle, outbound) = MpscSenderHandle::channel(capacity);
+
+        Self {
+            socket,
+            buffer: vec![0; MAX_DATAGRAM_SIZE],
+            outbound,
+            handle: Some(handle),
+        }
+    }
+
+    /// Constructs a new service with a socket bound to `addr`.
+    ///
+    /// See [`Self::new`].
+    pub async fn bind(addr: impl ToSocketAddrs, capacity: usize) -> io::Result<Self> {
+        Ok(Self::new(UdpSocket::bind(addr).await?, capacity))
+    }
+
+    /// Returns the address the socket is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.socket.local_addr()
+    }
+}
+
+/// Checks if `error` has been caused by a datagram sent earlier, rather than
+/// by the socket itself.
+fn is_delivery_error(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::ConnectionRefused | io::ErrorKind::ConnectionReset
+    )
+}
+
+impl Cancellable for UdpService {
+    type Result = (Bytes, SocketAddr);
+    type Handle = MpscSenderHandle<(Bytes, SocketAddr)>;
+    type Error = io::Error;
+    type Output = ();
+
+    async fn new_handle(&mut self) -> Self::Handle {
+        self.handle.take().expect("handle to be present")
+    }
+
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        let result = tokio::select! {
+            received = self.socket.recv_from(&mut self.buffer) => received.map(|(len, from)| {
+                CancellationResult::item((Bytes::copy_from_slice(&self.buffer[..len]), from))
+            }),
+            Some((datagram, to)) = self.outbound.recv() => self
+                .socket
+                .send_to(&datagram, to)
+                .await
+                .map(|_| CancellationResult::Continue),
+        };
+
+        match result {
+            Err(e) if is_delivery_error(&e) => Ok(CancellationResult::Continue),
+            result => result,
+        }
+    }
+}
+
+impl std::fmt::Debug for UdpService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UdpService")
+            .field("socket", &self.socket)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::StreamExt;
+    use tokio::net::UdpSocket;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{Cancellable, SenderHandle, UdpService};
+
+    #[tokio::test]
+    async fn should_receive_and_send_datagrams() {
+        // Arrange
+        let peer = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let service = UdpService::bind("127.0.0.1:0", 4).await.unwrap();
+        let service_addr = service.local_addr().unwrap();
+        let (handle, mut datagrams) = service.spawn_stream(CancellationToken::new()).await;
+
+        // Act
+        peer.send_to(b"hello", service_addr).await.unwrap();
+        let (received, from) = datagrams.next().await.unwrap();
+        handle.send(("world".into(), from)).await.unwrap();
+        let mut buffer = [0; 16];
+        let (len, _) = peer.recv_from(&mut buffer).await.unwrap();
+
+        // Assert
+        assert_eq!("hello", received);
+        assert_eq!(peer.local_addr().unwrap(), from);
+        assert_eq!(b"world", &buffer[..len]);
+        handle.cancel();
+        assert!(handle.join().await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn should_ignore_refused_delivery() {
+        // Arrange
+        let closed = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let closed_addr = closed.local_addr().unwrap();
+        drop(closed);
+        let peer = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let service = UdpService::bind("127.0.0.1:0", 4).await.unwrap();
+        let service_addr = service.local_addr().unwrap();
+        let (handle, mut datagrams) = service.spawn_stream(CancellationToken::new()).await;
+
+        // Act
+        handle.send(("lost".into(), closed_addr)).await.unwrap();
+        peer.send_to(b"hello", service_addr).await.unwrap();
+        let received = datagrams.next().await;
+
+        // Assert
+        assert_eq!("hello", received.unwrap().0);
+        assert!(!handle.is_finished());
+    }
+}
@@ -0,0 +1,297 @@
+use std::{future::Future, sync::Arc, time::Duration};
+
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
+
+use crate::{cancel_reason::ReasonSlot, clock::SharedClock, trace::event, CancelReason};
+
+/// Watchdog detecting stalled services.
+///
+/// A service is considered stalled when none of its calls to
+/// [`Cancellable::run`] completes within the watchdog's interval, and the
+/// service doesn't signal its progress with [`RunContext::ping`] either. The
+/// time the work loop spends waiting on its own, e.g. for the delay of
+/// [`CancellationResult::Delay`], before a restart, or for the rate limit,
+/// doesn't count. A stall is always reported with a warning event, when the
+/// `tracing` feature is enabled. It's reported again only after the service
+/// has made progress.
+///
+/// Unlike [`SpawnOptions::iteration_timeout`], the watchdog doesn't interrupt
+/// the stalled call.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+///
+/// use cancellable::{SpawnOptions, Watchdog};
+///
+/// let watchdog = Watchdog::new(Duration::from_secs(30))
+///     .on_stall(|| eprintln!("Service has stalled"))
+///     .cancel_on_stall();
+/// let options = SpawnOptions::new().watchdog(watchdog);
+/// ```
+///
+/// [`Cancellable::run`]: crate::Cancellable::run
+/// [`RunContext::ping`]: crate::RunContext::ping
+/// [`CancellationResult::Delay`]: crate::CancellationResult#variant.Delay
+/// [`SpawnOptions::iteration_timeout`]: crate::SpawnOptions::iteration_timeout
+#[derive(Clone)]
+pub struct Watchdog {
+    interval: Duration,
+    on_stall: Option<Arc<dyn Fn() + Send + Sync>>,
+    cancel_on_stall: bool,
+}
+
+impl Watchdog {
+    /// Constructs a watchdog requiring the service to make progress at least
+    /// once every `interval`.
+    pub fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            on_stall: None,
+            cancel_on_stall: false,
+        }
+    }
+
+    /// Calls `f` every time the service stalls.
+    pub fn on_stall<F>(mut self, f: F) -> Self
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.on_stall = Some(Arc::new(f));
+        self
+    }
+
+    /// Cancels the service with [`Stalled`] when it stalls.
+    pub fn cancel_on_stall(mut self) -> Self {
+        self.cancel_on_stall = true;
+        self
+    }
+
+    /// Monitors the `heartbeats` of a service, whose cancellation token is
+    /// `cancellation_token`.
+    ///
+    /// The returned future never completes.
+    pub(crate) async fn monitor(
+        self,
+        mut heartbeats: watch::Receiver<bool>,
+        cancellation_token: CancellationToken,
+        cancel_reason: ReasonSlot,
+        clock: SharedClock,
+    ) {
+        loop {
+            if *heartbeats.borrow_and_update() {
+                // The work loop is waiting on its own, so the service cannot
+                // stall until it's resumed.
+                if heartbeats.changed().await.is_err() {
+                    break;
+                }
+                continue;
+            }
+
+            match clock.timeout(self.interval, heartbeats.changed()).await {
+                Some(Ok(())) => continue,
+                Some(Err(_)) => break,
+                None => {}
+            }
+
+            event!(warn, interval = ?self.interval, "Service has stalled");
+            if let Some(on_stall) = &self.on_stall {
+                on_stall();
+            }
+            if self.cancel_on_stall {
+                let _ = cancel_reason.set(CancelReason::new(Stalled));
+                cancellation_token.cancel();
+            }
+
+            if heartbeats.changed().await.is_err() {
+                break;
+            }
+        }
+
+        std::future::pending().await
+    }
+}
+
+/// Reason of the cancellation of a service which has stalled.
+///
+/// See [`Watchdog::cancel_on_stall`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Stalled;
+
+/// Heartbeats of a service, monitored by its [`Watchdog`].
+///
+/// They're paused while the work loop is waiting on its own, since the
+/// service cannot stall then.
+#[derive(Debug, Clone)]
+pub(crate) struct Heartbeat(Arc<watch::Sender<bool>>);
+
+impl Heartbeat {
+    pub(crate) fn new() -> Self {
+        Self(Arc::new(watch::channel(false).0))
+    }
+
+    /// Signals that the service is making progress.
+    pub(crate) fn beat(&self) {
+        self.0.send_replace(false);
+    }
+
+    /// Awaits `future` with the heartbeats paused, and signals the progress
+    /// once it has completed.
+    pub(crate) async fn paused<F>(&self, future: F) -> F::Output
+    where
+        F: Future,
+    {
+        self.0.send_replace(true);
+        let output = future.await;
+        self.beat();
+        output
+    }
+
+    /// Returns a receiver of the heartbeats, whose value tells whether they're
+    /// paused.
+    pub(crate) fn subscribe(&self) -> watch::Receiver<bool> {
+        self.0.subscribe()
+    }
+}
+
+impl std::fmt::Debug for Watchdog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Watchdog")
+            .field("interval", &self.interval)
+            .field("cancel_on_stall", &self.cancel_on_stall)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{Cancellable, CancellationResult, RunContext, SpawnOptions, Stalled, Watchdog};
+
+    struct StallingCancellable {
+        pings: usize,
+    }
+
+    impl Cancellable for StallingCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            let context = RunContext::current().unwrap();
+            for _ in 0..self.pings {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                context.ping();
+            }
+
+            if self.pings == 0 {
+                std::future::pending().await
+            } else {
+                Ok(CancellationResult::Break)
+            }
+        }
+    }
+
+    fn watchdog(stalls: &Arc<AtomicUsize>) -> Watchdog {
+        let stalls = Arc::clone(stalls);
+        Watchdog::new(Duration::from_millis(50))
+            .on_stall(move || {
+                stalls.fetch_add(1, Ordering::SeqCst);
+            })
+            .cancel_on_stall()
+    }
+
+    #[tokio::test]
+    async fn should_cancel_stalled_service() {
+        // Arrange
+        let stalls = Arc::new(AtomicUsize::new(0));
+        let options = SpawnOptions::new().watchdog(watchdog(&stalls));
+
+        // Act
+        let handle = StallingCancellable { pings: 0 }
+            .spawn_with_options(CancellationToken::new(), options, |_| {
+                crate::CallbackResult::Continue
+            })
+            .await;
+        let result = tokio::time::timeout(Duration::from_secs(1), handle.join_with_reason()).await;
+
+        // Assert
+        let (result, reason) = result.unwrap();
+        assert!(result.is_ok());
+        assert!(reason.unwrap().is::<Stalled>());
+        assert_eq!(1, stalls.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn should_not_report_service_pinging_watchdog() {
+        // Arrange
+        let stalls = Arc::new(AtomicUsize::new(0));
+        let options = SpawnOptions::new().watchdog(watchdog(&stalls));
+
+        // Act
+        let handle = StallingCancellable { pings: 10 }
+            .spawn_with_options(CancellationToken::new(), options, |_| {
+                crate::CallbackResult::Continue
+            })
+            .await;
+        handle.join().await.unwrap();
+
+        // Assert
+        assert_eq!(0, stalls.load(Ordering::SeqCst));
+    }
+
+    struct DelayingCancellable {
+        delays: usize,
+    }
+
+    impl Cancellable for DelayingCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            if self.delays == 0 {
+                return Ok(CancellationResult::Break);
+            }
+            self.delays -= 1;
+            Ok(CancellationResult::Delay(Duration::from_secs(1)))
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn should_not_report_service_waiting_for_delay() {
+        // Arrange
+        let stalls = Arc::new(AtomicUsize::new(0));
+        let options = SpawnOptions::new().watchdog(watchdog(&stalls));
+
+        // Act
+        let handle = DelayingCancellable { delays: 3 }
+            .spawn_with_options(CancellationToken::new(), options, |_| {
+                crate::CallbackResult::Continue
+            })
+            .await;
+        let (result, reason) = handle.join_with_reason().await;
+
+        // Assert
+        assert!(result.is_ok());
+        assert!(reason.is_none());
+        assert_eq!(0, stalls.load(Ordering::SeqCst));
+    }
+}
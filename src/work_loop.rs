@@ -0,0 +1,902 @@
+use std::{
+    any::Any,
+    future::Future,
+    ops::ControlFlow,
+    panic::AssertUnwindSafe,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use tokio::sync::{mpsc, watch};
+use tokio_util::sync::CancellationToken;
+
+use crate::{
+    ack::AckTracker,
+    callback_panic::CallbackPanicked,
+    cancel_reason::ReasonSlot,
+    cancellable_handle::ServiceResult,
+    catch_unwind::CatchUnwind,
+    clock::SharedClock,
+    completion_reason::CompletionSlot,
+    idle,
+    in_flight::InFlight,
+    observer::Observers,
+    rate_limiter::RateLimiter,
+    run_context::{ItemSender, YieldedItem},
+    service_stats::{StatsCounters, StatsSlot},
+    spawn_options::Metrics,
+    time_budget::{BudgetExceeded, BudgetTracker, Overrun},
+    trace::event,
+    watchdog::Heartbeat,
+    CallbackPanicPolicy, CallbackResult, CancelReason, CancellationPriority, CancellationResult,
+    CompletionReason, ErrorDirective, Health, Idle, LocalCancellable, RunContext, Scope,
+    SpawnOptions,
+};
+
+/// Result of a single call to [`LocalCancellable::run`].
+type RunResult<T> = Result<
+    CancellationResult<<T as LocalCancellable>::Result, <T as LocalCancellable>::Output>,
+    <T as LocalCancellable>::Error,
+>;
+
+/// State of a spawned service's work loop.
+pub(crate) struct WorkLoop<T, F>
+where
+    T: LocalCancellable,
+{
+    service: T,
+    dispatcher: Dispatcher,
+    cancellation_token: CancellationToken,
+    options: SpawnOptions,
+    callback: F,
+    restart_attempts: usize,
+    health: Arc<watch::Sender<Health>>,
+    heartbeat: Heartbeat,
+    activity: Arc<watch::Sender<()>>,
+    cancel_reason: ReasonSlot,
+    completion: CompletionSlot,
+    children: Scope,
+    rate_limiter: Option<RateLimiter>,
+    budget: Option<BudgetTracker>,
+    stats: StatsSlot,
+    iterations: Arc<AtomicU64>,
+    item_sender: ItemSender,
+    items: mpsc::Receiver<YieldedItem>,
+    verdict: Option<Result<Exit<T::Output>, T::Error>>,
+    dead_letters: Option<mpsc::Sender<T::Result>>,
+    tracking: Tracking<T::Result>,
+}
+
+/// How the values passed to the callback are tracked until their consumer
+/// has processed them.
+#[derive(Debug)]
+pub(crate) enum Tracking<R> {
+    /// The values are processed by the callback itself.
+    None,
+
+    /// The values are processed once they're taken from the consumer's queue.
+    InFlight(InFlight),
+
+    /// The values are processed once they're acknowledged.
+    Acks(AckTracker<R>),
+}
+
+impl<R> Tracking<R> {
+    fn in_flight(&self) -> Option<&InFlight> {
+        match self {
+            Self::None => None,
+            Self::InFlight(in_flight) => Some(in_flight),
+            Self::Acks(acks) => Some(acks.in_flight()),
+        }
+    }
+}
+
+/// Reason of the work loop's completion.
+enum Exit<O> {
+    Completed(Option<O>),
+    Cancelled,
+}
+
+impl<O> Exit<O> {
+    /// Returns the final value of the service, if it has completed with one.
+    fn into_output(self) -> Option<O> {
+        match self {
+            Self::Completed(output) => output,
+            Self::Cancelled => None,
+        }
+    }
+}
+
+impl<T, F> WorkLoop<T, F>
+where
+    T: LocalCancellable + 'static,
+    F: FnMut(T::Result) -> CallbackResult<T::Error, T::Result>,
+{
+    pub(crate) fn new(
+        service: T,
+        cancellation_token: CancellationToken,
+        options: SpawnOptions,
+        callback: F,
+    ) -> Self {
+        let rate_limiter = options
+            .rate_limit
+            .map(|max_per_second| RateLimiter::new(max_per_second, options.clock.clone()));
+        let budget = options
+            .time_budget
+            .map(|budget| BudgetTracker::new(budget, options.clock.clone()));
+        let stats = Arc::new(StatsCounters::new(options.clock.now()));
+        let (item_sender, items) = ItemSender::channel::<T::Result>();
+        let children = Scope::new(cancellation_token.child_token());
+        let cancel_reason = ReasonSlot::default();
+        let completion = CompletionSlot::default();
+        let dispatcher = Dispatcher {
+            name: service.name().to_owned(),
+            metrics: options.metrics.clone(),
+            observers: options.observers.clone(),
+            callback_panic: options.callback_panic,
+            panicked: AtomicBool::new(false),
+            completion: Arc::clone(&completion),
+            cancel_reason: Arc::clone(&cancel_reason),
+            cancellation_token: cancellation_token.clone(),
+        };
+
+        Self {
+            service,
+            dispatcher,
+            cancellation_token,
+            options,
+            callback,
+            restart_attempts: 0,
+            health: Arc::new(watch::channel(Health::default()).0),
+            heartbeat: Heartbeat::new(),
+            activity: Arc::new(watch::channel(()).0),
+            cancel_reason,
+            completion,
+            children,
+            rate_limiter,
+            budget,
+            stats,
+            iterations: Arc::default(),
+            item_sender,
+            items,
+            verdict: None,
+            dead_letters: None,
+            tracking: Tracking::None,
+        }
+    }
+
+    /// Diverts the values which cannot be delivered to `dead_letters`.
+    pub(crate) fn with_dead_letters(mut self, dead_letters: mpsc::Sender<T::Result>) -> Self {
+        self.dead_letters = Some(dead_letters);
+        self
+    }
+
+    /// Tracks the values passed to the callback with `tracking`.
+    pub(crate) fn with_tracking(mut self, tracking: Tracking<T::Result>) -> Self {
+        self.tracking = tracking;
+        self
+    }
+
+    /// Returns the statistics of the service.
+    pub(crate) fn stats(&self) -> StatsSlot {
+        Arc::clone(&self.stats)
+    }
+
+    /// Returns a receiver of the health reported by the service.
+    pub(crate) fn health(&self) -> watch::Receiver<Health> {
+        self.health.subscribe()
+    }
+
+    /// Returns the slot for the reason of the service's cancellation.
+    pub(crate) fn cancel_reason(&self) -> ReasonSlot {
+        Arc::clone(&self.cancel_reason)
+    }
+
+    /// Returns the slot for the reason of the service's completion.
+    pub(crate) fn completion(&self) -> CompletionSlot {
+        Arc::clone(&self.completion)
+    }
+
+    /// Returns the scope of the service's children.
+    pub(crate) fn children(&self) -> Scope {
+        self.children.clone()
+    }
+
+    /// Drives the service until it completes.
+    pub(crate) async fn run(self) -> ServiceResult<T> {
+        self.run_with_service().await.0
+    }
+
+    /// Drives the service until it completes, and gives the service back
+    /// along with its result.
+    pub(crate) async fn run_with_service(mut self) -> (ServiceResult<T>, T) {
+        let cancellation_token = self.cancellation_token.clone();
+        let soft_stop = self.options.soft_stop.clone().unwrap_or_default();
+        let watchdog = self.options.watchdog.clone().map(|watchdog| {
+            watchdog.monitor(
+                self.heartbeat.subscribe(),
+                cancellation_token.clone(),
+                Arc::clone(&self.cancel_reason),
+                self.options.clock.clone(),
+            )
+        });
+        let idle = self.options.idle_timeout.map(|timeout| {
+            idle::monitor(
+                timeout,
+                self.activity.subscribe(),
+                cancellation_token.clone(),
+                Arc::clone(&self.cancel_reason),
+                self.options.clock.clone(),
+            )
+        });
+        let monitors = async {
+            tokio::join!(never_completing(watchdog), never_completing(idle));
+        };
+        let context = RunContext::new(
+            &cancellation_token,
+            &soft_stop,
+            Arc::clone(&self.health),
+            self.heartbeat.clone(),
+            Arc::clone(&self.cancel_reason),
+            Arc::clone(&self.iterations),
+            self.item_sender.clone(),
+        )
+        .with_children(self.children.clone())
+        .with_activity(Arc::clone(&self.activity))
+        .with_stats(Arc::clone(&self.stats))
+        .with_completion(Arc::clone(&self.completion))
+        .with_clock(self.options.clock.clone());
+        let _children = context.children_guard();
+
+        let future = async {
+            tokio::select! {
+                biased;
+                result = self.run_to_completion() => result,
+                _ = monitors => unreachable!("monitors never complete"),
+            }
+        };
+        let result = context.clone().enter(future).await;
+        if let Tracking::Acks(acks) = &self.tracking {
+            let acks = acks.clone();
+            acks.settle(|item| self.drop_item(item)).await;
+        }
+        context.close_children().await;
+
+        // A more specific reason may have been recorded already, e.g. by the
+        // callback, so these are only the fallbacks.
+        let result = match result {
+            Ok(output) => {
+                event!(debug, "Service has completed");
+                let _ = self.completion.set(CompletionReason::Completed);
+                Ok(output)
+            }
+            Err(e) => {
+                event!(error, error = %e, "Service has failed");
+                let _ = self.completion.set(CompletionReason::Failed);
+                Err(e)
+            }
+        };
+        if let Some(reason) = self.completion.get() {
+            self.dispatcher
+                .observers
+                .on_finish(&self.dispatcher.name, *reason);
+        }
+        (result, self.service)
+    }
+
+    async fn run_to_completion(&mut self) -> ServiceResult<T> {
+        self.service.on_start().await?;
+        event!(debug, "Service has started");
+        self.dispatcher.observers.on_start(&self.dispatcher.name);
+
+        let output = match self.work().await? {
+            Exit::Completed(output) => output,
+            Exit::Cancelled => {
+                event!(debug, "Service has been cancelled");
+                self.dispatcher
+                    .observers
+                    .on_cancelled(&self.dispatcher.name);
+                let idle = self
+                    .cancel_reason
+                    .get()
+                    .is_some_and(|reason| reason.is::<Idle>());
+                let reason = if idle {
+                    CompletionReason::Idle
+                } else {
+                    CompletionReason::Cancelled
+                };
+                let _ = self.completion.set(reason);
+                self.service.on_cancel().await;
+                let output = if self.options.graceful_shutdown {
+                    self.drain().await?
+                } else {
+                    None
+                };
+                output.or_else(|| self.service.cancelled_output())
+            }
+        };
+
+        self.service.on_stop().await;
+        Ok(output)
+    }
+
+    /// Repetitively calls [`LocalCancellable::run`] until the service breaks,
+    /// fails, or is cancelled.
+    async fn work(&mut self) -> Result<Exit<T::Output>, T::Error> {
+        loop {
+            let result = self.iterate().await;
+            if let Some(verdict) = self.verdict.take() {
+                return verdict;
+            }
+            let Some(result) = result else {
+                return Ok(Exit::Cancelled);
+            };
+
+            let result = match result {
+                Ok(result) => {
+                    self.restart_attempts = 0;
+                    result
+                }
+                Err(e) => {
+                    self.dispatcher
+                        .observers
+                        .on_error(&self.dispatcher.name, &e);
+                    let e = match self.service.on_error(e).await {
+                        ErrorDirective::Continue => {
+                            self.stats.record_tolerated_error();
+                            continue;
+                        }
+                        ErrorDirective::Retry { delay } => {
+                            self.stats.record_tolerated_error();
+                            event!(debug, ?delay, "Service has failed, retrying");
+                            if sleep(
+                                &self.cancellation_token,
+                                &self.options.clock,
+                                &self.heartbeat,
+                                delay,
+                            )
+                            .await
+                            .is_break()
+                            {
+                                return Ok(Exit::Cancelled);
+                            }
+                            continue;
+                        }
+                        ErrorDirective::Break => return Ok(Exit::Completed(None)),
+                        ErrorDirective::Fail(e) => e,
+                    };
+
+                    let Some(delay) = self.restart_delay() else {
+                        return Err(e);
+                    };
+                    event!(warn, error = %e, ?delay, "Service has failed, restarting");
+                    if sleep(
+                        &self.cancellation_token,
+                        &self.options.clock,
+                        &self.heartbeat,
+                        delay,
+                    )
+                    .await
+                    .is_break()
+                    {
+                        return Ok(Exit::Cancelled);
+                    }
+
+                    self.restart_attempts += 1;
+                    self.stats.record_restart();
+                    self.service.restart(e).await?;
+                    continue;
+                }
+            };
+
+            if let CancellationResult::Delay(delay) = result {
+                if sleep(
+                    &self.cancellation_token,
+                    &self.options.clock,
+                    &self.heartbeat,
+                    delay,
+                )
+                .await
+                .is_break()
+                {
+                    return Ok(Exit::Cancelled);
+                }
+                continue;
+            }
+
+            if let ControlFlow::Break(exit) = self.handle(result) {
+                return exit;
+            }
+        }
+    }
+
+    /// Performs a single iteration of the loop.
+    ///
+    /// Returns `None` if the service has been cancelled mid iteration.
+    async fn iterate(&mut self) -> Option<RunResult<T>> {
+        let cooperative = self.options.cooperative_cancellation;
+        let priority = self.options.cancellation_priority;
+        // No new iteration is started once the service has been cancelled, as
+        // an iteration in flight gets to complete if it's ready.
+        if self.cancellation_token.is_cancelled() {
+            return None;
+        }
+
+        if let Some(delay) = self.rate_limiter.as_mut().and_then(RateLimiter::acquire) {
+            if sleep(
+                &self.cancellation_token,
+                &self.options.clock,
+                &self.heartbeat,
+                delay,
+            )
+            .await
+            .is_break()
+            {
+                return None;
+            }
+        }
+
+        if let Some(overrun) = self.budget.as_mut().and_then(BudgetTracker::check) {
+            let cancelled = match overrun {
+                Overrun::Throttle(delay) => {
+                    event!(debug, ?delay, "Service is over its time budget, throttling");
+                    sleep(
+                        &self.cancellation_token,
+                        &self.options.clock,
+                        &self.heartbeat,
+                        delay,
+                    )
+                    .await
+                    .is_break()
+                }
+                Overrun::Cancel => {
+                    event!(debug, "Service is over its time budget, cancelling");
+                    let _ = self.cancel_reason.set(CancelReason::new(BudgetExceeded));
+                    self.cancellation_token.cancel();
+                    true
+                }
+            };
+            if cancelled {
+                return None;
+            }
+        }
+
+        if let (Some(limit), Some(in_flight)) =
+            (self.options.max_in_flight, self.tracking.in_flight())
+        {
+            let wait = async {
+                tokio::select! {
+                    biased;
+                    _ = self.cancellation_token.cancelled() => ControlFlow::Break(()),
+                    _ = in_flight.wait_below(limit) => ControlFlow::Continue(()),
+                }
+            };
+            if self.heartbeat.paused(wait).await.is_break() {
+                return None;
+            }
+        }
+
+        if let Some(every) = self.options.yield_every {
+            let completed = self.iterations.load(Ordering::Relaxed);
+            if completed > 0 && completed % every == 0 {
+                tokio::task::yield_now().await;
+            }
+        }
+
+        let iteration_timeout = self.options.iteration_timeout;
+        let started = self.options.clock.now();
+        self.iterations.fetch_add(1, Ordering::Relaxed);
+        let run = race(
+            &self.cancellation_token,
+            cooperative,
+            priority,
+            timeout(
+                &self.options.clock,
+                iteration_timeout,
+                CatchUnwind::new(self.service.run()),
+            ),
+        );
+        let callback = &mut self.callback;
+        let dispatcher = &self.dispatcher;
+        let activity = &self.activity;
+        let clock = &self.options.clock;
+        let stats = &self.stats;
+        let mut callback_time = Duration::ZERO;
+        let mut dropped = Vec::new();
+        let mut rejected = Vec::new();
+        let result = forward(
+            run,
+            &mut self.items,
+            &mut self.verdict,
+            &mut dropped,
+            |item| {
+                activity.send_replace(());
+                let delivered = clock.now();
+                let flow = dispatcher.deliver(callback, item);
+                let now = clock.now();
+                stats.record_item(now.duration_since(delivered), now);
+                callback_time += now.duration_since(delivered);
+                match flow {
+                    ControlFlow::Continue(item) => {
+                        rejected.extend(item);
+                        ControlFlow::Continue(())
+                    }
+                    ControlFlow::Break(result) => ControlFlow::Break(result),
+                }
+            },
+        )
+        .await;
+        let now = self.options.clock.now();
+        let elapsed = now.duration_since(started);
+        self.stats
+            .record_iteration(elapsed.saturating_sub(callback_time), now);
+        if let Some(budget) = &mut self.budget {
+            budget.record(elapsed);
+        }
+        rejected
+            .into_iter()
+            .chain(dropped)
+            .for_each(|item| self.drop_item(item));
+        self.drop_unacked();
+        let result = result?;
+        self.heartbeat.beat();
+
+        if let Some(metrics) = &self.options.metrics {
+            metrics
+                .0
+                .on_iteration(self.options.clock.now().duration_since(started));
+            if let Some(Ok(Err(_))) = &result {
+                metrics.0.on_error();
+            }
+        }
+
+        match result {
+            Some(Ok(result)) => Some(result),
+            Some(Err(panic)) => {
+                event!(error, "Service has panicked");
+                race(
+                    &self.cancellation_token,
+                    cooperative,
+                    priority,
+                    self.service.on_panic(panic),
+                )
+                .await
+            }
+            None => {
+                event!(warn, "Iteration has timed out");
+                race(
+                    &self.cancellation_token,
+                    cooperative,
+                    priority,
+                    self.service.on_timeout(),
+                )
+                .await
+            }
+        }
+    }
+
+    /// Repetitively calls [`LocalCancellable::drain`] until it breaks, fails, or
+    /// the drain timeout elapses.
+    async fn drain(&mut self) -> ServiceResult<T> {
+        let drain_timeout = self.options.drain_timeout;
+        let clock = self.options.clock.clone();
+        let drain = async {
+            loop {
+                let result = self.service.drain().await?;
+                if let CancellationResult::Delay(delay) = result {
+                    // The service has already been cancelled, so the drain
+                    // timeout is the only limit.
+                    clock.sleep(delay).await;
+                    continue;
+                }
+
+                if let ControlFlow::Break(exit) = self.handle(result) {
+                    return exit.map(Exit::into_output);
+                }
+            }
+        };
+
+        match drain_timeout {
+            Some(timeout) => clock.timeout(timeout, drain).await.unwrap_or(Ok(None)),
+            None => drain.await,
+        }
+    }
+
+    /// Passes the result of a single iteration to the callback and decides
+    /// whether the loop should continue.
+    fn handle(
+        &mut self,
+        result: CancellationResult<T::Result, T::Output>,
+    ) -> ControlFlow<Result<Exit<T::Output>, T::Error>> {
+        match result {
+            CancellationResult::Item(item) => self.deliver(item),
+            CancellationResult::Items(items) => {
+                let mut items = items.into_iter();
+                let flow = items.try_for_each(|item| self.deliver(item));
+                items.for_each(|item| self.drop_item(item));
+                flow
+            }
+            CancellationResult::LastItem(item) => match self.deliver(item) {
+                ControlFlow::Continue(()) => ControlFlow::Break(Ok(Exit::Completed(None))),
+                flow => flow,
+            },
+            // The delay is applied by the caller.
+            CancellationResult::Continue | CancellationResult::Delay(_) => {
+                ControlFlow::Continue(())
+            }
+            CancellationResult::Break => ControlFlow::Break(Ok(Exit::Completed(None))),
+            CancellationResult::BreakWith(output) => {
+                ControlFlow::Break(Ok(Exit::Completed(Some(output))))
+            }
+            CancellationResult::Cancelled => ControlFlow::Break(Ok(Exit::Cancelled)),
+        }
+    }
+
+    /// Passes a single yielded value to the callback.
+    fn deliver(&mut self, item: T::Result) -> ControlFlow<Result<Exit<T::Output>, T::Error>> {
+        self.activity.send_replace(());
+        let delivered = self.options.clock.now();
+        let flow = self.dispatcher.deliver(&mut self.callback, item);
+        let now = self.options.clock.now();
+        self.stats.record_item(now.duration_since(delivered), now);
+        if let Some(budget) = &mut self.budget {
+            budget.record(now.duration_since(delivered));
+        }
+        match flow {
+            ControlFlow::Continue(rejected) => {
+                rejected.into_iter().for_each(|item| self.drop_item(item));
+                ControlFlow::Continue(())
+            }
+            ControlFlow::Break(result) => ControlFlow::Break(result),
+        }
+    }
+
+    /// Diverts a value which cannot be delivered to the dead letters, or passes
+    /// it to [`LocalCancellable::on_item_dropped`] if there are none, or
+    /// they're full.
+    fn drop_item(&mut self, item: T::Result) {
+        let item = match &self.dead_letters {
+            Some(dead_letters) => match dead_letters.try_send(item) {
+                Ok(()) => return,
+                Err(e) => e.into_inner(),
+            },
+            None => item,
+        };
+        self.service.on_item_dropped(item);
+    }
+
+    /// Drops the values which the callback has handed back without
+    /// acknowledging them so far.
+    fn drop_unacked(&mut self) {
+        let unacked = match &self.tracking {
+            Tracking::Acks(acks) => acks.take_unacked(),
+            _ => Vec::new(),
+        };
+        unacked.into_iter().for_each(|item| self.drop_item(item));
+    }
+
+    /// Returns the delay before the next restart, or `None` if the service
+    /// shouldn't be restarted.
+    fn restart_delay(&self) -> Option<Duration> {
+        self.options
+            .restart_policy
+            .as_ref()
+            .and_then(|policy| policy.delay(self.restart_attempts))
+    }
+}
+
+/// Sleeps for `duration` with `clock` unless `cancellation_token` is
+/// cancelled in the meantime, with the `heartbeat` paused.
+async fn sleep(
+    cancellation_token: &CancellationToken,
+    clock: &SharedClock,
+    heartbeat: &Heartbeat,
+    duration: Duration,
+) -> ControlFlow<()> {
+    heartbeat
+        .paused(async {
+            tokio::select! {
+                _ = cancellation_token.cancelled() => ControlFlow::Break(()),
+                _ = clock.sleep(duration) => ControlFlow::Continue(()),
+            }
+        })
+        .await
+}
+
+/// Awaits `monitor`, if there's one, or never completes.
+async fn never_completing<F>(monitor: Option<F>)
+where
+    F: Future<Output = ()>,
+{
+    match monitor {
+        Some(monitor) => monitor.await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Passes the values yielded by a service to its callback.
+///
+/// It's kept apart from the rest of the [`WorkLoop`], so the callback can be
+/// called while the service is borrowed by the iteration in flight.
+#[derive(Debug)]
+struct Dispatcher {
+    name: String,
+    metrics: Option<Metrics>,
+    observers: Observers,
+    callback_panic: CallbackPanicPolicy,
+    /// Set once the callback has panicked and the service has been cancelled
+    /// because of it, so the callback isn't called anymore.
+    panicked: AtomicBool,
+    completion: CompletionSlot,
+    cancel_reason: ReasonSlot,
+    cancellation_token: CancellationToken,
+}
+
+impl Dispatcher {
+    /// Passes a single value yielded by the service to `callback`.
+    ///
+    /// If the callback breaks or fails, it's recorded in the completion slot.
+    /// The loop continues with the value the callback has rejected, if any,
+    /// which has to be dropped. Once the callback has panicked and the service
+    /// has been cancelled because of it, the values are rejected right away.
+    fn deliver<R, O, E, F>(
+        &self,
+        callback: &mut F,
+        item: R,
+    ) -> ControlFlow<Result<Exit<O>, E>, Option<R>>
+    where
+        E: std::fmt::Display,
+        F: FnMut(R) -> CallbackResult<E, R>,
+    {
+        if self.panicked.load(Ordering::Relaxed) {
+            return ControlFlow::Continue(Some(item));
+        }
+
+        event!(trace, "Service has yielded an item");
+        if let Some(metrics) = &self.metrics {
+            metrics.0.on_item();
+        }
+        self.observers.on_item(&self.name);
+        let result = match self.callback_panic {
+            CallbackPanicPolicy::Propagate => callback(item),
+            CallbackPanicPolicy::Skip | CallbackPanicPolicy::Cancel => {
+                // The callback is told to keep its state consistent when it
+                // panics, see `SpawnOptions::callback_panic`.
+                match std::panic::catch_unwind(AssertUnwindSafe(|| callback(item))) {
+                    Ok(result) => result,
+                    Err(panic) => return self.on_panic(panic),
+                }
+            }
+        };
+        match result {
+            CallbackResult::Continue => ControlFlow::Continue(None),
+            CallbackResult::Reject(item) => {
+                event!(debug, "Callback has rejected an item");
+                ControlFlow::Continue(Some(item))
+            }
+            CallbackResult::Break => {
+                event!(debug, "Callback has requested to break");
+                let _ = self.completion.set(CompletionReason::Rejected);
+                ControlFlow::Break(Ok(Exit::Completed(None)))
+            }
+            CallbackResult::Fail(e) => {
+                event!(warn, error = %e, "Callback has failed");
+                let _ = self.completion.set(CompletionReason::Rejected);
+                ControlFlow::Break(Err(e))
+            }
+        }
+    }
+
+    /// Reports a caught panic of the callback, and cancels the service if
+    /// the policy says so, in which case the loop breaks, so the rest of the
+    /// values are dropped.
+    fn on_panic<R, O, E>(
+        &self,
+        panic: Box<dyn Any + Send>,
+    ) -> ControlFlow<Result<Exit<O>, E>, Option<R>> {
+        event!(error, "Callback has panicked");
+        self.observers.on_callback_panic(&self.name, &*panic);
+        if self.callback_panic != CallbackPanicPolicy::Cancel {
+            return ControlFlow::Continue(None);
+        }
+
+        let reason = CallbackPanicked::new(&*panic);
+        let _ = self.cancel_reason.set(CancelReason::new(reason));
+        self.panicked.store(true, Ordering::Relaxed);
+        self.cancellation_token.cancel();
+        ControlFlow::Break(Ok(Exit::Cancelled))
+    }
+}
+
+/// Awaits `future`, meanwhile passing the values yielded with
+/// [`RunContext::yield_item`] to `deliver`.
+///
+/// Once `deliver` breaks, no more values are taken and its verdict is stored
+/// in `verdict`. The values taken in the meantime are moved to `dropped`.
+async fn forward<Fut, R, B, D>(
+    future: Fut,
+    items: &mut mpsc::Receiver<YieldedItem>,
+    verdict: &mut Option<B>,
+    dropped: &mut Vec<R>,
+    mut deliver: D,
+) -> Fut::Output
+where
+    Fut: Future,
+    R: 'static,
+    D: FnMut(R) -> ControlFlow<B>,
+{
+    let mut accept = |item: YieldedItem, items: &mut mpsc::Receiver<YieldedItem>| {
+        let item = *item.downcast::<R>().expect("item type to be checked");
+        if verdict.is_some() {
+            dropped.push(item);
+            return;
+        }
+        if let ControlFlow::Break(result) = deliver(item) {
+            *verdict = Some(result);
+            items.close();
+        }
+    };
+
+    tokio::pin!(future);
+    let output = loop {
+        tokio::select! {
+            biased;
+            output = &mut future => break output,
+            Some(item) = items.recv() => accept(item, items),
+        }
+    };
+
+    // Take the values yielded right before the future has completed.
+    while let Ok(item) = items.try_recv() {
+        accept(item, items);
+    }
+
+    output
+}
+
+/// Awaits `future`, unless `cancellation_token` is cancelled in the meantime.
+///
+/// If `cooperative` is set, then `future` is always awaited to completion.
+/// Otherwise, `priority` decides which one wins when both are ready.
+async fn race<F>(
+    cancellation_token: &CancellationToken,
+    cooperative: bool,
+    priority: CancellationPriority,
+    future: F,
+) -> Option<F::Output>
+where
+    F: Future,
+{
+    if cooperative {
+        return Some(future.await);
+    }
+
+    match priority {
+        CancellationPriority::Iteration => tokio::select! {
+            biased;
+            output = future => Some(output),
+            _ = cancellation_token.cancelled() => None,
+        },
+        CancellationPriority::Cancellation => tokio::select! {
+            biased;
+            _ = cancellation_token.cancelled() => None,
+            output = future => Some(output),
+        },
+    }
+}
+
+/// Awaits `future` for at most `duration` of `clock`, if it's present.
+///
+/// Returns `None` if `duration` has elapsed first.
+async fn timeout<F>(clock: &SharedClock, duration: Option<Duration>, future: F) -> Option<F::Output>
+where
+    F: Future,
+{
+    match duration {
+        Some(duration) => clock.timeout(duration, future).await,
+        None => Some(future.await),
+    }
+}
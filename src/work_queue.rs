@@ -0,0 +1,649 @@
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    fs::{File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    marker::PhantomData,
+    ops::Deref,
+    path::Path,
+    sync::{Arc, Mutex},
+};
+
+use tokio::sync::Notify;
+
+/// Defines an interface for a journal of a work queue, see
+/// [`WorkQueueHandle`].
+///
+/// Each item is appended to the journal before it's queued, and removed from it
+/// once it has been acknowledged. The items which haven't been acknowledged
+/// are queued again whenever a queue is opened on the journal. See
+/// [`MemoryBackend`] and [`FileBackend`].
+pub trait QueueBackend<T>: Send + Sync + 'static {
+    /// Appends `item` to the journal, and returns its id.
+    fn append(&self, item: &T) -> io::Result<u64>;
+
+    /// Removes the item with the given `id` from the journal.
+    fn ack(&self, id: u64) -> io::Result<()>;
+
+    /// Returns the items which haven't been acknowledged yet, ordered by their
+    /// ids.
+    fn pending(&self) -> io::Result<Vec<(u64, T)>>;
+}
+
+/// Backend keeping the journal in memory.
+///
+/// The clones of the backend share the same journal, so the items survive the
+/// queue which has been opened on it, e.g. when a service is created anew
+/// after a failure. They don't survive the process.
+#[derive(Debug)]
+pub struct MemoryBackend<T> {
+    journal: Arc<Mutex<MemoryJournal<T>>>,
+}
+
+#[derive(Debug)]
+struct MemoryJournal<T> {
+    next_id: u64,
+    entries: BTreeMap<u64, T>,
+}
+
+impl<T> MemoryBackend<T> {
+    /// Constructs a new, empty, backend.
+    pub fn new() -> Self {
+        Self {
+            journal: Arc::new(Mutex::new(MemoryJournal {
+                next_id: 0,
+                entries: BTreeMap::new(),
+            })),
+        }
+    }
+}
+
+impl<T> Default for MemoryBackend<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for MemoryBackend<T> {
+    fn clone(&self) -> Self {
+        Self {
+            journal: Arc::clone(&self.journal),
+        }
+    }
+}
+
+impl<T> QueueBackend<T> for MemoryBackend<T>
+where
+    T: Clone + Send + 'static,
+{
+    fn append(&self, item: &T) -> io::Result<u64> {
+        let mut journal = self.journal.lock().expect("lock not to be poisoned");
+        let id = journal.next_id;
+        journal.next_id += 1;
+        journal.entries.insert(id, item.clone());
+
+        Ok(id)
+    }
+
+    fn ack(&self, id: u64) -> io::Result<()> {
+        self.journal
+            .lock()
+            .expect("lock not to be poisoned")
+            .entries
+            .remove(&id);
+
+        Ok(())
+    }
+
+    fn pending(&self) -> io::Result<Vec<(u64, T)>> {
+        let journal = self.journal.lock().expect("lock not to be poisoned");
+
+        Ok(journal
+            .entries
+            .iter()
+            .map(|(id, item)| (*id, item.clone()))
+            .collect())
+    }
+}
+
+/// Defines how the items of a [`FileBackend`] are stored.
+pub trait Journaled: Sized {
+    /// Encodes the item.
+    fn encode(&self) -> Vec<u8>;
+
+    /// Decodes an item encoded with [`Self::encode`].
+    fn decode(bytes: Vec<u8>) -> io::Result<Self>;
+}
+
+impl Journaled for Vec<u8> {
+    fn encode(&self) -> Vec<u8> {
+        self.clone()
+    }
+
+    fn decode(bytes: Vec<u8>) -> io::Result<Self> {
+        Ok(bytes)
+    }
+}
+
+impl Journaled for String {
+    fn encode(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+
+    fn decode(bytes: Vec<u8>) -> io::Result<Self> {
+        String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// Tag of a record journaling an item.
+const ITEM_RECORD: u8 = 0;
+
+/// Tag of a record acknowledging an item.
+const ACK_RECORD: u8 = 1;
+
+/// Backend keeping the journal in an append-only file.
+///
+/// Each item is synced to the disk before it's queued, so it survives both
+/// the service and the process. The file is truncated once all of its items
+/// have been acknowledged. A record torn by a crash while it was written is
+/// discarded when the file is opened.
+#[derive(Debug)]
+pub struct FileBackend<T> {
+    journal: Mutex<FileJournal>,
+    _item: PhantomData<fn() -> T>,
+}
+
+#[derive(Debug)]
+struct FileJournal {
+    file: File,
+    next_id: u64,
+    pending: HashSet<u64>,
+}
+
+impl<T> FileBackend<T> {
+    /// Opens the journal at `path`, creating it if it doesn't exist.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(path)?;
+        let (entries, len) = replay(&mut file)?;
+        file.set_len(len)?;
+
+        Ok(Self {
+            journal: Mutex::new(FileJournal {
+                file,
+                next_id: entries.keys().next_back().map_or(0, |id| id + 1),
+                pending: entries.into_keys().collect(),
+            }),
+            _item: PhantomData,
+        })
+    }
+}
+
+/// Reads the records of `file`, and returns the items which haven't been
+/// acknowledged, along with the length of the file's valid part.
+fn replay(file: &mut File) -> io::Result<(BTreeMap<u64, Vec<u8>>, u64)> {
+    let mut bytes = Vec::new();
+    file.seek(SeekFrom::Start(0))?;
+    file.read_to_end(&mut bytes)?;
+
+    let mut entries = BTreeMap::new();
+    let mut records = bytes.as_slice();
+    let mut len = 0;
+    while let Some((&tag, rest)) = records.split_first() {
+        let Some((id, rest)) = split_u64(rest) else {
+            break;
+        };
+
+        let rest = match tag {
+            ITEM_RECORD => {
+                let Some((item, rest)) = split_item(rest) else {
+                    break;
+                };
+                entries.insert(id, item.to_vec());
+                rest
+            }
+            ACK_RECORD => {
+                entries.remove(&id);
+                rest
+            }
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "unknown record")),
+        };
+
+        len += (records.len() - rest.len()) as u64;
+        records = rest;
+    }
+
+    Ok((entries, len))
+}
+
+fn split_u64(bytes: &[u8]) -> Option<(u64, &[u8])> {
+    let (value, rest) = bytes.split_first_chunk()?;
+    Some((u64::from_le_bytes(*value), rest))
+}
+
+fn split_item(bytes: &[u8]) -> Option<(&[u8], &[u8])> {
+    let (len, rest) = bytes.split_first_chunk()?;
+    let len = u32::from_le_bytes(*len) as usize;
+    (rest.len() >= len).then(|| rest.split_at(len))
+}
+
+impl<T> QueueBackend<T> for FileBackend<T>
+where
+    T: Journaled + 'static,
+{
+    fn append(&self, item: &T) -> io::Result<u64> {
+        let item = item.encode();
+        let len = u32::try_from(item.len())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+
+        let mut journal = self.journal.lock().expect("lock not to be poisoned");
+        let id = journal.next_id;
+        let mut record = Vec::with_capacity(13 + item.len());
+        record.push(ITEM_RECORD);
+        record.extend_from_slice(&id.to_le_bytes());
+        record.extend_from_slice(&len.to_le_bytes());
+        record.extend_from_slice(&item);
+        journal.file.write_all(&record)?;
+        journal.file.sync_data()?;
+
+        journal.next_id += 1;
+        journal.pending.insert(id);
+        Ok(id)
+    }
+
+    fn ack(&self, id: u64) -> io::Result<()> {
+        let mut journal = self.journal.lock().expect("lock not to be poisoned");
+        if !journal.pending.remove(&id) {
+            return Ok(());
+        }
+
+        if journal.pending.is_empty() {
+            journal.file.set_len(0)
+        } else {
+            let mut record = vec![ACK_RECORD];
+            record.extend_from_slice(&id.to_le_bytes());
+            journal.file.write_all(&record)
+        }
+    }
+
+    fn pending(&self) -> io::Result<Vec<(u64, T)>> {
+        let mut journal = self.journal.lock().expect("lock not to be poisoned");
+        let (entries, _) = replay(&mut journal.file)?;
+
+        entries
+            .into_iter()
+            .map(|(id, item)| Ok((id, T::decode(item)?)))
+            .collect()
+    }
+}
+
+/// State of a work queue shared by its handles and its receiver.
+#[derive(Debug)]
+struct Shared<T, B> {
+    backend: B,
+    queue: Mutex<Queue<T>>,
+    notify: Notify,
+}
+
+#[derive(Debug)]
+struct Queue<T> {
+    ready: VecDeque<(u64, T)>,
+    closed: bool,
+}
+
+/// Handle pushing items to a service through a work queue with at-least-once
+/// delivery.
+///
+/// Each item is journaled in the queue's [`QueueBackend`] before it's queued,
+/// and stays there until the service acknowledges it, see [`Delivery::ack`].
+/// If the service drops a delivery without acknowledging it, e.g. because it
+/// has failed while processing it, then the item is delivered again. The items
+/// which haven't been acknowledged before the queue is dropped are delivered
+/// by the next queue opened on the same backend.
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{
+///     Cancellable, CancellationResult, CancellationToken, MemoryBackend, WorkQueueHandle,
+///     WorkQueueReceiver,
+/// };
+/// use futures::StreamExt;
+///
+/// struct Job {
+///     receiver: WorkQueueReceiver<String, MemoryBackend<String>>,
+///     handle: WorkQueueHandle<String, MemoryBackend<String>>,
+/// }
+///
+/// impl Cancellable for Job {
+///     type Result = String;
+///     type Handle = WorkQueueHandle<String, MemoryBackend<String>>;
+///     type Error = std::io::Error;
+///     type Output = ();
+///
+///     async fn new_handle(&mut self) -> Self::Handle {
+///         self.handle.clone()
+///     }
+///
+///     async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+///         let Some(delivery) = self.receiver.recv().await else {
+///             return Ok(CancellationResult::Break);
+///         };
+///
+///         let processed = delivery.to_uppercase();
+///         delivery.ack()?;
+///         Ok(CancellationResult::item(processed))
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() -> std::io::Result<()> {
+/// let (handle, receiver) = WorkQueueHandle::open(MemoryBackend::new())?;
+/// let (handle, mut processed) = Job { receiver, handle }
+///     .spawn_stream(CancellationToken::new())
+///     .await;
+///
+/// handle.push("job".to_owned())?;
+/// assert_eq!(Some("JOB".to_owned()), processed.next().await);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct WorkQueueHandle<T, B> {
+    shared: Arc<Shared<T, B>>,
+}
+
+impl<T, B> WorkQueueHandle<T, B>
+where
+    B: QueueBackend<T>,
+{
+    /// Opens a work queue on `backend`.
+    ///
+    /// The items of the backend which haven't been acknowledged yet are
+    /// queued first. The receiving side is meant to be owned by the service,
+    /// while the handle is returned by [`Cancellable::new_handle`].
+    ///
+    /// [`Cancellable::new_handle`]: crate::Cancellable::new_handle
+    pub fn open(backend: B) -> io::Result<(Self, WorkQueueReceiver<T, B>)> {
+        let ready = backend.pending()?.into();
+        let shared = Arc::new(Shared {
+            backend,
+            queue: Mutex::new(Queue {
+                ready,
+                closed: false,
+            }),
+            notify: Notify::new(),
+        });
+
+        Ok((
+            Self {
+                shared: Arc::clone(&shared),
+            },
+            WorkQueueReceiver { shared },
+        ))
+    }
+
+    /// Journals `item`, and queues it for the service.
+    ///
+    /// If the queue has been closed, then the item is neither journaled nor
+    /// queued, and a [`io::ErrorKind::BrokenPipe`] error is returned.
+    pub fn push(&self, item: T) -> io::Result<()> {
+        let mut queue = self.shared.queue.lock().expect("lock not to be poisoned");
+        if queue.closed {
+            return Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "work queue closed",
+            ));
+        }
+
+        let id = self.shared.backend.append(&item)?;
+        queue.ready.push_back((id, item));
+        drop(queue);
+
+        self.shared.notify.notify_one();
+        Ok(())
+    }
+
+    /// Signals the end of input to the service.
+    ///
+    /// Once the service has received the items queued so far, the receiver
+    /// returns `None`. Pushing fails afterwards.
+    pub fn close(&self) {
+        self.shared
+            .queue
+            .lock()
+            .expect("lock not to be poisoned")
+            .closed = true;
+        self.shared.notify.notify_one();
+    }
+
+    /// Checks if the queue has been closed with [`Self::close`].
+    pub fn is_closed(&self) -> bool {
+        self.shared
+            .queue
+            .lock()
+            .expect("lock not to be poisoned")
+            .closed
+    }
+}
+
+impl<T, B> Clone for WorkQueueHandle<T, B> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: Arc::clone(&self.shared),
+        }
+    }
+}
+
+/// Receiving side of a work queue, see [`WorkQueueHandle`].
+#[derive(Debug)]
+pub struct WorkQueueReceiver<T, B> {
+    shared: Arc<Shared<T, B>>,
+}
+
+impl<T, B> WorkQueueReceiver<T, B>
+where
+    B: QueueBackend<T>,
+{
+    /// Waits for the next item.
+    ///
+    /// It returns `None` once the queue has been closed, and all of its
+    /// queued items have been received. Waiting is cancellation safe.
+    pub async fn recv(&mut self) -> Option<Delivery<T, B>> {
+        loop {
+            let notified = self.shared.notify.notified();
+
+            {
+                let mut queue = self.shared.queue.lock().expect("lock not to be poisoned");
+                if let Some((id, item)) = queue.ready.pop_front() {
+                    return Some(Delivery {
+                        id,
+                        item: Some(item),
+                        shared: Arc::clone(&self.shared),
+                    });
+                }
+                if queue.closed {
+                    return None;
+                }
+            }
+
+            notified.await;
+        }
+    }
+}
+
+/// Item received from a work queue, which is delivered again unless it's
+/// acknowledged.
+///
+/// It dereferences to the item.
+#[derive(Debug)]
+pub struct Delivery<T, B>
+where
+    B: QueueBackend<T>,
+{
+    id: u64,
+    item: Option<T>,
+    shared: Arc<Shared<T, B>>,
+}
+
+impl<T, B> Delivery<T, B>
+where
+    B: QueueBackend<T>,
+{
+    /// Acknowledges the item, so it's removed from the journal, and returns
+    /// it.
+    ///
+    /// If the backend fails to remove the item, then the error is returned,
+    /// and the item may be delivered again by the next queue opened on the
+    /// backend.
+    pub fn ack(mut self) -> io::Result<T> {
+        let item = self.item.take().expect("item to be present");
+        self.shared.backend.ack(self.id)?;
+
+        Ok(item)
+    }
+}
+
+impl<T, B> Deref for Delivery<T, B>
+where
+    B: QueueBackend<T>,
+{
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        self.item.as_ref().expect("item to be present")
+    }
+}
+
+impl<T, B> Drop for Delivery<T, B>
+where
+    B: QueueBackend<T>,
+{
+    fn drop(&mut self) {
+        if let Some(item) = self.item.take() {
+            self.shared
+                .queue
+                .lock()
+                .expect("lock not to be poisoned")
+                .ready
+                .push_front((self.id, item));
+            self.shared.notify.notify_one();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io,
+        path::PathBuf,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+
+    use tokio::sync::mpsc;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{
+        CallbackResult, Cancellable, CancellationResult, FileBackend, MemoryBackend, QueueBackend,
+        RestartPolicy, SpawnOptions, WorkQueueHandle, WorkQueueReceiver,
+    };
+
+    /// Fails while processing the first item it receives.
+    struct FlakyWorker {
+        receiver: WorkQueueReceiver<u32, MemoryBackend<u32>>,
+        handle: WorkQueueHandle<u32, MemoryBackend<u32>>,
+        attempts: Arc<AtomicUsize>,
+    }
+
+    impl Cancellable for FlakyWorker {
+        type Result = u32;
+        type Handle = WorkQueueHandle<u32, MemoryBackend<u32>>;
+        type Error = io::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {
+            self.handle.clone()
+        }
+
+        async fn run(&mut self) -> Result<CancellationResult<u32>, Self::Error> {
+            let Some(delivery) = self.receiver.recv().await else {
+                return Ok(CancellationResult::Break);
+            };
+
+            if self.attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                return Err(io::Error::other("processing failed"));
+            }
+
+            Ok(CancellationResult::item(delivery.ack()?))
+        }
+    }
+
+    #[tokio::test]
+    async fn should_redeliver_item_unacknowledged_by_failed_service() {
+        // Arrange
+        let backend = MemoryBackend::new();
+        let (handle, receiver) = WorkQueueHandle::open(backend.clone()).unwrap();
+        let worker = FlakyWorker {
+            receiver,
+            handle,
+            attempts: Arc::new(AtomicUsize::new(0)),
+        };
+        let options =
+            SpawnOptions::new().restart_policy(RestartPolicy::fixed(Duration::from_millis(1)));
+        let (sender, mut processed) = mpsc::unbounded_channel();
+        let handle = worker
+            .spawn_with_options(CancellationToken::new(), options, move |item| {
+                let _ = sender.send(item);
+                CallbackResult::Continue
+            })
+            .await;
+
+        // Act
+        handle.push(7).unwrap();
+        let item = processed.recv().await;
+
+        // Assert
+        assert_eq!(Some(7), item);
+        assert!(backend.pending().unwrap().is_empty());
+    }
+
+    /// Returns a path of a journal for a single test.
+    fn journal_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("cancellable-{}-{name}.journal", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[tokio::test]
+    async fn should_recover_unacknowledged_items_from_file() {
+        // Arrange
+        let path = journal_path("recovered");
+        let (handle, mut receiver) =
+            WorkQueueHandle::open(FileBackend::<String>::open(&path).unwrap()).unwrap();
+        handle.push("first".to_owned()).unwrap();
+        handle.push("second".to_owned()).unwrap();
+        receiver.recv().await.unwrap().ack().unwrap();
+        drop((handle, receiver));
+
+        // Act
+        let (handle, mut receiver) =
+            WorkQueueHandle::open(FileBackend::<String>::open(&path).unwrap()).unwrap();
+        handle.close();
+        let delivery = receiver.recv().await.unwrap();
+        let recovered = delivery.ack().unwrap();
+        let next = receiver.recv().await;
+
+        // Assert
+        assert_eq!("second", recovered);
+        assert!(next.is_none());
+        assert_eq!(0, std::fs::metadata(&path).unwrap().len());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
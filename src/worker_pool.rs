@@ -0,0 +1,263 @@
+use std::{
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
+
+use tokio::sync::{mpsc, Mutex};
+use tokio_util::sync::CancellationToken;
+
+use crate::{Cancellable, CancellableError, CancellableHandle, MpscSenderHandle};
+
+/// Receiving side of a work queue shared by the workers of a pool.
+///
+/// Every item is received by exactly one of the workers. See [`spawn_pool`].
+#[derive(Debug)]
+pub struct SharedReceiver<I> {
+    inner: Arc<Mutex<mpsc::Receiver<I>>>,
+}
+
+impl<I> SharedReceiver<I> {
+    /// Receives the next item of the queue.
+    ///
+    /// Returns `None` once all handles of the pool have been dropped and the
+    /// queue is empty.
+    pub async fn recv(&self) -> Option<I> {
+        self.inner.lock().await.recv().await
+    }
+
+    /// Receives the next item of the queue, if it's immediately available.
+    ///
+    /// Returns `None` if the queue is empty, or another worker is currently
+    /// waiting for an item.
+    pub fn try_recv(&self) -> Option<I> {
+        self.inner.try_lock().ok()?.try_recv().ok()
+    }
+}
+
+impl<I> Clone for SharedReceiver<I> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+/// Handle of a pool of identical services, spawned with [`spawn_pool`].
+///
+/// The handle dereferences to the sender of the pool's work queue.
+#[derive(Debug)]
+pub struct PoolHandle<T, I>
+where
+    T: Cancellable,
+{
+    workers: Vec<CancellableHandle<T, ()>>,
+    cancellation_token: CancellationToken,
+    sender: MpscSenderHandle<I>,
+}
+
+impl<T, I> PoolHandle<T, I>
+where
+    T: Cancellable,
+{
+    /// Cancels all workers of the pool.
+    pub fn cancel(&self) {
+        self.cancellation_token.cancel();
+    }
+
+    /// Returns the number of workers of the pool.
+    pub fn size(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Waits for all workers of the pool to complete.
+    ///
+    /// # Returns
+    ///
+    /// The result of every worker, in the order in which they have been
+    /// spawned.
+    pub async fn join(self) -> Vec<Result<Option<T::Output>, CancellableError<T::Error>>> {
+        drop(self.sender);
+
+        let mut results = Vec::with_capacity(self.workers.len());
+        for worker in self.workers {
+            results.push(worker.join().await);
+        }
+
+        results
+    }
+}
+
+impl<T, I> Deref for PoolHandle<T, I>
+where
+    T: Cancellable,
+{
+    type Target = MpscSenderHandle<I>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.sender
+    }
+}
+
+impl<T, I> DerefMut for PoolHandle<T, I>
+where
+    T: Cancellable,
+{
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.sender
+    }
+}
+
+/// Spawns a pool of `size` services, which share a single work queue with the
+/// given `capacity`.
+///
+/// Each worker is constructed by `factory` from the receiving side of the
+/// queue, while items are sent to the pool through the returned handle. The
+/// workers' own handles are dropped.
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{
+///     Cancellable, CancellationResult, CancellationToken, SenderHandle, SharedReceiver,
+/// };
+///
+/// struct Worker {
+///     jobs: SharedReceiver<u32>,
+/// }
+///
+/// impl Cancellable for Worker {
+///     type Result = ();
+///     type Handle = ();
+///     type Error = std::io::Error;
+///     type Output = ();
+///
+///     async fn new_handle(&mut self) -> Self::Handle {}
+///
+///     async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+///         match self.jobs.recv().await {
+///             Some(job) => println!("Processing job {job}."),
+///             None => return Ok(CancellationResult::Break),
+///         }
+///
+///         Ok(CancellationResult::Continue)
+///     }
+/// }
+///
+/// # #[tokio::main]
+/// # async fn main() {
+/// let pool = cancellable::spawn_pool(4, 16, CancellationToken::new(), |jobs| Worker { jobs }).await;
+/// pool.send(42).await.unwrap();
+/// pool.join().await;
+/// # }
+/// ```
+///
+/// # Panics
+///
+/// This function panics if `capacity` is zero.
+pub async fn spawn_pool<T, I, F>(
+    size: usize,
+    capacity: usize,
+    cancellation_token: CancellationToken,
+    mut factory: F,
+) -> PoolHandle<T, I>
+where
+    T: Cancellable + 'static,
+    F: FnMut(SharedReceiver<I>) -> T,
+{
+    let (sender, receiver) = MpscSenderHandle::channel(capacity);
+    let receiver = SharedReceiver {
+        inner: Arc::new(Mutex::new(receiver)),
+    };
+    let cancellation_token = cancellation_token.child_token();
+
+    let mut workers = Vec::with_capacity(size);
+    for _ in 0..size {
+        let worker = factory(receiver.clone());
+        let (join, _) = worker.spawn(cancellation_token.clone()).await.into_parts();
+        workers.push(join);
+    }
+
+    PoolHandle {
+        workers,
+        cancellation_token,
+        sender,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{Cancellable, CancellationResult, SenderHandle, SharedReceiver};
+
+    struct SummingCancellable {
+        jobs: SharedReceiver<usize>,
+        sum: Arc<AtomicUsize>,
+    }
+
+    impl Cancellable for SummingCancellable {
+        type Result = ();
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<()>, Self::Error> {
+            match self.jobs.recv().await {
+                Some(job) => {
+                    self.sum.fetch_add(job, Ordering::SeqCst);
+                    Ok(CancellationResult::Continue)
+                }
+                None => Ok(CancellationResult::Break),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn should_distribute_items_across_workers() {
+        // Arrange
+        let sum = Arc::new(AtomicUsize::new(0));
+        let pool = crate::spawn_pool(3, 4, CancellationToken::new(), |jobs| SummingCancellable {
+            jobs,
+            sum: Arc::clone(&sum),
+        })
+        .await;
+
+        // Act
+        for job in 1..=10 {
+            pool.send(job).await.unwrap();
+        }
+        let results = pool.join().await;
+
+        // Assert
+        assert_eq!(3, results.len());
+        assert!(results.iter().all(Result::is_ok));
+        assert_eq!(55, sum.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn should_cancel_all_workers() {
+        // Arrange
+        let sum = Arc::new(AtomicUsize::new(0));
+        let pool = crate::spawn_pool(2, 1, CancellationToken::new(), |jobs| SummingCancellable {
+            jobs,
+            sum: Arc::clone(&sum),
+        })
+        .await;
+        let sender = pool.clone();
+
+        // Act
+        pool.cancel();
+        let results = pool.join().await;
+
+        // Assert
+        assert!(results.iter().all(Result::is_ok));
+        drop(sender);
+    }
+}
@@ -0,0 +1,191 @@
+use std::marker::PhantomData;
+
+use tokio::sync::mpsc;
+
+use crate::run_context::YieldedItem;
+
+/// Sender of the values yielded by a service before its iteration completes.
+///
+/// It's obtained with [`RunContext::yielder`] and behaves just like
+/// [`RunContext::yield_item`], but the type of the values is checked only
+/// once. It can be cloned and moved to other tasks, e.g. to a reader of a
+/// socket spawned by the service. Its values are of the type of the values
+/// the service itself yields, even when the service is wrapped in an adapter,
+/// e.g. [`Cancellable::map`], and are passed through the adapter.
+///
+/// # Examples
+///
+/// ```
+/// use cancellable::{Cancellable, CancellationResult, RunContext};
+/// use tokio::sync::mpsc;
+///
+/// struct Proxy {
+///     frames: mpsc::Receiver<Vec<u8>>,
+/// }
+///
+/// impl Cancellable for Proxy {
+///     type Result = Vec<u8>;
+///     type Handle = ();
+///     type Error = std::io::Error;
+///     type Output = ();
+///
+///     async fn new_handle(&mut self) -> Self::Handle {}
+///
+///     async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+///         let context = RunContext::current().expect("to be called by the work loop");
+///         let yielder = context.yielder::<Vec<u8>>().expect("to match the service's values");
+///
+///         while let Some(frame) = self.frames.recv().await {
+///             if yielder.send(frame).await.is_err() {
+///                 break;
+///             }
+///         }
+///
+///         Ok(CancellationResult::Break)
+///     }
+/// }
+/// ```
+///
+/// [`Cancellable::map`]: crate::Cancellable::map
+/// [`RunContext::yielder`]: crate::RunContext::yielder
+/// [`RunContext::yield_item`]: crate::RunContext::yield_item
+pub struct Yielder<T> {
+    sender: mpsc::Sender<YieldedItem>,
+    _item: PhantomData<fn(T)>,
+}
+
+impl<T> Yielder<T>
+where
+    T: Send + 'static,
+{
+    pub(crate) fn new(sender: mpsc::Sender<YieldedItem>) -> Self {
+        Self {
+            sender,
+            _item: PhantomData,
+        }
+    }
+
+    /// Yields `item` to the service's consumer.
+    ///
+    /// See [`RunContext::yield_item`].
+    ///
+    /// # Errors
+    ///
+    /// Returns `item` back if the consumer has stopped accepting values.
+    ///
+    /// [`RunContext::yield_item`]: crate::RunContext::yield_item
+    pub async fn send(&self, item: T) -> Result<(), T> {
+        self.sender
+            .send(Box::new(item))
+            .await
+            .map_err(|e| *e.0.downcast().expect("item type to be checked"))
+    }
+
+    /// Checks if the consumer has stopped accepting values.
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+}
+
+impl<T> Clone for Yielder<T> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            _item: PhantomData,
+        }
+    }
+}
+
+impl<T> std::fmt::Debug for Yielder<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Yielder").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use tokio::sync::mpsc;
+    use tokio_util::sync::CancellationToken;
+
+    use crate::{CallbackResult, Cancellable, CancellationResult, RunContext, Yielder};
+
+    struct YieldingCancellable;
+
+    impl Cancellable for YieldingCancellable {
+        type Result = i32;
+        type Handle = ();
+        type Error = anyhow::Error;
+        type Output = ();
+
+        async fn new_handle(&mut self) -> Self::Handle {}
+
+        async fn run(&mut self) -> Result<CancellationResult<i32>, Self::Error> {
+            let context = RunContext::current().expect("to be called by the work loop");
+            let yielder = context
+                .yielder::<i32>()
+                .expect("to match the service's values");
+            for item in 1..=3 {
+                if yielder.send(item).await.is_err() {
+                    break;
+                }
+            }
+
+            Ok(CancellationResult::Break)
+        }
+    }
+
+    #[tokio::test]
+    async fn should_send_item() {
+        // Arrange
+        let (sender, mut receiver) = mpsc::channel(1);
+        let yielder = Yielder::new(sender);
+
+        // Act
+        yielder.send(42).await.unwrap();
+
+        // Assert
+        let item = receiver.recv().await.unwrap();
+        assert_eq!(Some(&42), item.downcast_ref::<i32>());
+    }
+
+    #[tokio::test]
+    async fn should_return_item_when_consumer_is_closed() {
+        // Arrange
+        let (sender, receiver) = mpsc::channel(1);
+        let yielder = Yielder::new(sender);
+
+        // Act
+        drop(receiver);
+        let result = yielder.send(42).await;
+
+        // Assert
+        assert!(yielder.is_closed());
+        assert_eq!(Err(42), result);
+    }
+
+    #[tokio::test]
+    async fn should_pass_items_through_adapters() {
+        // Arrange
+        let items = Arc::new(Mutex::new(Vec::new()));
+        let items_clone = Arc::clone(&items);
+        let service = YieldingCancellable
+            .filter(|item| *item != 2)
+            .map(|item| item.to_string());
+
+        // Act
+        service
+            .spawn_with_callback(CancellationToken::new(), move |item| {
+                items_clone.lock().unwrap().push(item);
+                CallbackResult::Continue
+            })
+            .await
+            .join()
+            .await
+            .unwrap();
+
+        // Assert
+        assert_eq!(vec!["1", "3"], *items.lock().unwrap());
+    }
+}
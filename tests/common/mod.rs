@@ -32,13 +32,22 @@ impl MockCancellable {
             receiver,
         }
     }
+
+    fn process(item: Option<i32>) -> Result<CancellationResult<i32>, anyhow::Error> {
+        match item {
+            Some(item) if item < 0 => Ok(CancellationResult::Continue),
+            Some(0) => Err(anyhow::anyhow!("Received zero")),
+            Some(item) => Ok(CancellationResult::Item(item * 2)),
+            None => Ok(CancellationResult::Break),
+        }
+    }
 }
 
-#[async_trait::async_trait]
 impl Cancellable for MockCancellable {
     type Result = i32;
     type Handle = Feeder;
     type Error = anyhow::Error;
+    type Output = ();
 
     async fn new_handle(&mut self) -> Self::Handle {
         self.sender
@@ -47,12 +56,15 @@ impl Cancellable for MockCancellable {
             .expect("MockCancellable's sender to be present.")
     }
 
-    async fn run(&mut self) -> Result<CancellationResult<Self::Result>, Self::Error> {
-        match self.receiver.recv().await {
-            Some(item) if item < 0 => Ok(CancellationResult::Continue),
-            Some(0) => Err(anyhow::anyhow!("Received zero")),
-            Some(item) => Ok(CancellationResult::Item(item * 2)),
-            None => Ok(CancellationResult::Break),
-        }
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        let item = self.receiver.recv().await;
+        Self::process(item)
+    }
+
+    async fn drain(
+        &mut self,
+    ) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        let item = self.receiver.try_recv().ok();
+        Self::process(item)
     }
 }
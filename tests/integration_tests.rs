@@ -1,6 +1,15 @@
-use std::time::Duration;
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
-use cancellable::{Cancellable, CancellationToken};
+use cancellable::{
+    CallbackResult, Cancellable, CancellableMetrics, CancellationToken, RestartPolicy, SpawnOptions,
+};
+use futures::StreamExt;
 use tokio::{
     sync::mpsc::{error::SendError, unbounded_channel},
     time::timeout,
@@ -19,8 +28,8 @@ async fn should_receive_sent_items() -> Result<(), anyhow::Error> {
     let mut handle = cancellable
         .spawn_with_callback(CancellationToken::new(), move |item| {
             match sender.send(item) {
-                Ok(()) => Ok(()),
-                Err(SendError(item)) => Err(item),
+                Ok(()) => CallbackResult::Continue,
+                Err(SendError(_)) => CallbackResult::Break,
             }
         })
         .await;
@@ -43,8 +52,8 @@ async fn should_complete_when_produced_error() -> Result<(), anyhow::Error> {
     let mut handle = cancellable
         .spawn_with_callback(CancellationToken::new(), move |item| {
             match sender.send(item) {
-                Ok(()) => Ok(()),
-                Err(SendError(item)) => Err(item),
+                Ok(()) => CallbackResult::Continue,
+                Err(SendError(_)) => CallbackResult::Break,
             }
         })
         .await;
@@ -67,8 +76,8 @@ async fn should_await_when_produces_continue() -> Result<(), anyhow::Error> {
     let mut handle = cancellable
         .spawn_with_callback(CancellationToken::new(), move |item| {
             match sender.send(item) {
-                Ok(()) => Ok(()),
-                Err(SendError(item)) => Err(item),
+                Ok(()) => CallbackResult::Continue,
+                Err(SendError(_)) => CallbackResult::Break,
             }
         })
         .await;
@@ -92,8 +101,8 @@ async fn should_pass_result_after_continue() -> Result<(), anyhow::Error> {
     let mut handle = cancellable
         .spawn_with_callback(CancellationToken::new(), move |item| {
             match sender.send(item) {
-                Ok(()) => Ok(()),
-                Err(SendError(item)) => Err(item),
+                Ok(()) => CallbackResult::Continue,
+                Err(SendError(_)) => CallbackResult::Break,
             }
         })
         .await;
@@ -118,8 +127,8 @@ async fn should_complete_when_produces_break() -> Result<(), anyhow::Error> {
     let handle = cancellable
         .spawn_with_callback(CancellationToken::new(), move |item| {
             match sender.send(item) {
-                Ok(()) => Ok(()),
-                Err(SendError(item)) => Err(item),
+                Ok(()) => CallbackResult::Continue,
+                Err(SendError(_)) => CallbackResult::Break,
             }
         })
         .await;
@@ -179,3 +188,170 @@ async fn should_complete_when_cancelled_via_handle() -> Result<(), anyhow::Error
 
     Ok(())
 }
+
+#[tokio::test]
+async fn should_stream_yielded_items() -> Result<(), anyhow::Error> {
+    // Arrange
+    let cancellable = MockCancellable::new();
+    let (mut handle, stream) = cancellable.spawn_stream(CancellationToken::new()).await;
+
+    // Act
+    handle.send(1).await.unwrap();
+    handle.send(-1).await.unwrap();
+    handle.send(2).await.unwrap();
+
+    // Assert
+    let items: Vec<_> = stream.map(|item| item + 1).take(2).collect().await;
+    assert_eq!(vec![3, 5], items);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn should_complete_stream_when_cancelled() -> Result<(), anyhow::Error> {
+    // Arrange
+    let cancellation_token = CancellationToken::new();
+
+    let cancellable = MockCancellable::new();
+    let (handle, mut stream) = cancellable.spawn_stream(cancellation_token.clone()).await;
+
+    // Act
+    cancellation_token.cancel();
+
+    // Assert
+    assert!(stream.next().await.is_none());
+    handle.await??;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn should_drain_pending_items_when_gracefully_cancelled() -> Result<(), anyhow::Error> {
+    // Arrange
+    let cancellation_token = CancellationToken::new();
+    let (sender, mut receiver) = unbounded_channel();
+
+    let cancellable = MockCancellable::new();
+    let mut handle = cancellable
+        .spawn_with_options(
+            cancellation_token.clone(),
+            SpawnOptions::new().graceful_shutdown(),
+            move |item| match sender.send(item) {
+                Ok(()) => CallbackResult::Continue,
+                Err(SendError(_)) => CallbackResult::Break,
+            },
+        )
+        .await;
+
+    handle.send(1).await.unwrap();
+    handle.send(2).await.unwrap();
+    handle.send(3).await.unwrap();
+
+    // Act
+    cancellation_token.cancel();
+    handle.await??;
+
+    // Assert
+    let mut items = Vec::new();
+    while let Some(item) = receiver.recv().await {
+        items.push(item);
+    }
+    assert_eq!(vec![2, 4, 6], items);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn should_restart_when_produced_error() -> Result<(), anyhow::Error> {
+    // Arrange
+    let (sender, mut receiver) = unbounded_channel();
+
+    let cancellable = MockCancellable::new();
+    let options = SpawnOptions::new().restart_policy(RestartPolicy::fixed(Duration::ZERO));
+    let mut handle =
+        cancellable
+            .spawn_with_options(CancellationToken::new(), options, move |item| match sender
+                .send(item)
+            {
+                Ok(()) => CallbackResult::Continue,
+                Err(SendError(_)) => CallbackResult::Break,
+            })
+            .await;
+
+    handle.send(0).await.unwrap();
+
+    // Act
+    handle.send(42).await.unwrap();
+
+    // Assert
+    assert_eq!(42 * 2, receiver.recv().await.unwrap());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn should_complete_when_restart_attempts_exhausted() -> Result<(), anyhow::Error> {
+    // Arrange
+    let cancellable = MockCancellable::new();
+    let policy = RestartPolicy::fixed(Duration::ZERO).max_attempts(1);
+    let mut handle = cancellable
+        .spawn_with_restart(CancellationToken::new(), policy)
+        .await;
+
+    // Act
+    handle.send(0).await.unwrap();
+    handle.send(0).await.unwrap();
+
+    // Assert
+    assert!(handle.await?.is_err());
+
+    Ok(())
+}
+
+#[derive(Default)]
+struct CountingMetrics {
+    iterations: AtomicUsize,
+    items: AtomicUsize,
+    errors: AtomicUsize,
+}
+
+impl CancellableMetrics for CountingMetrics {
+    fn on_iteration(&self, _duration: Duration) {
+        self.iterations.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn on_item(&self) {
+        self.items.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn on_error(&self) {
+        self.errors.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[tokio::test]
+async fn should_report_metrics() -> Result<(), anyhow::Error> {
+    // Arrange
+    let metrics = Arc::new(CountingMetrics::default());
+
+    let cancellable = MockCancellable::new();
+    let options = SpawnOptions::new().metrics(metrics.clone());
+    let mut handle = cancellable
+        .spawn_with_options(CancellationToken::new(), options, |_| {
+            CallbackResult::Continue
+        })
+        .await;
+
+    // Act
+    handle.send(-1).await.unwrap();
+    handle.send(1).await.unwrap();
+    handle.send(0).await.unwrap();
+
+    // Assert
+    assert!(handle.await?.is_err());
+    assert_eq!(3, metrics.iterations.load(Ordering::SeqCst));
+    assert_eq!(1, metrics.items.load(Ordering::SeqCst));
+    assert_eq!(1, metrics.errors.load(Ordering::SeqCst));
+
+    Ok(())
+}
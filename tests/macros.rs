@@ -0,0 +1,115 @@
+#![cfg(feature = "macros")]
+
+use cancellable::{
+    service, Cancellable, CancellationResult, CancellationToken, MpscSenderHandle, SenderHandle,
+};
+use futures::StreamExt;
+use tokio::sync::mpsc::{Receiver, UnboundedReceiver};
+
+#[service]
+struct Adder {
+    addend: i32,
+    #[handle_receiver]
+    numbers: UnboundedReceiver<i32>,
+}
+
+#[service]
+impl Cancellable for Adder {
+    type Result = i32;
+    type Error = std::io::Error;
+    type Output = ();
+
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        match self.numbers.recv().await {
+            Some(number) => Ok(CancellationResult::item(number + self.addend)),
+            None => Ok(CancellationResult::Break),
+        }
+    }
+}
+
+#[service]
+struct Doubler {
+    receiver: Receiver<i32>,
+    #[handle_sender]
+    handle: Option<MpscSenderHandle<i32>>,
+}
+
+#[service]
+impl Cancellable for Doubler {
+    type Result = i32;
+    type Error = std::io::Error;
+    type Output = ();
+
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        match self.receiver.recv().await {
+            Some(number) => Ok(CancellationResult::item(number * 2)),
+            None => Ok(CancellationResult::Break),
+        }
+    }
+}
+
+#[service]
+struct Countdown {
+    remaining: u32,
+}
+
+#[service]
+impl Cancellable for Countdown {
+    type Result = u32;
+    type Error = std::io::Error;
+    type Output = ();
+
+    async fn run(&mut self) -> Result<CancellationResult<Self::Result, Self::Output>, Self::Error> {
+        match self.remaining.checked_sub(1) {
+            Some(remaining) => {
+                self.remaining = remaining;
+                Ok(CancellationResult::item(remaining))
+            }
+            None => Ok(CancellationResult::Break),
+        }
+    }
+}
+
+#[tokio::test]
+async fn should_generate_channel_of_handle_receiver() {
+    // Arrange
+    let service = Adder::new(10);
+
+    // Act
+    let (handle, items) = service.spawn_stream(CancellationToken::new()).await;
+    handle.send(1).await.unwrap();
+    handle.send(2).await.unwrap();
+
+    // Assert
+    assert_eq!(vec![11, 12], items.take(2).collect::<Vec<_>>().await);
+}
+
+#[tokio::test]
+async fn should_take_handle_of_handle_sender() {
+    // Arrange
+    let (handle, receiver) = MpscSenderHandle::channel(1);
+    let service = Doubler {
+        receiver,
+        handle: Some(handle),
+    };
+
+    // Act
+    let (handle, items) = service.spawn_stream(CancellationToken::new()).await;
+    handle.send(21).await.unwrap();
+
+    // Assert
+    assert_eq!(vec![42], items.take(1).collect::<Vec<_>>().await);
+}
+
+#[tokio::test]
+async fn should_generate_unit_handle_without_handle_field() {
+    // Arrange
+    let service = Countdown { remaining: 3 };
+
+    // Act
+    let (handle, items) = service.spawn_stream(CancellationToken::new()).await;
+
+    // Assert
+    assert_eq!(vec![2, 1, 0], items.collect::<Vec<_>>().await);
+    assert!(handle.join().await.is_ok());
+}